@@ -0,0 +1,213 @@
+//! And-inverter graphs, the representation used by ABC and most hardware
+//! model checkers, with an AIGER writer.
+//!
+//! Literals follow AIGER: variable `v` is literal `2v`, its negation `2v + 1`,
+//! and literals `0` and `1` are the constants false and true. Assumptions
+//! become AIGER 1.9 invariant constraints, which a model checker only
+//! considers the inputs that satisfy.
+
+use crate::intern::Symbol;
+use std::{collections::HashMap, fmt::Write};
+
+use crate::parser::{BinOp, Equation, Expr, ExprRef};
+
+pub type Literal = u32;
+
+pub const FALSE: Literal = 0;
+pub const TRUE: Literal = 1;
+
+fn not(lit: Literal) -> Literal {
+    lit ^ 1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aig {
+    pub inputs: Vec<Symbol>,
+    /// The two operands of each AND gate, the `i`th defining variable
+    /// `inputs.len() + 1 + i`. Operands always refer to earlier variables.
+    pub ands: Vec<(Literal, Literal)>,
+    pub output: Literal,
+    pub output_name: Symbol,
+    /// A literal for each assumption, which must be true.
+    pub constraints: Vec<Literal>,
+}
+
+struct Builder {
+    num_inputs: u32,
+    ands: Vec<(Literal, Literal)>,
+    hashed: HashMap<(Literal, Literal), Literal>,
+}
+
+impl Builder {
+    fn and(&mut self, a: Literal, b: Literal) -> Literal {
+        let (a, b) = if a >= b { (a, b) } else { (b, a) };
+        if b == FALSE || a == not(b) {
+            return FALSE;
+        }
+        if b == TRUE || a == b {
+            return a;
+        }
+        if let Some(&lit) = self.hashed.get(&(a, b)) {
+            return lit;
+        }
+
+        self.ands.push((a, b));
+        let lit = 2 * (self.num_inputs + self.ands.len() as u32);
+        self.hashed.insert((a, b), lit);
+        lit
+    }
+
+    fn or(&mut self, a: Literal, b: Literal) -> Literal {
+        not(self.and(not(a), not(b)))
+    }
+
+    fn xor(&mut self, a: Literal, b: Literal) -> Literal {
+        let left = self.and(a, not(b));
+        let right = self.and(not(a), b);
+        self.or(left, right)
+    }
+
+    fn build(&mut self, expr: ExprRef) -> Literal {
+        match expr.node() {
+            Expr::Bool(b) => b as Literal,
+            Expr::Var(v) => 2 * (v as Literal + 1),
+            Expr::Not(e) => not(self.build(e)),
+            Expr::BinOp { op, lhs, rhs } => {
+                let (lhs, rhs) = (self.build(lhs), self.build(rhs));
+                match op {
+                    BinOp::And => self.and(lhs, rhs),
+                    BinOp::Or => self.or(lhs, rhs),
+                    BinOp::Xor => self.xor(lhs, rhs),
+                }
+            }
+        }
+    }
+}
+
+impl Aig {
+    /// Build the graph for an equation, folding constants and sharing
+    /// structurally identical gates.
+    pub fn new(equation: &Equation) -> Self {
+        let mut builder = Builder {
+            num_inputs: equation.inputs.len() as u32,
+            ands: vec![],
+            hashed: HashMap::new(),
+        };
+        let output = builder.build(equation.lhs.expr());
+        let constraints = equation
+            .assumptions
+            .iter()
+            .map(|assumption| builder.build(assumption.expr()))
+            .collect();
+
+        Self {
+            inputs: equation.inputs.clone(),
+            ands: builder.ands,
+            output,
+            output_name: equation.output,
+            constraints,
+        }
+    }
+
+    /// The header's counts, which only go as far as constraints, with no
+    /// bad states before them, if there are any.
+    fn header(&self, format: &str) -> String {
+        let mut out = format!(
+            "{} {} {} 0 1 {}",
+            format,
+            self.max_var(),
+            self.inputs.len(),
+            self.ands.len()
+        );
+        if !self.constraints.is_empty() {
+            write!(out, " 0 {}", self.constraints.len()).unwrap();
+        }
+        out.push('\n');
+
+        out
+    }
+
+    /// The output literal, followed by the constraints'.
+    fn outputs(&self) -> String {
+        let mut out = String::new();
+        for lit in [self.output].iter().chain(&self.constraints) {
+            writeln!(out, "{}", lit).unwrap();
+        }
+
+        out
+    }
+
+    fn max_var(&self) -> usize {
+        self.inputs.len() + self.ands.len()
+    }
+
+    fn symbols(&self) -> String {
+        let mut out = String::new();
+        for (i, name) in self.inputs.iter().enumerate() {
+            writeln!(out, "i{} {}", i, name).unwrap();
+        }
+        writeln!(out, "o0 {}", self.output_name).unwrap();
+        out
+    }
+
+    /// The ASCII format, conventionally saved as `.aag`.
+    pub fn to_aag(&self) -> String {
+        let mut out = self.header("aag");
+        for i in 1..=self.inputs.len() {
+            writeln!(out, "{}", 2 * i).unwrap();
+        }
+        out.push_str(&self.outputs());
+        for (i, (a, b)) in self.ands.iter().enumerate() {
+            let lhs = 2 * (self.inputs.len() + 1 + i);
+            writeln!(out, "{} {} {}", lhs, a, b).unwrap();
+        }
+        out.push_str(&self.symbols());
+
+        out
+    }
+
+    /// The binary format, conventionally saved as `.aig`, in which inputs are
+    /// implicit and each gate is stored as two deltas.
+    pub fn to_aig(&self) -> Vec<u8> {
+        let mut out = (self.header("aig") + &self.outputs()).into_bytes();
+        for (i, &(a, b)) in self.ands.iter().enumerate() {
+            let lhs = 2 * (self.inputs.len() as Literal + 1 + i as Literal);
+            write_delta(&mut out, lhs - a);
+            write_delta(&mut out, a - b);
+        }
+        out.extend_from_slice(self.symbols().as_bytes());
+
+        out
+    }
+}
+
+fn write_delta(out: &mut Vec<u8>, mut n: u32) {
+    while n >= 0x80 {
+        out.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new("(A AND B) OR (B AND A) OR 0 = Q");
+    let aig = Aig::new(&parser.parse_equation().unwrap());
+    assert_eq!(
+        aig.to_aag(),
+        "aag 3 2 0 1 1\n2\n4\n6\n6 4 2\ni0 A\ni1 B\no0 Q\n"
+    );
+    assert_eq!(
+        aig.to_aig(),
+        b"aig 3 2 0 1 1\n6\n\x02\x02i0 A\ni1 B\no0 Q\n"
+    );
+
+    let mut parser = Parser::new("assume NOT A; A OR B = Q");
+    let aig = Aig::new(&parser.parse_equation().unwrap());
+    assert_eq!(
+        aig.to_aag(),
+        "aag 3 2 0 1 1 0 1\n2\n4\n7\n3\n6 5 3\ni0 A\ni1 B\no0 Q\n"
+    );
+}
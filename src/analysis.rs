@@ -0,0 +1,306 @@
+//! Quantitative comparisons of equations, for when it matters how far apart
+//! two functions are and not only whether they're equal, and checks of the
+//! parts of an equation.
+
+use alloc::vec::Vec;
+
+use crate::{
+    compiler::Compiler,
+    equiv,
+    intern::Symbol,
+    interp,
+    lexer::Span,
+    parser::{Equation, Expr, ExprRef, SpanExpr},
+    rng::Rng,
+    vm::VM,
+};
+
+/// How many random assignments `sample_sat` tries for each sample it's asked
+/// for before giving up, which bounds the time spent on functions that are
+/// rarely or never true.
+pub const ATTEMPTS_PER_SAMPLE: usize = 1 << 12;
+
+/// Up to this many inputs, `sample_sat` draws from the enumerated true rows
+/// instead, so that it always finds them however rare they are.
+const ENUMERATE_INPUTS: usize = 12;
+
+/// The size of an expression built as a circuit of two-input gates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cost {
+    /// Variables as written, counting repeats.
+    pub literals: usize,
+    /// AND, OR and XOR gates.
+    pub gates: usize,
+    pub inverters: usize,
+}
+
+pub fn cost(expr: ExprRef) -> Cost {
+    match expr.node() {
+        Expr::Bool(_) => Cost {
+            literals: 0,
+            gates: 0,
+            inverters: 0,
+        },
+        Expr::Var(_) => Cost {
+            literals: 1,
+            gates: 0,
+            inverters: 0,
+        },
+        Expr::Not(e) => Cost {
+            inverters: cost(e).inverters + 1,
+            ..cost(e)
+        },
+        Expr::BinOp { lhs, rhs, .. } => {
+            let (lhs, rhs) = (cost(lhs), cost(rhs));
+            Cost {
+                literals: lhs.literals + rhs.literals,
+                gates: lhs.gates + rhs.gates + 1,
+                inverters: lhs.inverters + rhs.inverters,
+            }
+        }
+    }
+}
+
+/// Subexpressions over more inputs than this aren't checked by
+/// `constant_subexpressions`, though the smaller ones inside them are.
+pub const CONSTANT_CHECK_INPUTS: usize = 12;
+
+/// A part of an expression with the same value for every assignment, like
+/// `A OR NOT A`, which could be replaced by `value`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantSubexpr {
+    pub span: Span,
+    pub value: bool,
+}
+
+/// The largest subexpressions of `expr`, other than the whole of it, that
+/// are always true or always false. Only those made of variables alone are
+/// reported, since one with a constant in it, like `A AND 0`, is constant
+/// on purpose or is already warned about as a dead input.
+pub fn constant_subexpressions(expr: &SpanExpr) -> Vec<ConstantSubexpr> {
+    let mut found = Vec::new();
+    for child in children(expr.expr()) {
+        find_constants(child, &mut found);
+    }
+
+    found
+}
+
+fn children(expr: ExprRef) -> Vec<ExprRef> {
+    match expr.node() {
+        Expr::Bool(_) | Expr::Var(_) => Vec::new(),
+        Expr::Not(e) => alloc::vec![e],
+        Expr::BinOp { lhs, rhs, .. } => alloc::vec![lhs, rhs],
+    }
+}
+
+fn find_constants(expr: ExprRef, found: &mut Vec<ConstantSubexpr>) {
+    if let Some(value) = constant(expr) {
+        found.push(ConstantSubexpr {
+            span: expr.span(),
+            value,
+        });
+        return;
+    }
+    for child in children(expr) {
+        find_constants(child, found);
+    }
+}
+
+/// The value of `expr` if it's an operator over variables alone and has it
+/// for every assignment of them.
+fn constant(expr: ExprRef) -> Option<bool> {
+    fn variables(expr: ExprRef, vars: &mut Vec<usize>) -> bool {
+        match expr.node() {
+            Expr::Bool(_) => false,
+            Expr::Var(v) => {
+                if !vars.contains(&v) {
+                    vars.push(v);
+                }
+                true
+            }
+            Expr::Not(e) => variables(e, vars),
+            Expr::BinOp { lhs, rhs, .. } => variables(lhs, vars) && variables(rhs, vars),
+        }
+    }
+
+    let mut vars = Vec::new();
+    if matches!(expr.node(), Expr::Var(_))
+        || !variables(expr, &mut vars)
+        || vars.len() > CONSTANT_CHECK_INPUTS
+    {
+        return None;
+    }
+
+    let mut inputs = alloc::vec![false; vars.iter().max().unwrap() + 1];
+    let first = interp::eval(expr, &inputs);
+    for row in 1..1usize << vars.len() {
+        for (i, v) in vars.iter().enumerate() {
+            inputs[*v] = row >> i & 1 == 1;
+        }
+        if interp::eval(expr, &inputs) != first {
+            return None;
+        }
+    }
+
+    Some(first)
+}
+
+/// How two equations differ over every assignment of their shared inputs
+/// that both of their assumptions allow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distance {
+    /// The shared inputs, as in `equiv::shared_inputs`, which minterms
+    /// number with the first most significant.
+    pub inputs: Vec<Symbol>,
+    /// The minterms on which the outputs differ, in order.
+    pub differing: Vec<usize>,
+    /// The number of assignments compared, `2^n` without assumptions.
+    pub total: usize,
+}
+
+impl Distance {
+    /// The Hamming distance between the output columns.
+    pub fn count(&self) -> usize {
+        self.differing.len()
+    }
+
+    /// The fraction of assignments on which the outputs agree.
+    pub fn agreement(&self) -> f64 {
+        match self.total {
+            0 => 1.0,
+            total => (total - self.count()) as f64 / total as f64,
+        }
+    }
+
+    /// The correlation of the outputs read as `+1` and `-1`, from `1` for
+    /// equal functions through `0` to `-1` for complements.
+    pub fn correlation(&self) -> f64 {
+        2.0 * self.agreement() - 1.0
+    }
+}
+
+/// Compare `a` and `b` over the union of their inputs, skipping the rows
+/// either's assumptions leave out.
+pub fn distance(a: &Equation, b: &Equation) -> Distance {
+    let (inputs, outputs) = equiv::outputs(a, b);
+    Distance {
+        inputs,
+        differing: outputs
+            .iter()
+            .filter(|(_, a, b)| a != b)
+            .map(|(minterm, _, _)| *minterm)
+            .collect(),
+        total: outputs.len(),
+    }
+}
+
+/// Up to `n` assignments of `equation.inputs` on which it's true (and its
+/// assumptions hold), drawn uniformly and independently, so they may repeat.
+/// Small tables are enumerated, otherwise random assignments are rejected
+/// until they're true, giving fewer than `n` if that takes more than
+/// `ATTEMPTS_PER_SAMPLE` tries per sample. The same seed gives the same
+/// samples.
+pub fn sample_sat(equation: &Equation, n: usize, seed: u64) -> Vec<Vec<bool>> {
+    let mut rng = Rng::new(seed);
+    let mut vm = VM::new(Compiler::new(equation.clone()).compile());
+    let length = equation.inputs.len();
+
+    if length <= ENUMERATE_INPUTS {
+        let table = vm.gen();
+        let true_rows: Vec<_> = table.rows().filter(|row| row.output).collect();
+        if true_rows.is_empty() {
+            return Vec::new();
+        }
+        return (0..n)
+            .map(|_| true_rows[rng.below(true_rows.len())].assignment.to_vec())
+            .collect();
+    }
+
+    let mut samples = Vec::new();
+    for _ in 0..n.saturating_mul(ATTEMPTS_PER_SAMPLE) {
+        if samples.len() == n {
+            break;
+        }
+        let mut bits = 0;
+        let assignment: Vec<bool> = (0..length)
+            .map(|i| {
+                if i % 64 == 0 {
+                    bits = rng.next_u64();
+                }
+                bits >> (i % 64) & 1 == 1
+            })
+            .collect();
+        if vm.assumed(&assignment) && vm.eval(&assignment) {
+            samples.push(assignment);
+        }
+    }
+
+    samples
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let parse = |source| Parser::new(source).parse_equation().unwrap();
+
+    let d = distance(&parse("A AND B = Q"), &parse("A OR B = Q"));
+    assert_eq!(d.inputs, ["A", "B"]);
+    assert_eq!(d.differing, [1, 2]);
+    assert_eq!(d.agreement(), 0.5);
+    assert_eq!(d.correlation(), 0.0);
+
+    let d = distance(&parse("A = Q"), &parse("NOT A = Q"));
+    assert_eq!((d.count(), d.correlation()), (2, -1.0));
+
+    // B only appears on the right, so each of A's rows is counted twice
+    let d = distance(&parse("A = Q"), &parse("A AND B = Q"));
+    assert_eq!((d.differing, d.total), (vec![2], 4));
+    let d = distance(&parse("assume A; A = Q"), &parse("A AND B = Q"));
+    assert_eq!((d.differing, d.total), (vec![2], 2));
+
+    let samples = sample_sat(&parse("assume NOT C; (A XOR B) OR C = Q"), 20, 1);
+    assert_eq!(samples.len(), 20);
+    // C is numbered first, by the assumption
+    assert!(samples.iter().all(|s| !s[0] && s[1] != s[2]));
+    assert_eq!(
+        samples,
+        sample_sat(&parse("assume NOT C; (A XOR B) OR C = Q"), 20, 1)
+    );
+    assert!(sample_sat(&parse("A AND NOT A = Q"), 5, 1).is_empty());
+
+    let constants = |source| {
+        constant_subexpressions(&parse(source).lhs)
+            .iter()
+            .map(|c| (c.span.start..c.span.end, c.value))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(constants("B AND (A OR NOT A) = Q"), [(6..18, true)]);
+    assert_eq!(
+        constants("(A AND NOT A) OR ((B XOR B) XOR C) = Q"),
+        [(0..13, false), (18..27, false)]
+    );
+    // Only the largest is reported, and the whole expression isn't
+    assert_eq!(constants("C OR ((A OR NOT A) OR B) = Q"), [(5..24, true)]);
+    assert!(constants("A OR NOT A = Q").is_empty());
+    assert!(constants("B AND (A AND 0) = Q").is_empty());
+
+    assert_eq!(
+        cost(parse("NOT (A AND B) OR (NOT C) OR A = Q").lhs.expr()),
+        Cost {
+            literals: 4,
+            gates: 3,
+            inverters: 2
+        }
+    );
+
+    // Too many inputs to enumerate, but true for half of the assignments
+    let wide =
+        parse("A XOR B XOR C XOR D XOR E XOR F XOR G XOR H XOR I XOR J XOR K XOR L XOR M = Q");
+    let samples = sample_sat(&wide, 10, 7);
+    assert_eq!(samples.len(), 10);
+    assert!(samples
+        .iter()
+        .all(|s| s.iter().filter(|b| **b).count() % 2 == 1));
+}
@@ -0,0 +1,272 @@
+//! The engine as a JSON-in, JSON-out API, shared by the wasm module and
+//! `booltable serve`. Every function takes the source of an equation and
+//! returns a JSON document, either the result or, as the error,
+//! `{"error": {"message": "...", "span": {"start": 0, "end": 1}}}`, so that
+//! a host only ever has to pass strings across the boundary.
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    compiler::Compiler,
+    equiv,
+    export::JsonTable,
+    intern::Symbol,
+    lexer::Span,
+    minimize,
+    parser::{BinOp, Equation, Expr, ExprRef, Parser},
+    vm::VM,
+};
+
+/// Tables are generated eagerly, so anything wider would hang the caller.
+pub const MAX_INPUTS: usize = 20;
+
+#[derive(Serialize)]
+struct ErrorDocument<'a> {
+    error: Error<'a>,
+}
+
+#[derive(Serialize)]
+struct Error<'a> {
+    message: &'a str,
+    span: Option<Span>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Ast {
+    Bool {
+        value: bool,
+        span: Span,
+    },
+    Var {
+        name: Symbol,
+        span: Span,
+    },
+    Not {
+        operand: Box<Ast>,
+        span: Span,
+    },
+    And {
+        lhs: Box<Ast>,
+        rhs: Box<Ast>,
+        span: Span,
+    },
+    Or {
+        lhs: Box<Ast>,
+        rhs: Box<Ast>,
+        span: Span,
+    },
+    Xor {
+        lhs: Box<Ast>,
+        rhs: Box<Ast>,
+        span: Span,
+    },
+}
+
+#[derive(Serialize)]
+struct Parsed<'a> {
+    inputs: &'a [Symbol],
+    output: Symbol,
+    source: String,
+    ast: Ast,
+}
+
+#[derive(Serialize)]
+struct Minimised {
+    expression: String,
+    output: Symbol,
+}
+
+#[derive(Serialize)]
+struct Equivalence {
+    equivalent: bool,
+    counterexample: Option<Counterexample>,
+}
+
+#[derive(Serialize)]
+struct Counterexample {
+    #[serde(serialize_with = "assignment")]
+    inputs: Vec<(Symbol, bool)>,
+    left: bool,
+    right: bool,
+}
+
+/// The inputs as an object, keeping the order they're given in.
+fn assignment<S: Serializer>(inputs: &[(Symbol, bool)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(inputs.iter().copied())
+}
+
+fn document(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+/// An error document, for callers reporting their own errors in the same
+/// shape.
+pub fn error(message: &str, span: Option<Span>) -> String {
+    document(&ErrorDocument {
+        error: Error {
+            message,
+            span,
+            side: None,
+        },
+    })
+}
+
+fn with_equation(
+    source: &str,
+    f: impl FnOnce(Equation) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut parser = Parser::new(source);
+    match parser.parse_equation() {
+        Ok(equation) if equation.inputs.len() > MAX_INPUTS => Err(error(
+            &format!(
+                "{} inputs is more than the limit of {}",
+                equation.inputs.len(),
+                MAX_INPUTS
+            ),
+            Some(equation.lhs.span()),
+        )),
+        Ok(equation) => f(equation),
+        Err(err) => Err(error(&err.to_string(), Some(err.span()))),
+    }
+}
+
+fn ast(expr: ExprRef, names: &[Symbol]) -> Ast {
+    let span = expr.span();
+    match expr.node() {
+        Expr::Bool(value) => Ast::Bool { value, span },
+        Expr::Var(v) => Ast::Var {
+            name: names[v],
+            span,
+        },
+        Expr::Not(e) => Ast::Not {
+            operand: Box::new(ast(e, names)),
+            span,
+        },
+        Expr::BinOp { op, lhs, rhs } => {
+            let (lhs, rhs) = (Box::new(ast(lhs, names)), Box::new(ast(rhs, names)));
+            match op {
+                BinOp::And => Ast::And { lhs, rhs, span },
+                BinOp::Or => Ast::Or { lhs, rhs, span },
+                BinOp::Xor => Ast::Xor { lhs, rhs, span },
+            }
+        }
+    }
+}
+
+/// The parsed equation: its inputs, output, normalised source and syntax tree.
+pub fn parse(source: &str) -> Result<String, String> {
+    with_equation(source, |equation| {
+        Ok(document(&Parsed {
+            inputs: &equation.inputs,
+            output: equation.output,
+            source: equation.to_source(Default::default()),
+            ast: ast(equation.lhs.expr(), &equation.inputs),
+        }))
+    })
+}
+
+/// The equation's truth table, in the same shape as `--format json`.
+pub fn truth_table_json(source: &str) -> Result<String, String> {
+    with_equation(source, |equation| {
+        let table = VM::new(Compiler::new(equation).compile()).gen();
+        Ok(document(&JsonTable::new(&table)))
+    })
+}
+
+/// The minimised sum of products for the equation.
+pub fn minimize(source: &str) -> Result<String, String> {
+    with_equation(source, |equation| {
+        minimize::check_size(equation.inputs.len())
+            .map_err(|err| error(&err, Some(equation.lhs.span())))?;
+        let table = VM::new(Compiler::new(equation).compile()).gen();
+        let minimised = minimize::minimize(&table);
+        Ok(document(&Minimised {
+            expression: minimised.display(&table.input_names).to_string(),
+            output: table.output_name,
+        }))
+    })
+}
+
+/// Whether two equations agree on every assignment of their shared inputs,
+/// with the simplest assignment on which they differ if they don't, the one
+/// with the fewest true inputs. Spans in
+/// errors refer to whichever equation was invalid, given as `"side"`.
+pub fn equiv(left: &str, right: &str) -> Result<String, String> {
+    let side = |source, name| {
+        let mut parser = Parser::new(source);
+        parser.parse_equation().map_err(|err| {
+            document(&ErrorDocument {
+                error: Error {
+                    message: &err.to_string(),
+                    span: Some(err.span()),
+                    side: Some(name),
+                },
+            })
+        })
+    };
+    let (left, right) = (side(left, "left")?, side(right, "right")?);
+
+    let inputs = equiv::shared_inputs(&left, &right).len();
+    if inputs > MAX_INPUTS {
+        return Err(error(
+            &format!("{} inputs is more than the limit of {}", inputs, MAX_INPUTS),
+            None,
+        ));
+    }
+
+    let counterexample = equiv::check(&left, &right)
+        .err()
+        .map(|counterexample| Counterexample {
+            inputs: counterexample.assignment,
+            left: counterexample.left,
+            right: counterexample.right,
+        });
+    Ok(document(&Equivalence {
+        equivalent: counterexample.is_none(),
+        counterexample,
+    }))
+}
+
+#[test]
+fn test() {
+    use crate::export;
+
+    assert_eq!(
+        minimize("A.B + A.!B = Q"),
+        Ok(r#"{"expression":"A","output":"Q"}"#.to_string())
+    );
+    assert!(
+        minimize("A ^ B ^ C ^ D ^ E ^ F ^ G ^ H ^ I ^ J ^ K ^ L ^ M ^ N ^ O ^ P ^ R = Q")
+            .unwrap_err()
+            .contains("17 inputs is too large to minimise")
+    );
+    assert_eq!(
+        parse("A + = Q"),
+        Err(r#"{"error":{"message":"Expected boolean expression, got =","span":{"start":4,"end":5}}}"#.to_string())
+    );
+    assert_eq!(
+        parse("!A ^ 1 = Q"),
+        Ok(r#"{"inputs":["A"],"output":"Q","source":"NOT (A XOR 1) = Q","ast":{"kind":"not","operand":{"kind":"xor","lhs":{"kind":"var","name":"A","span":{"start":1,"end":2}},"rhs":{"kind":"bool","value":true,"span":{"start":5,"end":6}},"span":{"start":1,"end":6}},"span":{"start":0,"end":6}}}"#.to_string())
+    );
+    assert_eq!(
+        truth_table_json("A = Q").map(|table| table + "\n"),
+        Ok(export::json(
+            &VM::new(Compiler::new(Parser::new("A = Q").parse_equation().unwrap()).compile()).gen()
+        ))
+    );
+    assert_eq!(
+        equiv("A ^ B = Q", "(A + B) . !(A . B) = Q"),
+        Ok(r#"{"equivalent":true,"counterexample":null}"#.to_string())
+    );
+    assert_eq!(
+        equiv("A = Q", "B + = Q"),
+        Err(r#"{"error":{"message":"Expected boolean expression, got =","span":{"start":4,"end":5},"side":"right"}}"#.to_string())
+    );
+    assert_eq!(
+        equiv("A = Q", "B = Q"),
+        Ok(r#"{"equivalent":false,"counterexample":{"inputs":{"A":false,"B":true},"left":false,"right":true}}"#.to_string())
+    );
+}
@@ -0,0 +1,129 @@
+//! Assignments of `n` inputs and the rows of a table they belong to. As
+//! everywhere in the crate, input 0 is the most significant bit of the row
+//! number, so row 0 has every input false and the last row every input true.
+
+use alloc::vec::Vec;
+use core::iter::{FusedIterator, Rev};
+
+/// The values of `n` inputs in row `row`, the first most significant.
+pub fn to_bools(row: usize, n: usize) -> Vec<bool> {
+    (0..n).map(|i| row >> (n - 1 - i) & 1 == 1).collect()
+}
+
+/// The row that `assignment` is in, the inverse of `to_bools`.
+pub fn to_row(assignment: &[bool]) -> usize {
+    assignment.iter().fold(0, |n, b| n << 1 | *b as usize)
+}
+
+/// The `i`th number in Gray code order, in which each differs from the last
+/// in a single bit.
+pub fn gray_code(i: usize) -> usize {
+    i ^ (i >> 1)
+}
+
+/// The position of `code` in Gray code order, the inverse of `gray_code`.
+pub fn from_gray_code(code: usize) -> usize {
+    let mut i = code;
+    let mut shift = 1;
+    while shift < usize::BITS {
+        i ^= i >> shift;
+        shift <<= 1;
+    }
+
+    i
+}
+
+/// Every assignment of some number of inputs, from `assignments`, `gray` or
+/// `reversed`.
+#[derive(Debug, Clone)]
+pub struct Assignments {
+    n: usize,
+    gray: bool,
+    /// The range of positions in the order that are left.
+    front: usize,
+    back: usize,
+}
+
+impl Assignments {
+    fn at(&self, position: usize) -> Vec<bool> {
+        let row = if self.gray {
+            gray_code(position)
+        } else {
+            position
+        };
+        to_bools(row, self.n)
+    }
+}
+
+impl Iterator for Assignments {
+    type Item = Vec<bool>;
+
+    fn next(&mut self) -> Option<Vec<bool>> {
+        (self.front < self.back).then(|| {
+            self.front += 1;
+            self.at(self.front - 1)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Assignments {
+    fn next_back(&mut self) -> Option<Vec<bool>> {
+        (self.front < self.back).then(|| {
+            self.back -= 1;
+            self.at(self.back)
+        })
+    }
+}
+
+impl ExactSizeIterator for Assignments {}
+
+impl FusedIterator for Assignments {}
+
+/// Every assignment of `n` inputs in table order, from all false to all
+/// true.
+pub fn assignments(n: usize) -> Assignments {
+    Assignments {
+        n,
+        gray: false,
+        front: 0,
+        back: 1 << n,
+    }
+}
+
+/// Every assignment of `n` inputs in Gray code order, each differing from
+/// the last in a single input, starting from all false.
+pub fn gray(n: usize) -> Assignments {
+    Assignments {
+        gray: true,
+        ..assignments(n)
+    }
+}
+
+/// Every assignment of `n` inputs in reverse table order, from all true to
+/// all false.
+pub fn reversed(n: usize) -> Rev<Assignments> {
+    assignments(n).rev()
+}
+
+#[test]
+fn test() {
+    assert_eq!(to_bools(0b101, 3), [true, false, true]);
+    assert_eq!(to_row(&[true, false, true]), 0b101);
+    assert!((0..64).all(|row| to_row(&to_bools(row, 6)) == row));
+    assert!((0..1 << 10).all(|i| from_gray_code(gray_code(i)) == i));
+
+    let rows = |iter: &mut dyn Iterator<Item = Vec<bool>>| -> Vec<usize> {
+        iter.map(|assignment| to_row(&assignment)).collect()
+    };
+    assert_eq!(rows(&mut assignments(2)), [0, 1, 2, 3]);
+    assert_eq!(rows(&mut reversed(2)), [3, 2, 1, 0]);
+    assert_eq!(rows(&mut gray(3)), [0, 1, 3, 2, 6, 7, 5, 4]);
+    assert_eq!(rows(&mut gray(2).rev()), [2, 3, 1, 0]);
+    assert_eq!(assignments(0).collect::<Vec<_>>(), [Vec::<bool>::new()]);
+    assert_eq!(gray(4).len(), 16);
+}
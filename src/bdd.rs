@@ -0,0 +1,423 @@
+//! Reduced ordered binary decision diagrams, which test the inputs one at a
+//! time in a fixed order, sharing identical subgraphs and skipping tests that
+//! make no difference. The same function can need very different numbers of
+//! nodes in different orders, so there are heuristics for choosing one from
+//! an expression and sifting to improve on one.
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use crate::{
+    classes,
+    intern::Symbol,
+    parser::{Equation, Expr, ExprRef},
+    vm::TruthTable,
+};
+
+/// A node of the diagram, where `0` and `1` are the false and true
+/// terminals and decision nodes are numbered from `2`.
+pub type NodeId = usize;
+
+pub const FALSE: NodeId = 0;
+pub const TRUE: NodeId = 1;
+
+/// A test of one input, going to `low` when it's false and `high` when it's
+/// true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Node {
+    pub input: usize,
+    pub low: NodeId,
+    pub high: NodeId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bdd {
+    pub input_names: Vec<Symbol>,
+    pub output_name: Symbol,
+    /// The inputs in the order they're tested, from the root.
+    pub order: Vec<usize>,
+    /// The decision nodes, the `i`th being node `i + 2`. Children always come
+    /// before their parents.
+    pub nodes: Vec<Node>,
+    pub root: NodeId,
+}
+
+impl Bdd {
+    /// The diagram for the table's function, testing the inputs in `order`.
+    /// Rows left out by assumptions are taken as false.
+    pub fn new(table: &TruthTable, order: &[usize]) -> Self {
+        let mut builder = Builder {
+            num_inputs: table.input_names.len(),
+            order,
+            outputs: classes::column(table),
+            nodes: vec![],
+            unique: BTreeMap::new(),
+        };
+        let root = builder.build(0, 0);
+
+        Self {
+            input_names: table.input_names.clone(),
+            output_name: table.output_name,
+            order: order.to_vec(),
+            nodes: builder.nodes,
+            root,
+        }
+    }
+
+    /// The number of decision nodes, leaving out the terminals.
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node(&self, id: NodeId) -> Node {
+        self.nodes[id - 2]
+    }
+
+    /// The output for an assignment of every input, following the diagram
+    /// from the root.
+    pub fn eval(&self, assignment: &[bool]) -> bool {
+        let mut id = self.root;
+        while id > TRUE {
+            let node = self.node(id);
+            id = match assignment[node.input] {
+                true => node.high,
+                false => node.low,
+            };
+        }
+
+        id == TRUE
+    }
+
+    /// The diagram with complement edges, where there is only the true
+    /// terminal and an edge can negate what it leads to, so a function and
+    /// its complement share their nodes. High edges are never complemented,
+    /// which keeps it canonical. The nodes are numbered from `2` as before,
+    /// and returned with the edge to the root.
+    pub fn complement_edges(&self) -> (Vec<ComplementNode>, Edge) {
+        let terminal = |id| Edge {
+            to: TRUE,
+            complemented: id == FALSE,
+        };
+        let mut edges: Vec<Edge> = vec![];
+        let mut nodes = vec![];
+        let mut unique = BTreeMap::new();
+        let edge = |edges: &[Edge], id: NodeId| match id {
+            FALSE | TRUE => terminal(id),
+            _ => edges[id - 2],
+        };
+        for node in &self.nodes {
+            let (mut low, mut high) = (edge(&edges, node.low), edge(&edges, node.high));
+            let complemented = high.complemented;
+            if complemented {
+                low.complemented = !low.complemented;
+                high.complemented = false;
+            }
+
+            let node = ComplementNode {
+                input: node.input,
+                low,
+                high,
+            };
+            let to = *unique.entry(node).or_insert_with(|| {
+                nodes.push(node);
+                nodes.len() + 1
+            });
+            edges.push(Edge { to, complemented });
+        }
+
+        (nodes, edge(&edges, self.root))
+    }
+
+    /// The diagram with complement edges in Graphviz's DOT language, labelled
+    /// with its size with and without them. Low edges are dashed and
+    /// complement edges end in a circle.
+    pub fn to_dot(&self) -> String {
+        let (nodes, root) = self.complement_edges();
+        let arrow = |edge: Edge| match edge.complemented {
+            true => ", arrowhead=odot",
+            false => "",
+        };
+
+        let mut out = String::new();
+        writeln!(out, "digraph bdd {{").unwrap();
+        writeln!(
+            out,
+            "  label=\"{}: {} nodes, {} with complement edges\";",
+            self.output_name,
+            self.size(),
+            nodes.len()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "  output [shape=plaintext, label=\"{}\"];",
+            self.output_name
+        )
+        .unwrap();
+        writeln!(out, "  n1 [shape=box, label=\"1\"];").unwrap();
+        for (i, node) in nodes.iter().enumerate() {
+            let name = self.input_names[node.input];
+            writeln!(out, "  n{} [shape=circle, label=\"{}\"];", i + 2, name).unwrap();
+        }
+        writeln!(
+            out,
+            "  output -> n{}{};",
+            root.to,
+            match root.complemented {
+                true => " [arrowhead=odot]",
+                false => "",
+            }
+        )
+        .unwrap();
+        for (i, node) in nodes.iter().enumerate() {
+            writeln!(
+                out,
+                "  n{} -> n{} [style=dashed{}];",
+                i + 2,
+                node.low.to,
+                arrow(node.low)
+            )
+            .unwrap();
+            writeln!(out, "  n{} -> n{};", i + 2, node.high.to).unwrap();
+        }
+        out.push_str("}\n");
+
+        out
+    }
+}
+
+/// An edge of a diagram with complement edges, which negates the function
+/// of the node it leads to when `complemented`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub to: NodeId,
+    pub complemented: bool,
+}
+
+/// A test of one input in a diagram with complement edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ComplementNode {
+    pub input: usize,
+    pub low: Edge,
+    pub high: Edge,
+}
+
+struct Builder<'a> {
+    num_inputs: usize,
+    order: &'a [usize],
+    outputs: Vec<bool>,
+    nodes: Vec<Node>,
+    unique: BTreeMap<Node, NodeId>,
+}
+
+impl Builder<'_> {
+    /// The node for the rows agreeing with `row` on the inputs before
+    /// `level` in the order.
+    fn build(&mut self, level: usize, row: usize) -> NodeId {
+        if level == self.order.len() {
+            return self.outputs[row] as NodeId;
+        }
+
+        let input = self.order[level];
+        let bit = 1 << (self.num_inputs - 1 - input);
+        let low = self.build(level + 1, row);
+        let high = self.build(level + 1, row | bit);
+        if low == high {
+            return low;
+        }
+
+        let node = Node { input, low, high };
+        if let Some(&id) = self.unique.get(&node) {
+            return id;
+        }
+        self.nodes.push(node);
+        let id = self.nodes.len() + 1;
+        self.unique.insert(node, id);
+        id
+    }
+}
+
+/// The inputs in the order they first appear in the equation's expression,
+/// then any that only appear in its assumptions, since inputs used together
+/// are usually best kept close.
+pub fn appearance_order(equation: &Equation) -> Vec<usize> {
+    fn visit(expr: ExprRef, order: &mut Vec<usize>) {
+        match expr.node() {
+            Expr::Bool(_) => {}
+            Expr::Var(v) => {
+                if !order.contains(&v) {
+                    order.push(v);
+                }
+            }
+            Expr::Not(e) => visit(e, order),
+            Expr::BinOp { lhs, rhs, .. } => {
+                visit(lhs, order);
+                visit(rhs, order);
+            }
+        }
+    }
+
+    let mut order = vec![];
+    visit(equation.lhs.expr(), &mut order);
+    let unused: Vec<usize> = (0..equation.inputs.len())
+        .filter(|i| !order.contains(i))
+        .collect();
+    order.extend(unused);
+    order
+}
+
+/// At most this many rounds of `force_order` are run.
+const FORCE_ROUNDS: usize = 16;
+
+/// The FORCE heuristic, which starts from `appearance_order` and treats
+/// every operator as pulling the inputs under it together. Each round moves
+/// every input to the average of the centres of the operators it's under,
+/// and sorts by that, until the operators' total span stops shrinking.
+pub fn force_order(equation: &Equation) -> Vec<usize> {
+    fn supports(expr: ExprRef, edges: &mut Vec<Vec<usize>>) -> Vec<usize> {
+        match expr.node() {
+            Expr::Bool(_) => vec![],
+            Expr::Var(v) => vec![v],
+            Expr::Not(e) => supports(e, edges),
+            Expr::BinOp { lhs, rhs, .. } => {
+                let mut support = supports(lhs, edges);
+                for v in supports(rhs, edges) {
+                    if !support.contains(&v) {
+                        support.push(v);
+                    }
+                }
+                if support.len() > 1 {
+                    edges.push(support.clone());
+                }
+                support
+            }
+        }
+    }
+
+    let mut edges = vec![];
+    supports(equation.lhs.expr(), &mut edges);
+    let span = |position: &[f64]| -> f64 {
+        edges
+            .iter()
+            .map(|edge| {
+                let positions = edge.iter().map(|v| position[*v]);
+                positions.clone().fold(f64::MIN, f64::max) - positions.fold(f64::MAX, f64::min)
+            })
+            .sum()
+    };
+    let positions = |order: &[usize]| {
+        let mut position = vec![0.0; order.len()];
+        for (level, input) in order.iter().enumerate() {
+            position[*input] = level as f64;
+        }
+        position
+    };
+
+    let mut order = appearance_order(equation);
+    let mut best = span(&positions(&order));
+    for _ in 0..FORCE_ROUNDS {
+        let position = positions(&order);
+        let mut pull = vec![(0.0, 0); order.len()];
+        for edge in &edges {
+            let centre = edge.iter().map(|v| position[*v]).sum::<f64>() / edge.len() as f64;
+            for v in edge {
+                pull[*v].0 += centre;
+                pull[*v].1 += 1;
+            }
+        }
+
+        // Inputs under no operator stay where they are
+        let target = |v: usize| match pull[v] {
+            (_, 0) => position[v],
+            (total, count) => total / count as f64,
+        };
+        let mut next = order.clone();
+        next.sort_by(|a, b| target(*a).total_cmp(&target(*b)));
+        let next_span = span(&positions(&next));
+        if next_span >= best {
+            break;
+        }
+        (order, best) = (next, next_span);
+    }
+
+    order
+}
+
+/// Improve on `order` by sifting: each input in turn is tried at every
+/// level, keeping the others in order, and left wherever the diagram was
+/// smallest. Every position is measured by building the diagram again.
+pub fn sift(table: &TruthTable, order: &[usize]) -> Vec<usize> {
+    let size = |order: &[usize]| Bdd::new(table, order).size();
+    let mut order = order.to_vec();
+    let mut best = size(&order);
+
+    for input in order.clone() {
+        let mut others = order.clone();
+        others.retain(|i| *i != input);
+        for level in 0..=others.len() {
+            let mut candidate = others.clone();
+            candidate.insert(level, input);
+            let candidate_size = size(&candidate);
+            if candidate_size < best {
+                (order, best) = (candidate, candidate_size);
+            }
+        }
+    }
+
+    order
+}
+
+#[test]
+fn test() {
+    use crate::{assignments, compiler::Compiler, interp, parser::Parser, vm::VM};
+
+    let parse = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+        (equation, table)
+    };
+
+    // Pairs far apart need a node for every way the first half can go
+    let (equation, table) = parse("(A AND D) OR (B AND E) OR (C AND F) = Q");
+    let alphabetical = [0, 2, 4, 1, 3, 5];
+    let far = Bdd::new(&table, &alphabetical);
+    assert_eq!(far.size(), 14);
+    assert_eq!(appearance_order(&equation), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(Bdd::new(&table, &appearance_order(&equation)).size(), 6);
+    assert_eq!(Bdd::new(&table, &sift(&table, &alphabetical)).size(), 6);
+    for assignment in assignments::assignments(6) {
+        assert_eq!(
+            far.eval(&assignment),
+            interp::eval(equation.lhs.expr(), &assignment)
+        );
+    }
+
+    let (equation, table) = parse("(A AND E) OR (B AND C) OR (D AND A) OR (E AND D) = Q");
+    assert_eq!(Bdd::new(&table, &appearance_order(&equation)).size(), 8);
+    assert_eq!(force_order(&equation), [0, 1, 4, 2, 3]);
+    assert_eq!(Bdd::new(&table, &force_order(&equation)).size(), 6);
+
+    // Inputs only in assumptions come last
+    let (equation, _) = parse("assume A OR D; (A AND C) OR B = Q");
+    assert_eq!(appearance_order(&equation), [0, 2, 3, 1]);
+    let (_, table) = parse("A AND NOT A = Q");
+    assert_eq!(Bdd::new(&table, &[0]).root, FALSE);
+
+    // Parity needs a node and its complement for every input but the first
+    let (_, table) = parse("A XOR B XOR C = Q");
+    let parity = Bdd::new(&table, &[0, 1, 2]);
+    assert_eq!(parity.size(), 5);
+    let (nodes, root) = parity.complement_edges();
+    assert_eq!(nodes.len(), 3);
+    assert!(nodes.iter().all(|node| !node.high.complemented));
+    assert_eq!(
+        root,
+        Edge {
+            to: 4,
+            complemented: false
+        }
+    );
+    let dot = parity.to_dot();
+    assert!(dot.starts_with("digraph bdd {\n  label=\"Q: 5 nodes, 3 with complement edges\";\n"));
+    assert!(dot.contains("  n2 -> n1 [style=dashed, arrowhead=odot];\n  n2 -> n1;\n"));
+}
@@ -0,0 +1,301 @@
+use alloc::vec::Vec;
+use core::str;
+
+use derive_more::Display;
+
+use crate::{
+    compiler::{self, Equation, VerifyError},
+    intern::Symbol,
+    vm::{self, Op},
+};
+
+/// Identifies a serialised equation, followed by the format version.
+pub const MAGIC: &[u8; 4] = b"BTBC";
+/// Version 2 added assumptions, and version 1 files are still read.
+pub const VERSION: u8 = 2;
+
+const PUSH_FALSE: u8 = 0;
+const PUSH_TRUE: u8 = 1;
+const LOAD: u8 = 2;
+const NOT: u8 = 3;
+const AND: u8 = 4;
+const OR: u8 = 5;
+const XOR: u8 = 6;
+const JUMP_IF_FALSE: u8 = 7;
+const JUMP_IF_TRUE: u8 = 8;
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    #[display(fmt = "not a booltable bytecode file")]
+    BadMagic,
+    #[display(fmt = "unsupported bytecode version {}", _0)]
+    UnsupportedVersion(u8),
+    #[display(fmt = "unexpected end of data")]
+    Truncated,
+    #[display(fmt = "{} trailing bytes after the equation", _0)]
+    TrailingBytes(usize),
+    #[display(fmt = "a varint is overlong or doesn't fit in a usize")]
+    BadVarint,
+    #[display(fmt = "{} inputs is more than the limit of {}", _0, vm::MAX_INPUTS)]
+    TooManyInputs(usize),
+    #[display(fmt = "a name isn't valid UTF-8")]
+    InvalidName,
+    #[display(fmt = "unknown opcode {} at op {}", opcode, index)]
+    UnknownOpcode { opcode: u8, index: usize },
+    #[display(fmt = "{}", _0)]
+    Invalid(VerifyError),
+}
+
+/// Serialise a compiled equation. Integers are LEB128 varints and names are
+/// length-prefixed UTF-8.
+pub fn encode(equation: &Equation) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+
+    write_varint(&mut out, equation.inputs.len());
+    for name in &equation.inputs {
+        write_str(&mut out, name);
+    }
+    write_str(&mut out, &equation.output);
+
+    write_varint(&mut out, equation.assumptions.len());
+    for ops in &equation.assumptions {
+        write_ops(&mut out, ops);
+    }
+    write_ops(&mut out, &equation.lhs);
+
+    out
+}
+
+fn write_ops(out: &mut Vec<u8>, ops: &[Op]) {
+    write_varint(out, ops.len());
+    for op in ops {
+        match op {
+            Op::Push(false) => out.push(PUSH_FALSE),
+            Op::Push(true) => out.push(PUSH_TRUE),
+            Op::Load(i) => {
+                out.push(LOAD);
+                write_varint(out, *i);
+            }
+            Op::Not => out.push(NOT),
+            Op::And => out.push(AND),
+            Op::Or => out.push(OR),
+            Op::Xor => out.push(XOR),
+            Op::JumpIfFalse(target) => {
+                out.push(JUMP_IF_FALSE);
+                write_varint(out, *target);
+            }
+            Op::JumpIfTrue(target) => {
+                out.push(JUMP_IF_TRUE);
+                write_varint(out, *target);
+            }
+        }
+    }
+}
+
+/// Deserialise an equation written by `encode`, interning its names. The
+/// ops are verified, so the bytes needn't be trusted.
+pub fn decode(bytes: &[u8]) -> Result<Equation, LoadError> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(LoadError::BadMagic);
+    }
+    let version = match reader.byte()? {
+        version @ (1 | VERSION) => version,
+        version => return Err(LoadError::UnsupportedVersion(version)),
+    };
+
+    let num_inputs = reader.varint()?;
+    if num_inputs > vm::MAX_INPUTS {
+        return Err(LoadError::TooManyInputs(num_inputs));
+    }
+    let inputs = (0..num_inputs)
+        .map(|_| reader.str().map(Symbol::intern))
+        .collect::<Result<Vec<_>, _>>()?;
+    let output = Symbol::intern(reader.str()?);
+
+    let num_assumptions = if version == 1 { 0 } else { reader.varint()? };
+    let assumptions = (0..num_assumptions)
+        .map(|_| reader.ops(inputs.len()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let lhs = reader.ops(inputs.len())?;
+
+    let trailing = bytes.len() - reader.position();
+    if trailing != 0 {
+        return Err(LoadError::TrailingBytes(trailing));
+    }
+
+    Ok(Equation {
+        inputs,
+        lhs,
+        output,
+        assumptions,
+    })
+}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], LoadError> {
+        let end = self.pos.checked_add(n).ok_or(LoadError::Truncated)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(LoadError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// A varint as written by `write_varint`, which never ends in a zero
+    /// byte after the first or has bits beyond a `usize`.
+    pub(crate) fn varint(&mut self) -> Result<usize, LoadError> {
+        let mut n = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as usize;
+            if (bits << shift) >> shift != bits || (byte == 0 && shift > 0) {
+                return Err(LoadError::BadVarint);
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+
+        Err(LoadError::BadVarint)
+    }
+
+    /// A length-prefixed list of ops, checked with `compiler::verify` so
+    /// that they can be run safely.
+    fn ops(&mut self, num_inputs: usize) -> Result<Vec<Op>, LoadError> {
+        let num_ops = self.varint()?;
+        let mut ops = Vec::with_capacity(num_ops.min(self.bytes.len()));
+        for index in 0..num_ops {
+            ops.push(match self.byte()? {
+                PUSH_FALSE => Op::Push(false),
+                PUSH_TRUE => Op::Push(true),
+                LOAD => Op::Load(self.varint()?),
+                NOT => Op::Not,
+                AND => Op::And,
+                OR => Op::Or,
+                XOR => Op::Xor,
+                JUMP_IF_FALSE => Op::JumpIfFalse(self.varint()?),
+                JUMP_IF_TRUE => Op::JumpIfTrue(self.varint()?),
+                opcode => return Err(LoadError::UnknownOpcode { opcode, index }),
+            });
+        }
+        compiler::verify(&ops, num_inputs).map_err(LoadError::Invalid)?;
+
+        Ok(ops)
+    }
+
+    pub(crate) fn str(&mut self) -> Result<&'a str, LoadError> {
+        let len = self.varint()?;
+        str::from_utf8(self.take(len)?).map_err(|_| LoadError::InvalidName)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser};
+
+    let mut parser = Parser::new("A AND NOT (B XOR 1) = Q");
+    let equation = Compiler::new(parser.parse_equation().unwrap()).compile();
+    let bytes = encode(&equation);
+    let decoded = decode(&bytes).unwrap();
+    assert_eq!(decoded.disassemble(), equation.disassemble());
+    assert_eq!(decoded.output, "Q");
+
+    let mut parser = Parser::new("assume A != B; A AND C = Q");
+    let constrained = Compiler::new(parser.parse_equation().unwrap()).compile();
+    let encoded = encode(&constrained);
+    assert_eq!(
+        decode(&encoded).unwrap().disassemble(),
+        constrained.disassemble()
+    );
+
+    // Version 1 had no assumption count after the names, and is otherwise
+    // the same
+    let mut old = bytes.clone();
+    old[MAGIC.len()] = 1;
+    old.remove(MAGIC.len() + 8);
+    assert_eq!(decode(&old).unwrap().disassemble(), equation.disassemble());
+
+    assert_eq!(
+        decode(&bytes[..bytes.len() - 1]).err(),
+        Some(LoadError::Truncated)
+    );
+    let header = |rest: &[u8]| [&MAGIC[..], &[VERSION], rest].concat();
+    assert_eq!(
+        decode(&header(&[64])).err(),
+        Some(LoadError::TooManyInputs(64))
+    );
+    assert_eq!(
+        decode(&header(&[0x80, 0])).err(),
+        Some(LoadError::BadVarint)
+    );
+    assert_eq!(
+        decode(&header(&[0xff; 11])).err(),
+        Some(LoadError::BadVarint)
+    );
+    let mut max = vec![];
+    write_varint(&mut max, usize::MAX);
+    assert_eq!(Reader::new(&max).varint(), Ok(usize::MAX));
+    *max.last_mut().unwrap() += 1;
+    assert_eq!(Reader::new(&max).varint(), Err(LoadError::BadVarint));
+    let mut unbalanced = bytes.clone();
+    *unbalanced.last_mut().unwrap() = PUSH_TRUE;
+    assert_eq!(
+        decode(&unbalanced).err(),
+        Some(LoadError::Invalid(VerifyError::UnbalancedStack(3)))
+    );
+
+    let mut parser = Parser::new("(A OR B) AND C = Q");
+    let short = Compiler::new(parser.parse_equation().unwrap()).compile_short_circuit();
+    let mut bytes = encode(&short);
+    assert_eq!(decode(&bytes).unwrap().disassemble(), short.disassemble());
+    // `LOAD C, JUMP IF FALSE 7, ...`, where jumping into the `OR` instead
+    // would leave two more values on the stack than it expects
+    let jump = bytes
+        .windows(2)
+        .position(|w| w == [JUMP_IF_FALSE, 7])
+        .unwrap();
+    bytes[jump + 1] = 5;
+    assert_eq!(
+        decode(&bytes).err(),
+        Some(LoadError::Invalid(VerifyError::BadJump {
+            index: 1,
+            target: 5
+        }))
+    );
+}
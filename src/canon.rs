@@ -0,0 +1,127 @@
+//! A canonical form for expressions, so that ones differing only in the
+//! order or grouping of operands, or in how negations are written, have the
+//! same structure.
+
+use alloc::{string::ToString, vec, vec::Vec};
+
+use crate::{
+    lexer::Span,
+    parser::{Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr},
+};
+
+impl ExprRef<'_> {
+    /// The expression with double negations removed, negated constants
+    /// folded, negations in `XOR` operands moved above it, and chains of
+    /// the same operator flattened, sorted and rebuilt nesting to the right.
+    /// Spans are kept where nodes survive, so compare the results with
+    /// `simplify::same` rather than `==`.
+    pub fn canonicalise(self) -> SpanExpr {
+        SpanExpr::build(|arena| canonicalise(self, arena))
+    }
+}
+
+fn canonicalise(expr: ExprRef, arena: &mut Arena) -> ExprId {
+    let span = expr.span();
+
+    match expr.node() {
+        Expr::Bool(_) | Expr::Var(_) => arena.copy(expr),
+        Expr::Not(e) => {
+            let e = canonicalise(e, arena);
+            negate(arena, e, span)
+        }
+        Expr::BinOp { op, .. } => {
+            let mut operands = vec![];
+            flatten(expr, op, &mut operands);
+            let mut operands: Vec<ExprId> = operands
+                .into_iter()
+                .map(|operand| canonicalise(operand, arena))
+                .collect();
+
+            // `NOT A XOR B` is `NOT (A XOR B)`, so an odd number of negated
+            // operands leaves one negation over the whole chain
+            let mut negated = false;
+            if op == BinOp::Xor {
+                for operand in &mut operands {
+                    if let Node::Not(inner) = arena[*operand] {
+                        *operand = inner;
+                        negated = !negated;
+                    }
+                }
+            }
+
+            // Canonicalising an operand can expose another chain of `op`
+            let mut flat = vec![];
+            for operand in operands {
+                flatten(arena.get(operand), op, &mut flat);
+            }
+            let mut flat: Vec<ExprId> = flat.into_iter().map(|operand| operand.id()).collect();
+            flat.sort_by_cached_key(|&operand| arena.get(operand).to_string());
+
+            let chain = flat
+                .into_iter()
+                .rev()
+                .reduce(|rhs, lhs| arena.push(span, Node::BinOp { op, lhs, rhs }))
+                .unwrap();
+            if negated {
+                negate(arena, chain, span)
+            } else {
+                chain
+            }
+        }
+    }
+}
+
+/// Collect the operands of a chain of `op`, however it is grouped.
+fn flatten<'a>(expr: ExprRef<'a>, op: BinOp, out: &mut Vec<ExprRef<'a>>) {
+    match expr.node() {
+        Expr::BinOp { op: o, lhs, rhs } if o == op => {
+            flatten(lhs, op, out);
+            flatten(rhs, op, out);
+        }
+        _ => out.push(expr),
+    }
+}
+
+/// `NOT id` for an already canonical `id`.
+fn negate(arena: &mut Arena, id: ExprId, span: Span) -> ExprId {
+    match arena[id] {
+        Node::Bool(b) => arena.push(span, Node::Bool(!b)),
+        Node::Not(inner) => inner,
+        _ => arena.push(span, Node::Not(id)),
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{parser::Parser, simplify::same};
+
+    let canonical = |source| {
+        Parser::new(source)
+            .parse_equation()
+            .unwrap()
+            .lhs
+            .expr()
+            .canonicalise()
+    };
+
+    for (a, b) in [
+        ("A AND B = Q", "B AND A = Q"),
+        ("(A OR B) OR C = Q", "C OR (B OR A) = Q"),
+        ("NOT NOT (A XOR NOT B) = Q", "NOT (B XOR A) = Q"),
+        (
+            "(NOT 1) OR (A AND (B AND C)) = Q",
+            "0 OR ((C AND A) AND B) = Q",
+        ),
+    ] {
+        assert!(
+            same(canonical(a).expr(), canonical(b).expr()),
+            "{} and {}",
+            a,
+            b
+        );
+    }
+    assert!(!same(
+        canonical("A AND B = Q").expr(),
+        canonical("A OR B = Q").expr()
+    ));
+}
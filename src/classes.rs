@@ -0,0 +1,363 @@
+//! Classes of boolean functions, which say something about a function
+//! whatever expression it's written as. Each check works on a table's output
+//! column, with rows left out by assumptions taken as false.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{assignments, vm::TruthTable};
+
+/// Threshold functions are only looked for with up to this many inputs,
+/// since the search for weights grows quickly.
+pub const THRESHOLD_MAX_INPUTS: usize = 6;
+
+/// The largest weight any threshold function of `n` inputs needs, by `n`,
+/// which bounds the search.
+const MAX_WEIGHTS: [usize; THRESHOLD_MAX_INPUTS + 1] = [0, 1, 1, 2, 3, 5, 9];
+
+/// The table's output for every row, in order.
+pub(crate) fn column(table: &TruthTable) -> Vec<bool> {
+    let mut outputs = vec![false; 1 << table.input_names.len()];
+    for row in table.rows() {
+        outputs[assignments::to_row(row.assignment)] = row.output;
+    }
+
+    outputs
+}
+
+/// The inputs, by number, that the function isn't positive in: those for
+/// which some row is true with the input false and false with it true.
+pub fn not_positive_in(table: &TruthTable) -> Vec<usize> {
+    decreasing(&column(table), table.input_names.len(), true)
+}
+
+/// The inputs of `outputs` that turning on turns the output off in some row
+/// when `positive`, or on when not.
+fn decreasing(outputs: &[bool], num_inputs: usize, positive: bool) -> Vec<usize> {
+    (0..num_inputs)
+        .filter(|i| {
+            let bit = 1 << (num_inputs - 1 - i);
+            (0..outputs.len()).any(|row| {
+                row & bit == 0 && outputs[row] == positive && outputs[row | bit] != positive
+            })
+        })
+        .collect()
+}
+
+/// Whether the function is monotone, positive in every input, so that
+/// turning an input on never turns the output off.
+pub fn is_monotone(table: &TruthTable) -> bool {
+    not_positive_in(table).is_empty()
+}
+
+/// The minimal true points of a monotone function, the rows where it's true
+/// but false with any one of the true inputs turned off, in order. The
+/// function is true exactly on the rows with all of the true inputs of one
+/// of them, so they describe it completely. `None` if it isn't monotone.
+pub fn minimal_true_points(table: &TruthTable) -> Option<Vec<usize>> {
+    if !is_monotone(table) {
+        return None;
+    }
+
+    let num_inputs = table.input_names.len();
+    let outputs = column(table);
+    let minimal = (0..outputs.len())
+        .filter(|&row| {
+            outputs[row]
+                && (0..num_inputs)
+                    .map(|i| 1 << i)
+                    .all(|bit| row & bit == 0 || !outputs[row & !bit])
+        })
+        .collect();
+
+    Some(minimal)
+}
+
+/// Whether the function is self-dual, so that negating every input negates
+/// the output, like majority of three. Its dual is then itself.
+pub fn is_self_dual(table: &TruthTable) -> bool {
+    let outputs = column(table);
+    let all = outputs.len() - 1;
+    (0..outputs.len()).all(|row| outputs[row] != outputs[row ^ all])
+}
+
+/// Whether negating every input keeps the output the same, like `A XOR B`.
+/// Its dual is then its complement.
+pub fn is_self_anti_dual(table: &TruthTable) -> bool {
+    let outputs = column(table);
+    let all = outputs.len() - 1;
+    (0..outputs.len()).all(|row| outputs[row] == outputs[row ^ all])
+}
+
+/// Whether the function is affine, the XOR of some of its inputs and maybe
+/// `1`, found from its algebraic normal form: the XOR of products of inputs,
+/// each of which is true in only one row of the form's column.
+pub fn is_affine(table: &TruthTable) -> bool {
+    let mut anf = column(table);
+    let mut bit = 1;
+    while bit < anf.len() {
+        for row in 0..anf.len() {
+            if row & bit != 0 {
+                anf[row] ^= anf[row ^ bit];
+            }
+        }
+        bit <<= 1;
+    }
+
+    (0..anf.len()).all(|row| !anf[row] || row.count_ones() <= 1)
+}
+
+/// Post's five classes of functions, each closed under composition, so that
+/// a set of functions can only build every function if none holds all of
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostClass {
+    /// False when every input is false.
+    PreservesFalse,
+    /// True when every input is true.
+    PreservesTrue,
+    SelfDual,
+    Monotone,
+    Affine,
+}
+
+impl PostClass {
+    pub const ALL: [PostClass; 5] = [
+        PostClass::PreservesFalse,
+        PostClass::PreservesTrue,
+        PostClass::SelfDual,
+        PostClass::Monotone,
+        PostClass::Affine,
+    ];
+
+    /// The class's usual name, like `T0`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostClass::PreservesFalse => "T0",
+            PostClass::PreservesTrue => "T1",
+            PostClass::SelfDual => "self-dual",
+            PostClass::Monotone => "monotone",
+            PostClass::Affine => "affine",
+        }
+    }
+
+    pub fn contains(&self, table: &TruthTable) -> bool {
+        let outputs = column(table);
+        match self {
+            PostClass::PreservesFalse => !outputs[0],
+            PostClass::PreservesTrue => outputs[outputs.len() - 1],
+            PostClass::SelfDual => is_self_dual(table),
+            PostClass::Monotone => is_monotone(table),
+            PostClass::Affine => is_affine(table),
+        }
+    }
+}
+
+/// The classes the function is in, in the order of `PostClass::ALL`. It's
+/// functionally complete by itself, like NAND, if there are none.
+pub fn post_classes(table: &TruthTable) -> Vec<PostClass> {
+    PostClass::ALL
+        .into_iter()
+        .filter(|class| class.contains(table))
+        .collect()
+}
+
+/// The classes that hold every one of the functions, which are functionally
+/// complete together, building every function, if there are none.
+pub fn shared_classes(tables: &[TruthTable]) -> Vec<PostClass> {
+    PostClass::ALL
+        .into_iter()
+        .filter(|class| tables.iter().all(|table| class.contains(table)))
+        .collect()
+}
+
+/// A threshold function's weights, one for each input, and its threshold:
+/// it's true when the weights of the true inputs add up to at least the
+/// threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Threshold {
+    pub weights: Vec<i64>,
+    pub threshold: i64,
+}
+
+/// The smallest integer weights and threshold that give the function, if it
+/// is a threshold function, such as weights 1, 1, 1 and threshold 2 for
+/// majority of three. Tables with more than `THRESHOLD_MAX_INPUTS` inputs
+/// aren't checked, giving `None`.
+pub fn threshold(table: &TruthTable) -> Option<Threshold> {
+    let num_inputs = table.input_names.len();
+    if num_inputs > THRESHOLD_MAX_INPUTS {
+        return None;
+    }
+
+    // A threshold function is positive or negative in every input. Those it's
+    // negative in are flipped to make it positive, and given negative
+    // weights at the end
+    let outputs = column(table);
+    let negative = decreasing(&outputs, num_inputs, true);
+    if decreasing(&outputs, num_inputs, false)
+        .iter()
+        .any(|i| negative.contains(i))
+    {
+        return None;
+    }
+    let flip = negative
+        .iter()
+        .fold(0, |mask, i| mask | 1 << (num_inputs - 1 - i));
+    let positive: Vec<bool> = (0..outputs.len()).map(|row| outputs[row ^ flip]).collect();
+
+    // Inputs that are true in more of the true rows need at least as much
+    // weight, so weights are tried in that order, never increasing, and by
+    // their total so the smallest are found first
+    let mut order: Vec<usize> = (0..num_inputs).collect();
+    order.sort_by_key(|i| {
+        let bit = 1 << (num_inputs - 1 - i);
+        let count = (0..positive.len()).filter(|row| positive[*row] && row & bit != 0);
+        core::cmp::Reverse(count.count())
+    });
+    let max = MAX_WEIGHTS[num_inputs];
+    let mut sorted = vec![0; num_inputs];
+    let found = (0..=max * num_inputs).find_map(|total| {
+        search(&mut sorted, 0, total, max, &mut |sorted| {
+            let mut weights = vec![0; num_inputs];
+            for (i, weight) in order.iter().zip(sorted) {
+                weights[*i] = *weight as i64;
+            }
+            separates(&positive, &weights).map(|threshold| (weights, threshold))
+        })
+    });
+    let (mut weights, mut threshold) = found?;
+
+    for i in negative {
+        weights[i] = -weights[i];
+        threshold += weights[i];
+    }
+
+    Some(Threshold { weights, threshold })
+}
+
+/// Fill `weights[at..]` with weights of at most `max` adding up to `total`,
+/// never increasing, calling `check` with each until it finds something.
+fn search<T>(
+    weights: &mut [usize],
+    at: usize,
+    total: usize,
+    max: usize,
+    check: &mut impl FnMut(&[usize]) -> Option<T>,
+) -> Option<T> {
+    if at == weights.len() {
+        return if total == 0 { check(weights) } else { None };
+    }
+
+    let most = max.min(total);
+    let least = total.div_ceil(weights.len() - at);
+    for weight in (least..=most).rev() {
+        weights[at] = weight;
+        if let Some(found) = search(weights, at + 1, total - weight, weight, check) {
+            return Some(found);
+        }
+    }
+
+    None
+}
+
+/// The threshold for which the weights of the true inputs of a row add up
+/// to at least it exactly on the true rows, if there is one.
+fn separates(outputs: &[bool], weights: &[i64]) -> Option<i64> {
+    let num_inputs = weights.len();
+    let sum = |row: usize| -> i64 {
+        (0..num_inputs)
+            .filter(|i| row >> (num_inputs - 1 - i) & 1 == 1)
+            .map(|i| weights[i])
+            .sum()
+    };
+    let (mut lowest_true, mut highest_false) = (None, None);
+    for (row, output) in outputs.iter().enumerate() {
+        let total = sum(row);
+        let bound = if *output {
+            &mut lowest_true
+        } else {
+            &mut highest_false
+        };
+        *bound = Some(match (*output, *bound) {
+            (true, Some(lowest)) => total.min(lowest),
+            (false, Some(highest)) => total.max(highest),
+            (_, None) => total,
+        });
+    }
+
+    match (lowest_true, highest_false) {
+        (Some(lowest), Some(highest)) => (lowest > highest).then_some(lowest),
+        (Some(lowest), None) => Some(lowest),
+        (None, Some(highest)) => Some(highest + 1),
+        (None, None) => Some(0),
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let table = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen()
+    };
+
+    let majority = table("(A AND B) OR (C AND (A OR B)) = Q");
+    assert!(is_monotone(&majority));
+    assert_eq!(
+        minimal_true_points(&majority),
+        Some(vec![0b011, 0b101, 0b110])
+    );
+    let implication = table("(NOT A) OR B = Q");
+    assert_eq!(not_positive_in(&implication), [0]);
+    assert_eq!(minimal_true_points(&implication), None);
+    assert_eq!(not_positive_in(&table("A XOR B = Q")), [0, 1]);
+    assert_eq!(minimal_true_points(&table("A OR 1 = Q")), Some(vec![0]));
+    assert_eq!(minimal_true_points(&table("A AND 0 = Q")), Some(vec![]));
+
+    assert!(is_self_dual(&majority));
+    assert!(!is_self_anti_dual(&majority));
+    assert!(is_self_anti_dual(&table("A XOR B = Q")));
+    assert!(is_self_dual(&table("A XOR B XOR C = Q")));
+    assert!(is_self_dual(&table("A AND 1 = Q")));
+    assert!(is_self_anti_dual(&table("A OR 1 = Q")));
+    assert!(!is_self_dual(&implication) && !is_self_anti_dual(&implication));
+
+    use PostClass::*;
+    assert_eq!(
+        post_classes(&majority),
+        [PreservesFalse, PreservesTrue, SelfDual, Monotone]
+    );
+    assert_eq!(
+        post_classes(&table("A XOR B XOR 1 = Q")),
+        [PreservesTrue, Affine]
+    );
+    assert_eq!(post_classes(&table("NOT (A AND B) = Q")), []);
+    assert_eq!(
+        shared_classes(&[table("A AND B = Q"), table("A OR B = Q")]),
+        [PreservesFalse, PreservesTrue, Monotone]
+    );
+    assert_eq!(
+        shared_classes(&[table("A AND B = Q"), table("NOT A = Q")]),
+        []
+    );
+
+    let check = |source, weights: &[i64], expected| {
+        assert_eq!(
+            threshold(&table(source)),
+            Some(Threshold {
+                weights: weights.to_vec(),
+                threshold: expected
+            }),
+            "{}",
+            source
+        );
+    };
+    check("(A AND B) OR (C AND (A OR B)) = Q", &[1, 1, 1], 2);
+    check("A AND (B OR C) = Q", &[2, 1, 1], 3);
+    check("(NOT A) OR B = Q", &[-1, 1], 0);
+    check("A AND NOT A = Q", &[0], 1);
+    check("A OR NOT A = Q", &[0], 0);
+    assert_eq!(threshold(&table("A XOR B = Q")), None);
+    assert_eq!(threshold(&table("(A AND B) OR (C AND D) = Q")), None);
+}
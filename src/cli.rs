@@ -0,0 +1,844 @@
+use std::{path::PathBuf, str::FromStr};
+
+use booltable::{
+    codegen::Lang,
+    export::{Format, RowSort},
+    intern::Symbol,
+    lut,
+};
+
+use crate::{completions::Shell, config::Colour};
+
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub struct Subcommand {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub help: &'static str,
+}
+
+pub const FLAGS: &[Flag] = &[
+    Flag {
+        long: "format",
+        short: Some('f'),
+        value: Some("FORMAT"),
+        help: "Table format: markdown, csv, latex, html, json, wavedrom, bdd-dot, readmemh, ihex or verilog-case",
+    },
+    Flag {
+        long: "output",
+        short: Some('o'),
+        value: Some("FILE"),
+        help: "Write the table to FILE, inferring the format from its extension",
+    },
+    Flag {
+        long: "force",
+        short: None,
+        value: None,
+        help: "Overwrite the output file if it already exists",
+    },
+    Flag {
+        long: "symbols",
+        short: None,
+        value: Some("TRUE,FALSE"),
+        help: "Words for true and false cells, e.g. H,L or T,F",
+    },
+    Flag {
+        long: "sort",
+        short: None,
+        value: Some("KEY"),
+        help: "Row order: index, output (true rows first) or ones (fewest inputs set first)",
+    },
+    Flag {
+        long: "summary",
+        short: None,
+        value: None,
+        help: "Follow the table with the number of true and false rows",
+    },
+    Flag {
+        long: "full",
+        short: None,
+        value: None,
+        help: "Print every row, even if the table is taller than the terminal",
+    },
+    Flag {
+        long: "dump",
+        short: Some('v'),
+        value: Some("WHAT"),
+        help: "Print tokens, ast, sexpr, bytecode, registers or all (comma separated) before the table",
+    },
+    Flag {
+        long: "explain",
+        short: None,
+        value: None,
+        help: "Print the equation read out in English, to check how it was parsed",
+    },
+    Flag {
+        long: "classify",
+        short: None,
+        value: None,
+        help: "Print which classes of functions the equation's function belongs to",
+    },
+    Flag {
+        long: "factor",
+        short: None,
+        value: None,
+        help: "Print a sum of products, or the minimised one otherwise, factored into a multi-level form",
+    },
+    Flag {
+        long: "forms",
+        short: None,
+        value: None,
+        help: "Print the canonical and minimised sum of products and product of sums, and the sum with XORs",
+    },
+    Flag {
+        long: "cost",
+        short: None,
+        value: None,
+        help: "Compare the literals and gates of the equation, its minimised forms and a factored form",
+    },
+    Flag {
+        long: "bdd",
+        short: None,
+        value: None,
+        help: "Compare the size of the equation's decision diagram in different input orders",
+    },
+    Flag {
+        long: "luts",
+        short: None,
+        value: None,
+        help: "Estimate the FPGA lookup tables the equation needs, and how many deep they go",
+    },
+    Flag {
+        long: "lut-size",
+        short: None,
+        value: Some("K"),
+        help: "Inputs per lookup table for '--luts', 6 by default",
+    },
+    Flag {
+        long: "redundant",
+        short: None,
+        value: None,
+        help: "List the terms of a sum of products that can be dropped, and why",
+    },
+    Flag {
+        long: "simplify",
+        short: None,
+        value: None,
+        help: "Print the equation simplified by the laws of boolean algebra",
+    },
+    Flag {
+        long: "proof",
+        short: None,
+        value: None,
+        help: "Like --simplify, but print each law as it is applied",
+    },
+    Flag {
+        long: "dual",
+        short: None,
+        value: None,
+        help: "Print the dual of the equation, with AND and OR and 0 and 1 swapped",
+    },
+    Flag {
+        long: "complement",
+        short: None,
+        value: None,
+        help: "Print the complement of the equation, negated by De Morgan's laws",
+    },
+    Flag {
+        long: "ternary",
+        short: Some('x'),
+        value: None,
+        help: "Print a three-valued table, where inputs can also be X for unknown",
+    },
+    Flag {
+        long: "set",
+        short: Some('s'),
+        value: Some("ASSIGNMENT"),
+        help: "Input values for 'eval', e.g. A=1,B=0",
+    },
+    Flag {
+        long: "trace",
+        short: None,
+        value: None,
+        help: "Show each instruction executed by 'eval'",
+    },
+    Flag {
+        long: "property",
+        short: Some('p'),
+        value: Some("PROPERTY"),
+        help: "Property for 'check' and 'smt': satisfiable, tautology or contradiction",
+    },
+    Flag {
+        long: "expect",
+        short: Some('e'),
+        value: Some("VECTOR"),
+        help: "Expected outputs for 'verify' as hex or binary, minterm 0 lowest, e.g. 0xE8",
+    },
+    Flag {
+        long: "lang",
+        short: None,
+        value: Some("LANG"),
+        help: "Language for 'codegen': rust (the default), c or python",
+    },
+    Flag {
+        long: "lookup",
+        short: None,
+        value: None,
+        help: "Make 'codegen' look the output up in a packed table instead of evaluating the expression",
+    },
+    Flag {
+        long: "quiet",
+        short: Some('q'),
+        value: None,
+        help: "Don't print anything, only set the exit code",
+    },
+    Flag {
+        long: "repeat",
+        short: Some('n'),
+        value: Some("N"),
+        help: "Number of runs for 'bench' (default 100)",
+    },
+    Flag {
+        long: "vars",
+        short: None,
+        value: Some("N"),
+        help: "Number of inputs for 'random' and 'quiz' (default 4)",
+    },
+    Flag {
+        long: "seed",
+        short: None,
+        value: Some("SEED"),
+        help: "Seed for 'random' and 'quiz', which give the same functions for the same seed",
+    },
+    Flag {
+        long: "balanced",
+        short: None,
+        value: None,
+        help: "Make random functions true on exactly half of the rows",
+    },
+    Flag {
+        long: "port",
+        short: None,
+        value: Some("PORT"),
+        help: "Port for 'serve' to listen on (default 8080)",
+    },
+    Flag {
+        long: "config",
+        short: Some('c'),
+        value: Some("FILE"),
+        help: "Read defaults from FILE instead of ~/.config/booltable/config.toml",
+    },
+    Flag {
+        long: "color",
+        short: None,
+        value: Some("WHEN"),
+        help: "Colour output: auto, always or never",
+    },
+    Flag {
+        long: "plain",
+        short: None,
+        value: None,
+        help: "Print only ASCII, without colour, for piping into other tools",
+    },
+    Flag {
+        long: "help",
+        short: Some('h'),
+        value: None,
+        help: "Print this help",
+    },
+];
+
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "tui",
+        args: "<EQUATION>",
+        help: "Explore the equation's truth table interactively",
+    },
+    Subcommand {
+        name: "eval",
+        args: "<EQUATION>",
+        help: "Print the output for the inputs given with --set",
+    },
+    Subcommand {
+        name: "check",
+        args: "<EQUATION>",
+        help: "Exit with 0 if the equation has --property, or 1 otherwise",
+    },
+    Subcommand {
+        name: "verify",
+        args: "<EQUATION>",
+        help: "Exit with 0 if the outputs match --expect, or list the minterms that don't",
+    },
+    Subcommand {
+        name: "bench",
+        args: "<EQUATION>",
+        help: "Time parsing, compilation and table generation",
+    },
+    Subcommand {
+        name: "smt",
+        args: "<EQUATION>",
+        help: "Print an SMT-LIB2 script for checking --property with a solver",
+    },
+    Subcommand {
+        name: "aiger",
+        args: "<EQUATION>",
+        help: "Print the equation as an AIGER and-inverter graph",
+    },
+    Subcommand {
+        name: "wgsl",
+        args: "<EQUATION>",
+        help: "Export the equation as a WGSL compute shader, 32 rows per invocation",
+    },
+    Subcommand {
+        name: "gpu",
+        args: "<EQUATION>",
+        help: "Count the true rows on the GPU, writing the table to a .btt --output",
+    },
+    Subcommand {
+        name: "codegen",
+        args: "<EQUATION>",
+        help: "Print the function as source code in the language given with --lang",
+    },
+    Subcommand {
+        name: "tree",
+        args: "<EQUATION>",
+        help: "Print the function as a decision tree of nested if/else blocks",
+    },
+    Subcommand {
+        name: "prove",
+        args: "<EQUATION> <EQUATION>",
+        help: "Derive one equation from the other by the laws of boolean algebra",
+    },
+    Subcommand {
+        name: "compare",
+        args: "<EQUATION> <EQUATION>",
+        help: "Print both outputs side by side, marking the rows where they differ",
+    },
+    Subcommand {
+        name: "compatible",
+        args: "<SPEC> <EQUATION>",
+        help: "Check the equation against SPEC on the rows SPEC's assumptions include",
+    },
+    Subcommand {
+        name: "complete",
+        args: "<EQUATION>...",
+        help: "Check whether the equations' functions can build every function",
+    },
+    Subcommand {
+        name: "stdlib",
+        args: "<NAME> <N>",
+        help: "Print the equations for a built-in function of size N, e.g. adder 4",
+    },
+    Subcommand {
+        name: "random",
+        args: "",
+        help: "Print a random truth vector and a minimised equation for it",
+    },
+    Subcommand {
+        name: "quiz",
+        args: "",
+        help: "Practise reading and writing truth tables of random functions",
+    },
+    Subcommand {
+        name: "report",
+        args: "<FILE>",
+        help: "Write a markdown report for each equation in FILE",
+    },
+    Subcommand {
+        name: "open",
+        args: "<FILE>",
+        help: "Print a table saved as .btt, or the row given with --set",
+    },
+    Subcommand {
+        name: "diff",
+        args: "<FILE> <FILE>",
+        help: "List the rows on which two saved .btt tables differ",
+    },
+    Subcommand {
+        name: "serve",
+        args: "",
+        help: "Serve a JSON API on localhost: /parse, /table, /minimize and /equiv",
+    },
+    Subcommand {
+        name: "completions",
+        args: "<SHELL>",
+        help: "Print a completion script for bash, zsh, fish or powershell",
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property {
+    Satisfiable,
+    Tautology,
+    Contradiction,
+}
+
+impl FromStr for Property {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "satisfiable" | "sat" => Ok(Property::Satisfiable),
+            "tautology" | "taut" => Ok(Property::Tautology),
+            "contradiction" | "unsat" => Ok(Property::Contradiction),
+            _ => Err(format!(
+                "unknown property '{}', expected satisfiable, tautology or contradiction",
+                s
+            )),
+        }
+    }
+}
+
+/// Which intermediate representations to print before the table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dump {
+    pub tokens: bool,
+    pub ast: bool,
+    pub sexpr: bool,
+    pub bytecode: bool,
+    pub registers: bool,
+}
+
+impl Dump {
+    pub fn any(&self) -> bool {
+        self.tokens || self.ast || self.sexpr || self.bytecode || self.registers
+    }
+}
+
+impl FromStr for Dump {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut dump = Dump::default();
+        for part in s.split(',') {
+            match part.trim() {
+                "tokens" => dump.tokens = true,
+                "ast" => dump.ast = true,
+                "sexpr" => dump.sexpr = true,
+                "bytecode" => dump.bytecode = true,
+                "registers" => dump.registers = true,
+                "all" => {
+                    dump = Dump {
+                        tokens: true,
+                        ast: true,
+                        sexpr: true,
+                        bytecode: true,
+                        registers: true,
+                    }
+                }
+                other => {
+                    return Err(format!(
+                    "unknown dump '{}', expected tokens, ast, sexpr, bytecode, registers or all",
+                    other
+                ))
+                }
+            }
+        }
+
+        Ok(dump)
+    }
+}
+
+pub enum Command {
+    Repl,
+    Table(String),
+    Tui(String),
+    Eval(String),
+    Check(String),
+    Verify(String),
+    Bench(String),
+    Smt(String),
+    Aiger(String),
+    Wgsl(String),
+    Gpu(String),
+    Tree(String),
+    Codegen(String),
+    Prove(String, String),
+    Compare(String, String),
+    Compatible(String, String),
+    Complete(Vec<String>),
+    Stdlib(String, usize),
+    Random,
+    Quiz,
+    Report(PathBuf),
+    Open(PathBuf),
+    Diff(PathBuf, PathBuf),
+    Serve,
+    Completions(Shell),
+    Help,
+}
+
+pub struct Args {
+    pub command: Command,
+    pub format: Option<Format>,
+    pub output: Option<PathBuf>,
+    pub force: bool,
+    pub full: bool,
+    pub summary: bool,
+    pub sort: Option<RowSort>,
+    pub symbols: Option<(String, String)>,
+    pub dump: Dump,
+    pub explain: bool,
+    pub classify: bool,
+    pub factor: bool,
+    pub forms: bool,
+    pub cost: bool,
+    pub bdd: bool,
+    pub luts: bool,
+    pub lut_size: usize,
+    pub redundant: bool,
+    pub simplify: bool,
+    pub proof: bool,
+    pub dual: bool,
+    pub complement: bool,
+    pub ternary: bool,
+    pub set: Option<String>,
+    pub trace: bool,
+    pub property: Property,
+    pub expect: Option<String>,
+    pub lang: Lang,
+    pub lookup: bool,
+    pub quiet: bool,
+    pub repeat: usize,
+    pub vars: usize,
+    pub seed: Option<u64>,
+    pub balanced: bool,
+    pub port: u16,
+    pub config: Option<PathBuf>,
+    pub colour: Option<Colour>,
+    pub plain: bool,
+}
+
+impl Args {
+    /// Whether any of the flags printing something about the equation
+    /// before its table were given.
+    pub fn analyses(&self) -> bool {
+        self.explain
+            || self.classify
+            || self.factor
+            || self.forms
+            || self.cost
+            || self.bdd
+            || self.luts
+            || self.redundant
+            || self.simplify
+            || self.proof
+            || self.dual
+            || self.complement
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            command: Command::Repl,
+            format: None,
+            output: None,
+            force: false,
+            full: false,
+            summary: false,
+            sort: None,
+            symbols: None,
+            dump: Dump::default(),
+            explain: false,
+            classify: false,
+            factor: false,
+            forms: false,
+            cost: false,
+            bdd: false,
+            luts: false,
+            lut_size: lut::DEFAULT_K,
+            redundant: false,
+            simplify: false,
+            proof: false,
+            dual: false,
+            complement: false,
+            ternary: false,
+            set: None,
+            trace: false,
+            property: Property::Satisfiable,
+            expect: None,
+            lang: Lang::Rust,
+            lookup: false,
+            quiet: false,
+            repeat: 100,
+            vars: 4,
+            seed: None,
+            balanced: false,
+            port: 8080,
+            config: None,
+            colour: None,
+            plain: false,
+        };
+        let mut positional = vec![];
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let flag = match arg.strip_prefix("--") {
+                Some(long) => FLAGS.iter().find(|f| f.long == long),
+                None if arg.len() > 1 && arg.starts_with('-') => {
+                    let short = arg[1..].chars().next();
+                    FLAGS.iter().find(|f| f.short.is_some() && f.short == short)
+                }
+                None => {
+                    positional.push(arg);
+                    continue;
+                }
+            }
+            .ok_or_else(|| format!("unknown flag '{}'", arg))?;
+
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("'--{}' expects a value", flag.long))
+            };
+            match flag.long {
+                "format" => parsed.format = Some(value()?.parse()?),
+                "output" => parsed.output = Some(value()?.into()),
+                "force" => parsed.force = true,
+                "full" => parsed.full = true,
+                "summary" => parsed.summary = true,
+                "sort" => parsed.sort = Some(value()?.parse()?),
+                "symbols" => {
+                    parsed.symbols = match value()?.split_once(',') {
+                        Some((t, f)) if !t.is_empty() && !f.is_empty() => {
+                            Some((t.to_string(), f.to_string()))
+                        }
+                        _ => return Err("'--symbols' expects TRUE,FALSE, e.g. H,L".to_string()),
+                    }
+                }
+                "dump" => parsed.dump = value()?.parse()?,
+                "explain" => parsed.explain = true,
+                "classify" => parsed.classify = true,
+                "factor" => parsed.factor = true,
+                "forms" => parsed.forms = true,
+                "cost" => parsed.cost = true,
+                "bdd" => parsed.bdd = true,
+                "luts" => parsed.luts = true,
+                "lut-size" => {
+                    parsed.lut_size = match value()?.parse() {
+                        Ok(k) if k >= 2 => k,
+                        _ => {
+                            return Err("'--lut-size' expects an integer of at least 2".to_string())
+                        }
+                    }
+                }
+                "redundant" => parsed.redundant = true,
+                "simplify" => parsed.simplify = true,
+                "proof" => parsed.proof = true,
+                "dual" => parsed.dual = true,
+                "complement" => parsed.complement = true,
+                "ternary" => parsed.ternary = true,
+                "set" => parsed.set = Some(value()?),
+                "trace" => parsed.trace = true,
+                "property" => parsed.property = value()?.parse()?,
+                "expect" => parsed.expect = Some(value()?),
+                "lang" => parsed.lang = value()?.parse()?,
+                "lookup" => parsed.lookup = true,
+                "quiet" => parsed.quiet = true,
+                "repeat" => {
+                    parsed.repeat = match value()?.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => return Err("'--repeat' expects a positive integer".to_string()),
+                    }
+                }
+                "vars" => {
+                    parsed.vars = match value()?.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => return Err("'--vars' expects a positive integer".to_string()),
+                    }
+                }
+                "seed" => {
+                    parsed.seed = Some(
+                        value()?
+                            .parse()
+                            .map_err(|_| "'--seed' expects a non-negative integer".to_string())?,
+                    )
+                }
+                "balanced" => parsed.balanced = true,
+                "port" => {
+                    parsed.port = value()?
+                        .parse()
+                        .map_err(|_| "'--port' expects a port number".to_string())?
+                }
+                "config" => parsed.config = Some(value()?.into()),
+                "color" => parsed.colour = Some(value()?.parse()?),
+                "plain" => parsed.plain = true,
+                "help" => parsed.command = Command::Help,
+                _ => unreachable!(),
+            }
+        }
+
+        if let Command::Help = parsed.command {
+            return Ok(parsed);
+        }
+
+        let mut positional = positional.into_iter();
+        parsed.command = match positional.next() {
+            None => Command::Repl,
+            Some(sub) if sub == "tui" => {
+                Command::Tui(positional.next().ok_or("'tui' expects an equation")?)
+            }
+            Some(sub) if sub == "eval" => {
+                Command::Eval(positional.next().ok_or("'eval' expects an equation")?)
+            }
+            Some(sub) if sub == "check" => {
+                Command::Check(positional.next().ok_or("'check' expects an equation")?)
+            }
+            Some(sub) if sub == "verify" => {
+                Command::Verify(positional.next().ok_or("'verify' expects an equation")?)
+            }
+            Some(sub) if sub == "bench" => {
+                Command::Bench(positional.next().ok_or("'bench' expects an equation")?)
+            }
+            Some(sub) if sub == "smt" => {
+                Command::Smt(positional.next().ok_or("'smt' expects an equation")?)
+            }
+            Some(sub) if sub == "aiger" => {
+                Command::Aiger(positional.next().ok_or("'aiger' expects an equation")?)
+            }
+            Some(sub) if sub == "wgsl" => {
+                Command::Wgsl(positional.next().ok_or("'wgsl' expects an equation")?)
+            }
+            Some(sub) if sub == "gpu" => {
+                Command::Gpu(positional.next().ok_or("'gpu' expects an equation")?)
+            }
+            Some(sub) if sub == "tree" => {
+                Command::Tree(positional.next().ok_or("'tree' expects an equation")?)
+            }
+            Some(sub) if sub == "codegen" => {
+                Command::Codegen(positional.next().ok_or("'codegen' expects an equation")?)
+            }
+            Some(sub) if sub == "prove" => Command::Prove(
+                positional.next().ok_or("'prove' expects two equations")?,
+                positional.next().ok_or("'prove' expects two equations")?,
+            ),
+            Some(sub) if sub == "compare" => Command::Compare(
+                positional.next().ok_or("'compare' expects two equations")?,
+                positional.next().ok_or("'compare' expects two equations")?,
+            ),
+            Some(sub) if sub == "compatible" => Command::Compatible(
+                positional
+                    .next()
+                    .ok_or("'compatible' expects a specification and an equation")?,
+                positional
+                    .next()
+                    .ok_or("'compatible' expects a specification and an equation")?,
+            ),
+            Some(sub) if sub == "complete" => {
+                let equations: Vec<String> = positional.by_ref().collect();
+                if equations.is_empty() {
+                    return Err("'complete' expects at least one equation".into());
+                }
+                Command::Complete(equations)
+            }
+            Some(sub) if sub == "stdlib" => {
+                let name = positional
+                    .next()
+                    .ok_or("'stdlib' expects a name and a size")?;
+                let size = positional
+                    .next()
+                    .ok_or("'stdlib' expects a name and a size")?;
+                let size = size
+                    .parse()
+                    .map_err(|_| format!("'{}' isn't a valid size", size))?;
+                Command::Stdlib(name, size)
+            }
+            Some(sub) if sub == "random" => Command::Random,
+            Some(sub) if sub == "quiz" => Command::Quiz,
+            Some(sub) if sub == "report" => {
+                Command::Report(positional.next().ok_or("'report' expects a file")?.into())
+            }
+            Some(sub) if sub == "open" => {
+                Command::Open(positional.next().ok_or("'open' expects a file")?.into())
+            }
+            Some(sub) if sub == "diff" => Command::Diff(
+                positional.next().ok_or("'diff' expects two files")?.into(),
+                positional.next().ok_or("'diff' expects two files")?.into(),
+            ),
+            Some(sub) if sub == "serve" => Command::Serve,
+            Some(sub) if sub == "completions" => Command::Completions(
+                positional
+                    .next()
+                    .ok_or("'completions' expects a shell")?
+                    .parse()?,
+            ),
+            Some(equation) => Command::Table(equation),
+        };
+        if let Some(extra) = positional.next() {
+            return Err(format!("unexpected argument '{}'", extra));
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parse a `--set` value such as `A=1,B=0` into a value for each of `names`.
+pub fn parse_assignment(spec: &str, names: &[Symbol]) -> Result<Vec<bool>, String> {
+    let mut values: Vec<Option<bool>> = vec![None; names.len()];
+
+    for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", pair))?;
+        let index = names
+            .iter()
+            .position(|n| *n == name.trim())
+            .ok_or_else(|| format!("'{}' is not an input of the equation", name.trim()))?;
+        values[index] = Some(match value.trim() {
+            "1" | "true" => true,
+            "0" | "false" => false,
+            other => return Err(format!("'{}' is not a boolean value", other)),
+        });
+    }
+
+    let missing: Vec<&str> = (0..names.len())
+        .filter(|&i| values[i].is_none())
+        .map(|i| names[i].as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("no value given for {}", missing.join(", ")));
+    }
+
+    Ok(values.into_iter().flatten().collect())
+}
+
+pub fn help() -> String {
+    let mut out = String::from(
+        "Truth table generator for boolean equations\n\n\
+         Usage: booltable [OPTIONS] [EQUATION]\n       booltable <COMMAND> [ARGS]\n\n\
+         Without an equation, starts a REPL.\n\nCommands:\n",
+    );
+    let commands: Vec<(String, &str)> = SUBCOMMANDS
+        .iter()
+        .map(|sub| (format!("{} {}", sub.name, sub.args), sub.help))
+        .collect();
+    let options: Vec<(String, &str)> = FLAGS
+        .iter()
+        .map(|flag| {
+            let short = flag.short.map(|c| format!("-{}, ", c)).unwrap_or_default();
+            let usage = format!("{}--{} {}", short, flag.long, flag.value.unwrap_or(""));
+            (usage, flag.help)
+        })
+        .collect();
+    // Every description starts in the same column, two spaces past the
+    // longest usage
+    let width = commands
+        .iter()
+        .chain(&options)
+        .map(|(usage, _)| usage.trim_end().len() + 2)
+        .max()
+        .unwrap_or(0);
+
+    for (usage, help) in commands {
+        out.push_str(&format!("  {:<width$}{}\n", usage, help));
+    }
+    out.push_str("\nOptions:\n");
+    for (usage, help) in options {
+        out.push_str(&format!("  {:<width$}{}\n", usage, help));
+    }
+
+    out
+}
@@ -0,0 +1,519 @@
+//! Source code for an equation's function in Rust, C or Python with NumPy,
+//! to paste into a project. The function takes a parameter for each input, named after it
+//! in lower case, and is named after the output. It's written either as the
+//! equation's expression, with any assumptions as extra conditions, or as a
+//! lookup in the packed output column, where rows left out by assumptions
+//! are false.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write, str::FromStr};
+
+use derive_more::Display;
+
+use crate::{
+    assignments,
+    compiler::Compiler,
+    parser::{BinOp, Dialect, Equation, Expr, ExprRef},
+    vm::VM,
+};
+
+/// Lookup tables are only generated for up to this many inputs, or 1024
+/// words.
+pub const MAX_LOOKUP_INPUTS: usize = 16;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    #[display(fmt = "rust")]
+    Rust,
+    #[display(fmt = "c")]
+    C,
+    #[display(fmt = "python")]
+    Python,
+}
+
+impl Lang {
+    pub const ALL: &'static [Lang] = &[Lang::Rust, Lang::C, Lang::Python];
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Lang::Rust => &[
+                "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+                "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for",
+                "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+                "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super",
+                "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+                "where", "while", "yield",
+            ],
+            // C's, and C++'s so that the code works in either, and the
+            // lookup's variables
+            Lang::C => &[
+                "row",
+                "table",
+                "and",
+                "and_eq",
+                "auto",
+                "bitand",
+                "bitor",
+                "bool",
+                "break",
+                "case",
+                "catch",
+                "char",
+                "class",
+                "compl",
+                "const",
+                "continue",
+                "default",
+                "delete",
+                "do",
+                "double",
+                "else",
+                "enum",
+                "explicit",
+                "extern",
+                "false",
+                "float",
+                "for",
+                "friend",
+                "goto",
+                "if",
+                "inline",
+                "int",
+                "long",
+                "mutable",
+                "namespace",
+                "new",
+                "not",
+                "not_eq",
+                "operator",
+                "or",
+                "or_eq",
+                "private",
+                "protected",
+                "public",
+                "register",
+                "restrict",
+                "return",
+                "short",
+                "signed",
+                "sizeof",
+                "static",
+                "struct",
+                "switch",
+                "template",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "typedef",
+                "typename",
+                "union",
+                "unsigned",
+                "using",
+                "virtual",
+                "void",
+                "volatile",
+                "while",
+                "xor",
+                "xor_eq",
+            ],
+            // Python's, and the names the function uses
+            Lang::Python => &[
+                "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+                "continue", "def", "del", "elif", "else", "except", "finally", "for", "from",
+                "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass",
+                "raise", "return", "try", "while", "with", "yield", "np", "ones", "row", "table",
+                "zeros",
+            ],
+        }
+    }
+
+    fn syntax(self) -> Syntax {
+        match self {
+            Lang::Rust | Lang::C => Syntax {
+                not: "!",
+                and: "&",
+                or: "|",
+                xor: "^",
+                true_: "true",
+                false_: "false",
+            },
+            // Every bit of an integer is a separate case
+            Lang::Python => Syntax {
+                not: "~",
+                and: "&",
+                or: "|",
+                xor: "^",
+                true_: "ones",
+                false_: "zeros",
+            },
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Ok(Lang::Rust),
+            "c" | "c++" | "cpp" => Ok(Lang::C),
+            "python" | "py" | "numpy" => Ok(Lang::Python),
+            _ => Err(format!(
+                "unknown language '{}', expected one of: {}",
+                s,
+                Lang::ALL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// How the function works out its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Expression,
+    Lookup,
+}
+
+/// The operators and constants of a language's boolean expressions.
+struct Syntax {
+    not: &'static str,
+    and: &'static str,
+    or: &'static str,
+    xor: &'static str,
+    true_: &'static str,
+    false_: &'static str,
+}
+
+/// The equation's function in `lang`, or an error if a lookup table would
+/// be too big.
+pub fn generate(equation: &Equation, lang: Lang, style: Style) -> Result<String, String> {
+    if style == Style::Lookup && equation.inputs.len() > MAX_LOOKUP_INPUTS {
+        return Err(format!(
+            "lookup tables are limited to {} inputs",
+            MAX_LOOKUP_INPUTS
+        ));
+    }
+
+    let (names, renamed) = identifiers(equation, lang);
+    let (params, function) = names.split_at(equation.inputs.len());
+    let body = match style {
+        Style::Expression => expression(equation, params, lang),
+        Style::Lookup => lookup(equation, params, lang),
+    };
+
+    let mut out = String::new();
+    let source = equation.lhs.display_in(&equation.inputs, Dialect::Words);
+    match lang {
+        Lang::Rust => {
+            writeln!(
+                out,
+                "/// {} = {}, generated by booltable.",
+                equation.output, source
+            )
+            .unwrap();
+            if renamed {
+                writeln!(out, "#[allow(non_snake_case)]").unwrap();
+            }
+            let params: Vec<String> = params.iter().map(|p| format!("{}: bool", p)).collect();
+            writeln!(
+                out,
+                "pub fn {}({}) -> bool {{",
+                function[0],
+                params.join(", ")
+            )
+            .unwrap();
+            for line in body.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+            out.push_str("}\n");
+        }
+        Lang::C => {
+            writeln!(out, "#include <stdbool.h>").unwrap();
+            if style == Style::Lookup {
+                writeln!(out, "#include <stdint.h>").unwrap();
+            }
+            writeln!(
+                out,
+                "\n/* {} = {}, generated by booltable. */",
+                equation.output, source
+            )
+            .unwrap();
+            let params: Vec<String> = params.iter().map(|p| format!("bool {}", p)).collect();
+            let params = match params.is_empty() {
+                true => "void".to_string(),
+                false => params.join(", "),
+            };
+            writeln!(out, "static inline bool {}({}) {{", function[0], params).unwrap();
+            for line in body.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+            out.push_str("}\n");
+        }
+        Lang::Python => {
+            let doc = match style {
+                Style::Expression => {
+                    "Each input is an array of integers whose bits are separate cases, or of\n\
+                     booleans, and so is the result."
+                }
+                Style::Lookup => {
+                    "Each input is an array of 0s and 1s or of booleans, and the result is an\n\
+                     array of booleans."
+                }
+            };
+            writeln!(out, "import numpy as np\n\n").unwrap();
+            writeln!(out, "def {}({}):", function[0], params.join(", ")).unwrap();
+            writeln!(
+                out,
+                "    \"\"\"{} = {}, generated by booltable.\n",
+                equation.output, source
+            )
+            .unwrap();
+            for line in doc.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+            writeln!(out, "    \"\"\"").unwrap();
+            for line in body.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Names for the inputs and then the output that are valid identifiers in
+/// `lang`: in lower case, unless that would make two the same, and with an
+/// underscore after keywords. Also whether any kept their case.
+fn identifiers(equation: &Equation, lang: Lang) -> (Vec<String>, bool) {
+    let names: Vec<&str> = equation
+        .inputs
+        .iter()
+        .chain([&equation.output])
+        .map(|name| name.as_str())
+        .collect();
+    let lower: Vec<String> = names.iter().map(|name| name.to_ascii_lowercase()).collect();
+    let clash = (0..lower.len()).any(|i| lower[..i].contains(&lower[i]));
+    let chosen: Vec<String> = match clash {
+        true => names.iter().map(ToString::to_string).collect(),
+        false => lower,
+    };
+    let renamed = clash
+        && names
+            .iter()
+            .any(|name| name.chars().any(|c| c.is_uppercase()));
+
+    let names = chosen
+        .into_iter()
+        .map(|name| match lang.keywords().contains(&name.as_str()) {
+            true => format!("{}_", name),
+            false => name,
+        })
+        .collect();
+    (names, renamed)
+}
+
+/// The expression, with a condition for each assumption before it.
+fn expression(equation: &Equation, params: &[String], lang: Lang) -> String {
+    let syntax = lang.syntax();
+    let mut out = String::new();
+    let conditions = equation.assumptions.iter().chain([&equation.lhs]);
+    for (i, condition) in conditions.enumerate() {
+        if i > 0 {
+            write!(out, " {} ", syntax.and).unwrap();
+        }
+        let group = !equation.assumptions.is_empty();
+        write_expr(&mut out, condition.expr(), params, &syntax, !group);
+    }
+
+    match lang {
+        Lang::Rust => out,
+        Lang::C => format!("return {};", out),
+        Lang::Python => {
+            let mut conditions = equation.assumptions.iter().chain([&equation.lhs]);
+            if !conditions.any(|condition| has_constant(condition.expr())) {
+                return format!("return {}", out);
+            }
+            let zeros = match params.first() {
+                Some(param) => format!("np.zeros_like({})", param),
+                None => "np.uint64(0)".to_string(),
+            };
+            format!("zeros = {}\nones = ~zeros\nreturn {}", zeros, out)
+        }
+    }
+}
+
+fn has_constant(expr: ExprRef) -> bool {
+    match expr.node() {
+        Expr::Bool(_) => true,
+        Expr::Var(_) => false,
+        Expr::Not(e) => has_constant(e),
+        Expr::BinOp { lhs, rhs, .. } => has_constant(lhs) || has_constant(rhs),
+    }
+}
+
+fn write_expr(out: &mut String, expr: ExprRef, names: &[String], syntax: &Syntax, top: bool) {
+    match expr.node() {
+        Expr::Bool(true) => out.push_str(syntax.true_),
+        Expr::Bool(false) => out.push_str(syntax.false_),
+        Expr::Var(v) => out.push_str(&names[v]),
+        Expr::Not(e) => {
+            out.push_str(syntax.not);
+            write_expr(out, e, names, syntax, false);
+        }
+        Expr::BinOp { op, lhs, rhs } => {
+            if !top {
+                out.push('(');
+            }
+            let op = match op {
+                BinOp::And => syntax.and,
+                BinOp::Or => syntax.or,
+                BinOp::Xor => syntax.xor,
+            };
+            write_expr(out, lhs, names, syntax, false);
+            write!(out, " {} ", op).unwrap();
+            write_expr(out, rhs, names, syntax, false);
+            if !top {
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// The output column packed into 64-bit words, row `r` being bit `r % 64`
+/// of word `r / 64`.
+fn words(equation: &Equation) -> Vec<u64> {
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let mut words = alloc::vec![0u64; 1.max((1 << table.input_names.len()) / 64)];
+    for row in table.rows().filter(|row| row.output) {
+        let row = assignments::to_row(row.assignment);
+        words[row / 64] |= 1 << (row % 64);
+    }
+
+    words
+}
+
+/// A lookup of the row the parameters pick out in the packed column.
+fn lookup(equation: &Equation, params: &[String], lang: Lang) -> String {
+    let words = words(equation);
+    let n = params.len();
+    let row: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let cast = match lang {
+                Lang::Rust => format!("({} as usize)", param),
+                Lang::C => format!("(unsigned){}", param),
+                Lang::Python => format!("np.asarray({}, dtype=np.intp)", param),
+            };
+            match n - 1 - i {
+                0 => cast,
+                shift => format!("({} << {})", cast, shift),
+            }
+        })
+        .collect();
+    let row = match row.is_empty() {
+        true => "0".to_string(),
+        false => row.join(" | "),
+    };
+
+    let mut out = String::new();
+    match lang {
+        Lang::Rust => {
+            writeln!(out, "const TABLE: [u64; {}] = [", words.len()).unwrap();
+            for word in &words {
+                writeln!(out, "    0x{:016x},", word).unwrap();
+            }
+            writeln!(out, "];").unwrap();
+            writeln!(out, "let row = {};", row).unwrap();
+            writeln!(out, "(TABLE[row / 64] >> (row % 64)) & 1 == 1").unwrap();
+        }
+        Lang::Python => {
+            // The column's bytes, lowest row first
+            let bytes: String = words
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .take(1.max((1 << n) / 8))
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            writeln!(out, "table = np.unpackbits(").unwrap();
+            writeln!(
+                out,
+                "    np.frombuffer(bytes.fromhex(\"{}\"), dtype=np.uint8), bitorder=\"little\"",
+                bytes
+            )
+            .unwrap();
+            writeln!(out, ").astype(bool)").unwrap();
+            writeln!(out, "row = {}", row).unwrap();
+            writeln!(out, "return table[row]").unwrap();
+        }
+        Lang::C => {
+            writeln!(out, "static const uint64_t table[{}] = {{", words.len()).unwrap();
+            for word in &words {
+                writeln!(out, "    UINT64_C(0x{:016x}),", word).unwrap();
+            }
+            writeln!(out, "}};").unwrap();
+            writeln!(out, "unsigned row = {};", row).unwrap();
+            writeln!(out, "return (table[row / 64] >> (row % 64)) & 1;").unwrap();
+        }
+    }
+
+    out
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let equation = Parser::new("(A AND B) OR NOT C = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(
+        generate(&equation, Lang::Rust, Style::Expression).unwrap(),
+        "/// Q = (A AND B) OR NOT C, generated by booltable.\n\
+         pub fn q(a: bool, b: bool, c: bool) -> bool {\n    (a & b) | !c\n}\n"
+    );
+    assert_eq!(
+        generate(&equation, Lang::Rust, Style::Lookup).unwrap(),
+        "/// Q = (A AND B) OR NOT C, generated by booltable.\n\
+         pub fn q(a: bool, b: bool, c: bool) -> bool {\n    \
+         const TABLE: [u64; 1] = [\n        0x00000000000000d5,\n    ];\n    \
+         let row = ((a as usize) << 2) | ((b as usize) << 1) | (c as usize);\n    \
+         (TABLE[row / 64] >> (row % 64)) & 1 == 1\n}\n"
+    );
+
+    assert_eq!(
+        generate(&equation, Lang::C, Style::Expression).unwrap(),
+        "#include <stdbool.h>\n\n/* Q = (A AND B) OR NOT C, generated by booltable. */\n\
+         static inline bool q(bool a, bool b, bool c) {\n    return (a & b) | !c;\n}\n"
+    );
+    assert!(generate(&equation, Lang::C, Style::Lookup)
+        .unwrap()
+        .contains("    unsigned row = ((unsigned)a << 2) | ((unsigned)b << 1) | (unsigned)c;\n"));
+
+    assert!(generate(&equation, Lang::Python, Style::Expression)
+        .unwrap()
+        .ends_with("    \"\"\"\n    return (a & b) | ~c\n"));
+    assert!(generate(&equation, Lang::Python, Style::Lookup)
+        .unwrap()
+        .contains("bytes.fromhex(\"d5\")"));
+
+    // Assumptions become conditions, and names that would clash keep
+    // their case
+    let equation = Parser::new("assume NOT a; A OR a = if")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(
+        generate(&equation, Lang::Rust, Style::Expression).unwrap(),
+        "/// if = A OR a, generated by booltable.\n#[allow(non_snake_case)]\n\
+         pub fn if_(a: bool, A: bool) -> bool {\n    !a & (A | a)\n}\n"
+    );
+}
@@ -0,0 +1,168 @@
+//! Packed columns of a truth table, 64 rows to a word, for analyses that
+//! combine whole columns at once.
+
+use alloc::{vec, vec::Vec};
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+use crate::vm::TruthTable;
+
+/// One bit per row of a table. Bits past `len` in the last word are always
+/// clear, so columns of the same length can be compared with `==`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitColumn {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitColumn {
+    pub fn from_bools(bits: impl IntoIterator<Item = bool>) -> Self {
+        let mut column = BitColumn {
+            words: vec![],
+            len: 0,
+        };
+        for bit in bits {
+            if column.len.is_multiple_of(64) {
+                column.words.push(0);
+            }
+            column.words[column.len / 64] |= (bit as u64) << (column.len % 64);
+            column.len += 1;
+        }
+
+        column
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, row: usize) -> Option<bool> {
+        (row < self.len).then(|| self.words[row / 64] >> (row % 64) & 1 == 1)
+    }
+
+    /// How many rows are set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Whether every row set in this column is also set in `other`, that is,
+    /// whether this column implies it.
+    pub fn is_subset(&self, other: &BitColumn) -> bool {
+        self.check_len(other);
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(a, b)| a & !b == 0)
+    }
+
+    /// The rows that are set, in order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&row| self.get(row) == Some(true))
+    }
+
+    fn check_len(&self, other: &BitColumn) {
+        assert_eq!(
+            self.len, other.len,
+            "columns of different lengths can't be combined"
+        );
+    }
+
+    fn zip_with(&self, other: &BitColumn, f: impl Fn(u64, u64) -> u64) -> BitColumn {
+        self.check_len(other);
+        BitColumn {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| f(*a, *b))
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+impl BitAnd for &BitColumn {
+    type Output = BitColumn;
+
+    /// # Panics
+    ///
+    /// If the columns have different lengths, as for `|` and `^`.
+    fn bitand(self, other: &BitColumn) -> BitColumn {
+        self.zip_with(other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BitColumn {
+    type Output = BitColumn;
+
+    fn bitor(self, other: &BitColumn) -> BitColumn {
+        self.zip_with(other, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BitColumn {
+    type Output = BitColumn;
+
+    fn bitxor(self, other: &BitColumn) -> BitColumn {
+        self.zip_with(other, |a, b| a ^ b)
+    }
+}
+
+impl Not for &BitColumn {
+    type Output = BitColumn;
+
+    fn not(self) -> BitColumn {
+        let mut words: Vec<u64> = self.words.iter().map(|w| !w).collect();
+        // Keep the bits past the end clear
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+
+        BitColumn {
+            words,
+            len: self.len,
+        }
+    }
+}
+
+impl TruthTable {
+    /// The column for the input or output called `name`, if there is one.
+    pub fn column(&self, name: &str) -> Option<BitColumn> {
+        if let Some(index) = self.input_names.iter().position(|n| *n == name) {
+            Some(BitColumn::from_bools(
+                self.inputs.iter().map(|inputs| inputs[index]),
+            ))
+        } else if name == self.output_name {
+            Some(BitColumn::from_bools(self.outputs.iter().copied()))
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let equation = Parser::new("A AND NOT B = Q").parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    let (a, b, q) = (
+        table.column("A").unwrap(),
+        table.column("B").unwrap(),
+        table.column("Q").unwrap(),
+    );
+    assert!(table.column("C").is_none());
+
+    assert_eq!(a.count_ones(), 2);
+    assert_eq!(&a & &!&b, q);
+    assert!(q.is_subset(&a));
+    assert!(!a.is_subset(&q));
+    assert_eq!((&a ^ &b).ones().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!((&a | &b).count_ones(), 3);
+    assert_eq!((!&BitColumn::from_bools([true; 70])).count_ones(), 0);
+}
@@ -1,38 +1,193 @@
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+
+use derive_more::Display;
+
 use crate::{
-    parser::{self, BinOp, Expr, SpanExpr},
+    equiv,
+    intern::Symbol,
+    parser::{self, Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr},
+    peephole, simplify,
     vm::Op,
 };
 
-pub struct Equation<'input> {
-    pub inputs: Vec<&'input str>,
+/// Why ops can't be run safely, found by `verify`.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    #[display(
+        fmt = "op {} loads input {}, but there are only {}",
+        index,
+        input,
+        inputs
+    )]
+    InputOutOfRange {
+        index: usize,
+        input: usize,
+        inputs: usize,
+    },
+    #[display(fmt = "op {} pops from an empty stack", _0)]
+    StackUnderflow(usize),
+    #[display(fmt = "the ops leave {} values on the stack instead of 1", _0)]
+    UnbalancedStack(usize),
+    #[display(
+        fmt = "op {} jumps to {}, which isn't a later op with the same stack",
+        index,
+        target
+    )]
+    BadJump { index: usize, target: usize },
+}
+
+pub struct Equation {
+    pub inputs: Vec<Symbol>,
     pub lhs: Vec<Op>,
-    pub output: &'input str,
+    pub output: Symbol,
+    /// The ops for each assumption, which must all leave true for a row to
+    /// be enumerated.
+    pub assumptions: Vec<Vec<Op>>,
+}
+
+impl Equation {
+    /// List the ops one per line with their index, naming the input each
+    /// `LOAD` refers to, followed by those of each assumption.
+    pub fn disassemble(&self) -> String {
+        let list = |ops: &[Op]| -> String {
+            ops.iter()
+                .enumerate()
+                .map(|(i, op)| match op {
+                    Op::Load(v) => format!("{:04} LOAD {}\n", i, self.inputs[*v]),
+                    op => format!("{:04} {}\n", i, op),
+                })
+                .collect()
+        };
+
+        let mut out = list(&self.lhs);
+        for (i, ops) in self.assumptions.iter().enumerate() {
+            out.push_str(&format!("assume {}:\n{}", i, list(ops)));
+        }
+
+        out
+    }
+
+    /// The most values on the VM's stack at once while running the equation
+    /// or any of its assumptions. Jumps only skip ahead past ops that would
+    /// leave the stack as it was, so following the ops in order finds it.
+    pub fn max_stack_depth(&self) -> usize {
+        let depth = |ops: &[Op]| {
+            let (mut depth, mut max) = (0usize, 0);
+            for op in ops {
+                match op {
+                    Op::Push(_) | Op::Load(_) => depth += 1,
+                    Op::And | Op::Or | Op::Xor => depth -= 1,
+                    Op::Not | Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => {}
+                }
+                max = max.max(depth);
+            }
+            max
+        };
+
+        self.assumptions
+            .iter()
+            .map(|ops| depth(ops))
+            .fold(depth(&self.lhs), usize::max)
+    }
 }
 
-pub struct Compiler<'input> {
-    equation: parser::Equation<'input>,
+pub struct Compiler {
+    equation: parser::Equation,
 }
 
-impl<'input> Compiler<'input> {
-    pub fn new(equation: parser::Equation<'input>) -> Self {
+impl Compiler {
+    pub fn new(equation: parser::Equation) -> Self {
         Self { equation }
     }
 
+    /// Compile the expression and assumptions, tidying the ops with the
+    /// peephole optimiser.
     pub fn compile(&self) -> Equation {
-        let mut lhs = vec![];
-        Self::compile_expr(&mut lhs, &self.equation.lhs);
+        let mut equation = self.compile_unoptimised();
+        equation.lhs = peephole::optimise(&equation.lhs);
+        for ops in &mut equation.assumptions {
+            *ops = peephole::optimise(ops);
+        }
+
+        equation
+    }
+
+    /// Like `compile`, but with each `AND` and `OR` evaluating its shorter
+    /// operand first and jumping over the other when the first decides the
+    /// result, so that rows where it does cost less to evaluate.
+    pub fn compile_short_circuit(&self) -> Equation {
+        let mut equation = self.compile();
+        equation.lhs = short_circuit(&equation.lhs);
+        for ops in &mut equation.assumptions {
+            *ops = short_circuit(ops);
+        }
+
+        equation
+    }
+
+    /// Compile the expression and assumptions as written.
+    pub fn compile_unoptimised(&self) -> Equation {
+        let compile = |expr: &SpanExpr| {
+            let mut ops = vec![];
+            Self::compile_expr(&mut ops, expr.expr());
+            debug_assert!(
+                decompile(&ops).is_some_and(|tree| simplify::same(tree.expr(), expr.expr())),
+                "compiled ops don't keep the operand order of {}",
+                expr
+            );
+            ops
+        };
 
         Equation {
             inputs: self.equation.inputs.clone(),
-            lhs,
+            lhs: compile(&self.equation.lhs),
             output: self.equation.output,
+            assumptions: self.equation.assumptions.iter().map(compile).collect(),
+        }
+    }
+
+    /// Fold constants out of the expression and assumptions, then drop the
+    /// inputs that no longer appear in either, returning their names. Such an
+    /// input can't change the output, so its column would only double the
+    /// size of the table.
+    pub fn eliminate_dead_inputs(&mut self) -> Vec<Symbol> {
+        let equation = &mut self.equation;
+        equation.lhs = fold(&equation.lhs);
+        equation.assumptions = equation.assumptions.iter().map(fold).collect();
+
+        let mut live = vec![false; equation.inputs.len()];
+        for expr in equation.assumptions.iter().chain([&equation.lhs]) {
+            mark_live(expr.expr(), &mut live);
+        }
+        if live.iter().all(|l| *l) {
+            return vec![];
         }
+
+        let inputs = equation.inputs.clone();
+        let names = |keep: bool| -> Vec<Symbol> {
+            inputs
+                .iter()
+                .zip(&live)
+                .filter(|(_, live)| **live == keep)
+                .map(|(name, _)| *name)
+                .collect()
+        };
+        let (kept, dead) = (names(true), names(false));
+        equation.lhs = equiv::remap(&equation.lhs, &inputs, &kept);
+        equation.assumptions = equation
+            .assumptions
+            .iter()
+            .map(|expr| equiv::remap(expr, &inputs, &kept))
+            .collect();
+        equation.inputs = kept;
+
+        dead
     }
 
-    fn compile_expr(ops: &mut Vec<Op>, expr: &SpanExpr) {
-        match &expr.node {
-            Expr::Bool(b) => ops.push(Op::Push(*b)),
-            Expr::Var(v) => ops.push(Op::Load(*v)),
+    fn compile_expr(ops: &mut Vec<Op>, expr: ExprRef) {
+        match expr.node() {
+            Expr::Bool(b) => ops.push(Op::Push(b)),
+            Expr::Var(v) => ops.push(Op::Load(v)),
             Expr::Not(e) => {
                 Self::compile_expr(ops, e);
                 ops.push(Op::Not);
@@ -50,3 +205,281 @@ impl<'input> Compiler<'input> {
         }
     }
 }
+
+/// `expr` with its constant operands folded away. Operands that survive keep
+/// their spans.
+pub(crate) fn fold(expr: &SpanExpr) -> SpanExpr {
+    SpanExpr::build(|arena| fold_in(expr.expr(), arena))
+}
+
+fn fold_in(expr: ExprRef, arena: &mut Arena) -> ExprId {
+    let span = expr.span();
+    let node = match expr.node() {
+        Expr::Bool(_) | Expr::Var(_) => return arena.copy(expr),
+        Expr::Not(e) => match fold_in(e, arena) {
+            e if arena[e] == Node::Bool(true) => Node::Bool(false),
+            e if arena[e] == Node::Bool(false) => Node::Bool(true),
+            e => Node::Not(e),
+        },
+        Expr::BinOp { op, lhs, rhs } => {
+            let (lhs, rhs) = (fold_in(lhs, arena), fold_in(rhs, arena));
+            let is = |id: ExprId, b: bool| arena[id] == Node::Bool(b);
+            match (op, lhs, rhs) {
+                (BinOp::And, c, e) | (BinOp::And, e, c) if is(c, true) => return e,
+                (BinOp::Or | BinOp::Xor, c, e) | (BinOp::Or | BinOp::Xor, e, c) if is(c, false) => {
+                    return e
+                }
+                (BinOp::And, c, _) | (BinOp::And, _, c) if is(c, false) => Node::Bool(false),
+                (BinOp::Or, c, _) | (BinOp::Or, _, c) if is(c, true) => Node::Bool(true),
+                (BinOp::Xor, c, e) | (BinOp::Xor, e, c) if is(c, true) => match arena[e] {
+                    Node::Bool(b) => Node::Bool(!b),
+                    _ => Node::Not(e),
+                },
+                (op, lhs, rhs) => Node::BinOp { op, lhs, rhs },
+            }
+        }
+    };
+
+    arena.push(span, node)
+}
+
+/// Check that `ops` only load inputs below `num_inputs`, never pop from an
+/// empty stack, only jump forwards to where the stack is as deep as at the
+/// jump, and leave exactly one value. Ops that pass can be run by the VM
+/// without panicking, so this is worth running on bytecode from anywhere
+/// untrusted, as `bytecode::decode` does.
+pub fn verify(ops: &[Op], num_inputs: usize) -> Result<(), VerifyError> {
+    let mut depth = 0usize;
+    // The jump to each target seen so far, and the depth it expects there
+    let mut targets: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    let arrive = |targets: &mut BTreeMap<_, _>, index, depth| match targets.remove(&index) {
+        Some((from, expected)) if expected != depth => Err(VerifyError::BadJump {
+            index: from,
+            target: index,
+        }),
+        _ => Ok(()),
+    };
+
+    for (index, op) in ops.iter().enumerate() {
+        arrive(&mut targets, index, depth)?;
+        let (pops, pushes) = match *op {
+            Op::Push(_) => (0, 1),
+            Op::Load(input) if input < num_inputs => (0, 1),
+            Op::Load(input) => {
+                return Err(VerifyError::InputOutOfRange {
+                    index,
+                    input,
+                    inputs: num_inputs,
+                })
+            }
+            Op::Not => (1, 1),
+            Op::And | Op::Or | Op::Xor => (2, 1),
+            Op::JumpIfFalse(target) | Op::JumpIfTrue(target) => {
+                let bad = VerifyError::BadJump { index, target };
+                if target <= index || target > ops.len() || depth == 0 {
+                    return Err(bad);
+                }
+                match targets.get(&target) {
+                    Some((_, expected)) if *expected != depth => return Err(bad),
+                    _ => targets.insert(target, (index, depth)),
+                };
+                (0, 0)
+            }
+        };
+        depth = depth
+            .checked_sub(pops)
+            .ok_or(VerifyError::StackUnderflow(index))?
+            + pushes;
+    }
+    arrive(&mut targets, ops.len(), depth)?;
+
+    match depth {
+        1 => Ok(()),
+        depth => Err(VerifyError::UnbalancedStack(depth)),
+    }
+}
+
+/// Rebuild the tree that straight-line `ops` were compiled from, following
+/// the operand order documented on `Op`, or `None` if they don't leave a
+/// single value or contain jumps. Spans are empty.
+pub fn decompile(ops: &[Op]) -> Option<SpanExpr> {
+    let mut arena = Arena::new();
+    let mut stack: Vec<ExprId> = vec![];
+    for op in ops {
+        let node = match op {
+            Op::Push(b) => Node::Bool(*b),
+            Op::Load(i) => Node::Var(*i),
+            Op::Not => Node::Not(stack.pop()?),
+            Op::And | Op::Or | Op::Xor => {
+                let lhs = stack.pop()?;
+                let rhs = stack.pop()?;
+                let op = match op {
+                    Op::And => BinOp::And,
+                    Op::Or => BinOp::Or,
+                    _ => BinOp::Xor,
+                };
+                Node::BinOp { op, lhs, rhs }
+            }
+            Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => return None,
+        };
+        stack.push(arena.push((0..0).into(), node));
+    }
+
+    match stack[..] {
+        [root] => Some(SpanExpr::new(arena, root)),
+        _ => None,
+    }
+}
+
+/// Re-emit straight-line `ops` with jumps, as `compile_short_circuit`
+/// describes.
+fn short_circuit(ops: &[Op]) -> Vec<Op> {
+    // Where the value that each op leaves on the stack starts
+    let mut starts: Vec<usize> = vec![];
+    for (i, op) in ops.iter().enumerate() {
+        starts.push(match op {
+            Op::Push(_) | Op::Load(_) => i,
+            Op::Not => starts[i - 1],
+            Op::And | Op::Or | Op::Xor => starts[starts[i - 1] - 1],
+            Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => return ops.to_vec(),
+        });
+    }
+
+    let mut out = vec![];
+    emit_short_circuit(ops, &starts, ops.len() - 1, &mut out);
+    out
+}
+
+/// Emit the value whose last op is `ops[end]`.
+fn emit_short_circuit(ops: &[Op], starts: &[usize], end: usize, out: &mut Vec<Op>) {
+    let op = ops[end];
+    match op {
+        Op::Push(_) | Op::Load(_) => out.push(op),
+        Op::Not => {
+            emit_short_circuit(ops, starts, end - 1, out);
+            out.push(op);
+        }
+        _ => {
+            let lhs = end - 1;
+            let rhs = starts[lhs] - 1;
+            let jump = match op {
+                Op::And => Op::JumpIfFalse,
+                Op::Or => Op::JumpIfTrue,
+                _ => {
+                    emit_short_circuit(ops, starts, rhs, out);
+                    emit_short_circuit(ops, starts, lhs, out);
+                    out.push(op);
+                    return;
+                }
+            };
+
+            let (first, second) = if end - starts[lhs] <= starts[lhs] - starts[rhs] {
+                (lhs, rhs)
+            } else {
+                (rhs, lhs)
+            };
+            emit_short_circuit(ops, starts, first, out);
+            let at = out.len();
+            out.push(jump(0));
+            emit_short_circuit(ops, starts, second, out);
+            out.push(op);
+            out[at] = jump(out.len());
+        }
+    }
+}
+
+fn mark_live(expr: ExprRef, live: &mut [bool]) {
+    match expr.node() {
+        Expr::Bool(_) => {}
+        Expr::Var(v) => live[v] = true,
+        Expr::Not(e) => mark_live(e, live),
+        Expr::BinOp { lhs, rhs, .. } => {
+            mark_live(lhs, live);
+            mark_live(rhs, live);
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use crate::vm::VM;
+
+    let mut parser = parser::Parser::new("A AND NOT (B XOR 1) = Q");
+    let compiler = Compiler::new(parser.parse_equation().unwrap());
+    assert_eq!(
+        compiler.compile_unoptimised().disassemble(),
+        "0000 PUSH true\n0001 LOAD B\n0002 XOR\n0003 NOT\n0004 LOAD A\n0005 AND\n"
+    );
+    assert_eq!(
+        compiler.compile().disassemble(),
+        "0000 LOAD B\n0001 LOAD A\n0002 AND\n"
+    );
+    assert_eq!(compiler.compile().max_stack_depth(), 2);
+    // Both operands of the OR need two slots, and one is held while the
+    // other is computed
+    let balanced = parser::Parser::new("(A AND B) OR (C AND D) = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(Compiler::new(balanced).compile().max_stack_depth(), 3);
+
+    // Swapping the operands of a binary op is caught, even though it makes
+    // no difference to the table
+    let equation = parser::Parser::new("(A OR B) AND NOT C = Q")
+        .parse_equation()
+        .unwrap();
+    let ops = Compiler::new(equation.clone()).compile_unoptimised().lhs;
+    assert!(simplify::same(
+        decompile(&ops).unwrap().expr(),
+        equation.lhs.expr()
+    ));
+    // `NOT C` is the right operand, so it is pushed first
+    let swapped = [ops[2], ops[3], ops[4], ops[0], ops[1], ops[5]];
+    assert!(!simplify::same(
+        decompile(&swapped).unwrap().expr(),
+        equation.lhs.expr()
+    ));
+    assert_eq!(decompile(&[Op::Load(0), Op::And]), None);
+    assert_eq!(verify(&swapped, 3), Ok(()));
+    assert_eq!(
+        verify(&swapped, 2),
+        Err(VerifyError::InputOutOfRange {
+            index: 3,
+            input: 2,
+            inputs: 2
+        })
+    );
+    assert_eq!(
+        verify(&[Op::Load(0), Op::And], 1),
+        Err(VerifyError::StackUnderflow(1))
+    );
+    assert_eq!(
+        verify(&[Op::Load(0), Op::Load(0)], 1),
+        Err(VerifyError::UnbalancedStack(2))
+    );
+
+    let mut parser = parser::Parser::new("A AND ((B XOR C) OR NOT D) = Q");
+    let compiler = Compiler::new(parser.parse_equation().unwrap());
+    let short = compiler.compile_short_circuit();
+    assert_eq!(
+        short.disassemble(),
+        "0000 LOAD A\n0001 JUMP IF FALSE 10\n0002 LOAD D\n0003 NOT\n\
+         0004 JUMP IF TRUE 9\n0005 LOAD C\n0006 LOAD B\n0007 XOR\n0008 OR\n0009 AND\n"
+    );
+    let mut vm = VM::new(compiler.compile());
+    let mut short_vm = VM::new(short);
+    for row in 0..16usize {
+        let inputs: Vec<bool> = (0..4).map(|i| row >> i & 1 == 1).collect();
+        assert_eq!(short_vm.eval(&inputs), vm.eval(&inputs));
+    }
+
+    // B is only ever ANDed with 0, and C is in an assumption
+    let mut parser = parser::Parser::new("assume C; (B AND 0) OR (A XOR NOT 0) = Q");
+    let mut compiler = Compiler::new(parser.parse_equation().unwrap());
+    assert_eq!(compiler.eliminate_dead_inputs(), ["B"]);
+    let equation = compiler.compile();
+    assert_eq!(equation.inputs, ["C", "A"]);
+    assert_eq!(
+        equation.disassemble(),
+        "0000 LOAD A\n0001 NOT\nassume 0:\n0000 LOAD C\n"
+    );
+}
@@ -0,0 +1,235 @@
+use std::{fmt::Write, str::FromStr};
+
+use booltable::{codegen::Lang, export::Format};
+
+use crate::cli::{Flag, FLAGS, SUBCOMMANDS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    pub const NAMES: &'static [&'static str] = &["bash", "zsh", "fish", "powershell"];
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::Powershell),
+            _ => Err(format!(
+                "unknown shell '{}', expected one of: {}",
+                s,
+                Shell::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// The fixed set of values a flag accepts, if there is one.
+fn choices(flag: &Flag) -> Option<Vec<String>> {
+    match flag.long {
+        "format" => Some(Format::ALL.iter().map(ToString::to_string).collect()),
+        "lang" => Some(Lang::ALL.iter().map(ToString::to_string).collect()),
+        "property" => Some(
+            ["satisfiable", "tautology", "contradiction"]
+                .map(String::from)
+                .to_vec(),
+        ),
+        "dump" => Some(
+            ["tokens", "ast", "sexpr", "bytecode", "registers", "all"]
+                .map(String::from)
+                .to_vec(),
+        ),
+        "sort" => Some(["index", "output", "ones"].map(String::from).to_vec()),
+        "color" => Some(["auto", "always", "never"].map(String::from).to_vec()),
+        _ => None,
+    }
+}
+
+fn is_path(flag: &Flag) -> bool {
+    matches!(flag.long, "output" | "config")
+}
+
+pub fn generate(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+        Shell::Powershell => powershell(),
+    }
+}
+
+fn bash() -> String {
+    let mut out = String::from(
+        "_booltable() {\n    local cur prev\n    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n    \
+         prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n\n    case \"$prev\" in\n",
+    );
+
+    for flag in FLAGS.iter().filter(|f| f.value.is_some()) {
+        let names = match flag.short {
+            Some(short) => format!("-{}|--{}", short, flag.long),
+            None => format!("--{}", flag.long),
+        };
+        let action = match choices(flag) {
+            Some(values) => format!("compgen -W \"{}\" -- \"$cur\"", values.join(" ")),
+            None if is_path(flag) => "compgen -f -- \"$cur\"".to_string(),
+            None => "true".to_string(),
+        };
+        writeln!(
+            out,
+            "        {})\n            COMPREPLY=($({}))\n            return\n            ;;",
+            names, action
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "        completions)\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return\n            ;;",
+        Shell::NAMES.join(" ")
+    )
+    .unwrap();
+
+    let flags: Vec<String> = FLAGS
+        .iter()
+        .flat_map(|f| {
+            let short = f.short.map(|c| format!("-{}", c));
+            short.into_iter().chain([format!("--{}", f.long)])
+        })
+        .collect();
+    let subcommands: Vec<&str> = SUBCOMMANDS.iter().map(|s| s.name).collect();
+    write!(
+        out,
+        "    esac\n\n    if [[ \"$cur\" == -* ]]; then\n        \
+         COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    \
+         elif [[ $COMP_CWORD -eq 1 ]]; then\n        \
+         COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    fi\n}}\n\n\
+         complete -F _booltable booltable\n",
+        flags.join(" "),
+        subcommands.join(" ")
+    )
+    .unwrap();
+
+    out
+}
+
+fn zsh() -> String {
+    let escape = |s: &str| {
+        s.replace('\'', "'\\''")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+    };
+
+    let mut out = String::from("#compdef booltable\n\n_booltable() {\n    _arguments \\\n");
+    for flag in FLAGS {
+        let names = match flag.short {
+            Some(short) => format!("'(-{0} --{1})'{{-{0},--{1}}}'", short, flag.long),
+            None => format!("'--{}", flag.long),
+        };
+        let value = match (flag.value, choices(flag)) {
+            (Some(name), Some(values)) => format!(":{}:({})", name, values.join(" ")),
+            (Some(name), None) if is_path(flag) => format!(":{}:_files", name),
+            (Some(name), None) => format!(":{}:", name),
+            (None, _) => String::new(),
+        };
+        writeln!(out, "        {}[{}]{}' \\", names, escape(flag.help), value).unwrap();
+    }
+
+    let subcommands: Vec<String> = SUBCOMMANDS
+        .iter()
+        .map(|s| format!("{}\\:\"{}\"", s.name, escape(s.help).replace('"', "\\\"")))
+        .collect();
+    writeln!(out, "        '1:command:(({}))' \\", subcommands.join(" ")).unwrap();
+    writeln!(
+        out,
+        "        '*::argument:_default'\n}}\n\n_booltable \"$@\""
+    )
+    .unwrap();
+
+    out
+}
+
+fn fish() -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('\'', "\\'");
+
+    let mut out = String::from("complete -c booltable -f\n");
+    for sub in SUBCOMMANDS {
+        writeln!(
+            out,
+            "complete -c booltable -n __fish_use_subcommand -a {} -d '{}'",
+            sub.name,
+            escape(sub.help)
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "complete -c booltable -n '__fish_seen_subcommand_from completions' -a '{}'",
+        Shell::NAMES.join(" ")
+    )
+    .unwrap();
+
+    for flag in FLAGS {
+        let mut line = String::from("complete -c booltable");
+        if let Some(short) = flag.short {
+            write!(line, " -s {}", short).unwrap();
+        }
+        write!(line, " -l {} -d '{}'", flag.long, escape(flag.help)).unwrap();
+        if flag.value.is_some() {
+            line.push_str(" -r");
+            match choices(flag) {
+                Some(values) => write!(line, " -a '{}'", values.join(" ")).unwrap(),
+                None if is_path(flag) => line.push_str(" -F"),
+                None => {}
+            }
+        }
+        writeln!(out, "{}", line).unwrap();
+    }
+
+    out
+}
+
+fn powershell() -> String {
+    let escape = |s: &str| s.replace('\'', "''");
+    let item = |text: &str, kind: &str, help: &str| {
+        format!(
+            "        [CompletionResult]::new('{0}', '{0}', [CompletionResultType]::{1}, '{2}')\n",
+            text,
+            kind,
+            escape(help)
+        )
+    };
+
+    let mut out = String::from(
+        "using namespace System.Management.Automation\n\n\
+         Register-ArgumentCompleter -Native -CommandName booltable -ScriptBlock {\n    \
+         param($wordToComplete, $commandAst, $cursorPosition)\n\n    $completions = @(\n",
+    );
+    for sub in SUBCOMMANDS {
+        out.push_str(&item(sub.name, "ParameterValue", sub.help));
+    }
+    for flag in FLAGS {
+        if let Some(short) = flag.short {
+            out.push_str(&item(&format!("-{}", short), "ParameterName", flag.help));
+        }
+        out.push_str(&item(
+            &format!("--{}", flag.long),
+            "ParameterName",
+            flag.help,
+        ));
+    }
+    out.push_str(
+        "    )\n\n    $completions.Where{ $_.CompletionText -like \"$wordToComplete*\" } |\n        \
+         Sort-Object -Property ListItemText\n}\n",
+    );
+
+    out
+}
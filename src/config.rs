@@ -0,0 +1,325 @@
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[cfg(feature = "image")]
+use booltable::image::Theme;
+use booltable::{
+    export::{Format, TableStyle},
+    parser::Dialect,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Colour {
+    pub fn enabled(self) -> bool {
+        match self {
+            // See https://no-color.org
+            Colour::Auto => {
+                io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+            Colour::Always => true,
+            Colour::Never => false,
+        }
+    }
+}
+
+impl FromStr for Colour {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Colour::Auto),
+            "always" => Ok(Colour::Always),
+            "never" => Ok(Colour::Never),
+            _ => Err(format!(
+                "unknown colour setting '{}', expected auto, always or never",
+                s
+            )),
+        }
+    }
+}
+
+/// Defaults read from the config file, which command line flags override.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub dialect: Dialect,
+    pub format: Format,
+    pub style: TableStyle,
+    pub colour: Colour,
+    pub max_inputs: usize,
+    /// Tables with at least this many inputs show a progress bar.
+    pub progress_inputs: usize,
+    /// Follow markdown tables with the number of true and false rows.
+    pub summary: bool,
+    /// Print only ASCII, without colour.
+    pub plain: bool,
+    /// The colours of PNG tables.
+    #[cfg(feature = "image")]
+    pub theme: Theme,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::Words,
+            format: Format::Markdown,
+            style: TableStyle::default(),
+            colour: Colour::Auto,
+            max_inputs: 20,
+            progress_inputs: 18,
+            summary: false,
+            plain: false,
+            #[cfg(feature = "image")]
+            theme: Theme::default(),
+        }
+    }
+}
+
+pub(crate) struct Entry {
+    pub key: String,
+    pub line: usize,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/booltable/config.toml`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(base.join("booltable").join("config.toml"))
+    }
+
+    /// Load the config from `path`, or from the default path if there is a
+    /// file there. A missing default config isn't an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                Self::parse(&source).map_err(|err| format!("{}: {}", path.display(), err))
+            }
+            Err(err) if !required && err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        for Entry { key, line, value } in parse_toml(source)? {
+            let at = |err: String| format!("line {}: {}", line, err);
+            let string = || match &value {
+                Value::String(s) => Ok(s.clone()),
+                _ => Err(at(format!("'{}' should be a string", key))),
+            };
+
+            match key.as_str() {
+                "dialect" => config.dialect = string()?.parse().map_err(at)?,
+                "format" => config.format = string()?.parse().map_err(at)?,
+                "true" => config.style.true_symbol = string()?,
+                "false" => config.style.false_symbol = string()?,
+                "row_order" => config.style.row_order = string()?.parse().map_err(at)?,
+                "sort" => config.style.sort = string()?.parse().map_err(at)?,
+                "align" => config.style.align = string()?.parse().map_err(at)?,
+                "max_header_width" => match value {
+                    Value::Integer(n) if n > 0 => config.style.max_header_width = Some(n as usize),
+                    _ => {
+                        return Err(at(
+                            "'max_header_width' should be a positive integer".to_string()
+                        ))
+                    }
+                },
+                "vertical_headers" => match value {
+                    Value::Boolean(b) => config.style.vertical_headers = b,
+                    _ => return Err(at("'vertical_headers' should be true or false".to_string())),
+                },
+                "summary" => match value {
+                    Value::Boolean(b) => config.summary = b,
+                    _ => return Err(at("'summary' should be true or false".to_string())),
+                },
+                "plain" => match value {
+                    Value::Boolean(b) => config.plain = b,
+                    _ => return Err(at("'plain' should be true or false".to_string())),
+                },
+                "group_headers" => match value {
+                    Value::Boolean(b) => config.style.group_headers = b,
+                    _ => return Err(at("'group_headers' should be true or false".to_string())),
+                },
+                #[cfg(feature = "image")]
+                "image.theme" => config.theme = string()?.parse().map_err(at)?,
+                "colour" | "color" => {
+                    config.colour = match value {
+                        Value::Boolean(true) => Colour::Always,
+                        Value::Boolean(false) => Colour::Never,
+                        _ => string()?.parse().map_err(at)?,
+                    }
+                }
+                "max_inputs" => match value {
+                    Value::Integer(n) if (0..64).contains(&n) => config.max_inputs = n as usize,
+                    _ => return Err(at("'max_inputs' should be an integer below 64".to_string())),
+                },
+                "progress_inputs" => match value {
+                    Value::Integer(n) if (0..64).contains(&n) => {
+                        config.progress_inputs = n as usize
+                    }
+                    _ => {
+                        return Err(at(
+                            "'progress_inputs' should be an integer below 64".to_string()
+                        ))
+                    }
+                },
+                _ => return Err(at(format!("unknown key '{}'", key))),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parse the subset of TOML needed for a flat config: `key = value` pairs with
+/// string, integer and boolean values, comments, and `[table]` headers, which
+/// prefix the keys below them with `table.`.
+pub(crate) fn parse_toml(source: &str) -> Result<Vec<Entry>, String> {
+    let mut entries: Vec<Entry> = vec![];
+    let mut table = String::new();
+
+    for (number, line) in source.lines().enumerate().map(|(i, l)| (i + 1, l)) {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = format!("{}.", name.trim());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected 'key = value'", number))?;
+        let key = format!("{}{}", table, key.trim());
+        let value = parse_value(value.trim()).map_err(|err| format!("line {}: {}", number, err))?;
+        if entries.iter().any(|entry| entry.key == key) {
+            return Err(format!("line {}: '{}' is set more than once", number, key));
+        }
+        entries.push(Entry {
+            key,
+            line: number,
+            value,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+fn parse_value(value: &str) -> Result<Value, String> {
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut string = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            string.push(match c {
+                '\\' => match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    other => return Err(format!("invalid escape '\\{}'", other.unwrap_or(' '))),
+                },
+                c => c,
+            });
+        }
+        return Ok(Value::String(string));
+    }
+
+    match value {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        _ => value
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("invalid value '{}'", value)),
+    }
+}
+
+/// `string` as a TOML string, the way `parse_value` reads it back.
+pub(crate) fn quote(string: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in string.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[test]
+fn test() {
+    let config = Config::parse(
+        "# defaults\ndialect = \"symbols\"\ntrue = \"H\" # high\nfalse = \"#\"\nmax_inputs = 8\n",
+    )
+    .unwrap();
+    assert_eq!(config.dialect, Dialect::Symbols);
+    assert_eq!(config.style.true_symbol, "H");
+    assert_eq!(config.style.false_symbol, "#");
+    assert_eq!(config.max_inputs, 8);
+    assert!(Config::parse("max_inputs = \"8\"").is_err());
+    let display = Config::parse("align = \"centre\"\nmax_header_width = 4\n").unwrap();
+    assert_eq!(display.style.align, booltable::export::Align::Centre);
+    assert_eq!(display.style.max_header_width, Some(4));
+
+    let tricky = "assume A;\n\"B\" \\ C = Q";
+    assert_eq!(
+        parse_value(&quote(tricky)),
+        Ok(Value::String(tricky.to_string()))
+    );
+}
@@ -0,0 +1,240 @@
+//! Covers, sets of cubes standing for the union of their minterms, with the
+//! set operations on them that espresso-style minimisers are built from. Any
+//! expression can be turned into a cover, and a cover back into a sum of
+//! products, so functions can be combined as on-sets and off-sets.
+
+use crate::{
+    assignments,
+    cube::Cube,
+    minimize,
+    parser::{BinOp, Expr, ExprRef, SpanExpr},
+    vm::TruthTable,
+};
+
+/// A set of cubes over `num_inputs` inputs. No cube is contained in another
+/// after any of the operations, but they can overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cover {
+    pub num_inputs: usize,
+    pub cubes: Vec<Cube>,
+}
+
+impl Cover {
+    pub fn new(num_inputs: usize, cubes: Vec<Cube>) -> Self {
+        Self { num_inputs, cubes }
+    }
+
+    /// The cover of no minterms.
+    pub fn empty(num_inputs: usize) -> Self {
+        Self::new(num_inputs, vec![])
+    }
+
+    /// The cover of every minterm, as the cube with no literals.
+    pub fn universe(num_inputs: usize) -> Self {
+        let all = (1 << num_inputs) - 1;
+        Self::new(
+            num_inputs,
+            vec![Cube {
+                value: 0,
+                mask: all,
+            }],
+        )
+    }
+
+    /// The rows on which the table is true, one cube each. Rows left out by
+    /// assumptions are in neither this nor `off_set`.
+    pub fn on_set(table: &TruthTable) -> Self {
+        Self::of_rows(table, true)
+    }
+
+    /// The rows on which the table is false, one cube each.
+    pub fn off_set(table: &TruthTable) -> Self {
+        Self::of_rows(table, false)
+    }
+
+    fn of_rows(table: &TruthTable, output: bool) -> Self {
+        let cubes = table
+            .rows()
+            .filter(|row| row.output == output)
+            .map(|row| Cube {
+                value: assignments::to_row(row.assignment),
+                mask: 0,
+            })
+            .collect();
+
+        Self::new(table.input_names.len(), cubes)
+    }
+
+    /// The cover of the minterms on which `expr`, over `num_inputs` inputs,
+    /// is true, built up from the operations below.
+    pub fn from_expr(expr: ExprRef, num_inputs: usize) -> Self {
+        match expr.node() {
+            Expr::Bool(true) => Self::universe(num_inputs),
+            Expr::Bool(false) => Self::empty(num_inputs),
+            Expr::Var(v) => {
+                let all = (1 << num_inputs) - 1;
+                let bit = 1 << (num_inputs - 1 - v);
+                Self::new(
+                    num_inputs,
+                    vec![Cube {
+                        value: bit,
+                        mask: all & !bit,
+                    }],
+                )
+            }
+            Expr::Not(e) => Self::from_expr(e, num_inputs).complement(),
+            Expr::BinOp { op, lhs, rhs } => {
+                let lhs = Self::from_expr(lhs, num_inputs);
+                let rhs = Self::from_expr(rhs, num_inputs);
+                match op {
+                    BinOp::And => lhs.intersection(&rhs),
+                    BinOp::Or => lhs.union(&rhs),
+                    BinOp::Xor => lhs.sharp(&rhs).union(&rhs.sharp(&lhs)),
+                }
+            }
+        }
+    }
+
+    /// The cover as a sum of products.
+    pub fn to_expr(&self) -> SpanExpr {
+        minimize::to_expr(&self.cubes, self.num_inputs)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cubes.is_empty()
+    }
+
+    pub fn covers(&self, minterm: usize) -> bool {
+        self.cubes.iter().any(|cube| cube.covers(minterm))
+    }
+
+    /// Every minterm of the cover, in increasing order.
+    pub fn minterms(&self) -> Vec<usize> {
+        (0..1 << self.num_inputs)
+            .filter(|m| self.covers(*m))
+            .collect()
+    }
+
+    /// The minterms of either cover.
+    pub fn union(&self, other: &Cover) -> Cover {
+        let mut cubes = self.cubes.clone();
+        cubes.extend(&other.cubes);
+        self.tidy(cubes)
+    }
+
+    /// The minterms of both covers.
+    pub fn intersection(&self, other: &Cover) -> Cover {
+        let cubes = self
+            .cubes
+            .iter()
+            .flat_map(|a| other.cubes.iter().filter_map(|b| a.intersection(b)))
+            .collect();
+        self.tidy(cubes)
+    }
+
+    /// The minterms of this cover that aren't in `other`.
+    pub fn sharp(&self, other: &Cover) -> Cover {
+        let mut cubes = self.cubes.clone();
+        for b in &other.cubes {
+            let sharpened = cubes
+                .iter()
+                .flat_map(|a| sharp(a, b, self.num_inputs))
+                .collect();
+            cubes = self.tidy(sharpened).cubes;
+        }
+
+        self.tidy(cubes)
+    }
+
+    /// The minterms that aren't in the cover.
+    pub fn complement(&self) -> Cover {
+        Self::universe(self.num_inputs).sharp(self)
+    }
+
+    /// `cubes` as a cover, without those contained in another, keeping the
+    /// first of any that are the same.
+    fn tidy(&self, cubes: Vec<Cube>) -> Cover {
+        let kept = cubes
+            .iter()
+            .enumerate()
+            .filter(|(i, cube)| {
+                !cubes
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| j != *i && other.contains(cube) && (other != *cube || j < *i))
+            })
+            .map(|(_, cube)| *cube)
+            .collect();
+
+        Self::new(self.num_inputs, kept)
+    }
+}
+
+/// The minterms of `a` that aren't in `b`: for each literal of `b` that `a`
+/// doesn't have, `a` with that literal's opposite.
+fn sharp(a: &Cube, b: &Cube, num_inputs: usize) -> Vec<Cube> {
+    if a.intersection(b).is_none() {
+        return vec![*a];
+    }
+
+    (0..num_inputs)
+        .map(|i| 1 << (num_inputs - 1 - i))
+        .filter(|bit| a.mask & bit != 0 && b.mask & bit == 0)
+        .map(|bit| Cube {
+            value: a.value | (!b.value & bit),
+            mask: a.mask & !bit,
+        })
+        .collect()
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, interp, parser::Parser, vm::VM};
+
+    for source in [
+        "(A AND B) OR (C AND (A OR B)) = Q",
+        "A XOR B XOR NOT C = Q",
+        "NOT (A AND (B OR NOT C)) = Q",
+        "A AND NOT A = Q",
+    ] {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let n = equation.inputs.len();
+        let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+        let on = Cover::from_expr(equation.lhs.expr(), n);
+        assert_eq!(
+            on.minterms(),
+            Cover::on_set(&table).minterms(),
+            "{}",
+            source
+        );
+        assert_eq!(
+            on.complement().minterms(),
+            Cover::off_set(&table).minterms(),
+            "{}",
+            source
+        );
+
+        // The sum of products means the same as the cover
+        let sop = on.to_expr();
+        for row in 0..1 << n {
+            let inputs = assignments::to_bools(row, n);
+            assert_eq!(interp::eval(sop.expr(), &inputs), on.covers(row));
+        }
+    }
+
+    // Every equation numbers A, B and C the same way
+    let mut session = crate::parser::ParserSession::new();
+    session.parse_equation("A AND B AND C = Q").unwrap();
+    let mut parse = |source| {
+        let equation = session.parse_equation(source).unwrap();
+        Cover::from_expr(equation.lhs.expr(), 3)
+    };
+    let (a, b) = (parse("A OR B = Q"), parse("B OR C = Q"));
+    assert_eq!(a.union(&b).minterms(), [1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(a.intersection(&b).minterms(), [2, 3, 5, 6, 7]);
+    assert_eq!(a.sharp(&b).minterms(), [4]);
+    assert!(a.sharp(&a).is_empty());
+    assert_eq!(Cover::universe(3).complement(), Cover::empty(3));
+    // Contained cubes are dropped
+    assert_eq!(parse("A OR (A AND B) = Q").cubes.len(), 1);
+}
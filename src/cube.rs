@@ -0,0 +1,137 @@
+//! Cubes, the products of literals that covers are made of. A cube over `n`
+//! inputs gives each input a value of 0 or 1, or leaves it as a don't-care,
+//! and stands for the rows that agree with it on every input it gives.
+
+use alloc::string::String;
+
+/// A product term over the inputs of a table. Bits set in `mask` are
+/// don't-cares; the remaining bits of `value` give each literal's polarity.
+/// Bit `n - 1 - i` corresponds to input `i`, matching the table's row order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub value: usize,
+    pub mask: usize,
+}
+
+impl Cube {
+    /// The cube giving input `i` the value `literals[i]`, or leaving it as a
+    /// don't-care for `None`.
+    pub fn from_literals(literals: &[Option<bool>]) -> Cube {
+        literals
+            .iter()
+            .fold(Cube { value: 0, mask: 0 }, |cube, literal| Cube {
+                value: cube.value << 1 | (*literal == Some(true)) as usize,
+                mask: cube.mask << 1 | literal.is_none() as usize,
+            })
+    }
+
+    /// The value the cube gives input `i` of `num_inputs`, or `None` if it's
+    /// a don't-care.
+    pub fn get(&self, i: usize, num_inputs: usize) -> Option<bool> {
+        let bit = 1 << (num_inputs - 1 - i);
+        (self.mask & bit == 0).then_some(self.value & bit != 0)
+    }
+
+    /// The cube as `0`, `1` and `-` for each of `num_inputs` inputs, as in
+    /// `1-0` for `A.!C`.
+    pub fn pattern(&self, num_inputs: usize) -> String {
+        (0..num_inputs)
+            .map(|i| match self.get(i, num_inputs) {
+                Some(true) => '1',
+                Some(false) => '0',
+                None => '-',
+            })
+            .collect()
+    }
+
+    pub fn covers(&self, minterm: usize) -> bool {
+        minterm & !self.mask == self.value
+    }
+
+    pub fn literals(&self, num_inputs: usize) -> usize {
+        num_inputs - self.mask.count_ones() as usize
+    }
+
+    /// Whether every minterm of `other` is one of this cube's.
+    pub fn contains(&self, other: &Cube) -> bool {
+        other.mask & !self.mask == 0 && (other.value ^ self.value) & !self.mask == 0
+    }
+
+    /// The minterms of both cubes, as a cube, or `None` if they have none in
+    /// common because some input has opposite values in them.
+    pub fn intersection(&self, other: &Cube) -> Option<Cube> {
+        if (self.value ^ other.value) & !self.mask & !other.mask != 0 {
+            return None;
+        }
+        let mask = self.mask & other.mask;
+
+        Some(Cube {
+            value: (self.value | other.value) & !mask,
+            mask,
+        })
+    }
+
+    /// The consensus of two cubes with exactly one variable in opposite
+    /// polarities, e.g. `A.C` from `A.B` and `!B.C`.
+    pub fn consensus(&self, other: &Cube) -> Option<Cube> {
+        let opposite = (self.value ^ other.value) & !self.mask & !other.mask;
+        if opposite.count_ones() != 1 {
+            return None;
+        }
+        let mask = self.mask & other.mask | opposite;
+
+        Some(Cube {
+            value: (self.value | other.value) & !mask,
+            mask,
+        })
+    }
+
+    /// Every minterm of the cube, in increasing order.
+    pub fn minterms(&self) -> impl Iterator<Item = usize> + '_ {
+        // Count through the subsets of the mask's bits
+        let mut subset = Some(0usize);
+        core::iter::from_fn(move || {
+            let current = subset?;
+            subset = match current == self.mask {
+                true => None,
+                false => Some((current | !self.mask).wrapping_add(1) & self.mask),
+            };
+            Some(self.value | current)
+        })
+    }
+}
+
+#[test]
+fn test() {
+    let cube = |pattern: &str| {
+        let literals: Vec<Option<bool>> = pattern
+            .chars()
+            .map(|c| match c {
+                '-' => None,
+                c => Some(c == '1'),
+            })
+            .collect();
+        Cube::from_literals(&literals)
+    };
+
+    let a_not_c = cube("1-0");
+    assert_eq!(
+        a_not_c,
+        Cube {
+            value: 0b100,
+            mask: 0b010
+        }
+    );
+    assert_eq!(a_not_c.pattern(3), "1-0");
+    assert_eq!(a_not_c.get(1, 3), None);
+    assert_eq!(a_not_c.get(2, 3), Some(false));
+    assert_eq!(a_not_c.literals(3), 2);
+    assert_eq!(a_not_c.minterms().collect::<Vec<_>>(), [0b100, 0b110]);
+
+    assert!(cube("1--").contains(&a_not_c));
+    assert!(!a_not_c.contains(&cube("1--")));
+    assert_eq!(a_not_c.intersection(&cube("-1-")), Some(cube("110")));
+    assert_eq!(a_not_c.intersection(&cube("--1")), None);
+    assert_eq!(cube("11-").consensus(&cube("-01")), Some(cube("1-1")));
+    assert_eq!(cube("11-").consensus(&cube("00-")), None);
+}
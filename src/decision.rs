@@ -0,0 +1,165 @@
+//! Decision trees, which work out a function by testing one input at a time,
+//! each test choosing a branch that only has to handle the rows left. The
+//! tree is built by Shannon expansion, splitting on whichever input leaves
+//! the two halves depending on the fewest inputs between them, so inputs
+//! that settle the output early are tested first.
+
+use crate::intern::Symbol;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{classes, vm::TruthTable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tree {
+    Leaf(bool),
+    /// Test `input`, following `low` when it's false and `high` when it's
+    /// true.
+    Split {
+        input: usize,
+        low: Box<Tree>,
+        high: Box<Tree>,
+    },
+}
+
+impl Tree {
+    /// The number of tests in the tree.
+    pub fn size(&self) -> usize {
+        match self {
+            Tree::Leaf(_) => 0,
+            Tree::Split { low, high, .. } => 1 + low.size() + high.size(),
+        }
+    }
+
+    /// The most tests on the way to a leaf.
+    pub fn depth(&self) -> usize {
+        match self {
+            Tree::Leaf(_) => 0,
+            Tree::Split { low, high, .. } => 1 + low.depth().max(high.depth()),
+        }
+    }
+
+    pub fn eval(&self, assignment: &[bool]) -> bool {
+        match self {
+            Tree::Leaf(output) => *output,
+            Tree::Split { input, low, high } => match assignment[*input] {
+                true => high.eval(assignment),
+                false => low.eval(assignment),
+            },
+        }
+    }
+
+    /// The tree as nested `if`/`else` blocks testing the inputs by name, with
+    /// tests on the false side chained as `else if`, and each leaf a `1` or
+    /// `0`.
+    pub fn to_code(&self, names: &[Symbol]) -> String {
+        let mut out = String::new();
+        self.write(&mut out, names, 0);
+        out.push('\n');
+        out
+    }
+
+    fn write(&self, out: &mut String, names: &[Symbol], depth: usize) {
+        let indent = "    ".repeat(depth);
+        let mut tree = self;
+        while let Tree::Split { input, low, high } = tree {
+            writeln!(out, "if {} {{", names[*input]).unwrap();
+            out.push_str(&indent);
+            out.push_str("    ");
+            high.write(out, names, depth + 1);
+            out.push('\n');
+            out.push_str(&indent);
+            out.push_str("} else ");
+            tree = low;
+        }
+
+        match (self, tree) {
+            (Tree::Leaf(output), _) => write!(out, "{}", *output as u8).unwrap(),
+            (_, Tree::Leaf(output)) => {
+                write!(out, "{{\n{}    {}\n{}}}", indent, *output as u8, indent).unwrap()
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The decision tree for the table's function. Rows left out by assumptions
+/// are taken as false.
+pub fn tree(table: &TruthTable) -> Tree {
+    let num_inputs = table.input_names.len();
+    let outputs = classes::column(table);
+    let rows: Vec<usize> = (0..outputs.len()).collect();
+    let inputs: Vec<usize> = (0..num_inputs).collect();
+    build(&outputs, num_inputs, &rows, &inputs)
+}
+
+/// The bit of `row` that gives `input`.
+fn bit(input: usize, num_inputs: usize) -> usize {
+    1 << (num_inputs - 1 - input)
+}
+
+/// The tree for the function on `rows`, which agree on every input that's
+/// been tested on the way to them, leaving the `free` ones.
+fn build(outputs: &[bool], num_inputs: usize, rows: &[usize], free: &[usize]) -> Tree {
+    let depends = |rows: &[usize], input: usize| {
+        let bit = bit(input, num_inputs);
+        rows.iter()
+            .any(|row| row & bit == 0 && outputs[*row] != outputs[row | bit])
+    };
+    let support: Vec<usize> = free.iter().copied().filter(|i| depends(rows, *i)).collect();
+    if support.is_empty() {
+        return Tree::Leaf(outputs[rows[0]]);
+    }
+
+    let halves = |input: usize| -> (Vec<usize>, Vec<usize>) {
+        let bit = bit(input, num_inputs);
+        rows.iter().partition(|row| *row & bit == 0)
+    };
+    let best = *support
+        .iter()
+        .min_by_key(|input| {
+            let (low, high) = halves(**input);
+            let rest = support.iter().filter(|i| i != input);
+            let count = |rows: &[usize]| rest.clone().filter(|i| depends(rows, **i)).count();
+            count(&low) + count(&high)
+        })
+        .unwrap();
+
+    let (low, high) = halves(best);
+    let free: Vec<usize> = support.into_iter().filter(|i| *i != best).collect();
+    Tree::Split {
+        input: best,
+        low: Box::new(build(outputs, num_inputs, &low, &free)),
+        high: Box::new(build(outputs, num_inputs, &high, &free)),
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{assignments, compiler::Compiler, parser::Parser, vm::VM};
+
+    let table = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen()
+    };
+
+    // The select input decides which of the others matters
+    let mux = table("(A AND S) OR (B AND NOT S) = Q");
+    let mux_tree = tree(&mux);
+    assert_eq!(mux_tree.size(), 3);
+    assert_eq!(mux_tree.depth(), 2);
+    assert_eq!(
+        mux_tree.to_code(&mux.input_names),
+        "if S {\n    if A {\n        1\n    } else {\n        0\n    }\n} else if B {\n    1\n} else {\n    0\n}\n"
+    );
+    for assignment in assignments::assignments(3) {
+        let row = assignments::to_row(&assignment);
+        assert_eq!(mux_tree.eval(&assignment), mux.outputs[row]);
+    }
+
+    let parity = table("A XOR B XOR C = Q");
+    assert_eq!(tree(&parity).size(), 7);
+    let constant = table("A OR NOT A = Q");
+    assert_eq!(tree(&constant), Tree::Leaf(true));
+    assert_eq!(tree(&constant).to_code(&["A".into()]), "1\n");
+}
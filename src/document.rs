@@ -0,0 +1,81 @@
+use std::fmt::Write;
+
+use booltable::{
+    compiler::Compiler,
+    export::{self, Format},
+    kmap, minimize,
+    parser::Parser,
+    session::{Column, Session},
+    vm::VM,
+};
+
+use crate::config::Config;
+
+/// A markdown document with a section for each equation in `source`, one
+/// per line. Blank lines and lines starting with `#` are skipped, and as in
+/// the REPL, equations can use the outputs of earlier ones.
+pub fn generate(source: &str, config: &Config) -> Result<String, String> {
+    let mut session = Session::new();
+    let mut out = String::from("# Truth tables\n");
+
+    for (number, line) in source.lines().enumerate().map(|(i, l)| (i + 1, l)) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parsed_equation = Parser::new(line).parse_equation().map_err(|err| {
+            format!(
+                "line {}: {} at column {}",
+                number,
+                err,
+                err.span().start + 1
+            )
+        })?;
+        let expanded_equation = session.expand(parsed_equation.clone());
+        crate::check_size(config, &expanded_equation)
+            .map_err(|err| format!("line {}: {}", number, err))?;
+
+        let source = expanded_equation.to_source(config.dialect);
+        let table = VM::new(Compiler::new(expanded_equation).compile()).gen();
+
+        writeln!(out, "\n## {}\n\n```\n{}\n```\n", table.output_name, source).unwrap();
+        writeln!(
+            out,
+            "### Truth table\n\n{}",
+            export::render(&table, Format::Markdown, &config.style)
+        )
+        .unwrap();
+        match table.input_names.len() > minimize::MAX_INPUTS {
+            true => writeln!(
+                out,
+                "### Minimised\n\nNot minimised for more than {} inputs.\n",
+                minimize::MAX_INPUTS
+            )
+            .unwrap(),
+            false => writeln!(
+                out,
+                "### Minimised\n\n`{} = {}`\n\nAs a product of sums:\n\n`{} = {}`\n",
+                minimize::minimize(&table).display_in(&table.input_names, config.dialect),
+                table.output_name,
+                minimize::minimize_pos(&table).display_in(&table.input_names, config.dialect),
+                table.output_name
+            )
+            .unwrap(),
+        }
+        match kmap::kmap(&table, &config.style) {
+            Some(map) => write!(out, "### Karnaugh map\n\n{}", map).unwrap(),
+            None => write!(
+                out,
+                "### Karnaugh map\n\nNot drawn for more than {} inputs.\n",
+                kmap::MAX_INPUTS
+            )
+            .unwrap(),
+        }
+
+        let column = Column::new(&table);
+        session.define(&parsed_equation, column);
+    }
+
+    Ok(out.trim_end().to_string() + "\n")
+}
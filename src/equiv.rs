@@ -0,0 +1,310 @@
+//! Equivalence of equations, compared over the union of their inputs, and
+//! refinement of a specification whose assumptions leave rows as don't-cares.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    assignments,
+    compiler::Compiler,
+    export::{self, TableStyle},
+    intern::Symbol,
+    parser::{Equation, Node, SpanExpr},
+    vm::VM,
+};
+
+/// An assignment on which two equations disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub assignment: Vec<(Symbol, bool)>,
+    pub left: bool,
+    pub right: bool,
+}
+
+/// The inputs of both equations, in order of first appearance in `left` and
+/// then `right`.
+pub fn shared_inputs(left: &Equation, right: &Equation) -> Vec<Symbol> {
+    let mut names = left.inputs.clone();
+    for name in &right.inputs {
+        if !names.contains(name) {
+            names.push(*name);
+        }
+    }
+
+    names
+}
+
+/// Renumber the variables of `expr` from its own `names` to their positions
+/// in `to`, which must include all of them.
+pub fn remap(expr: &SpanExpr, names: &[Symbol], to: &[Symbol]) -> SpanExpr {
+    SpanExpr::build(|arena| {
+        arena.substitute(expr.expr(), |arena, v, span| {
+            let v = to.iter().position(|n| *n == names[v]).unwrap();
+            arena.push(span, Node::Var(v))
+        })
+    })
+}
+
+/// The outputs of `left` and `right` for every assignment of their shared
+/// inputs that satisfies the assumptions of both, each with its minterm, in
+/// the order of `shared_inputs` with the first most significant.
+pub(crate) fn outputs(
+    left: &Equation,
+    right: &Equation,
+) -> (Vec<Symbol>, Vec<(usize, bool, bool)>) {
+    let names = shared_inputs(left, right);
+    let (left_positions, right_positions) = (positions(left, &names), positions(right, &names));
+
+    let mut left_vm = VM::new(Compiler::new(left.clone()).compile());
+    let mut right_vm = VM::new(Compiler::new(right.clone()).compile());
+    let outputs = (0..1usize << names.len())
+        .filter_map(|row| {
+            let values = assignments::to_bools(row, names.len());
+            let pick = |positions: &[usize]| -> Vec<bool> {
+                positions.iter().map(|&p| values[p]).collect()
+            };
+            let (left_inputs, right_inputs) = (pick(&left_positions), pick(&right_positions));
+            if !left_vm.assumed(&left_inputs) || !right_vm.assumed(&right_inputs) {
+                return None;
+            }
+            Some((
+                row,
+                left_vm.eval(&left_inputs),
+                right_vm.eval(&right_inputs),
+            ))
+        })
+        .collect();
+
+    (names, outputs)
+}
+
+/// Where each of `equation`'s inputs is in `names`.
+fn positions(equation: &Equation, names: &[Symbol]) -> Vec<usize> {
+    equation
+        .inputs
+        .iter()
+        .map(|name| names.iter().position(|n| n == name).unwrap())
+        .collect()
+}
+
+/// Check that `left` and `right` give the same output for every assignment
+/// of their shared inputs that both of their assumptions allow. An input
+/// that only one side mentions is one it doesn't depend on. The counterexample is the simplest one, as chosen by
+/// `simplest`.
+pub fn check(left: &Equation, right: &Equation) -> Result<(), Counterexample> {
+    simplest(left, right, |l, r| l != r)
+}
+
+/// Check that `right` is true whenever `left` is, returning the simplest
+/// assignment for which `left` is true and `right` false if not.
+pub fn implies(left: &Equation, right: &Equation) -> Result<(), Counterexample> {
+    simplest(left, right, |l, r| l && !r)
+}
+
+/// Check that `implementation` is a legal refinement of `spec`: that it
+/// gives the same output on every assignment satisfying `spec`'s
+/// assumptions, which are the rows the specification cares about. The rows
+/// its assumptions leave out are don't-cares, and any output will do there.
+/// `implementation`'s own assumptions are ignored, since it has to give an
+/// output everywhere `spec` cares.
+pub fn refines(spec: &Equation, implementation: &Equation) -> Result<(), Counterexample> {
+    let implementation = Equation {
+        assumptions: Vec::new(),
+        ..implementation.clone()
+    };
+    simplest(spec, &implementation, |l, r| l != r)
+}
+
+/// Whether each assignment of the inputs of `spec` and `other`, in the
+/// order of `outputs`, satisfies `spec`'s assumptions.
+pub fn cares(spec: &Equation, other: &Equation) -> Vec<bool> {
+    let names = shared_inputs(spec, other);
+    let spec_positions = positions(spec, &names);
+    let mut vm = VM::new(Compiler::new(spec.clone()).compile());
+
+    (0..1usize << names.len())
+        .map(|row| {
+            let values = assignments::to_bools(row, names.len());
+            let inputs: Vec<bool> = spec_positions.iter().map(|&p| values[p]).collect();
+            vm.assumed(&inputs)
+        })
+        .collect()
+}
+
+/// The assignment that fails with the fewest true inputs, breaking ties by
+/// taking the first in table order, since that's easiest to reason about.
+fn simplest(
+    left: &Equation,
+    right: &Equation,
+    fails: impl Fn(bool, bool) -> bool,
+) -> Result<(), Counterexample> {
+    let (names, outputs) = outputs(left, right);
+    let failing = outputs
+        .into_iter()
+        .filter(|(_, l, r)| fails(*l, *r))
+        .min_by_key(|(row, _, _)| (row.count_ones(), *row));
+
+    match failing {
+        None => Ok(()),
+        Some((row, left, right)) => Err(Counterexample {
+            assignment: names
+                .iter()
+                .copied()
+                .zip(assignments::to_bools(row, names.len()))
+                .collect(),
+            left,
+            right,
+        }),
+    }
+}
+
+/// Two equations' outputs side by side, over every assignment of their
+/// shared inputs that both of their assumptions allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub input_names: Vec<Symbol>,
+    pub left_name: Symbol,
+    pub right_name: Symbol,
+    /// Each row's minterm and the outputs of the left and right equations.
+    pub outputs: Vec<(usize, bool, bool)>,
+}
+
+pub fn compare(left: &Equation, right: &Equation) -> Comparison {
+    let (input_names, outputs) = outputs(left, right);
+    Comparison {
+        input_names,
+        left_name: left.output,
+        right_name: right.output,
+        outputs,
+    }
+}
+
+impl Comparison {
+    /// The rows on which the equations disagree, by position in the table.
+    pub fn differing(&self) -> impl Iterator<Item = usize> + '_ {
+        self.outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, l, r))| l != r)
+            .map(|(row, _)| row)
+    }
+
+    /// The table in markdown, with a column for each output and a last one
+    /// marking the rows where they disagree with `≠`, or `!=` if the style is
+    /// ASCII. Outputs with the same name are told apart by a `'` on the right
+    /// one.
+    pub fn to_markdown(&self, style: &TableStyle) -> String {
+        let marker = if style.ascii { "!=" } else { "≠" };
+        let right_name = if self.right_name == self.left_name {
+            format!("{}'", self.right_name)
+        } else {
+            self.right_name.to_string()
+        };
+        let mut headers: Vec<&str> = self.input_names.iter().map(|n| n.as_str()).collect();
+        headers.extend([self.left_name.as_str(), &right_name, marker]);
+        let length = self.input_names.len();
+        let rows: Vec<Vec<String>> = self
+            .outputs
+            .iter()
+            .map(|(row, l, r)| {
+                let mut cells: Vec<String> = assignments::to_bools(*row, length)
+                    .into_iter()
+                    .chain([*l, *r])
+                    .map(|value| style.symbol(value).to_string())
+                    .collect();
+                cells.push(if l != r { marker } else { "" }.to_string());
+                cells
+            })
+            .collect();
+
+        export::grid(&headers, length, &rows, style)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let left = Parser::new("NOT (A AND B) = Q").parse_equation().unwrap();
+    let right = Parser::new("(NOT A) OR (NOT B) = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(check(&left, &right), Ok(()));
+
+    let right = Parser::new("(NOT A) OR C = Q").parse_equation().unwrap();
+    let counterexample = check(&left, &right).unwrap_err();
+    assert_eq!(
+        counterexample.assignment,
+        vec![("A".into(), true), ("B".into(), false), ("C".into(), false)]
+    );
+    assert!(counterexample.left && !counterexample.right);
+
+    let left = Parser::new("A OR B OR C = Q").parse_equation().unwrap();
+    let right = Parser::new("A AND (B OR C) = Q").parse_equation().unwrap();
+    assert_eq!(implies(&right, &left), Ok(()));
+    // 011 comes first in the table, but 100 has fewer true inputs
+    let right = Parser::new("(NOT A) AND (B XOR C) = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(
+        implies(&left, &right).unwrap_err().assignment,
+        vec![("A".into(), true), ("B".into(), false), ("C".into(), false)]
+    );
+
+    // The specification doesn't care about rows where A and B are both true
+    let spec = Parser::new("assume NOT (A AND B); A OR B = Q")
+        .parse_equation()
+        .unwrap();
+    let xor = Parser::new("A XOR B = Q").parse_equation().unwrap();
+    assert_eq!(refines(&spec, &xor), Ok(()));
+    // Checking skips the rows the assumptions leave out too, while refining
+    // ignores the implementation's own
+    assert_eq!(check(&spec, &xor), Ok(()));
+    let assumes_c = Parser::new("assume C; A AND B = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(
+        check(&assumes_c, &xor).unwrap_err().assignment[0],
+        ("C".into(), true)
+    );
+    assert!(refines(
+        &Parser::new("A AND B = Q").parse_equation().unwrap(),
+        &assumes_c
+    )
+    .is_ok());
+    let and = Parser::new("A AND (B OR C) = Q").parse_equation().unwrap();
+    assert_eq!(
+        cares(&spec, &and),
+        [true, true, true, true, true, true, false, false]
+    );
+    assert_eq!(
+        refines(&spec, &and).unwrap_err().assignment,
+        vec![("A".into(), false), ("B".into(), true), ("C".into(), false)]
+    );
+
+    let comparison = compare(
+        &Parser::new("A AND B = Q").parse_equation().unwrap(),
+        &Parser::new("A OR B = Q").parse_equation().unwrap(),
+    );
+    assert_eq!(comparison.differing().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(
+        comparison.to_markdown(&TableStyle::default()),
+        "| A | B | Q | Q' | ≠ |\n\
+         |---|---|---|----|---|\n\
+         | 0 | 0 | 0 | 0  |   |\n\
+         | 0 | 1 | 0 | 1  | ≠ |\n\
+         | 1 | 0 | 0 | 1  | ≠ |\n\
+         | 1 | 1 | 1 | 1  |   |\n"
+    );
+    let assumed = compare(
+        &Parser::new("assume A; A AND B = Q")
+            .parse_equation()
+            .unwrap(),
+        &Parser::new("B = Q").parse_equation().unwrap(),
+    );
+    assert_eq!(assumed.outputs, [(2, false, false), (3, true, true)]);
+    assert_eq!(assumed.differing().count(), 0);
+}
@@ -0,0 +1,212 @@
+//! An English reading of an equation, e.g. `(A AND B) OR NOT C = Q` is "Q is
+//! true when A and B are both true, or C is false". Chains of one operator
+//! are read as a list, an OR inside another operator as "either ... or ..."
+//! and an AND inside a NOT or XOR as "both ... and ...", so the grouping the
+//! parser chose can be seen in the wording. Constants are folded away first,
+//! so that `A AND 0` reads as never true rather than "A is true and false".
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    compiler,
+    intern::Symbol,
+    parser::{BinOp, Equation, Expr, ExprRef, SpanExpr},
+};
+
+/// What a clause is an operand of, which decides how it's grouped.
+#[derive(Clone, Copy, PartialEq)]
+enum Parent {
+    Top,
+    Not,
+    Op(BinOp),
+}
+
+/// A sentence describing when the equation's output is true, followed by
+/// one for its assumptions, if it has any.
+pub fn explain(equation: &Equation) -> String {
+    let names = &equation.inputs;
+    let lhs = compiler::fold(&equation.lhs);
+    let mut out = match lhs.node() {
+        Expr::Bool(true) => format!("{} is always true.", equation.output),
+        Expr::Bool(false) => format!("{} is never true.", equation.output),
+        _ => format!(
+            "{} is true when {}.",
+            equation.output,
+            clause(lhs.expr(), names, Parent::Top)
+        ),
+    };
+
+    // Assumptions that are always true don't leave anything out
+    let assumptions: Vec<SpanExpr> = equation
+        .assumptions
+        .iter()
+        .map(compiler::fold)
+        .filter(|assumption| assumption.node() != Expr::Bool(true))
+        .collect();
+    if assumptions
+        .iter()
+        .any(|assumption| assumption.node() == Expr::Bool(false))
+    {
+        out.push_str(" No rows are included.");
+    } else if !assumptions.is_empty() {
+        let assumptions: Vec<String> = assumptions
+            .iter()
+            .map(|assumption| clause(assumption.expr(), names, Parent::Top))
+            .collect();
+        out.push_str(&format!(
+            " Only rows where {} are included.",
+            list(&assumptions, "and")
+        ));
+    }
+
+    out
+}
+
+/// `expr` as a clause that is true when it is.
+fn clause(expr: ExprRef, names: &[Symbol], parent: Parent) -> String {
+    match expr.node() {
+        Expr::Bool(value) => format!("{}", value),
+        Expr::Var(v) => format!("{} is true", names[v]),
+        Expr::Not(e) => match e.node() {
+            Expr::Var(v) => format!("{} is false", names[v]),
+            _ => format!("it's not the case that {}", clause(e, names, Parent::Not)),
+        },
+        Expr::BinOp { op, .. } => {
+            let mut operands = Vec::new();
+            flatten(op, expr, &mut operands);
+            binop(op, &operands, names, parent)
+        }
+    }
+}
+
+fn binop(op: BinOp, operands: &[ExprRef], names: &[Symbol], parent: Parent) -> String {
+    let literals: Option<Vec<(usize, bool)>> = operands.iter().map(|&e| literal(e)).collect();
+    // Literals of one polarity read best as a single list of names
+    if let Some(literals) = literals.filter(|l| l.iter().all(|(_, value)| *value == l[0].1)) {
+        let listed: Vec<String> = literals
+            .iter()
+            .map(|(v, _)| String::from(names[*v].as_str()))
+            .collect();
+        let value = literals[0].1;
+        let two = operands.len() == 2;
+        return match op {
+            BinOp::And if two => format!("{} are both {}", list(&listed, "and"), value),
+            BinOp::And => format!("{} are all {}", list(&listed, "and"), value),
+            BinOp::Or if two && parent == Parent::Top => {
+                format!("{} is {}", list(&listed, "or"), value)
+            }
+            BinOp::Or if two => format!("either {} is {}", list(&listed, "or"), value),
+            BinOp::Or => format!("at least one of {} is {}", list(&listed, "and"), value),
+            BinOp::Xor if two => format!("exactly one of {} is {}", list(&listed, "and"), value),
+            BinOp::Xor => format!("an odd number of {} are {}", list(&listed, "and"), value),
+        };
+    }
+
+    let clauses: Vec<String> = operands
+        .iter()
+        .map(|&e| clause(e, names, Parent::Op(op)))
+        .collect();
+    match op {
+        BinOp::And
+            if operands.len() == 2 && matches!(parent, Parent::Not | Parent::Op(BinOp::Xor)) =>
+        {
+            format!("both {} and {}", clauses[0], clauses[1])
+        }
+        BinOp::And => list(&clauses, "and"),
+        BinOp::Or if parent == Parent::Top => clauses.join(", or "),
+        BinOp::Or => format!("either {}", list(&clauses, "or")),
+        BinOp::Xor if operands.len() == 2 => {
+            format!("either {} or {}, but not both", clauses[0], clauses[1])
+        }
+        BinOp::Xor => format!("an odd number of these hold: {}", clauses.join("; ")),
+    }
+}
+
+/// A variable or its negation, as its number and the value making it true.
+fn literal(expr: ExprRef) -> Option<(usize, bool)> {
+    match expr.node() {
+        Expr::Var(v) => Some((v, true)),
+        Expr::Not(e) => match e.node() {
+            Expr::Var(v) => Some((v, false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The operands of a chain of `op`, however it's bracketed.
+fn flatten<'a>(op: BinOp, expr: ExprRef<'a>, operands: &mut Vec<ExprRef<'a>>) {
+    match expr.node() {
+        Expr::BinOp {
+            op: inner,
+            lhs,
+            rhs,
+        } if inner == op => {
+            flatten(op, lhs, operands);
+            flatten(op, rhs, operands);
+        }
+        _ => operands.push(expr),
+    }
+}
+
+/// `items` separated by commas, with `conjunction` before the last.
+fn list(items: &[String], conjunction: &str) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), conjunction, last),
+    }
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let explain = |source| explain(&Parser::new(source).parse_equation().unwrap());
+
+    assert_eq!(
+        explain("(A AND B) OR NOT C = Q"),
+        "Q is true when A and B are both true, or C is false."
+    );
+    assert_eq!(explain("A = Q"), "Q is true when A is true.");
+    assert_eq!(
+        explain("(NOT A) AND (NOT B) AND (NOT C) = Q"),
+        "Q is true when A, B and C are all false."
+    );
+    // NOT extends to the end of the expression
+    assert_eq!(
+        explain("NOT A AND NOT B AND C = Q"),
+        "Q is true when it's not the case that both A is true and it's not the case that B and C are both true."
+    );
+    assert_eq!(
+        explain("(A OR B) AND NOT C = Q"),
+        "Q is true when either A or B is true and C is false."
+    );
+    assert_eq!(
+        explain("(A AND NOT B) OR ((B OR C) AND D) = Q"),
+        "Q is true when A is true and B is false, or either B or C is true and D is true."
+    );
+    assert_eq!(
+        explain("A XOR B = Q"),
+        "Q is true when exactly one of A and B is true."
+    );
+    assert_eq!(
+        explain("NOT (A AND B) = Q"),
+        "Q is true when it's not the case that A and B are both true."
+    );
+    assert_eq!(explain("A OR 1 = Q"), "Q is always true.");
+    assert_eq!(explain("A . (B . 0) = Q"), "Q is never true.");
+    assert_eq!(
+        explain("(A XOR 1) OR (B AND 1) = Q"),
+        "Q is true when A is false, or B is true."
+    );
+    assert_eq!(
+        explain("assume A + 1; assume B AND 0; A = Q"),
+        "Q is true when A is true. No rows are included."
+    );
+    assert_eq!(explain("1 = Q"), "Q is always true.");
+    assert_eq!(
+        explain("assume A != B; A AND C = Q"),
+        "Q is true when A and C are both true. Only rows where exactly one of A and B is true are included."
+    );
+}
@@ -0,0 +1,658 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt::Write, str::FromStr};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bdd::{self, Bdd},
+    rom,
+    vm::TruthTable,
+};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[display(fmt = "markdown")]
+    Markdown,
+    #[display(fmt = "csv")]
+    Csv,
+    #[display(fmt = "latex")]
+    Latex,
+    #[display(fmt = "html")]
+    Html,
+    #[display(fmt = "json")]
+    Json,
+    #[display(fmt = "wavedrom")]
+    Wavedrom,
+    #[display(fmt = "bdd-dot")]
+    BddDot,
+    #[display(fmt = "readmemh")]
+    Readmemh,
+    #[display(fmt = "ihex")]
+    IntelHex,
+    #[display(fmt = "verilog-case")]
+    VerilogCase,
+}
+
+impl Format {
+    pub const ALL: &'static [Format] = &[
+        Format::Markdown,
+        Format::Csv,
+        Format::Latex,
+        Format::Html,
+        Format::Json,
+        Format::Wavedrom,
+        Format::BddDot,
+        Format::Readmemh,
+        Format::IntelHex,
+        Format::VerilogCase,
+    ];
+
+    /// Guess the format from a file's extension, looking past a `.gz`.
+    #[cfg(feature = "std")]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if crate::stream::is_gzip(path) {
+            return Self::from_path(Path::new(path.file_stem()?));
+        }
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "md" | "markdown" | "txt" => Some(Format::Markdown),
+            "csv" => Some(Format::Csv),
+            "tex" | "latex" => Some(Format::Latex),
+            "html" | "htm" => Some(Format::Html),
+            "json" => Some(Format::Json),
+            "dot" | "gv" => Some(Format::BddDot),
+            "mem" => Some(Format::Readmemh),
+            "hex" | "ihex" => Some(Format::IntelHex),
+            "v" => Some(Format::VerilogCase),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "markdown" | "md" | "table" => Ok(Format::Markdown),
+            "csv" => Ok(Format::Csv),
+            "latex" | "tex" => Ok(Format::Latex),
+            "html" => Ok(Format::Html),
+            "json" => Ok(Format::Json),
+            "wavedrom" | "wave" => Ok(Format::Wavedrom),
+            "bdd-dot" => Ok(Format::BddDot),
+            "readmemh" | "mem" => Ok(Format::Readmemh),
+            "ihex" | "intel-hex" => Ok(Format::IntelHex),
+            "verilog-case" => Ok(Format::VerilogCase),
+            _ => Err(format!(
+                "unknown format '{}', expected one of: {}",
+                s,
+                Format::ALL
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowOrder {
+    /// Count up from all inputs false.
+    #[default]
+    Ascending,
+    /// Count down from all inputs true.
+    Descending,
+}
+
+impl FromStr for RowOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascending" => Ok(RowOrder::Ascending),
+            "descending" => Ok(RowOrder::Descending),
+            _ => Err(format!(
+                "unknown row order '{}', expected ascending or descending",
+                s
+            )),
+        }
+    }
+}
+
+/// Which rows come first, before `RowOrder` decides the direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowSort {
+    /// By the inputs as a binary number.
+    #[default]
+    Index,
+    /// Rows with an output of 1 first, then by index.
+    Output,
+    /// By how many inputs are 1, then by index.
+    Ones,
+}
+
+impl FromStr for RowSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "index" => Ok(RowSort::Index),
+            "output" => Ok(RowSort::Output),
+            "ones" => Ok(RowSort::Ones),
+            _ => Err(format!(
+                "unknown sort '{}', expected index, output or ones",
+                s
+            )),
+        }
+    }
+}
+
+/// How cells are aligned within their column in markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Centre,
+    Right,
+}
+
+impl FromStr for Align {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Align::Left),
+            "centre" | "center" => Ok(Align::Centre),
+            "right" => Ok(Align::Right),
+            _ => Err(format!(
+                "unknown alignment '{}', expected left, centre or right",
+                s
+            )),
+        }
+    }
+}
+
+/// Presentation options shared by every format. The last three only apply
+/// to markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStyle {
+    pub true_symbol: String,
+    pub false_symbol: String,
+    pub row_order: RowOrder,
+    pub sort: RowSort,
+    pub align: Align,
+    /// Names longer than this are cut short, ending in `…`, so that long
+    /// names don't make their columns wide.
+    pub max_header_width: Option<usize>,
+    /// Write names down the page, a character per line, so that every
+    /// column is only as wide as its cells.
+    pub vertical_headers: bool,
+    /// Head the inputs and outputs with a line spanning each group, and
+    /// divide the groups with `‖`.
+    pub group_headers: bool,
+    /// Stick to ASCII, dividing groups with `|` and cutting names short
+    /// with `...`.
+    pub ascii: bool,
+}
+
+impl Default for TableStyle {
+    fn default() -> Self {
+        Self {
+            true_symbol: "1".to_string(),
+            false_symbol: "0".to_string(),
+            row_order: RowOrder::Ascending,
+            sort: RowSort::Index,
+            align: Align::Left,
+            max_header_width: None,
+            vertical_headers: false,
+            group_headers: false,
+            ascii: false,
+        }
+    }
+}
+
+impl TableStyle {
+    pub(crate) fn symbol(&self, value: bool) -> &str {
+        if value {
+            &self.true_symbol
+        } else {
+            &self.false_symbol
+        }
+    }
+
+    fn abbreviate(&self, name: &str) -> String {
+        match self.max_header_width {
+            Some(max) if name.chars().count() > max => {
+                let ellipsis = if self.ascii { "..." } else { "…" };
+                let keep = max.saturating_sub(ellipsis.chars().count());
+                let mut short: String = name.chars().take(keep).collect();
+                short.push_str(ellipsis);
+                short
+            }
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// A markdown table of `rows` under `headers`, where the first `inputs`
+/// columns are inputs and the rest outputs, laid out as `style` says.
+/// Shared by every kind of table, so they all look the same.
+pub(crate) fn grid(
+    headers: &[&str],
+    inputs: usize,
+    rows: &[Vec<String>],
+    style: &TableStyle,
+) -> String {
+    let headers: Vec<Vec<char>> = headers
+        .iter()
+        .map(|name| style.abbreviate(name).chars().collect())
+        .collect();
+    let mut widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            let header = if style.vertical_headers {
+                1
+            } else {
+                headers[i].len()
+            };
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .fold(header, usize::max)
+        })
+        .collect();
+
+    // Each group is headed by a label spanning its columns, which are widened
+    // at the end if the label doesn't fit
+    let outputs = headers.len() - inputs;
+    let mut groups = vec![];
+    if style.group_headers {
+        let label = |count, one, many| if count == 1 { one } else { many };
+        for (range, label) in [
+            (0..inputs, label(inputs, "Input", "Inputs")),
+            (inputs..headers.len(), label(outputs, "Output", "Outputs")),
+        ] {
+            if range.is_empty() {
+                continue;
+            }
+            // Every column after the first adds its width and a divider
+            let span =
+                |widths: &[usize]| widths[range.clone()].iter().map(|w| w + 3).sum::<usize>() - 3;
+            let short = (label.chars().count()).saturating_sub(span(&widths));
+            widths[range.end - 1] += short;
+            groups.push((label, span(&widths)));
+        }
+    }
+    let divider = |column: usize| {
+        if style.group_headers && column == inputs && column > 0 && !style.ascii {
+            '‖'
+        } else {
+            '|'
+        }
+    };
+    let line = |out: &mut String, cells: &[String]| {
+        for (i, (cell, width)) in cells.iter().zip(&widths).enumerate() {
+            out.push(divider(i));
+            match style.align {
+                Align::Left => write!(out, " {:<width$} ", cell),
+                Align::Centre => write!(out, " {:^width$} ", cell),
+                Align::Right => write!(out, " {:>width$} ", cell),
+            }
+            .unwrap();
+        }
+        out.push_str("|\n");
+    };
+
+    let mut out = String::new();
+    if !groups.is_empty() {
+        for (i, (label, span)) in groups.iter().enumerate() {
+            out.push(if i == 0 { '|' } else { divider(inputs) });
+            write!(out, " {:<span$} ", label).unwrap();
+        }
+        out.push_str("|\n");
+    }
+    if style.vertical_headers {
+        // Names end on the same line, just above the rule
+        let height = headers.iter().map(Vec::len).max().unwrap_or(0);
+        for i in 0..height {
+            let cells: Vec<String> = headers
+                .iter()
+                .map(|name| match (i + name.len()).checked_sub(height) {
+                    Some(c) => name[c].to_string(),
+                    None => String::new(),
+                })
+                .collect();
+            line(&mut out, &cells);
+        }
+    } else {
+        let cells: Vec<String> = headers.iter().map(|name| name.iter().collect()).collect();
+        line(&mut out, &cells);
+    }
+    for (i, width) in widths.iter().enumerate() {
+        out.push(divider(i));
+        let dashes = "-".repeat(*width);
+        match style.align {
+            Align::Left => write!(out, "-{}-", dashes),
+            Align::Centre => write!(out, ":{}:", dashes),
+            Align::Right => write!(out, "-{}:", dashes),
+        }
+        .unwrap();
+    }
+    out.push_str("|\n");
+    for row in rows {
+        line(&mut out, row);
+    }
+
+    out
+}
+
+pub fn render(table: &TruthTable, format: Format, style: &TableStyle) -> String {
+    match format {
+        Format::Markdown => markdown(table, style),
+        Format::Csv => csv(table, style),
+        Format::Latex => latex(table, style),
+        Format::Html => html(table, style),
+        Format::Json => json(table),
+        Format::Wavedrom => wavedrom(table, style),
+        Format::BddDot => bdd_dot(table),
+        Format::Readmemh => rom::readmemh(table),
+        Format::IntelHex => rom::intel_hex(table),
+        Format::VerilogCase => rom::verilog_case(table),
+    }
+}
+
+/// The indices of the table's rows in the order the style puts them.
+fn order(table: &TruthTable, style: &TableStyle) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..table.outputs.len()).collect();
+    // Rows are already by index, so a stable sort breaks ties by it
+    match style.sort {
+        RowSort::Index => {}
+        RowSort::Output => order.sort_by_key(|row| !table.outputs[*row]),
+        RowSort::Ones => order.sort_by_key(|row| table.inputs[*row].iter().filter(|b| **b).count()),
+    }
+    if style.row_order == RowOrder::Descending {
+        order.reverse();
+    }
+
+    order
+}
+
+/// Each row's cells, inputs followed by the output, in the style's order.
+pub(crate) fn rows<'a>(table: &TruthTable, style: &'a TableStyle) -> Vec<Vec<&'a str>> {
+    order(table, style)
+        .into_iter()
+        .map(|row| {
+            table.inputs[row]
+                .iter()
+                .chain([&table.outputs[row]])
+                .map(|b| style.symbol(*b))
+                .collect()
+        })
+        .collect()
+}
+
+fn headers(table: &TruthTable) -> impl Iterator<Item = &'static str> + '_ {
+    table
+        .input_names
+        .iter()
+        .chain([&table.output_name])
+        .map(|name| name.as_str())
+}
+
+fn markdown(table: &TruthTable, style: &TableStyle) -> String {
+    let headers: Vec<&str> = headers(table).collect();
+    let rows: Vec<Vec<String>> = rows(table, style)
+        .into_iter()
+        .map(|row| row.into_iter().map(str::to_string).collect())
+        .collect();
+
+    grid(&headers, table.input_names.len(), &rows, style)
+}
+
+fn csv(table: &TruthTable, style: &TableStyle) -> String {
+    let mut out = headers(table).collect::<Vec<_>>().join(",");
+    out.push('\n');
+    for row in rows(table, style) {
+        writeln!(out, "{}", row.join(",")).unwrap();
+    }
+
+    out
+}
+
+fn latex(table: &TruthTable, style: &TableStyle) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "\\begin{{tabular}}{{{}|c}}",
+        "c".repeat(table.input_names.len())
+    )
+    .unwrap();
+    let names: Vec<String> = headers(table)
+        .map(|name| name.replace('_', "\\_"))
+        .collect();
+    writeln!(out, "{} \\\\\n\\hline", names.join(" & ")).unwrap();
+    for row in rows(table, style) {
+        writeln!(out, "{} \\\\", row.join(" & ")).unwrap();
+    }
+    out.push_str("\\end{tabular}\n");
+
+    out
+}
+
+fn html(table: &TruthTable, style: &TableStyle) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+
+    let mut out = String::from("<table>\n  <thead>\n    <tr>");
+    for name in headers(table) {
+        write!(out, "<th>{}</th>", escape(name)).unwrap();
+    }
+    out.push_str("</tr>\n  </thead>\n  <tbody>\n");
+    for row in rows(table, style) {
+        out.push_str("    <tr>");
+        for cell in row {
+            write!(out, "<td>{}</td>", escape(cell)).unwrap();
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("  </tbody>\n</table>\n");
+
+    out
+}
+
+/// Quote and escape `s` as a JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// A table in the shape `--format json` writes, for reading one back with
+/// serde.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonTable {
+    pub inputs: Vec<String>,
+    pub output: String,
+    pub rows: Vec<JsonRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRow {
+    pub inputs: Vec<bool>,
+    pub output: bool,
+}
+
+impl JsonTable {
+    pub fn new(table: &TruthTable) -> Self {
+        Self {
+            inputs: table.input_names.iter().map(|n| n.to_string()).collect(),
+            output: table.output_name.to_string(),
+            rows: table
+                .inputs
+                .iter()
+                .zip(&table.outputs)
+                .map(|(inputs, &output)| JsonRow {
+                    inputs: inputs.clone(),
+                    output,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The table as JSON, ignoring the style since the values are booleans:
+/// `{"inputs": [...], "output": "Q", "rows": [{"inputs": [...], "output": ...}]}`
+pub fn json(table: &TruthTable) -> String {
+    let mut out = serde_json::to_string(&JsonTable::new(table)).unwrap();
+    out.push('\n');
+
+    out
+}
+
+/// A WaveDrom timing diagram stepping through the rows in the style's order,
+/// with each input as a signal and the output below them.
+fn wavedrom(table: &TruthTable, style: &TableStyle) -> String {
+    let order = order(table, style);
+    let wave = |value: &dyn Fn(usize) -> bool| {
+        let mut wave = String::new();
+        let mut previous = None;
+        for &row in &order {
+            let value = value(row);
+            wave.push(match previous {
+                Some(p) if p == value => '.',
+                _ => char::from(b'0' + value as u8),
+            });
+            previous = Some(value);
+        }
+        wave
+    };
+    let signal = |name: &str, wave: String| {
+        format!(
+            "  {{\"name\": {}, \"wave\": {}}}",
+            json_string(name),
+            json_string(&wave)
+        )
+    };
+
+    let mut signals: Vec<String> = table
+        .input_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| signal(name, wave(&|row| table.inputs[row][i])))
+        .collect();
+    signals.push("  {}".to_string());
+    signals.push(signal(&table.output_name, wave(&|row| table.outputs[row])));
+
+    format!("{{\"signal\": [\n{}\n]}}\n", signals.join(",\n"))
+}
+
+/// The function's decision diagram, in the order sifting finds starting from
+/// the table's.
+fn bdd_dot(table: &TruthTable) -> String {
+    let order: Vec<usize> = (0..table.input_names.len()).collect();
+    Bdd::new(table, &bdd::sift(table, &order)).to_dot()
+}
+
+/// Shorten a rendered table to at most `max_lines` lines, replacing rows
+/// from the middle with a line saying how many were left out. The first
+/// `header` lines are always kept, along with at least a row either side of
+/// the gap.
+pub fn elide(rendered: &str, header: usize, max_lines: usize) -> String {
+    let lines: Vec<&str> = rendered.lines().collect();
+    if lines.len() <= max_lines || lines.len() <= header + 3 {
+        return rendered.to_string();
+    }
+
+    let rows = &lines[header..];
+    let shown = max_lines.saturating_sub(header + 1).max(2);
+    let (head, tail) = (shown.div_ceil(2), shown / 2);
+    let mut out = String::new();
+    for line in lines[..header].iter().chain(&rows[..head]) {
+        writeln!(out, "{}", line).unwrap();
+    }
+    writeln!(out, "... {} rows omitted ...", rows.len() - head - tail).unwrap();
+    for line in &rows[rows.len() - tail..] {
+        writeln!(out, "{}", line).unwrap();
+    }
+
+    out
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let equation = Parser::new("A AND B AND C = Q").parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    let rendered = render(&table, Format::Markdown, &TableStyle::default());
+    assert_eq!(elide(&rendered, 2, 10), rendered);
+    assert_eq!(
+        elide(&rendered, 2, 7),
+        "| A | B | C | Q |\n|---|---|---|---|\n| 0 | 0 | 0 | 0 |\n| 0 | 0 | 1 | 0 |\n\
+         ... 4 rows omitted ...\n| 1 | 1 | 0 | 0 |\n| 1 | 1 | 1 | 1 |\n"
+    );
+    assert_eq!(elide(&rendered, 2, 0).lines().count(), 5);
+
+    let style = TableStyle {
+        align: Align::Right,
+        max_header_width: Some(3),
+        vertical_headers: true,
+        true_symbol: "yes".to_string(),
+        ..TableStyle::default()
+    };
+    assert_eq!(
+        grid(
+            &["A", "CARRY"],
+            1,
+            &[vec!["0".into(), "yes".into()]],
+            &style
+        ),
+        "|   |   C |\n|   |   A |\n| A |   … |\n|--:|----:|\n| 0 | yes |\n"
+    );
+    let sorted = TableStyle {
+        sort: RowSort::Output,
+        ..TableStyle::default()
+    };
+    assert!(
+        render(&table, Format::Csv, &sorted).starts_with("A,B,C,Q\n1,1,1,1\n0,0,0,0\n0,0,1,0\n")
+    );
+    let sorted = TableStyle {
+        sort: RowSort::Ones,
+        row_order: RowOrder::Descending,
+        ..TableStyle::default()
+    };
+    assert!(render(&table, Format::Csv, &sorted)
+        .starts_with("A,B,C,Q\n1,1,1,1\n1,1,0,0\n1,0,1,0\n0,1,1,0\n1,0,0,0\n"));
+
+    let style = TableStyle {
+        group_headers: true,
+        ..TableStyle::default()
+    };
+    assert_eq!(
+        grid(
+            &["A", "B", "Q"],
+            2,
+            &[vec!["0".into(), "1".into(), "0".into()]],
+            &style
+        ),
+        "| Inputs ‖ Output |\n| A | B  ‖ Q      |\n|---|----‖--------|\n| 0 | 1  ‖ 0      |\n"
+    );
+    let style = TableStyle {
+        group_headers: true,
+        max_header_width: Some(4),
+        ascii: true,
+        ..TableStyle::default()
+    };
+    assert_eq!(
+        grid(&["CARRY", "Q"], 1, &[vec!["0".into(), "1".into()]], &style),
+        "| Input | Output |\n| C...  | Q      |\n|-------|--------|\n| 0     | 1      |\n"
+    );
+}
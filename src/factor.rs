@@ -0,0 +1,247 @@
+//! Factoring a sum of products into a multi-level form, which usually needs
+//! fewer literals, by algebraic division: the products are treated as
+//! polynomials, ignoring the laws of boolean algebra, and divided by
+//! whichever divisor saves the most literals. The divisors tried are single
+//! literals, as in `A.B + A.C + D` to `A.(B + C) + D`, and the kernels of
+//! the sum, its quotients by a product that have no literal in common, as in
+//! `A.C + A.D + B.C + B.D + E` to `(A + B).(C + D) + E`. The quotient,
+//! divisor and remainder are then factored in turn.
+
+use alloc::{vec, vec::Vec};
+use core::cmp::Reverse;
+
+use crate::{
+    minimize::{self, Cube},
+    parser::{Arena, BinOp, ExprId, Node, SpanExpr},
+    vm::TruthTable,
+};
+
+/// At most this many kernels are tried as divisors of each sum, since a sum
+/// of many products can have very many.
+pub const MAX_KERNELS: usize = 64;
+
+/// The table's minimised sum of products, factored.
+pub fn factor(table: &TruthTable) -> SpanExpr {
+    let num_inputs = table.input_names.len();
+    factor_products(&minimize::cover(&minimize::minterms(table)), num_inputs)
+}
+
+/// Factor the sum of `products`.
+pub fn factor_products(products: &[Cube], num_inputs: usize) -> SpanExpr {
+    SpanExpr::build(|arena| factor_in(arena, products, num_inputs))
+}
+
+fn factor_in(arena: &mut Arena, products: &[Cube], num_inputs: usize) -> ExprId {
+    let all = (1 << num_inputs) - 1;
+    if products.iter().any(|p| p.mask == all) {
+        return minimize::node(arena, Node::Bool(true));
+    }
+    if products.len() < 2 {
+        return minimize::to_expr_in(arena, products, num_inputs);
+    }
+
+    // Single literals come first, so they win ties, earlier inputs and
+    // positive literals first among them
+    let literals = (0..num_inputs).flat_map(|i| {
+        let bit = 1 << (num_inputs - 1 - i);
+        [bit, 0].map(|value| {
+            vec![Cube {
+                value: value & bit,
+                mask: all & !bit,
+            }]
+        })
+    });
+    let divisors = literals.chain(
+        kernels(products, num_inputs)
+            .into_iter()
+            .filter(|kernel| kernel.len() < products.len()),
+    );
+
+    let size = |cubes: &[Cube]| -> usize {
+        cubes
+            .iter()
+            .map(|c| (!c.mask & all).count_ones() as usize)
+            .sum()
+    };
+    let best = divisors
+        .filter_map(|d| {
+            let (q, r) = divide(products, &d, num_inputs);
+            (!q.is_empty()).then(|| (size(&q) + size(&d) + size(&r), q, d, r))
+        })
+        .min_by_key(|(cost, ..)| *cost)
+        .filter(|(cost, ..)| *cost < size(products));
+    let Some((_, q, d, r)) = best else {
+        return minimize::to_expr_in(arena, products, num_inputs);
+    };
+
+    // A single literal reads best in front
+    let (first, second) = if d.len() == 1 { (&d, &q) } else { (&q, &d) };
+    let lhs = factor_in(arena, first, num_inputs);
+    let rhs = factor_in(arena, second, num_inputs);
+    let product = minimize::node(
+        arena,
+        Node::BinOp {
+            op: BinOp::And,
+            lhs,
+            rhs,
+        },
+    );
+    if r.is_empty() {
+        return product;
+    }
+    let rest = factor_in(arena, &r, num_inputs);
+
+    minimize::node(
+        arena,
+        Node::BinOp {
+            op: BinOp::Or,
+            lhs: product,
+            rhs: rest,
+        },
+    )
+}
+
+/// The bits of the variables that appear in `cube`.
+fn support(cube: &Cube, num_inputs: usize) -> usize {
+    !cube.mask & ((1 << num_inputs) - 1)
+}
+
+/// Whether `divisor`'s literals are all in `cube`.
+fn divides(divisor: &Cube, cube: &Cube, num_inputs: usize) -> bool {
+    let bits = support(divisor, num_inputs);
+    support(cube, num_inputs) & bits == bits && (cube.value ^ divisor.value) & bits == 0
+}
+
+/// `cube` without `divisor`'s literals.
+fn quotient(cube: &Cube, divisor: &Cube, num_inputs: usize) -> Cube {
+    let bits = support(divisor, num_inputs);
+    Cube {
+        value: cube.value & !bits,
+        mask: cube.mask | bits,
+    }
+}
+
+/// The literals that every one of `cubes` has.
+fn common_cube(cubes: &[Cube], num_inputs: usize) -> Cube {
+    let mut bits = (1 << num_inputs) - 1;
+    for cube in cubes {
+        bits &= support(cube, num_inputs) & !(cube.value ^ cubes[0].value);
+    }
+
+    Cube {
+        value: cubes[0].value & bits,
+        mask: !bits & ((1 << num_inputs) - 1),
+    }
+}
+
+/// Divide the sum `f` by the sum `d`, giving the largest `q` with `f = q.d
+/// + r` as polynomials, and the remainder `r`.
+fn divide(f: &[Cube], d: &[Cube], num_inputs: usize) -> (Vec<Cube>, Vec<Cube>) {
+    let mut q: Option<Vec<Cube>> = None;
+    for divisor in d {
+        let quotients: Vec<Cube> = f
+            .iter()
+            .filter(|cube| divides(divisor, cube, num_inputs))
+            .map(|cube| quotient(cube, divisor, num_inputs))
+            .collect();
+        q = Some(match q {
+            None => quotients,
+            Some(q) => q.into_iter().filter(|c| quotients.contains(c)).collect(),
+        });
+    }
+    let q = q.unwrap_or_default();
+
+    // Products of the quotient and divisor have no variable in common, or
+    // the quotient would have kept it
+    let divided: Vec<Cube> = q
+        .iter()
+        .flat_map(|a| {
+            d.iter().map(move |b| Cube {
+                value: a.value | b.value,
+                mask: a.mask & b.mask,
+            })
+        })
+        .collect();
+    let r = f.iter().filter(|c| !divided.contains(c)).copied().collect();
+
+    (q, r)
+}
+
+/// Up to `MAX_KERNELS` kernels of `f`, found by dividing by each literal in
+/// more than one product and then by whatever the quotients still have in
+/// common, and again within each kernel found.
+fn kernels(f: &[Cube], num_inputs: usize) -> Vec<Vec<Cube>> {
+    let all = (1 << num_inputs) - 1;
+    let mut found: Vec<Vec<Cube>> = vec![];
+    let mut pending = vec![f.to_vec()];
+
+    while let Some(g) = pending.pop() {
+        for i in 0..num_inputs {
+            let bit = 1 << (num_inputs - 1 - i);
+            for value in [bit, 0] {
+                let literal = Cube {
+                    value,
+                    mask: all & !bit,
+                };
+                let with: Vec<Cube> = g
+                    .iter()
+                    .filter(|cube| divides(&literal, cube, num_inputs))
+                    .copied()
+                    .collect();
+                if with.len() < 2 {
+                    continue;
+                }
+
+                let common = common_cube(&with, num_inputs);
+                let mut kernel: Vec<Cube> = with
+                    .iter()
+                    .map(|cube| quotient(cube, &common, num_inputs))
+                    .collect();
+                kernel.sort_unstable_by_key(|c| Reverse((c.value, c.mask)));
+                if found.len() < MAX_KERNELS && !found.contains(&kernel) {
+                    found.push(kernel.clone());
+                    pending.push(kernel);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, equiv, parser::Parser, vm::VM};
+
+    let check = |source, expected| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+        let factored = factor(&table);
+        assert_eq!(factored.display(&table.input_names).to_string(), expected);
+        let factored = crate::parser::Equation {
+            lhs: factored,
+            ..equation.clone()
+        };
+        assert_eq!(equiv::check(&equation, &factored), Ok(()));
+    };
+
+    check("(A AND B) OR (A AND C) OR D = Q", "(A AND (B OR C)) OR D");
+    check(
+        "(A AND C) OR (A AND D) OR (B AND C) OR (B AND D) OR E = Q",
+        "((A OR B) AND (C OR D)) OR E",
+    );
+    check(
+        "(A AND B) OR (A AND C) OR (B AND C) = Q",
+        "(A AND (B OR C)) OR (B AND C)",
+    );
+    check("A XOR B = Q", "(A AND NOT B) OR ((NOT A) AND B)");
+    check("A OR NOT A = Q", "1");
+    check("A AND NOT A = Q", "0");
+
+    // Every kernel divides its sum, and has nothing left in common
+    let f = minimize::cover(&[0b0101, 0b0110, 0b1001, 0b1010, 0b0111, 0b1111]);
+    for kernel in kernels(&f, 4) {
+        assert!(!divide(&f, &kernel, 4).0.is_empty());
+        assert_eq!(common_cube(&kernel, 4).mask, 0b1111);
+    }
+}
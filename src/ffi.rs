@@ -0,0 +1,311 @@
+//! A C interface for embedding booltable, laid out for cbindgen. Equations
+//! and tables are opaque handles owned by the caller, and must be released
+//! with `bt_free` and `bt_table_free`. Strings returned as `*mut c_char` are
+//! owned by the caller and released with `bt_string_free`, while those
+//! returned as `*const c_char` live as long as the handle they came from.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    compiler::{self, Compiler},
+    export::{self, Format, TableStyle},
+    intern::Symbol,
+    parser::{Dialect, Parser},
+    vm::{Op, TruthTable, VM},
+};
+
+pub struct BtEquation {
+    inputs: Vec<CString>,
+    output: CString,
+    source: CString,
+    ops: Vec<Op>,
+    assumptions: Vec<Vec<Op>>,
+}
+
+pub struct BtTable {
+    input_names: Vec<CString>,
+    output_name: CString,
+    inputs: Vec<Vec<bool>>,
+    outputs: Vec<bool>,
+}
+
+impl BtEquation {
+    fn compiled(&self) -> compiler::Equation {
+        compiler::Equation {
+            inputs: self.inputs.iter().map(|s| symbol(s)).collect(),
+            lhs: self.ops.clone(),
+            output: symbol(&self.output),
+            assumptions: self.assumptions.clone(),
+        }
+    }
+}
+
+impl BtTable {
+    fn table(&self) -> TruthTable {
+        TruthTable {
+            input_names: self.input_names.iter().map(|s| symbol(s)).collect(),
+            inputs: self.inputs.clone(),
+            output_name: symbol(&self.output_name),
+            outputs: self.outputs.clone(),
+        }
+    }
+}
+
+/// The names were valid UTF-8 when they were copied into C strings.
+fn symbol(s: &CStr) -> Symbol {
+    Symbol::intern(s.to_str().unwrap())
+}
+
+fn c_string(s: &str) -> CString {
+    // Names come from the lexer and messages from us, neither contain NULs
+    CString::new(s).unwrap()
+}
+
+fn set_error(error: *mut *mut c_char, message: &str) {
+    if !error.is_null() {
+        // SAFETY: the caller passes either null or a valid out pointer
+        unsafe { *error = c_string(message).into_raw() };
+    }
+}
+
+/// Parse and compile an equation. On failure returns null and, if `error`
+/// isn't null, stores a message in it for the caller to free.
+///
+/// # Safety
+///
+/// `source` must be a valid NUL-terminated string, and `error` null or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bt_parse(
+    source: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut BtEquation {
+    if source.is_null() {
+        set_error(error, "source is null");
+        return ptr::null_mut();
+    }
+    let source = match CStr::from_ptr(source).to_str() {
+        Ok(source) => source,
+        Err(_) => {
+            set_error(error, "source isn't valid UTF-8");
+            return ptr::null_mut();
+        }
+    };
+
+    let mut parser = Parser::new(source);
+    let parsed = match parser.parse_equation() {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let span = err.span();
+            set_error(error, &format!("{} at {}", err, span));
+            return ptr::null_mut();
+        }
+    };
+
+    let normalised = parsed.to_source(Dialect::Words);
+    let compiled = Compiler::new(parsed).compile();
+    Box::into_raw(Box::new(BtEquation {
+        inputs: compiled.inputs.iter().map(|n| c_string(n)).collect(),
+        output: c_string(&compiled.output),
+        source: c_string(&normalised),
+        ops: compiled.lhs,
+        assumptions: compiled.assumptions,
+    }))
+}
+
+/// # Safety
+///
+/// `equation` must be null or come from `bt_parse`, and not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn bt_free(equation: *mut BtEquation) {
+    if !equation.is_null() {
+        drop(Box::from_raw(equation));
+    }
+}
+
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_equation_num_inputs(equation: *const BtEquation) -> usize {
+    (*equation).inputs.len()
+}
+
+/// The name of input `index`, or null if it is out of range.
+///
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_equation_input_name(
+    equation: *const BtEquation,
+    index: usize,
+) -> *const c_char {
+    let equation = &*equation;
+    equation
+        .inputs
+        .get(index)
+        .map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_equation_output_name(equation: *const BtEquation) -> *const c_char {
+    (*equation).output.as_ptr()
+}
+
+/// The equation printed back in normalised syntax.
+///
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_equation_source(equation: *const BtEquation) -> *const c_char {
+    (*equation).source.as_ptr()
+}
+
+/// Evaluate the equation for one assignment, given as `num_inputs` values.
+///
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse` and `inputs` must point
+/// to as many values as the equation has inputs.
+#[no_mangle]
+pub unsafe extern "C" fn bt_eval(equation: *const BtEquation, inputs: *const bool) -> bool {
+    let equation = &*equation;
+    let inputs = std::slice::from_raw_parts(inputs, equation.inputs.len());
+    VM::new(equation.compiled()).eval(inputs)
+}
+
+/// # Safety
+///
+/// `equation` must be a live handle from `bt_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_gen_table(equation: *const BtEquation) -> *mut BtTable {
+    let equation = &*equation;
+    let table = VM::new(equation.compiled()).gen();
+    Box::into_raw(Box::new(BtTable {
+        input_names: table.input_names.iter().map(|n| c_string(n)).collect(),
+        output_name: c_string(&table.output_name),
+        inputs: table.inputs,
+        outputs: table.outputs,
+    }))
+}
+
+/// # Safety
+///
+/// `table` must be null or come from `bt_gen_table`, and not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn bt_table_free(table: *mut BtTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+/// # Safety
+///
+/// `table` must be a live handle from `bt_gen_table`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_table_num_rows(table: *const BtTable) -> usize {
+    (*table).outputs.len()
+}
+
+/// The value of input `column` in `row`, or false if either is out of range.
+///
+/// # Safety
+///
+/// `table` must be a live handle from `bt_gen_table`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_table_input(table: *const BtTable, row: usize, column: usize) -> bool {
+    let table = &*table;
+    table
+        .inputs
+        .get(row)
+        .and_then(|r| r.get(column))
+        .copied()
+        .unwrap_or(false)
+}
+
+/// The output in `row`, or false if it is out of range.
+///
+/// # Safety
+///
+/// `table` must be a live handle from `bt_gen_table`.
+#[no_mangle]
+pub unsafe extern "C" fn bt_table_output(table: *const BtTable, row: usize) -> bool {
+    let table = &*table;
+    table.outputs.get(row).copied().unwrap_or(false)
+}
+
+/// Render the table in `format` (`markdown`, `csv`, `latex`, `html` or
+/// `json`, or markdown if null), or return null for an unknown format.
+///
+/// # Safety
+///
+/// `table` must be a live handle from `bt_gen_table` and `format` null or a
+/// valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bt_table_to_string(
+    table: *const BtTable,
+    format: *const c_char,
+) -> *mut c_char {
+    let format = if format.is_null() {
+        Format::Markdown
+    } else {
+        match CStr::from_ptr(format)
+            .to_str()
+            .ok()
+            .and_then(|f| f.parse().ok())
+        {
+            Some(format) => format,
+            None => return ptr::null_mut(),
+        }
+    };
+
+    let rendered = export::render(&(*table).table(), format, &TableStyle::default());
+    c_string(&rendered).into_raw()
+}
+
+/// # Safety
+///
+/// `s` must be null or a string returned as `*mut c_char` by this library,
+/// and not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn bt_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[test]
+fn test() {
+    unsafe {
+        let mut error = ptr::null_mut();
+        let equation = bt_parse(c"A AND NOT B = Q".as_ptr(), &mut error);
+        assert!(error.is_null());
+        assert_eq!(bt_equation_num_inputs(equation), 2);
+        assert_eq!(CStr::from_ptr(bt_equation_input_name(equation, 1)), c"B");
+        assert!(bt_eval(equation, [true, false].as_ptr()));
+
+        let table = bt_gen_table(equation);
+        assert_eq!(bt_table_num_rows(table), 4);
+        assert!(bt_table_output(table, 2));
+        let csv = bt_table_to_string(table, c"csv".as_ptr());
+        assert_eq!(CStr::from_ptr(csv), c"A,B,Q\n0,0,0\n0,1,0\n1,0,1\n1,1,0\n");
+        bt_string_free(csv);
+        bt_table_free(table);
+        bt_free(equation);
+
+        assert!(bt_parse(c"A AND = Q".as_ptr(), &mut error).is_null());
+        assert_eq!(
+            CStr::from_ptr(error),
+            c"Expected boolean expression, got = at 6..7"
+        );
+        bt_string_free(error);
+    }
+}
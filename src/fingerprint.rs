@@ -0,0 +1,96 @@
+//! Stable hashes of boolean functions, for deduplicating and caching them
+//! across runs. Unlike `std`'s hashers, FNV-1a gives the same value on every
+//! platform and version, so fingerprints can be stored.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    compiler::Compiler,
+    intern::Symbol,
+    parser::Equation,
+    vm::{TruthTable, VM},
+};
+
+const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(PRIME);
+        }
+    }
+}
+
+impl TruthTable {
+    /// A hash of the inputs' names and the packed output column. Rows are
+    /// put in the order of the sorted names first, so the order in which
+    /// inputs appear doesn't matter, and the output's name is left out.
+    /// Tables with rows left out by assumptions also hash which rows are
+    /// present.
+    pub fn fingerprint(&self) -> u64 {
+        let mut names: Vec<(usize, Symbol)> =
+            self.input_names.iter().copied().enumerate().collect();
+        names.sort_by_key(|(_, name)| *name);
+
+        let num_rows = 1usize << names.len();
+        let mut outputs = vec![0u64; num_rows.div_ceil(64)];
+        let mut present = outputs.clone();
+        for (inputs, output) in self.inputs.iter().zip(&self.outputs) {
+            let row = names
+                .iter()
+                .fold(0, |n, (i, _)| n << 1 | inputs[*i] as usize);
+            outputs[row / 64] |= (*output as u64) << (row % 64);
+            present[row / 64] |= 1 << (row % 64);
+        }
+
+        let mut hash = Fnv(OFFSET);
+        hash.write(&(names.len() as u64).to_le_bytes());
+        for (_, name) in &names {
+            hash.write(name.as_bytes());
+            hash.write(&[0xff]);
+        }
+        for word in outputs {
+            hash.write(&word.to_le_bytes());
+        }
+        if self.outputs.len() != num_rows {
+            for word in present {
+                hash.write(&word.to_le_bytes());
+            }
+        }
+
+        hash.0
+    }
+}
+
+impl Equation {
+    /// The fingerprint of the equation's truth table, which is the same for
+    /// any two equations over the same inputs that compute the same function.
+    pub fn semantic_hash(&self) -> u64 {
+        VM::new(Compiler::new(self.clone()).compile())
+            .gen()
+            .fingerprint()
+    }
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let hash = |source| {
+        Parser::new(source)
+            .parse_equation()
+            .unwrap()
+            .semantic_hash()
+    };
+
+    assert_eq!(hash("A AND NOT B = Q"), hash("(NOT B) AND A = P"));
+    assert_eq!(hash("A AND NOT B = Q"), hash("NOT (B OR NOT A) = Q"));
+    assert_ne!(hash("A AND NOT B = Q"), hash("A AND B = Q"));
+    assert_ne!(hash("A AND NOT B = Q"), hash("X AND NOT Y = Q"));
+    assert_ne!(hash("A AND B = Q"), hash("assume A; A AND B = Q"));
+    // Stored fingerprints have to stay valid
+    assert_eq!(hash("A XOR B = Q"), 0x2b3b_de62_6aaa_9d76);
+}
@@ -0,0 +1,299 @@
+//! Generating tables on the GPU with wgpu, for equations with too many
+//! inputs for the CPU, up to [`MAX_INPUTS`]. It runs the shader from
+//! [`wgsl::shader`] a chunk of words at a time, so the whole table never has
+//! to fit in GPU memory, and reads each chunk back before starting the next:
+//! either the words of outputs and of rows that satisfy the assumptions, or
+//! only the count of rows that are 1, for model counting.
+
+use std::{
+    io::{self, Write},
+    sync::mpsc,
+};
+
+use derive_more::Display;
+
+use crate::{
+    compiler::Equation,
+    stream::ChunkedWriter,
+    tablefile,
+    wgsl::{self, MAX_INPUTS, WORKGROUP_SIZE},
+};
+
+/// The default number of words of 32 rows in a chunk: 4 MiB each of
+/// outputs and assumptions.
+pub const CHUNK_WORDS: u32 = 1 << 20;
+
+/// The most words a chunk can have, as a dispatch has at most 65535
+/// workgroups.
+pub const MAX_CHUNK_WORDS: u32 = 65535 * WORKGROUP_SIZE as u32;
+
+/// Called with each chunk as it comes back, see [`Gpu::run`].
+type OnChunk<'a> = &'a mut dyn FnMut(u64, &[u32], &[u32]) -> io::Result<()>;
+
+#[derive(Debug, Display)]
+pub enum GpuError {
+    #[display(fmt = "no GPU adapter was found")]
+    NoAdapter,
+    #[display(fmt = "{}", _0)]
+    Device(wgpu::RequestDeviceError),
+    #[display(fmt = "reading back from the GPU failed: {}", _0)]
+    Map(wgpu::BufferAsyncError),
+    #[display(fmt = "the GPU can take at most {} inputs", MAX_INPUTS)]
+    TooManyInputs,
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+}
+
+impl From<io::Error> for GpuError {
+    fn from(err: io::Error) -> Self {
+        GpuError::Io(err)
+    }
+}
+
+/// A device to run shaders on and its queue.
+#[derive(Debug)]
+pub struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+impl Gpu {
+    /// The first adapter wgpu finds, preferring a discrete GPU. Fails with
+    /// [`GpuError::NoAdapter`] on machines without one, as in most CI.
+    pub fn new() -> Result<Self, GpuError> {
+        pollster::block_on(async {
+            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    ..Default::default()
+                })
+                .await
+                .ok_or(GpuError::NoAdapter)?;
+            let (device, queue) = adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        label: Some("booltable"),
+                        required_limits: wgpu::Limits::downlevel_defaults(),
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .await
+                .map_err(GpuError::Device)?;
+
+            Ok(Self { device, queue })
+        })
+    }
+
+    /// Generate the table of `equation`, `chunk_words` words at a time
+    /// (clamped to `1..=MAX_CHUNK_WORDS`), calling `f` with the number of
+    /// each chunk's first word, its words of outputs and its words of rows
+    /// that satisfy the assumptions. Bit `j` of word `w` is row `32 * w +
+    /// j`, as in [`wgsl::eval_word`]. Returns the number of rows that
+    /// satisfy the assumptions and have an output of 1.
+    pub fn run(
+        &self,
+        equation: &Equation,
+        chunk_words: u32,
+        mut f: impl FnMut(u64, &[u32], &[u32]) -> io::Result<()>,
+    ) -> Result<u64, GpuError> {
+        self.dispatch(equation, chunk_words, Some(&mut f))
+    }
+
+    /// The number of rows of `equation`'s table that satisfy the assumptions
+    /// and have an output of 1, reading back only a count per chunk.
+    pub fn count(&self, equation: &Equation) -> Result<u64, GpuError> {
+        self.dispatch(equation, CHUNK_WORDS, None)
+    }
+
+    /// Generate the table of `equation`, writing it to `out` in the table
+    /// file format as each chunk comes back. Returns the same count as
+    /// [`Gpu::count`].
+    pub fn write_table(&self, equation: &Equation, out: impl Write) -> Result<u64, GpuError> {
+        let mut out = ChunkedWriter::new(out);
+        tablefile::write_header(&mut out, &equation.inputs, &equation.output)?;
+
+        // A table of fewer than 32 rows still takes a whole word, but only a
+        // group of eight in the file
+        let mut left = tablefile::data_len(equation.inputs.len());
+        let count = self.run(equation, CHUNK_WORDS, |_, outputs, assumed| {
+            for (outputs, assumed) in outputs.iter().zip(assumed) {
+                // The file has an output of 0 for rows left out
+                let outputs = outputs & assumed;
+                let groups = outputs.to_le_bytes().into_iter().zip(assumed.to_le_bytes());
+                for (outputs, included) in groups.take(left as usize / 2) {
+                    out.write_all(&[outputs, included])?;
+                    left -= 2;
+                }
+            }
+            Ok(())
+        })?;
+        out.finish()?;
+
+        Ok(count)
+    }
+
+    fn dispatch(
+        &self,
+        equation: &Equation,
+        chunk_words: u32,
+        mut f: Option<OnChunk>,
+    ) -> Result<u64, GpuError> {
+        let num_inputs = equation.inputs.len();
+        if num_inputs > MAX_INPUTS {
+            return Err(GpuError::TooManyInputs);
+        }
+        let words = wgsl::words(num_inputs);
+        let chunk = u64::from(chunk_words.clamp(1, MAX_CHUNK_WORDS)).min(words);
+
+        let device = &self.device;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("table"),
+            source: wgpu::ShaderSource::Wgsl(wgsl::shader(equation).into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("table"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let buffer = |label, size, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        use wgpu::BufferUsages as Usage;
+        let params = buffer("params", 16, Usage::UNIFORM | Usage::COPY_DST);
+        let outputs = buffer("outputs", chunk * 4, Usage::STORAGE | Usage::COPY_SRC);
+        let assumed = buffer("assumed", chunk * 4, Usage::STORAGE | Usage::COPY_SRC);
+        let count = buffer(
+            "count",
+            4,
+            Usage::STORAGE | Usage::COPY_SRC | Usage::COPY_DST,
+        );
+        // The count, padded to eight bytes, then the outputs and assumptions
+        // if they're wanted
+        let readback = 8 + if f.is_some() { 8 * chunk } else { 0 };
+        let staging = buffer("staging", readback, Usage::MAP_READ | Usage::COPY_DST);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("table"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[&params, &outputs, &assumed, &count]
+                .into_iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        let mut total = 0;
+        let mut first = 0;
+        while first < words {
+            let len = chunk.min(words - first);
+            self.queue
+                .write_buffer(&params, 0, &(first as u32).to_le_bytes());
+            self.queue.write_buffer(&count, 0, &0u32.to_le_bytes());
+
+            let mut encoder = device.create_command_encoder(&Default::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(len.div_ceil(WORKGROUP_SIZE as u64) as u32, 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&count, 0, &staging, 0, 4);
+            if f.is_some() {
+                encoder.copy_buffer_to_buffer(&outputs, 0, &staging, 8, 4 * len);
+                encoder.copy_buffer_to_buffer(&assumed, 0, &staging, 8 + 4 * chunk, 4 * len);
+            }
+            self.queue.submit([encoder.finish()]);
+
+            let slice = staging.slice(..);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            device.poll(wgpu::Maintain::Wait);
+            receiver
+                .recv()
+                .expect("the map callback runs during the poll")
+                .map_err(GpuError::Map)?;
+
+            {
+                let bytes = slice.get_mapped_range();
+                let words = |start: u64| -> Vec<u32> {
+                    bytes[start as usize..(start + 4 * len) as usize]
+                        .chunks_exact(4)
+                        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                        .collect()
+                };
+                total += u64::from(u32::from_le_bytes(bytes[..4].try_into().unwrap()));
+                if let Some(f) = &mut f {
+                    f(first, &words(8), &words(8 + 4 * chunk))?;
+                }
+            }
+            staging.unmap();
+            first += len;
+        }
+
+        Ok(total)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, register::Program, vm::VM};
+
+    // Without an adapter there's nothing to check against
+    let Ok(gpu) = Gpu::new() else {
+        return;
+    };
+
+    for source in [
+        "(A AND B) OR (C AND (A XOR B)) = Q",
+        "assume A != F; (A OR B) AND NOT (C XOR D) AND (E OR F OR G) = Q",
+        "assume H != I; (A XOR B XOR C XOR D) AND (E OR F) OR (G AND H AND NOT I) \
+         OR (J AND K) OR L = Q",
+    ] {
+        let compile = || Compiler::new(Parser::new(source).parse_equation().unwrap()).compile();
+        let equation = compile();
+        let num_inputs = equation.inputs.len();
+        let program = Program::from_equation(&equation);
+        let table = VM::new(compile()).gen();
+        let expected = table.outputs.iter().filter(|output| **output).count() as u64;
+
+        // Chunks that split the table unevenly, and one chunk for all of it
+        for chunk_words in [3, CHUNK_WORDS] {
+            let mut next = 0;
+            let count = gpu
+                .run(&equation, chunk_words, |first, outputs, assumed| {
+                    assert_eq!(first, next, "{}", source);
+                    for (i, word) in outputs.iter().zip(assumed).enumerate() {
+                        let cpu = wgsl::eval_word(&program, num_inputs, (first + i as u64) as u32);
+                        assert_eq!((*word.0, *word.1), cpu, "{}", source);
+                    }
+                    next += outputs.len() as u64;
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(next, wgsl::words(num_inputs), "{}", source);
+            assert_eq!(count, expected, "{}", source);
+        }
+        assert_eq!(gpu.count(&equation).unwrap(), expected, "{}", source);
+
+        let (mut written, mut generated) = (vec![], vec![]);
+        gpu.write_table(&equation, &mut written).unwrap();
+        tablefile::write(&mut VM::new(compile()), &mut generated).unwrap();
+        assert_eq!(written, generated, "{}", source);
+    }
+}
@@ -0,0 +1,184 @@
+//! Incremental table generation. Rows are visited in Gray-code order, so
+//! each differs from the last in a single input, and only the values that
+//! depend on that input are recomputed, stopping wherever a value comes out
+//! the same as before.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    assignments,
+    compiler::Equation,
+    register::{Instr, Program},
+    vm::TruthTable,
+};
+
+/// A value and the values it's computed from, each by its index.
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    Const(bool),
+    Load(usize),
+    Not(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    Xor(usize, usize),
+}
+
+impl Node {
+    fn eval(self, values: &[bool], inputs: &[bool]) -> bool {
+        match self {
+            Node::Const(value) => value,
+            Node::Load(input) => inputs[input],
+            Node::Not(a) => !values[a],
+            Node::And(a, b) => values[a] && values[b],
+            Node::Or(a, b) => values[a] || values[b],
+            Node::Xor(a, b) => values[a] ^ values[b],
+        }
+    }
+
+    fn operands(self) -> impl Iterator<Item = usize> {
+        let (a, b) = match self {
+            Node::Const(_) | Node::Load(_) => (None, None),
+            Node::Not(a) => (Some(a), None),
+            Node::And(a, b) | Node::Or(a, b) | Node::Xor(a, b) => (Some(a), Some(b)),
+        };
+        a.into_iter().chain(b)
+    }
+}
+
+/// An equation's output and assumptions as a graph of values, with the
+/// values that depend on each input.
+#[derive(Debug, Clone)]
+pub struct Incremental {
+    /// In an order where every value comes after its operands.
+    nodes: Vec<Node>,
+    /// The values to recompute when each input changes, in order.
+    cones: Vec<Vec<usize>>,
+    /// The output, then each assumption.
+    roots: Vec<usize>,
+}
+
+impl Incremental {
+    pub fn new(equation: &Equation) -> Self {
+        let program = Program::from_equation(equation);
+
+        // Slots are reused once a value is dead, so give every instruction
+        // its own node, reading whichever node last wrote each slot
+        let mut written = vec![0; program.slots];
+        let mut nodes = vec![];
+        for instr in &program.instrs {
+            let (dst, node) = match *instr {
+                Instr::Const { dst, value } => (dst, Node::Const(value)),
+                Instr::Load { dst, input } => (dst, Node::Load(input)),
+                Instr::Not { dst, src } => (dst, Node::Not(written[src])),
+                Instr::And { dst, lhs, rhs } => (dst, Node::And(written[lhs], written[rhs])),
+                Instr::Or { dst, lhs, rhs } => (dst, Node::Or(written[lhs], written[rhs])),
+                Instr::Xor { dst, lhs, rhs } => (dst, Node::Xor(written[lhs], written[rhs])),
+            };
+            nodes.push(node);
+            written[dst] = nodes.len() - 1;
+        }
+
+        let mut cones = vec![vec![]; equation.inputs.len()];
+        let mut depends = vec![false; nodes.len()];
+        for (input, cone) in cones.iter_mut().enumerate() {
+            depends.fill(false);
+            for (i, node) in nodes.iter().enumerate() {
+                depends[i] = match node {
+                    Node::Load(n) => *n == input,
+                    _ => node.operands().any(|operand| depends[operand]),
+                };
+                if depends[i] {
+                    cone.push(i);
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            cones,
+            roots: program.outputs.iter().map(|slot| written[*slot]).collect(),
+        }
+    }
+
+    /// The output for every row, indexed by row, along with whether the row
+    /// satisfies the assumptions.
+    pub fn outputs(&self, num_inputs: usize) -> Vec<(bool, bool)> {
+        let mut inputs = vec![false; num_inputs];
+        let mut values = vec![false; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            values[i] = node.eval(&values, &inputs);
+        }
+        // The step at which each value last changed
+        let mut changed = vec![0usize; self.nodes.len()];
+
+        let mut rows = vec![(false, false); 1 << num_inputs];
+        for step in 0..rows.len() {
+            if step > 0 {
+                // Gray code `step ^ (step >> 1)` differs from the previous
+                // one in the lowest set bit of `step`, and input 0 is the
+                // highest bit of the row
+                let input = num_inputs - 1 - step.trailing_zeros() as usize;
+                inputs[input] = !inputs[input];
+                for &i in &self.cones[input] {
+                    let node = self.nodes[i];
+                    let dirty = match node {
+                        Node::Load(_) => true,
+                        _ => node.operands().any(|operand| changed[operand] == step),
+                    };
+                    if dirty {
+                        let value = node.eval(&values, &inputs);
+                        if value != values[i] {
+                            values[i] = value;
+                            changed[i] = step;
+                        }
+                    }
+                }
+            }
+
+            let row = assignments::gray_code(step);
+            let (output, assumptions) = self.roots.split_first().unwrap();
+            rows[row] = (
+                values[*output],
+                assumptions.iter().all(|root| values[*root]),
+            );
+        }
+
+        rows
+    }
+
+    /// The same table as `VM::gen`.
+    pub fn gen(&self, equation: &Equation) -> TruthTable {
+        let length = equation.inputs.len();
+        let mut table = TruthTable {
+            input_names: equation.inputs.clone(),
+            inputs: vec![],
+            output_name: equation.output,
+            outputs: vec![],
+        };
+        for (row, (output, assumed)) in self.outputs(length).into_iter().enumerate() {
+            if assumed {
+                table.inputs.push(assignments::to_bools(row, length));
+                table.outputs.push(output);
+            }
+        }
+
+        table
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    for source in [
+        "(A AND B) OR (C AND (A XOR B)) = Q",
+        "assume A != D; (A OR B) AND NOT (C XOR (D AND 1)) = Q",
+        "1 = Q",
+    ] {
+        let equation = Compiler::new(Parser::new(source).parse_equation().unwrap()).compile();
+        let incremental = Incremental::new(&equation).gen(&equation);
+        let table = VM::new(equation).gen();
+        assert_eq!(incremental.inputs, table.inputs, "{}", source);
+        assert_eq!(incremental.outputs, table.outputs, "{}", source);
+    }
+}
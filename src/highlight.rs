@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use yansi::Paint;
+
+use crate::lexer::{Lexer, TK};
+
+/// Colourise `source` token by token, leaving whitespace and anything the
+/// lexer skips untouched.
+pub fn highlight(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for token in Lexer::new(source) {
+        out.push_str(&source[last..token.span.start]);
+        let text = token.text(source);
+        let _ = match token.kind {
+            TK::Not
+            | TK::And
+            | TK::Or
+            | TK::Xor
+            | TK::Exists
+            | TK::Forall
+            | TK::Assume
+            | TK::EqualsEquals
+            | TK::NotEquals => {
+                write!(out, "{}", Paint::magenta(text).bold())
+            }
+            TK::True | TK::False => write!(out, "{}", Paint::yellow(text)),
+            TK::Var | TK::Ref => write!(out, "{}", Paint::cyan(text)),
+            TK::LParen | TK::RParen | TK::Semicolon | TK::Comma => {
+                write!(out, "{}", Paint::new(text).dimmed())
+            }
+            TK::Equals => write!(out, "{}", Paint::new(text).bold()),
+            TK::Error => write!(out, "{}", Paint::red(text).underline()),
+            TK::Eof => Ok(()),
+        };
+        last = token.span.end;
+    }
+    out.push_str(&source[last..]);
+
+    out
+}
@@ -0,0 +1,412 @@
+//! Rasterising tables to PNG, for dropping them into slides without going
+//! through LaTeX or HTML. Text is drawn with a built-in 5x7 font covering
+//! digits, letters and a little punctuation, with lowercase drawn as
+//! uppercase and anything else as `?`. The PNG is written uncompressed, so
+//! nothing beyond `alloc` is needed.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::str::FromStr;
+
+use crate::{
+    export::{self, TableStyle},
+    kmap,
+    vm::TruthTable,
+};
+
+type Rgb = [u8; 3];
+
+/// The colours of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub background: Rgb,
+    pub text: Rgb,
+    pub grid: Rgb,
+    pub header: Rgb,
+    /// The fill of output cells that are true.
+    pub true_fill: Rgb,
+    pub false_fill: Rgb,
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        background: [0xff, 0xff, 0xff],
+        text: [0x20, 0x20, 0x20],
+        grid: [0xb0, 0xb0, 0xb0],
+        header: [0xe8, 0xe8, 0xe8],
+        true_fill: [0xc6, 0xef, 0xce],
+        false_fill: [0xff, 0xc7, 0xce],
+    };
+
+    pub const DARK: Theme = Theme {
+        background: [0x1e, 0x1e, 0x1e],
+        text: [0xe0, 0xe0, 0xe0],
+        grid: [0x50, 0x50, 0x50],
+        header: [0x30, 0x30, 0x30],
+        true_fill: [0x1f, 0x4d, 0x2b],
+        false_fill: [0x5c, 0x22, 0x26],
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::LIGHT
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::LIGHT),
+            "dark" => Ok(Theme::DARK),
+            _ => Err(alloc::format!(
+                "unknown theme '{}', expected light or dark",
+                s
+            )),
+        }
+    }
+}
+
+/// An RGB image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row from the top left.
+    pub pixels: Vec<Rgb>,
+}
+
+/// Each pixel of the font is drawn as a square this many pixels across.
+const SCALE: usize = 2;
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// The space around the text of a cell.
+const PADDING: usize = 6;
+/// The space around the image and between the table and the K-map.
+const MARGIN: usize = 12;
+
+/// The rows of `c`'s glyph, the leftmost pixel in the highest of five bits.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; 7],
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '\'' => [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+        '\\' => [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// A cell of a grid, and its fill if it isn't the background.
+struct Cell<'a> {
+    text: &'a str,
+    fill: Option<Rgb>,
+}
+
+/// The width of each column and the height of every row of a grid.
+fn measure(cells: &[Vec<Cell>]) -> (Vec<usize>, usize) {
+    let columns = cells.first().map_or(0, Vec::len);
+    let widths = (0..columns)
+        .map(|column| {
+            let chars = cells
+                .iter()
+                .map(|row| row[column].text.chars().count())
+                .max()
+                .unwrap_or(0);
+            chars * (GLYPH_WIDTH + 1) * SCALE - SCALE + 2 * PADDING
+        })
+        .collect();
+
+    (widths, GLYPH_HEIGHT * SCALE + 2 * PADDING)
+}
+
+/// The size of a grid including its outer lines.
+fn grid_size(cells: &[Vec<Cell>]) -> (usize, usize) {
+    let (widths, height) = measure(cells);
+    (
+        widths.iter().map(|w| w + 1).sum::<usize>() + 1,
+        cells.len() * (height + 1) + 1,
+    )
+}
+
+impl Image {
+    fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, colour: Rgb) {
+        for row in y..y + height {
+            self.pixels[row * self.width + x..row * self.width + x + width].fill(colour);
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, colour: Rgb) {
+        for (i, c) in text.chars().enumerate() {
+            let left = x + i * (GLYPH_WIDTH + 1) * SCALE;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits >> (GLYPH_WIDTH - 1 - column) & 1 == 1 {
+                        self.fill(left + column * SCALE, y + row * SCALE, SCALE, SCALE, colour);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw `cells` with their top left corner at `(x, y)`, centring the
+    /// text of each cell.
+    fn grid(&mut self, x: usize, y: usize, cells: &[Vec<Cell>], theme: &Theme) {
+        let (widths, height) = measure(cells);
+        let (total_width, total_height) = grid_size(cells);
+        for (r, row) in cells.iter().enumerate() {
+            let top = y + 1 + r * (height + 1);
+            let mut left = x + 1;
+            for (cell, width) in row.iter().zip(&widths) {
+                if let Some(fill) = cell.fill {
+                    self.fill(left, top, *width, height, fill);
+                }
+                let text_width =
+                    (cell.text.chars().count() * (GLYPH_WIDTH + 1) * SCALE).saturating_sub(SCALE);
+                self.text(
+                    left + (width - text_width) / 2,
+                    top + PADDING,
+                    cell.text,
+                    theme.text,
+                );
+                left += width + 1;
+            }
+        }
+
+        // Lines go between and around every cell
+        let mut left = x;
+        self.fill(left, y, 1, total_height, theme.grid);
+        for width in &widths {
+            left += width + 1;
+            self.fill(left, y, 1, total_height, theme.grid);
+        }
+        for r in 0..=cells.len() {
+            self.fill(x, y + r * (height + 1), total_width, 1, theme.grid);
+        }
+    }
+
+    /// The image as a PNG file.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, and the only compression, filter and
+        // interlace methods there are
+        header.extend([8, 2, 0, 0, 0]);
+        chunk(&mut out, b"IHDR", &header);
+
+        // Each scanline starts with its filter, which is none
+        let mut scanlines = Vec::with_capacity(self.height * (3 * self.width + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            scanlines.push(0);
+            scanlines.extend(row.iter().flatten());
+        }
+        chunk(&mut out, b"IDAT", &zlib_stored(&scanlines));
+        chunk(&mut out, b"IEND", &[]);
+
+        out
+    }
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// `data` as a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        let length = block.len() as u16;
+        out.extend(length.to_le_bytes());
+        out.extend((!length).to_le_bytes());
+        out.extend(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend((b << 16 | a).to_be_bytes());
+
+    out
+}
+
+/// The table as an image, with its output cells filled by value, and its
+/// K-map beside it if it has at most `kmap::MAX_INPUTS` inputs.
+pub fn render(table: &TruthTable, style: &TableStyle, theme: &Theme) -> Image {
+    let inputs = table.input_names.len();
+    let header = |text| Cell {
+        text,
+        fill: Some(theme.header),
+    };
+    let value_fill = |value: Option<bool>| match value {
+        Some(true) => Some(theme.true_fill),
+        Some(false) => Some(theme.false_fill),
+        None => None,
+    };
+
+    let mut cells = vec![table
+        .input_names
+        .iter()
+        .chain([&table.output_name])
+        .map(|name| header(name))
+        .collect::<Vec<_>>()];
+    for row in export::rows(table, style) {
+        let output = row[inputs] == style.true_symbol;
+        cells.push(
+            row.iter()
+                .enumerate()
+                .map(|(i, text)| Cell {
+                    text,
+                    fill: if i == inputs {
+                        value_fill(Some(output))
+                    } else {
+                        None
+                    },
+                })
+                .collect(),
+        );
+    }
+
+    let map = kmap::cells(table);
+    let mut map_cells = vec![];
+    if let Some(map) = &map {
+        map_cells.push(
+            [map.corner.as_str()]
+                .into_iter()
+                .chain(map.column_labels.iter().map(String::as_str))
+                .map(header)
+                .collect::<Vec<_>>(),
+        );
+        for (label, values) in map.row_labels.iter().zip(&map.values) {
+            let mut row = vec![header(label)];
+            row.extend(values.iter().map(|value| Cell {
+                text: match value {
+                    Some(true) => &style.true_symbol,
+                    Some(false) => &style.false_symbol,
+                    None => "-",
+                },
+                fill: value_fill(*value),
+            }));
+            map_cells.push(row);
+        }
+    }
+
+    let (table_width, table_height) = grid_size(&cells);
+    let (map_width, map_height) = if map_cells.is_empty() {
+        (0, 0)
+    } else {
+        let (width, height) = grid_size(&map_cells);
+        (width + MARGIN, height)
+    };
+    let mut image = Image::new(
+        table_width + map_width + 2 * MARGIN,
+        table_height.max(map_height) + 2 * MARGIN,
+        theme.background,
+    );
+    image.grid(MARGIN, MARGIN, &cells, theme);
+    if !map_cells.is_empty() {
+        image.grid(MARGIN + table_width + MARGIN, MARGIN, &map_cells, theme);
+    }
+
+    image
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+    let equation = Parser::new("A XOR B = Q").parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    let image = render(&table, &TableStyle::default(), &Theme::DARK);
+    assert_eq!(image.pixels.len(), image.width * image.height);
+    // The corner is the background, just inside it the grid, and the first
+    // header cell is filled as a header
+    assert_eq!(image.pixels[0], Theme::DARK.background);
+    let at = |x, y| image.pixels[(MARGIN + y) * image.width + MARGIN + x];
+    assert_eq!(at(0, 0), Theme::DARK.grid);
+    assert_eq!(at(1, 1), Theme::DARK.header);
+    assert!(image.pixels.contains(&Theme::DARK.true_fill));
+    assert!(image.pixels.contains(&Theme::DARK.false_fill));
+
+    let png = image.to_png();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+    assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+}
@@ -0,0 +1,190 @@
+//! Interned names. Equations, compiled equations and tables hold their
+//! variables' names as [`Symbol`]s, so each distinct name is stored once
+//! however many equations and tables mention it, and names compare and
+//! hash as integers. A symbol is only turned back into its name when it's
+//! printed.
+//!
+//! The names live in one table for the whole program and are never freed,
+//! which is what lets [`Symbol::as_str`] return a `&'static str`. That's no
+//! cost to a one-shot command or the REPL, but a long-running host seeing
+//! unboundedly many distinct names, such as `booltable serve`, grows by
+//! each one.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    hint,
+    ops::Deref,
+    sync::atomic::{self, AtomicBool},
+};
+
+use serde::{Serialize, Serializer};
+
+/// A name, interned in the program's table of names.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Symbol(u32);
+
+struct Interner {
+    names: Vec<&'static str>,
+    symbols: BTreeMap<&'static str, Symbol>,
+}
+
+/// The table behind a spin lock, since a mutex needs `std`. It's only held
+/// for a lookup, so there's little to wait for.
+struct Table {
+    locked: AtomicBool,
+    interner: UnsafeCell<Interner>,
+}
+
+// The interner is only reached through `with`, which holds the lock
+unsafe impl Sync for Table {}
+
+static TABLE: Table = Table {
+    locked: AtomicBool::new(false),
+    interner: UnsafeCell::new(Interner {
+        names: Vec::new(),
+        symbols: BTreeMap::new(),
+    }),
+};
+
+fn with<T>(f: impl FnOnce(&mut Interner) -> T) -> T {
+    while TABLE
+        .locked
+        .compare_exchange_weak(
+            false,
+            true,
+            atomic::Ordering::Acquire,
+            atomic::Ordering::Relaxed,
+        )
+        .is_err()
+    {
+        hint::spin_loop();
+    }
+    let result = f(unsafe { &mut *TABLE.interner.get() });
+    TABLE.locked.store(false, atomic::Ordering::Release);
+
+    result
+}
+
+impl Symbol {
+    /// The symbol for `name`, adding it to the table if it's new.
+    pub fn intern(name: &str) -> Self {
+        with(|interner| {
+            if let Some(&symbol) = interner.symbols.get(name) {
+                return symbol;
+            }
+            let name: &'static str = Box::leak(name.into());
+            let symbol = Symbol(interner.names.len() as u32);
+            interner.names.push(name);
+            interner.symbols.insert(name, symbol);
+            symbol
+        })
+    }
+
+    /// The symbol for `name` if it has been interned.
+    pub fn get(name: &str) -> Option<Self> {
+        with(|interner| interner.symbols.get(name).copied())
+    }
+
+    pub fn as_str(self) -> &'static str {
+        with(|interner| interner.names[self.0 as usize])
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+/// Symbols sort by name rather than by when they were interned, so sorted
+/// output doesn't depend on what else the program has seen.
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.0 == other.0 {
+            true => Ordering::Equal,
+            false => self.as_str().cmp(other.as_str()),
+        }
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[test]
+fn test() {
+    let a = Symbol::intern("intern_test_a");
+    assert_eq!(Symbol::intern("intern_test_a"), a);
+    assert_eq!(Symbol::get("intern_test_a"), Some(a));
+    assert_eq!(Symbol::get("intern_test_missing"), None);
+    assert_eq!(a, "intern_test_a");
+    assert_eq!(a.to_string(), "intern_test_a");
+    assert_eq!(alloc::format!("{:?}", a), "\"intern_test_a\"");
+
+    // Ordered by name, not by when they were interned
+    let z = Symbol::intern("intern_test_0");
+    assert!(z < a);
+    assert_eq!(a.len(), 13);
+}
@@ -0,0 +1,76 @@
+//! A tree-walking evaluator for the syntax tree. It is slower than compiling
+//! to bytecode, but simple enough to trust, which makes it an oracle for
+//! testing the compiler and VM.
+
+use alloc::vec::Vec;
+
+use crate::{
+    compiler::Compiler,
+    parser::{BinOp, Equation, Expr, ExprRef},
+    vm::VM,
+};
+
+/// Evaluate `expr` with variable `i` set to `inputs[i]`.
+pub fn eval(expr: ExprRef, inputs: &[bool]) -> bool {
+    match expr.node() {
+        Expr::Bool(b) => b,
+        Expr::Var(v) => inputs[v],
+        Expr::Not(e) => !eval(e, inputs),
+        Expr::BinOp { op, lhs, rhs } => {
+            let (lhs, rhs) = (eval(lhs, inputs), eval(rhs, inputs));
+            match op {
+                BinOp::And => lhs && rhs,
+                BinOp::Or => lhs || rhs,
+                BinOp::Xor => lhs ^ rhs,
+            }
+        }
+    }
+}
+
+/// A row on which the interpreter and the compiled equation disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub row: usize,
+    pub inputs: Vec<bool>,
+    pub interpreted: bool,
+    pub compiled: bool,
+}
+
+/// Evaluate every assignment with both the interpreter and the VM, running
+/// both the straight-line and short-circuiting code, and return the first
+/// row on which they differ.
+pub fn cross_check(equation: &Equation) -> Result<(), Mismatch> {
+    let compiler = Compiler::new(equation.clone());
+    for compiled in [compiler.compile(), compiler.compile_short_circuit()] {
+        let table = VM::new(compiled).gen();
+        for (row, (inputs, &compiled)) in table.inputs.into_iter().zip(&table.outputs).enumerate() {
+            let interpreted = eval(equation.lhs.expr(), &inputs);
+            if interpreted != compiled {
+                return Err(Mismatch {
+                    row,
+                    inputs,
+                    interpreted,
+                    compiled,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    for source in [
+        "A AND NOT B = Q",
+        "(A XOR B) OR (C AND 0) = Q",
+        "NOT (A OR B) XOR (NOT C AND D) = Q",
+    ] {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        assert_eq!(cross_check(&equation), Ok(()), "{}", source);
+    }
+    let equation = Parser::new("A OR NOT A = Q").parse_equation().unwrap();
+    assert!(eval(equation.lhs.expr(), &[false]));
+}
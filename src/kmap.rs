@@ -0,0 +1,127 @@
+//! Karnaugh maps, with rows and columns in Gray code order so that
+//! neighbouring cells differ in a single input.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use crate::{
+    assignments::{self, gray_code as gray},
+    export::TableStyle,
+    intern::Symbol,
+    vm::TruthTable,
+};
+
+/// K-maps stop being readable past four inputs.
+pub const MAX_INPUTS: usize = 4;
+
+fn label(code: usize, bits: usize) -> String {
+    (0..bits)
+        .map(|b| {
+            if code >> (bits - 1 - b) & 1 == 1 {
+                '1'
+            } else {
+                '0'
+            }
+        })
+        .collect()
+}
+
+/// The cells of a map, with their labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cells {
+    /// The names of the row inputs and the column inputs, as `AB \ CD`.
+    pub corner: String,
+    pub row_labels: Vec<String>,
+    pub column_labels: Vec<String>,
+    /// The output for each row and column, or `None` where the row was left
+    /// out by assumptions.
+    pub values: Vec<Vec<Option<bool>>>,
+}
+
+/// The map's cells, with the first half of the inputs along the rows and the
+/// rest along the columns, or `None` if there are more than `MAX_INPUTS`
+/// inputs.
+pub fn cells(table: &TruthTable) -> Option<Cells> {
+    let inputs = table.input_names.len();
+    if inputs > MAX_INPUTS {
+        return None;
+    }
+    let (row_bits, column_bits) = (inputs / 2, inputs - inputs / 2);
+    let mut outputs = vec![None; 1 << inputs];
+    for (row, output) in table.inputs.iter().zip(&table.outputs) {
+        outputs[assignments::to_row(row)] = Some(*output);
+    }
+
+    // Single letter names read fine run together, longer ones don't
+    let names = |names: &[Symbol]| {
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        if names.iter().all(|n| n.chars().count() == 1) {
+            names.concat()
+        } else {
+            names.join(",")
+        }
+    };
+
+    Some(Cells {
+        corner: format!(
+            "{} \\ {}",
+            names(&table.input_names[..row_bits]),
+            names(&table.input_names[row_bits..])
+        ),
+        row_labels: (0..1 << row_bits)
+            .map(|row| label(gray(row), row_bits))
+            .collect(),
+        column_labels: (0..1 << column_bits)
+            .map(|column| label(gray(column), column_bits))
+            .collect(),
+        values: (0..1 << row_bits)
+            .map(|row| {
+                (0..1 << column_bits)
+                    .map(|column| outputs[gray(row) << column_bits | gray(column)])
+                    .collect()
+            })
+            .collect(),
+    })
+}
+
+/// The map as a markdown table, or `None` if there are more than
+/// `MAX_INPUTS` inputs. Cells for rows left out by assumptions show `-`.
+pub fn kmap(table: &TruthTable, style: &TableStyle) -> Option<String> {
+    let cells = cells(table)?;
+    let symbol = |value| match value {
+        Some(true) => style.true_symbol.as_str(),
+        Some(false) => style.false_symbol.as_str(),
+        None => "-",
+    };
+
+    let mut out = format!("| {} |", cells.corner);
+    for label in &cells.column_labels {
+        write!(out, " {} |", label).unwrap();
+    }
+    out.push_str("\n|---|");
+    out.push_str(&"---|".repeat(cells.column_labels.len()));
+    out.push('\n');
+
+    for (label, values) in cells.row_labels.iter().zip(&cells.values) {
+        write!(out, "| **{}** |", label).unwrap();
+        for value in values {
+            write!(out, " {} |", symbol(*value)).unwrap();
+        }
+        out.push('\n');
+    }
+
+    Some(out)
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let equation = Parser::new("A XOR (B AND C) = Q").parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    assert_eq!(
+        kmap(&table, &TableStyle::default()).unwrap(),
+        "| A \\ BC | 00 | 01 | 11 | 10 |\n|---|---|---|---|---|\n\
+         | **0** | 0 | 0 | 1 | 0 |\n| **1** | 1 | 1 | 0 | 1 |\n"
+    );
+}
@@ -1,10 +1,8 @@
-use std::{
-    ops::{Index, Range},
-    usize,
-};
+use core::ops::{Index, Range};
 
 use derive_more::Display;
 use logos::{Logos, SpannedIter};
+use serde::Serialize;
 
 #[derive(Debug, Display, Logos, PartialEq, Clone, Copy)]
 pub enum TK {
@@ -33,6 +31,20 @@ pub enum TK {
     #[display(fmt = "XOR")]
     Xor,
 
+    #[token("exists")]
+    #[token("∃")]
+    #[display(fmt = "exists")]
+    Exists,
+
+    #[token("forall")]
+    #[token("∀")]
+    #[display(fmt = "forall")]
+    Forall,
+
+    #[token("assume")]
+    #[display(fmt = "assume")]
+    Assume,
+
     #[token("true")]
     #[token("1")]
     #[display(fmt = "True")]
@@ -47,6 +59,12 @@ pub enum TK {
     #[display(fmt = "Variable")]
     Var,
 
+    /// `$n`, the output of the REPL's `n`th equation, which the session
+    /// substitutes before parsing
+    #[regex(r"\$\d*")]
+    #[display(fmt = "$n")]
+    Ref,
+
     #[token("(")]
     #[display(fmt = "(")]
     LParen,
@@ -57,8 +75,25 @@ pub enum TK {
 
     #[token("=")]
     #[token("->")]
+    #[display(fmt = "=")]
     Equals,
 
+    #[token("==")]
+    #[display(fmt = "==")]
+    EqualsEquals,
+
+    #[token("!=")]
+    #[display(fmt = "!=")]
+    NotEquals,
+
+    #[token(";")]
+    #[display(fmt = ";")]
+    Semicolon,
+
+    #[token(",")]
+    #[display(fmt = ",")]
+    Comma,
+
     #[regex(r"[ \t\r\n\f]+", logos::skip)]
     #[error]
     Error,
@@ -78,7 +113,7 @@ impl Token {
     }
 }
 
-#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Serialize)]
 #[display(fmt = "{}..{}", start, end)]
 pub struct Span {
     pub start: usize,
@@ -124,7 +159,7 @@ impl<'input> Lexer<'input> {
     }
 }
 
-impl<'input> Iterator for Lexer<'_> {
+impl Iterator for Lexer<'_> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -144,3 +179,47 @@ impl<'input> Iterator for Lexer<'_> {
         }
     }
 }
+
+/// Whether `input` is unfinished, with a parenthesis left open or ending in
+/// an operator, so that the REPL should read another line onto it.
+pub fn incomplete(input: &str) -> bool {
+    let mut depth = 0isize;
+    let mut last = TK::Eof;
+    for token in Lexer::new(input) {
+        match token.kind {
+            TK::LParen => depth += 1,
+            TK::RParen => depth -= 1,
+            TK::Eof => break,
+            _ => {}
+        }
+        last = token.kind;
+    }
+
+    depth > 0
+        || matches!(
+            last,
+            TK::Not
+                | TK::And
+                | TK::Or
+                | TK::Xor
+                | TK::Exists
+                | TK::Forall
+                | TK::Assume
+                | TK::Equals
+                | TK::EqualsEquals
+                | TK::NotEquals
+                | TK::Semicolon
+                | TK::Comma
+        )
+}
+
+#[test]
+fn test() {
+    assert!(incomplete("(A AND B"));
+    assert!(incomplete("A AND (B OR\n  C"));
+    assert!(incomplete("A +"));
+    assert!(incomplete("assume A != B;"));
+    assert!(!incomplete("(A AND B) OR C = Q"));
+    assert!(!incomplete("A AND B)"));
+    assert!(!incomplete(""));
+}
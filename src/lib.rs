@@ -1,4 +1,78 @@
+//! Truth tables for boolean equations.
+//!
+//! Without the default `std` feature the crate is `no_std`, needing only
+//! `alloc`, and provides everything but the modules that need hashing, I/O
+//! or a terminal.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod aig;
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod api;
+pub mod assignments;
+pub mod bdd;
+pub mod bytecode;
+pub mod canon;
+pub mod classes;
+pub mod codegen;
+pub mod column;
 pub mod compiler;
+#[cfg(feature = "std")]
+pub mod cover;
+pub mod cube;
+pub mod decision;
+pub mod equiv;
+pub mod explain;
+pub mod export;
+#[cfg(feature = "std")]
+pub mod factor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fingerprint;
+#[cfg(feature = "wgpu")]
+pub mod gpu;
+pub mod gray;
+#[cfg(feature = "std")]
+pub mod highlight;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod intern;
+pub mod interp;
+pub mod kmap;
 pub mod lexer;
+#[cfg(feature = "std")]
+pub mod lut;
+#[cfg(feature = "std")]
+pub mod minimize;
+pub mod multi;
 pub mod parser;
+pub mod peephole;
+pub mod prove;
+pub mod register;
+mod rng;
+pub mod rom;
+#[cfg(feature = "std")]
+pub mod session;
+pub mod sexpr;
+pub mod simplify;
+pub mod smt;
+pub mod stdlib;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod tablefile;
+pub mod ternary;
+#[cfg(feature = "testutil")]
+pub mod testutil;
+pub mod transform;
+pub mod verify;
 pub mod vm;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+pub mod wgsl;
+#[cfg(feature = "std")]
+pub mod zdd;
@@ -0,0 +1,96 @@
+//! Equations saved by name with the REPL's `:save`, kept as `name =
+//! "equation"` pairs in `library.toml` beside the config file.
+
+use std::{fs, io, path::PathBuf};
+
+use crate::config::{self, Config, Value};
+
+pub struct Library {
+    path: Option<PathBuf>,
+    equations: Vec<(String, String)>,
+}
+
+impl Library {
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.with_file_name("library.toml"))
+    }
+
+    /// Read the library at `path`, which is empty if there's no file there
+    /// yet. Without a path, nothing can be saved.
+    pub fn open(path: Option<PathBuf>) -> Result<Self, String> {
+        let source = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(source) => source,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(err) => return Err(format!("{}: {}", path.display(), err)),
+            },
+            None => String::new(),
+        };
+        let at = |err: String| match &path {
+            Some(path) => format!("{}: {}", path.display(), err),
+            None => err,
+        };
+
+        let mut equations = vec![];
+        for entry in config::parse_toml(&source).map_err(at)? {
+            match entry.value {
+                Value::String(equation) => equations.push((entry.key, equation)),
+                _ => {
+                    return Err(at(format!(
+                        "line {}: '{}' should be an equation in a string",
+                        entry.line, entry.key
+                    )))
+                }
+            }
+        }
+
+        Ok(Self { path, equations })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.equations
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, equation)| equation.as_str())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.equations.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Save `equation` as `name`, replacing any equation already saved
+    /// under it, and write the library out.
+    pub fn save(&mut self, name: &str, equation: &str) -> Result<(), String> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "'{}' isn't a valid name, use letters, digits, '_' and '-'",
+                name
+            ));
+        }
+        let Some(path) = &self.path else {
+            return Err("there is no config directory to save equations in".to_string());
+        };
+
+        match self.equations.iter_mut().find(|(n, _)| n == name) {
+            Some((_, saved)) => *saved = equation.to_string(),
+            None => self
+                .equations
+                .push((name.to_string(), equation.to_string())),
+        }
+
+        let mut source = String::new();
+        for (name, equation) in &self.equations {
+            source.push_str(&format!("{} = {}\n", name, config::quote(equation)));
+        }
+        let written = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(path, source)),
+            None => fs::write(path, source),
+        };
+
+        written.map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,142 @@
+//! Mapping an equation onto the `k`-input lookup tables of an FPGA, as a
+//! quick estimate of what it would take. The equation's and-inverter graph
+//! is covered with cuts, sets of at most `k` nodes that every path from an
+//! input to a node passes through, each becoming a LUT computing the node
+//! from them. Each node keeps a few of its cuts, merged from its operands',
+//! and the one giving the fewest levels of LUTs is used, so the mapping
+//! aims for depth first and then for fewer inputs per LUT.
+
+use crate::aig::{Aig, Literal};
+
+/// The size of the LUTs in most current FPGAs.
+pub const DEFAULT_K: usize = 6;
+
+/// At most this many cuts are kept for each node.
+const MAX_CUTS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    pub k: usize,
+    /// The LUTs used, each as the variable it computes and the variables of
+    /// its inputs, the outputs of other LUTs or inputs of the equation.
+    pub luts: Vec<(Literal, Vec<Literal>)>,
+    /// The most LUTs on any path from an input to the output.
+    pub depth: usize,
+}
+
+#[derive(Clone)]
+struct Cut {
+    leaves: Vec<Literal>,
+    depth: usize,
+}
+
+/// Map the graph onto LUTs of `k` inputs, which must be at least 2.
+pub fn map(aig: &Aig, k: usize) -> Mapping {
+    let num_inputs = aig.inputs.len() as Literal;
+    // Variable 0 is the constant and never a leaf, since constants fold away
+    let mut cuts: Vec<Vec<Cut>> = vec![vec![]];
+    let mut best: Vec<Option<Cut>> = vec![None];
+    let mut depth = vec![0];
+    for v in 1..=num_inputs {
+        cuts.push(vec![Cut {
+            leaves: vec![v],
+            depth: 0,
+        }]);
+        best.push(None);
+        depth.push(0);
+    }
+
+    for (i, &(a, b)) in aig.ands.iter().enumerate() {
+        let v = num_inputs + 1 + i as Literal;
+        let (a, b) = ((a >> 1) as usize, (b >> 1) as usize);
+        let mut merged: Vec<Cut> = vec![];
+        for left in &cuts[a] {
+            for right in &cuts[b] {
+                let mut leaves = left.leaves.clone();
+                for leaf in &right.leaves {
+                    if !leaves.contains(leaf) {
+                        leaves.push(*leaf);
+                    }
+                }
+                if leaves.len() > k {
+                    continue;
+                }
+                leaves.sort_unstable();
+                if merged.iter().any(|cut| cut.leaves == leaves) {
+                    continue;
+                }
+                let cut_depth = 1 + leaves.iter().map(|l| depth[*l as usize]).max().unwrap_or(0);
+                merged.push(Cut {
+                    leaves,
+                    depth: cut_depth,
+                });
+            }
+        }
+        merged.sort_by_key(|cut| (cut.depth, cut.leaves.len()));
+        merged.truncate(MAX_CUTS);
+
+        // The node's own variable is a cut too, for its parents to use
+        let chosen = merged[0].clone();
+        depth.push(chosen.depth);
+        best.push(Some(chosen));
+        merged.push(Cut {
+            leaves: vec![v],
+            depth: 0,
+        });
+        cuts.push(merged);
+    }
+
+    // Cover the graph from the output, mapping the nodes the chosen cuts
+    // need
+    let mut luts = vec![];
+    let mut mapped = vec![false; best.len()];
+    let mut pending = vec![aig.output >> 1];
+    while let Some(v) = pending.pop() {
+        let Some(cut) = &best[v as usize] else {
+            continue;
+        };
+        if mapped[v as usize] {
+            continue;
+        }
+        mapped[v as usize] = true;
+        luts.push((v, cut.leaves.clone()));
+        pending.extend(&cut.leaves);
+    }
+
+    Mapping {
+        k,
+        luts,
+        depth: depth[(aig.output >> 1) as usize],
+    }
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let mapping = |source: &str, k| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        map(&Aig::new(&equation), k)
+    };
+
+    // A full adder's carry needs one LUT of three inputs
+    let carry = mapping("(A AND B) OR (C AND (A XOR B)) = Q", 6);
+    assert_eq!(carry.luts.len(), 1);
+    assert_eq!(carry.luts[0].1, [1, 2, 3]);
+    assert_eq!(carry.depth, 1);
+    // With two inputs each, it's a gate per operator, the XOR taking one
+    let carry = mapping("(A AND B) OR (C AND (A XOR B)) = Q", 2);
+    assert_eq!((carry.luts.len(), carry.depth), (4, 3));
+
+    // Twelve inputs need two levels of six-input LUTs when balanced, and a
+    // third when chained, each level taking only five more inputs
+    let names = ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L"];
+    let (left, right) = (names[..6].join(" XOR "), names[6..].join(" XOR "));
+    let wide = mapping(&format!("({}) XOR ({}) = Q", left, right), 6);
+    assert_eq!(wide.depth, 2);
+    assert_eq!(mapping(&format!("{} = Q", names.join(" XOR ")), 6).depth, 3);
+    assert!(wide.luts.iter().all(|(_, leaves)| leaves.len() <= 6));
+
+    assert!(mapping("A = Q", 6).luts.is_empty());
+    assert_eq!(mapping("A AND NOT A = Q", 6).depth, 0);
+}
@@ -1,23 +1,1707 @@
-use std::io::{self, Write};
+use std::{
+    env, fs, hint,
+    io::{self, IsTerminal, Write},
+    ops::Range,
+    path::Path,
+    process,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use booltable::{compiler::Compiler, parser::Parser, vm::VM};
+use ariadne::{Label, Report, ReportKind, Source};
+use booltable::{
+    aig::Aig,
+    analysis, assignments,
+    bdd::{self, Bdd},
+    classes, codegen,
+    compiler::{self, Compiler},
+    decision, equiv, explain,
+    export::{self, Format, RowSort},
+    factor,
+    gray::Incremental,
+    highlight::highlight,
+    lexer::{self, Lexer, Span},
+    lut,
+    minimize::{self, Redundancy},
+    multi::MultiEquation,
+    parser::{self, BinOp, Dialect, Parser, SyntaxError},
+    prove,
+    register::Program,
+    session::{Column, Session},
+    simplify, smt, stdlib, stream,
+    tablefile::{self, TableFile},
+    ternary::{self, TernaryTable},
+    transform, verify,
+    vm::{TruthTable, VM},
+    wgsl,
+};
+use cli::{Args, Command, Dump, Property};
+use config::Config;
+use library::Library;
+use yansi::Paint;
+
+mod cli;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod completions;
+mod config;
+mod document;
+mod library;
+mod quiz;
+mod serve;
+#[cfg(unix)]
+mod tui;
 
 fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => fail(&format!("{}\n\n{}", err, cli::help()), 2),
+    };
+
+    let mut config = Config::load(args.config.as_deref()).unwrap_or_else(|err| fail(&err, 2));
+    if let Some(colour) = args.colour {
+        config.colour = colour;
+    }
+    if let Some(sort) = args.sort {
+        config.style.sort = sort;
+    }
+    if args.summary {
+        config.summary = true;
+    }
+    if let Some((true_symbol, false_symbol)) = &args.symbols {
+        config.style.true_symbol = true_symbol.clone();
+        config.style.false_symbol = false_symbol.clone();
+    }
+    if args.plain {
+        config.plain = true;
+    }
+    if config.plain {
+        config.colour = config::Colour::Never;
+        config.style.ascii = true;
+        if config.dialect == Dialect::Unicode {
+            config.dialect = Dialect::Words;
+        }
+    }
+    if !config.colour.enabled() {
+        Paint::disable();
+    }
+
+    match &args.command {
+        Command::Repl if args.output.is_some() => fail("'--output' requires an equation", 2),
+        Command::Repl => repl(&args, &config),
+        Command::Table(input) => table(&args, &config, input),
+        Command::Tui(input) => explore(&config, input),
+        Command::Eval(input) => eval(&args, &config, input),
+        Command::Check(input) => check(&args, &config, input),
+        Command::Verify(input) => verify(&args, &config, input),
+        Command::Bench(input) => bench(&args, &config, input),
+        Command::Smt(input) => smt_script(&args, &config, input),
+        Command::Aiger(input) => aiger(&args, &config, input),
+        Command::Wgsl(input) => wgsl_shader(&args, &config, input),
+        Command::Gpu(input) => gpu_table(&args, &config, input),
+        Command::Tree(input) => decision_tree(&args, &config, input),
+        Command::Codegen(input) => codegen(&args, &config, input),
+        Command::Prove(left, right) => prove_equal(&config, left, right),
+        Command::Compare(left, right) => compare(&config, left, right),
+        Command::Compatible(spec, input) => compatible(&config, spec, input),
+        Command::Complete(inputs) => complete(&config, inputs),
+        Command::Random => random(&args, &config),
+        Command::Quiz => quiz::run(&args, &config, random_seed(&args)),
+        Command::Stdlib(name, size) => match stdlib::generate(name, *size) {
+            Ok(equations) => equations
+                .iter()
+                .for_each(|equation| println!("{}", equation)),
+            Err(err) => fail(&err, 2),
+        },
+        Command::Report(path) => write_report(&args, &config, path),
+        Command::Open(path) => open_table(&args, &config, path),
+        Command::Diff(left, right) => diff_tables(left, right),
+        Command::Serve => {
+            if let Err(err) = serve::run(args.port) {
+                fail(&err.to_string(), 1);
+            }
+        }
+        Command::Completions(shell) => print!("{}", completions::generate(*shell)),
+        Command::Help => print!("{}", cli::help()),
+    }
+}
+
+/// How many expressions `prove` explores from each side before giving up.
+const PROOF_LIMIT: usize = 5000;
+
+fn fail(message: &str, code: i32) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(code);
+}
+
+/// Parse a single equation given on the command line, exiting with a
+/// diagnostic if it is invalid.
+fn parse(config: &Config, input: &str) -> parser::Equation {
+    let mut parser = Parser::new(input);
+    let equation = parser.parse_equation().unwrap_or_else(|err| {
+        report(config, input, &err);
+        process::exit(2);
+    });
+    if let Err(err) = check_size(config, &equation) {
+        fail(&err, 2);
+    }
+
+    equation
+}
+
+fn check_size(config: &Config, equation: &parser::Equation) -> Result<(), String> {
+    if equation.inputs.len() > config.max_inputs {
+        Err(format!(
+            "the equation has {} inputs, more than the limit of {} set by 'max_inputs'",
+            equation.inputs.len(),
+            config.max_inputs
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn repl(args: &Args, config: &Config) {
+    let format = args.format.unwrap_or(config.format);
+    let mut session = Session::new();
+    let mut last: Option<Last> = None;
+    let mut library = Library::open(Library::default_path()).unwrap_or_else(|err| fail(&err, 2));
+
     loop {
         print!("> ");
         io::stdout().flush().unwrap();
         let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+        if io::stdin().read_line(&mut input).unwrap() == 0 {
+            break;
+        }
+        // Keep reading while brackets are open or the line ends in an
+        // operator, so long equations can be split across lines
+        let command = input.trim() == "!!" || input.trim_start().starts_with(':');
+        while !command && lexer::incomplete(&input) {
+            print!("... ");
+            io::stdout().flush().unwrap();
+            if io::stdin().read_line(&mut input).unwrap() == 0 {
+                break;
+            }
+        }
+        // Run an earlier or saved equation as if it had been entered
+        let recalled = match input.trim() {
+            "!!" | ":again" => Some(
+                last.as_ref()
+                    .map(|last| last.entered.clone())
+                    .ok_or_else(|| "no equation has been entered yet".to_string()),
+            ),
+            line => line.strip_prefix(":load ").map(|name| {
+                library
+                    .get(name.trim())
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("there is no saved equation '{}'", name.trim()))
+            }),
+        };
+        match recalled {
+            Some(Ok(equation)) => {
+                println!("{}", equation.trim_end());
+                input = equation;
+            }
+            Some(Err(err)) => {
+                eprintln!("Error: {}", err);
+                continue;
+            }
+            None => {
+                if let Some(command) = input.trim().strip_prefix(':') {
+                    repl_command(config, format, command, last.as_ref(), &mut library);
+                    continue;
+                }
+            }
+        }
+        let entered = input.clone();
+        let input = match session.substitute(&input) {
+            Ok(input) => input,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                continue;
+            }
+        };
 
+        let start = Instant::now();
         let mut parser = Parser::new(&input);
-        let parsed_equation = parser.parse_equation().unwrap();
+        let parsed_equation = match parser.parse_equation() {
+            Ok(equation) => equation,
+            Err(err) => {
+                report(config, &input, &err);
+                continue;
+            }
+        };
+        let parse_time = start.elapsed();
+
+        let expanded_equation = session.expand(parsed_equation.clone());
+        if let Err(err) = check_size(config, &expanded_equation) {
+            eprintln!("Error: {}", err);
+            continue;
+        }
+        println!(
+            "{}",
+            highlight(&expanded_equation.to_source(config.dialect))
+        );
+
+        if args.simplify || args.proof {
+            print_simplified(config, &expanded_equation, args.proof);
+        }
+        print_transformed(args, config, &expanded_equation);
 
-        let compiler = Compiler::new(parsed_equation);
+        let start = Instant::now();
+        warn_constants(config, &input, &parsed_equation.lhs);
+        let mut compiler = Compiler::new(expanded_equation.clone());
+        warn_dead_inputs(&mut compiler);
         let compiled_equation = compiler.compile();
+        let compile_time = start.elapsed();
+        dump(
+            config,
+            args.dump,
+            &input,
+            &expanded_equation,
+            &compiled_equation,
+        );
+        let ops = compiled_equation.lhs.len()
+            + compiled_equation
+                .assumptions
+                .iter()
+                .map(Vec::len)
+                .sum::<usize>();
+        let max_stack_depth = compiled_equation.max_stack_depth();
+        let num_inputs = compiled_equation.inputs.len();
+
+        // Earlier outputs are read from their stored tables where possible
+        let start = Instant::now();
+        let truth_table = session
+            .table(&parsed_equation, &compiled_equation.inputs)
+            .unwrap_or_else(|| generate(config, compiled_equation));
+        last = Some(Last {
+            entered,
+            expanded: expanded_equation.to_source(Dialect::Words),
+            parse: parse_time,
+            compile: compile_time,
+            generate: start.elapsed(),
+            rows: truth_table.len(),
+            num_inputs,
+            ops,
+            max_stack_depth,
+        });
+
+        println!("{}", display(args, config, format, &truth_table));
+
+        let column = Column::new(&truth_table);
+        session.define(&parsed_equation, column);
+    }
+}
+
+/// The last equation the REPL ran, and what it measured.
+struct Last {
+    /// As it was entered, for `!!`.
+    entered: String,
+    /// With earlier outputs inlined, so that it stands alone for `:copy` and
+    /// `:save`.
+    expanded: String,
+    parse: Duration,
+    compile: Duration,
+    generate: Duration,
+    rows: usize,
+    num_inputs: usize,
+    /// The number of ops, including those of assumptions.
+    ops: usize,
+    max_stack_depth: usize,
+}
+
+/// Run a REPL command, given without its leading `:`.
+fn repl_command(
+    config: &Config,
+    format: Format,
+    command: &str,
+    last: Option<&Last>,
+    library: &mut Library,
+) {
+    let (command, argument) = command.split_once(' ').unwrap_or((command, ""));
+    match (command, last) {
+        ("time", Some(stats)) => println!(
+            "parse {:.2?}, compile {:.2?}, generate {:.2?}",
+            stats.parse, stats.compile, stats.generate
+        ),
+        ("stats", Some(stats)) => println!(
+            "{} of {} rows, {} ops, stack depth {}",
+            stats.rows,
+            1usize << stats.num_inputs,
+            stats.ops,
+            stats.max_stack_depth
+        ),
+        ("copy", Some(last)) => {
+            let format = match argument.trim() {
+                "" => Ok(format),
+                format => format.parse(),
+            };
+            match format.and_then(|format| copy_table(config, format, last)) {
+                Ok(()) => println!("Copied the table"),
+                Err(err) => eprintln!("Error: {}", err),
+            }
+        }
+        ("save", Some(last)) => match library.save(argument.trim(), &last.expanded) {
+            Ok(()) => println!("Saved as {}", argument.trim()),
+            Err(err) => eprintln!("Error: {}", err),
+        },
+        ("load", _) => {
+            let names: Vec<&str> = library.names().collect();
+            if names.is_empty() {
+                println!("No equations have been saved");
+            } else {
+                println!("{}", names.join(" "));
+            }
+        }
+        ("time" | "stats" | "copy" | "save", None) => {
+            eprintln!("Error: no equation has been entered yet")
+        }
+        _ => eprintln!(
+            "Error: unknown command ':{}', expected :again, :time, :stats, :copy, :save or :load",
+            command
+        ),
+    }
+}
+
+/// Put the last equation's table on the clipboard in `format`.
+fn copy_table(config: &Config, format: Format, last: &Last) -> Result<(), String> {
+    #[cfg(feature = "clipboard")]
+    {
+        let equation = Parser::new(&last.expanded)
+            .parse_equation()
+            .map_err(|err| err.to_string())?;
+        let mut compiler = Compiler::new(equation);
+        compiler.eliminate_dead_inputs();
+        let table = VM::new(compiler.compile()).gen();
+        clipboard::copy(&export::render(&table, format, &config.style))
+            .map_err(|err| err.to_string())
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        let _ = (config, format, last);
+        Err("copying needs booltable built with the 'clipboard' feature".to_string())
+    }
+}
+
+fn table(args: &Args, config: &Config, input: &str) {
+    let lines: Vec<&str> = input.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() > 1 {
+        return multi_table(args, config, &lines);
+    }
+
+    let parsed_equation = parse(config, input);
+
+    warn_constants(config, input, &parsed_equation.lhs);
+    let mut compiler = Compiler::new(parsed_equation.clone());
+    warn_dead_inputs(&mut compiler);
+    let compiled_equation = compiler.compile();
+    analyse(
+        args,
+        args.dump,
+        config,
+        input,
+        &parsed_equation,
+        &compiled_equation,
+    );
+
+    if args.ternary {
+        if compiled_equation.inputs.len() > ternary::MAX_INPUTS {
+            fail(
+                &format!(
+                    "three-valued tables are limited to {} inputs",
+                    ternary::MAX_INPUTS
+                ),
+                2,
+            );
+        }
+        let rendered = TernaryTable::new(&compiled_equation).to_markdown(&config.style);
+        match &args.output {
+            Some(path) => {
+                if let Err(err) = write_output(path, rendered.as_bytes(), args.force) {
+                    fail(&format!("{}: {}", path.display(), err), 1);
+                }
+            }
+            None => print!("{}", rendered),
+        }
+        return;
+    }
+
+    // Table files are read in place and images are compressed already
+    if let Some(ext) = args
+        .output
+        .as_deref()
+        .filter(|path| stream::is_gzip(path))
+        .and_then(Path::file_stem)
+        .map(Path::new)
+        .and_then(Path::extension)
+        .filter(|ext| ext.eq_ignore_ascii_case("png") || *ext == tablefile::EXTENSION)
+    {
+        fail(
+            &format!("'.{}' files can't be gzipped", ext.to_string_lossy()),
+            2,
+        );
+    }
+
+    if let Some(path) = args.output.as_deref().filter(|path| {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+    }) {
+        write_png(args, config, path, compiled_equation);
+    } else if let Some(path) = &args.output {
+        let format = args
+            .format
+            .or_else(|| Format::from_path(path))
+            .unwrap_or(config.format);
+        // CSV and table files are written as they're generated, so big
+        // tables never need to fit in memory
+        let result = if path
+            .extension()
+            .is_some_and(|ext| ext == tablefile::EXTENSION)
+        {
+            create_output(path, args.force)
+                .and_then(|file| tablefile::write(&mut VM::new(compiled_equation), file).map(|_| 0))
+        } else if format == Format::Csv && config.style.sort == RowSort::Index {
+            with_output(path, args.force, |out| {
+                stream::write_csv(&mut VM::new(compiled_equation), out, &config.style)
+            })
+        } else {
+            let truth_table = generate(config, compiled_equation);
+            let rendered = export::render(&truth_table, format, &config.style);
+            write_output(path, rendered.as_bytes(), args.force).map(|_| 0)
+        };
+        if let Err(err) = result {
+            fail(&format!("{}: {}", path.display(), err), 1);
+        }
+    } else {
+        let truth_table = generate(config, compiled_equation);
+        let format = args.format.unwrap_or(config.format);
+        print!("{}", display(args, config, format, &truth_table));
+    }
+}
+
+/// Print whatever the flags ask for about the equation before its table.
+fn analyse(
+    args: &Args,
+    dump: Dump,
+    config: &Config,
+    input: &str,
+    parsed_equation: &parser::Equation,
+    compiled_equation: &compiler::Equation,
+) {
+    self::dump(config, dump, input, parsed_equation, compiled_equation);
+    if args.explain {
+        println!("{}", explain::explain(parsed_equation));
+    }
+    if args.classify {
+        print_classes(parsed_equation);
+    }
+    if args.factor {
+        print_factored(config, parsed_equation);
+    }
+    if args.forms {
+        print_forms(config, parsed_equation);
+    }
+    if args.cost {
+        print_costs(config, parsed_equation);
+    }
+    if args.bdd {
+        print_bdd_sizes(parsed_equation);
+    }
+    if args.luts {
+        print_luts(parsed_equation, args.lut_size);
+    }
+    if args.redundant {
+        print_redundant(input, parsed_equation);
+    }
+    if args.simplify || args.proof {
+        print_simplified(config, parsed_equation, args.proof);
+    }
+    print_transformed(args, config, parsed_equation);
+}
+
+/// Rasterise the table, and its K-map if it's small enough, to `path`.
+fn write_png(args: &Args, config: &Config, path: &Path, equation: compiler::Equation) {
+    #[cfg(feature = "image")]
+    {
+        let table = generate(config, equation);
+        let png = booltable::image::render(&table, &config.style, &config.theme).to_png();
+        if let Err(err) = write_output(path, &png, args.force) {
+            fail(&format!("{}: {}", path.display(), err), 1);
+        }
+    }
+    #[cfg(not(feature = "image"))]
+    {
+        let _ = (args, config, path, equation);
+        fail(
+            "writing PNGs needs booltable built with the 'image' feature",
+            2,
+        );
+    }
+}
+
+/// One table for several equations, given one per line, sharing their
+/// inputs and any terms they have in common.
+fn multi_table(args: &Args, config: &Config, lines: &[&str]) {
+    // Three-valued tables, images and table files only hold one output
+    if args.ternary {
+        fail("'--ternary' takes a single equation", 2);
+    }
+    if let Some(ext) = args.output.as_deref().and_then(Path::extension) {
+        if ext.eq_ignore_ascii_case("png") || ext == tablefile::EXTENSION {
+            fail(
+                &format!(
+                    "'.{}' files hold a single equation's table",
+                    ext.to_string_lossy()
+                ),
+                2,
+            );
+        }
+    }
+
+    let equations: Vec<_> = lines.iter().map(|line| parse(config, line)).collect();
+    let multi = MultiEquation::compile(&equations);
+    if multi.inputs.len() > config.max_inputs {
+        fail(
+            &format!(
+                "the equations have {} inputs, more than the limit of {} set by 'max_inputs'",
+                multi.inputs.len(),
+                config.max_inputs
+            ),
+            2,
+        );
+    }
+
+    // Everything but the registers is about each equation by itself, so
+    // it's printed under each one
+    let dump = Dump {
+        registers: false,
+        ..args.dump
+    };
+    for (line, equation) in lines.iter().zip(&equations) {
+        warn_constants(config, line, &equation.lhs);
+        if dump.any() || args.analyses() {
+            println!("{}:", highlight(line.trim()));
+        }
+        let compiled = Compiler::new(equation.clone()).compile();
+        analyse(args, dump, config, line, equation, &compiled);
+    }
+    if args.dump.registers {
+        println!("Registers:");
+        for line in multi.program.disassemble().lines() {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    let table = multi.gen();
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(Format::from_path))
+        .unwrap_or(config.format);
+    let mut rendered = table
+        .render(format, &config.style)
+        .unwrap_or_else(|err| fail(&err, 2));
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = write_output(path, rendered.as_bytes(), args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => {
+            rendered = fit_to_terminal(args, format, &rendered);
+            if config.summary && format == Format::Markdown {
+                rendered.push('\n');
+                for (name, summary) in table.output_names.iter().zip(table.summaries()) {
+                    rendered.push_str(&format!("{}: {}\n", name, summary));
+                }
+            }
+            print!("{}", rendered)
+        }
+    }
+}
+
+/// Generate a table, drawing a progress bar on stderr if it has at least
+/// `progress_inputs` inputs and stderr is a terminal.
+fn generate(config: &Config, equation: compiler::Equation) -> TruthTable {
+    const WIDTH: usize = 30;
+
+    let show = equation.inputs.len() >= config.progress_inputs && io::stderr().is_terminal();
+    let mut vm = VM::new(equation);
+    if !show {
+        return vm.gen();
+    }
+
+    let mut drawn = None;
+    let table = vm
+        .gen_with_progress(|done, total| {
+            let percent = done * 100 / total;
+            if drawn != Some(percent) {
+                drawn = Some(percent);
+                let filled = done * WIDTH / total;
+                eprint!(
+                    "\r[{}{}] {:>3}% {}/{} rows",
+                    "#".repeat(filled),
+                    ".".repeat(WIDTH - filled),
+                    percent,
+                    done,
+                    total
+                );
+            }
+            true
+        })
+        .unwrap();
+    // Clear the bar
+    eprint!("\r{}\r", " ".repeat(WIDTH + 40));
+
+    table
+}
+
+/// Render a table for stdout, fitted to the terminal and followed by a
+/// summary of its outputs if one was asked for.
+fn display(args: &Args, config: &Config, format: Format, table: &TruthTable) -> String {
+    let rendered = export::render(table, format, &config.style);
+    let mut out = fit_to_terminal(args, format, &rendered);
+    if config.summary && format == Format::Markdown {
+        out.push_str(&format!("\n{}\n", table.summary()));
+    }
+
+    out
+}
+
+/// Elide rows from the middle of a markdown table that is taller than the
+/// terminal, unless `--full` was given.
+fn fit_to_terminal(args: &Args, format: Format, rendered: &str) -> String {
+    if args.full || format != Format::Markdown || !io::stdout().is_terminal() {
+        return rendered.to_string();
+    }
+    let Some((height, _)) = terminal_size() else {
+        return rendered.to_string();
+    };
+
+    // Keep the names, which can take several lines, down to the rule, and
+    // leave room for the prompt and anything printed before the table
+    let header = rendered
+        .lines()
+        .position(|line| line.starts_with("|-") || line.starts_with("|:"))
+        .map_or(0, |rule| rule + 1);
+    export::elide(rendered, header, height.saturating_sub(4))
+}
+
+/// The terminal's height and width, where they can be found.
+fn terminal_size() -> Option<(usize, usize)> {
+    #[cfg(unix)]
+    {
+        tui::size()
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Leave out inputs the output can't depend on, saying so on stderr.
+fn warn_dead_inputs(compiler: &mut Compiler) {
+    for name in compiler.eliminate_dead_inputs() {
+        eprintln!(
+            "Warning: {} only appears where a constant decides the result, so it is left out of the table",
+            name
+        );
+    }
+}
+
+/// Point out the parts of `expr` that are always true or always false.
+fn warn_constants(config: &Config, input: &str, expr: &parser::SpanExpr) {
+    for constant in analysis::constant_subexpressions(expr) {
+        let message = format!(
+            "this is always {}, so it can be replaced with {}",
+            constant.value, constant.value as u8
+        );
+        if config.plain {
+            eprintln!("Warning: {} at {}", message, constant.span);
+            continue;
+        }
+        Report::<Range<usize>>::build(ReportKind::Warning, (), constant.span.start)
+            .with_message(format!("part of the equation is always {}", constant.value))
+            .with_label(Label::new(constant.span.into()).with_message(message))
+            .with_config(ariadne::Config::default().with_color(Paint::is_enabled()))
+            .finish()
+            .eprint(Source::from(input))
+            .unwrap();
+    }
+}
+
+/// Say which classes the function belongs to, with what shows it.
+fn print_classes(equation: &parser::Equation) {
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let names = &table.input_names;
+    let list = |inputs: Vec<&str>| format!("{{{}}}", inputs.join(", "));
+
+    match classes::minimal_true_points(&table) {
+        Some(points) if points.is_empty() => println!("Monotone: yes, and never true"),
+        Some(points) if points == [0] => println!("Monotone: yes, and always true"),
+        Some(points) => {
+            let points: Vec<String> = points
+                .into_iter()
+                .map(|row| {
+                    let values = assignments::to_bools(row, names.len());
+                    list(
+                        names
+                            .iter()
+                            .zip(values)
+                            .filter(|(_, v)| *v)
+                            .map(|(n, _)| n.as_str())
+                            .collect(),
+                    )
+                })
+                .collect();
+            println!(
+                "Monotone: yes, with minimal true points {}",
+                points.join(", ")
+            );
+        }
+        None => {
+            let inputs = classes::not_positive_in(&table)
+                .into_iter()
+                .map(|i| names[i].as_str());
+            println!(
+                "Monotone: no, not positive in {}",
+                inputs.collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+
+    if classes::is_self_dual(&table) {
+        println!("Self-dual: yes, negating every input negates the output");
+    } else if classes::is_self_anti_dual(&table) {
+        println!("Self-dual: no, negating every input keeps the output");
+    } else {
+        println!("Self-dual: no");
+    }
+
+    let post: Vec<&str> = classes::post_classes(&table)
+        .iter()
+        .map(|class| class.name())
+        .collect();
+    match post.is_empty() {
+        true => println!("Post's classes: none, so it's functionally complete by itself"),
+        false => println!("Post's classes: {}", post.join(", ")),
+    }
+
+    if names.len() > classes::THRESHOLD_MAX_INPUTS {
+        println!(
+            "Threshold: not checked for more than {} inputs",
+            classes::THRESHOLD_MAX_INPUTS
+        );
+    } else if let Some(threshold) = classes::threshold(&table) {
+        // A constant has no weights to speak of, only whether the empty sum
+        // reaches the threshold
+        if threshold.weights.iter().all(|weight| *weight == 0) {
+            println!(
+                "Threshold: yes, constant, threshold {}",
+                threshold.threshold
+            );
+        } else {
+            let weights: Vec<String> = names
+                .iter()
+                .zip(&threshold.weights)
+                .map(|(name, weight)| format!("{}={}", name, weight))
+                .collect();
+            println!(
+                "Threshold: yes, with weights {} and threshold {}",
+                weights.join(", "),
+                threshold.threshold
+            );
+        }
+    } else {
+        println!("Threshold: no");
+    }
+}
+
+/// Factor the equation as written if it's a sum of products, keeping its
+/// products, or its minimised sum of products otherwise.
+fn print_factored(config: &Config, equation: &parser::Equation) {
+    let num_inputs = equation.inputs.len();
+    let factored = match minimize::products(&equation.lhs, num_inputs) {
+        Some(products) => factor::factor_products(&products, num_inputs),
+        None => {
+            check_minimisable(num_inputs);
+            factor::factor(&VM::new(Compiler::new(equation.clone()).compile()).gen())
+        }
+    };
+    let factored = parser::Equation {
+        lhs: factored,
+        ..equation.clone()
+    };
+    println!("{}", highlight(&factored.to_source(config.dialect)));
+}
+
+/// Exit if a table of `num_inputs` is too large to minimise.
+fn check_minimisable(num_inputs: usize) {
+    if let Err(err) = minimize::check_size(num_inputs) {
+        fail(&err, 2);
+    }
+}
+
+/// Print the two-level forms of the equation, with the size of the
+/// minimised ones for comparison.
+fn print_forms(config: &Config, equation: &parser::Equation) {
+    check_minimisable(equation.inputs.len());
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let forms = [
+        ("Canonical SOP", minimize::canonical_sop(&table), BinOp::Or),
+        ("Canonical POS", minimize::canonical_pos(&table), BinOp::And),
+        ("Minimised SOP", minimize::minimize(&table), BinOp::Or),
+        ("Minimised POS", minimize::minimize_pos(&table), BinOp::And),
+        ("SOP with XOR", minimize::minimize_xor(&table), BinOp::Or),
+    ];
+    let count = |n: usize, noun: &str| match n {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    };
+    for (name, expr, op) in forms {
+        let terms = minimize::flatten(op, expr.expr()).len();
+        let literals = analysis::cost(expr.expr()).literals;
+        println!(
+            "{}: {} = {} ({}, {})",
+            name,
+            expr.display_in(&table.input_names, config.dialect),
+            table.output_name,
+            count(terms, "term"),
+            count(literals, "literal")
+        );
+    }
+}
+
+/// Print the size of the equation as written and in other forms, one-level
+/// and multi-level.
+fn print_costs(config: &Config, equation: &parser::Equation) {
+    check_minimisable(equation.inputs.len());
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let forms = [
+        ("Original", equation.lhs.clone()),
+        ("Minimised SOP", minimize::minimize(&table)),
+        ("Minimised POS", minimize::minimize_pos(&table)),
+        ("SOP with XOR", minimize::minimize_xor(&table)),
+        ("Factored", factor::factor(&table)),
+    ];
+
+    println!("Form           Literals  Gates  Inverters  Expression");
+    for (name, expr) in forms {
+        // The original keeps its own numbering of the inputs
+        let names = match name {
+            "Original" => &equation.inputs,
+            _ => &table.input_names,
+        };
+        let cost = analysis::cost(expr.expr());
+        println!(
+            "{:<15}{:>8}{:>7}{:>11}  {}",
+            name,
+            cost.literals,
+            cost.gates,
+            cost.inverters,
+            expr.display_in(names, config.dialect)
+        );
+    }
+}
+
+/// Print the number of nodes in the equation's decision diagram with the
+/// inputs in the order they appear, in the order FORCE gives, and sifted
+/// from the better of those.
+fn print_bdd_sizes(equation: &parser::Equation) {
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let appearance = bdd::appearance_order(equation);
+    let force = bdd::force_order(equation);
+    let size = |order: &[usize]| Bdd::new(&table, order).size();
+    let start = match size(&force) < size(&appearance) {
+        true => &force,
+        false => &appearance,
+    };
+    let sifted = bdd::sift(&table, start);
+
+    let names = |order: &[usize]| {
+        let names: Vec<&str> = order
+            .iter()
+            .map(|i| table.input_names[*i].as_str())
+            .collect();
+        names.join(", ")
+    };
+    println!("Order           Nodes  Inputs");
+    for (name, order) in [
+        ("Appearance", &appearance),
+        ("Force-directed", &force),
+        ("Sifted", &sifted),
+    ] {
+        println!("{:<16}{:>5}  {}", name, size(order), names(order));
+    }
+}
+
+/// Print how many `k`-input LUTs the equation maps onto, and the most on
+/// any path.
+fn print_luts(equation: &parser::Equation, k: usize) {
+    let mapping = lut::map(&Aig::new(equation), k);
+    println!(
+        "{}-input LUTs: {}, depth {}",
+        k,
+        mapping.luts.len(),
+        mapping.depth
+    );
+}
+
+fn print_redundant(input: &str, equation: &parser::Equation) {
+    let Some(redundant) = minimize::redundant_terms(&equation.lhs, equation.inputs.len()) else {
+        eprintln!("Warning: '--redundant' only looks at sums of products, like (A AND B) OR C");
+        return;
+    };
+    if redundant.is_empty() {
+        println!("No terms are redundant");
+    }
+
+    let source = |span: Span| &input[span.start..span.end];
+    for (span, reason) in redundant {
+        let reason = match reason {
+            Redundancy::Empty => "it's never true".to_string(),
+            Redundancy::Absorbed(by) => format!("it's absorbed by {}", source(by)),
+            Redundancy::Consensus(p, q) => {
+                format!("it's the consensus of {} and {}", source(p), source(q))
+            }
+            Redundancy::Covered => "the other terms cover it".to_string(),
+        };
+        println!("{} can be dropped: {}", source(span), reason);
+    }
+}
+
+fn print_simplified(config: &Config, equation: &parser::Equation, proof: bool) {
+    let steps = simplify::proof(&equation.lhs);
+    if proof {
+        let rendered =
+            simplify::render_proof(&equation.lhs, &steps, &equation.inputs, config.dialect);
+        println!("{}", ascii(config, rendered));
+    }
 
-        let mut vm = VM::new(compiled_equation);
-        let truth_table = vm.gen();
+    let simplified = parser::Equation {
+        lhs: steps.last().map_or(&equation.lhs, |s| &s.result).clone(),
+        ..equation.clone()
+    };
+    println!("{}", highlight(&simplified.to_source(config.dialect)));
+}
+
+/// Print the dual and complement of the equation if they were asked for.
+fn print_transformed(args: &Args, config: &Config, equation: &parser::Equation) {
+    let print = |lhs| {
+        let transformed = parser::Equation {
+            lhs,
+            ..equation.clone()
+        };
+        println!("{}", highlight(&transformed.to_source(config.dialect)));
+    };
+    if args.dual {
+        print(transform::dual(&equation.lhs));
+    }
+    if args.complement {
+        print(transform::complement(&equation.lhs));
+    }
+}
+
+/// Spell the arrows of proofs and the branches of syntax trees in ASCII
+/// under `--plain`.
+fn ascii(config: &Config, text: String) -> String {
+    if !config.plain {
+        return text;
+    }
+
+    text.replace('→', "->")
+        .replace("└── ", "`-- ")
+        .replace("├── ", "|-- ")
+        .replace("│   ", "|   ")
+}
+
+fn dump(
+    config: &Config,
+    dump: Dump,
+    input: &str,
+    parsed_equation: &parser::Equation,
+    compiled_equation: &compiler::Equation,
+) {
+    if dump.tokens {
+        println!("Tokens:");
+        for token in Lexer::new(input) {
+            println!(
+                "  {:<8} {:<10} {:?}",
+                token.span.to_string(),
+                token.kind.to_string(),
+                token.text(input)
+            );
+        }
+        println!();
+    }
+
+    if dump.ast {
+        println!("AST:");
+        let tree = ascii(config, parsed_equation.lhs.tree(&parsed_equation.inputs));
+        for line in tree.lines() {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    if dump.sexpr {
+        println!("S-expression:");
+        println!(
+            "  {}\n",
+            parsed_equation.lhs.expr().to_sexpr(&parsed_equation.inputs)
+        );
+    }
+
+    if dump.bytecode {
+        println!("Bytecode:");
+        for line in compiled_equation.disassemble().lines() {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    if dump.registers {
+        println!("Registers:");
+        for line in Program::from_equation(compiled_equation)
+            .disassemble()
+            .lines()
+        {
+            println!("  {}", line);
+        }
+        println!();
+    }
+}
+
+fn write_output(path: &Path, contents: &[u8], force: bool) -> io::Result<()> {
+    with_output(path, force, |out| out.write_all(contents))
+}
+
+/// Create the file at `path` as `create_output` does, and call `f` to write
+/// to it, compressing with gzip if the path ends in `.gz`.
+fn with_output<T>(
+    path: &Path,
+    force: bool,
+    f: impl FnOnce(&mut dyn Write) -> io::Result<T>,
+) -> io::Result<T> {
+    if !stream::is_gzip(path) {
+        return f(&mut create_output(path, force)?);
+    }
+
+    #[cfg(feature = "gzip")]
+    {
+        stream::gzip(create_output(path, force)?, |out| f(out))
+    }
+    #[cfg(not(feature = "gzip"))]
+    {
+        Err(io::Error::other(
+            "writing .gz files needs booltable built with the 'gzip' feature",
+        ))
+    }
+}
+
+/// Create the file at `path` and any missing parent directories, failing if
+/// it exists unless `force` is set.
+fn create_output(path: &Path, force: bool) -> io::Result<fs::File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .create_new(!force)
+        .truncate(true)
+        .open(path)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::AlreadyExists => io::Error::new(
+                err.kind(),
+                "file already exists, use '--force' to overwrite it",
+            ),
+            _ => err,
+        })
+}
+
+fn eval(args: &Args, config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+
+    let compiled_equation = Compiler::new(parsed_equation).compile();
+    let assignment = cli::parse_assignment(
+        args.set.as_deref().unwrap_or_default(),
+        &compiled_equation.inputs,
+    )
+    .unwrap_or_else(|err| fail(&err, 2));
+
+    let mut vm = VM::new(compiled_equation);
+    if args.trace {
+        let (output, steps) = vm.trace(&assignment);
+        for (op, stack) in steps {
+            let stack: Vec<u8> = stack.into_iter().map(u8::from).collect();
+            println!("{:<10} {:?}", op.to_string(), stack);
+        }
+        println!("{}", output as u8);
+    } else {
+        println!("{}", vm.eval(&assignment) as u8);
+    }
+}
+
+fn check(args: &Args, config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+    let mut vm = VM::new(Compiler::new(parsed_equation).compile());
+    let truth_table = vm.gen();
+
+    let holds = match args.property {
+        Property::Satisfiable => truth_table.is_satisfiable(),
+        Property::Tautology => truth_table.is_tautology(),
+        Property::Contradiction => truth_table.is_contradiction(),
+    };
+
+    if !args.quiet {
+        print!(
+            "{}",
+            export::render(
+                &truth_table,
+                args.format.unwrap_or(config.format),
+                &config.style
+            )
+        );
+        let verdict = match (args.property, holds) {
+            (Property::Satisfiable, true) => "is satisfiable",
+            (Property::Satisfiable, false) => "is not satisfiable",
+            (Property::Tautology, true) => "is a tautology",
+            (Property::Tautology, false) => "is not a tautology",
+            (Property::Contradiction, true) => "is a contradiction",
+            (Property::Contradiction, false) => "is not a contradiction",
+        };
+        println!(
+            "\n{} {}, true for {} of {} assignments",
+            truth_table.output_name,
+            verdict,
+            truth_table.count(),
+            truth_table.len()
+        );
+    }
+
+    process::exit(if holds { 0 } else { 1 });
+}
+
+fn verify(args: &Args, config: &Config, input: &str) {
+    let expect = args
+        .expect
+        .as_deref()
+        .unwrap_or_else(|| fail("'verify' expects a truth vector with --expect", 2));
+    let parsed_equation = parse(config, input);
+    let mut vm = VM::new(Compiler::new(parsed_equation).compile());
+    let truth_table = vm.gen();
+    let expected = verify::parse(expect, 1 << truth_table.input_names.len())
+        .unwrap_or_else(|err| fail(&err.to_string(), 2));
+
+    let mismatches = verify::mismatches(&truth_table, &expected);
+    if !args.quiet {
+        if mismatches.is_empty() {
+            println!("{} matches {}", truth_table.output_name, expect);
+        }
+        for minterm in &mismatches {
+            let assignment: Vec<String> = truth_table
+                .input_names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let shift = truth_table.input_names.len() - 1 - i;
+                    format!("{}={}", name, minterm >> shift & 1)
+                })
+                .collect();
+            let expected = expected.get(*minterm).unwrap();
+            println!(
+                "minterm {} ({}): expected {}, got {}",
+                minterm,
+                assignment.join(","),
+                expected as u8,
+                !expected as u8
+            );
+        }
+    }
+
+    process::exit(if mismatches.is_empty() { 0 } else { 1 });
+}
+
+fn smt_script(args: &Args, config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+
+    // Asserting the expression gives a script that is sat when the equation is
+    // satisfiable and unsat when it is a contradiction. A tautology is checked
+    // by asserting the negation, which is unsat when it holds.
+    let negate = args.property == Property::Tautology;
+    let script = smt::script(&parsed_equation, negate);
+
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = write_output(path, script.as_bytes(), args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => print!("{}", script),
+    }
+}
+
+fn wgsl_shader(args: &Args, config: &Config, input: &str) {
+    let compiled_equation = Compiler::new(parse(config, input)).compile();
+    if compiled_equation.inputs.len() > wgsl::MAX_INPUTS {
+        fail(
+            &format!("shaders are limited to {} inputs", wgsl::MAX_INPUTS),
+            2,
+        );
+    }
+
+    let shader = wgsl::shader(&compiled_equation);
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = write_output(path, shader.as_bytes(), args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => print!("{}", shader),
+    }
+}
+
+/// Generate the table on the GPU, printing how many rows are true and, with
+/// `--output`, writing it as a table file as it comes back.
+fn gpu_table(args: &Args, config: &Config, input: &str) {
+    let compiled_equation = Compiler::new(parse(config, input)).compile();
+    #[cfg(feature = "wgpu")]
+    {
+        let path = args.output.as_deref();
+        if path.is_some_and(|path| {
+            path.extension()
+                .is_none_or(|ext| ext != tablefile::EXTENSION)
+        }) {
+            fail(
+                &format!(
+                    "'gpu' writes table files, so --output must end in .{}",
+                    tablefile::EXTENSION
+                ),
+                2,
+            );
+        }
+
+        let gpu = booltable::gpu::Gpu::new().unwrap_or_else(|err| fail(&err.to_string(), 1));
+        let count = match path {
+            Some(path) => create_output(path, args.force)
+                .map_err(booltable::gpu::GpuError::Io)
+                .and_then(|file| gpu.write_table(&compiled_equation, file))
+                .unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err), 1)),
+            None => gpu
+                .count(&compiled_equation)
+                .unwrap_or_else(|err| fail(&err.to_string(), 1)),
+        };
+
+        let num_inputs = compiled_equation.inputs.len();
+        print!(
+            "{} is true for {} of 2^{} assignments",
+            compiled_equation.output, count, num_inputs
+        );
+        if compiled_equation.assumptions.is_empty() {
+            println!();
+        } else {
+            println!(", among those satisfying the assumptions");
+        }
+    }
+    #[cfg(not(feature = "wgpu"))]
+    {
+        let _ = (args, compiled_equation);
+        fail(
+            "running on the GPU needs booltable built with the 'wgpu' feature",
+            2,
+        );
+    }
+}
+
+fn aiger(args: &Args, config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+    let aig = Aig::new(&parsed_equation);
+
+    match &args.output {
+        // The binary format for `.aig` files, and ASCII for anything else
+        Some(path) => {
+            let contents = match path.extension() {
+                Some(ext) if ext == "aig" => aig.to_aig(),
+                _ => aig.to_aag().into_bytes(),
+            };
+            if let Err(err) = write_output(path, &contents, args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => print!("{}", aig.to_aag()),
+    }
+}
+
+fn decision_tree(args: &Args, config: &Config, input: &str) {
+    let table = VM::new(Compiler::new(parse(config, input)).compile()).gen();
+    let tree = decision::tree(&table);
+    let code = format!(
+        "{} = {}",
+        table.output_name,
+        tree.to_code(&table.input_names)
+    );
+
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = write_output(path, code.as_bytes(), args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => print!("{}", code),
+    }
+}
+
+fn codegen(args: &Args, config: &Config, input: &str) {
+    let style = match args.lookup {
+        true => codegen::Style::Lookup,
+        false => codegen::Style::Expression,
+    };
+    let code = match codegen::generate(&parse(config, input), args.lang, style) {
+        Ok(code) => code,
+        Err(err) => fail(&err, 2),
+    };
+
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = write_output(path, code.as_bytes(), args.force) {
+                fail(&format!("{}: {}", path.display(), err), 1);
+            }
+        }
+        None => print!("{}", code),
+    }
+}
+
+fn prove_equal(config: &Config, left: &str, right: &str) {
+    let (left, right) = (parse(config, left), parse(config, right));
+
+    // Number the variables the same way on both sides, so that they compare
+    let names = equiv::shared_inputs(&left, &right);
+    let left_lhs = equiv::remap(&left.lhs, &left.inputs, &names);
+    let right_lhs = equiv::remap(&right.lhs, &right.inputs, &names);
+    if let Some(steps) = prove::prove(&left_lhs, &right_lhs, PROOF_LIMIT) {
+        let rendered = simplify::render_proof(&left_lhs, &steps, &names, config.dialect);
+        print!("{}", ascii(config, rendered));
+        return;
+    }
+
+    println!("No derivation found, comparing truth tables instead");
+    match equiv::check(&left, &right) {
+        Ok(()) => println!("The equations are equivalent"),
+        Err(counterexample) => {
+            let assignment: Vec<String> = counterexample
+                .assignment
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, *value as u8))
+                .collect();
+            println!(
+                "The equations differ at {}: {} on the left and {} on the right",
+                assignment.join(","),
+                counterexample.left as u8,
+                counterexample.right as u8
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn compare(config: &Config, left: &str, right: &str) {
+    let (left, right) = (parse(config, left), parse(config, right));
+    let comparison = equiv::compare(&left, &right);
+    let differing: Vec<usize> = comparison.differing().collect();
+
+    // Rows follow the rule under the names, one per line
+    let rendered = comparison.to_markdown(&config.style);
+    let mut rows = None;
+    for line in rendered.lines() {
+        match rows {
+            Some(row) if differing.contains(&row) => println!("{}", Paint::red(line)),
+            _ => println!("{}", line),
+        }
+        if line.starts_with("|-") || line.starts_with("|:") {
+            rows = Some(0);
+        } else if let Some(row) = &mut rows {
+            *row += 1;
+        }
+    }
+
+    if !differing.is_empty() {
+        process::exit(1);
+    }
+}
+
+/// Check that the equation agrees with the specification wherever the
+/// specification's assumptions don't leave the output as a don't-care.
+fn compatible(config: &Config, spec: &str, input: &str) {
+    let (spec, equation) = (parse(config, spec), parse(config, input));
+    let cares = equiv::cares(&spec, &equation);
+    let dont_cares = cares.iter().filter(|cares| !**cares).count();
+
+    match equiv::refines(&spec, &equation) {
+        Ok(()) => println!(
+            "The equation refines the specification, which leaves {} of {} rows as don't-cares",
+            dont_cares,
+            cares.len()
+        ),
+        Err(counterexample) => {
+            let assignment: Vec<String> = counterexample
+                .assignment
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, *value as u8))
+                .collect();
+            println!(
+                "The equation differs from the specification at {}: {} in the specification and {} in the equation",
+                assignment.join(","),
+                counterexample.left as u8,
+                counterexample.right as u8
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn complete(config: &Config, inputs: &[String]) {
+    let tables: Vec<TruthTable> = inputs
+        .iter()
+        .map(|input| VM::new(Compiler::new(parse(config, input)).compile()).gen())
+        .collect();
+
+    let shared = classes::shared_classes(&tables);
+    if shared.is_empty() {
+        println!("The functions are functionally complete");
+    } else {
+        let names: Vec<&str> = shared.iter().map(|class| class.name()).collect();
+        println!(
+            "The functions aren't functionally complete, since they're all in Post's classes {}",
+            names.join(", ")
+        );
+        process::exit(1);
+    }
+}
+
+/// Names for the inputs of `random` functions.
+const RANDOM_NAMES: &[&str] = &[
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
+
+fn random(args: &Args, config: &Config) {
+    let source = random_source(args, config, random_seed(args));
+
+    // The vector is printed for the equation as it parses, whose inputs are
+    // numbered by first appearance and leave out any the function ignores,
+    // so that it can be checked with 'verify'
+    let parsed = parse(config, &source);
+    let table = VM::new(Compiler::new(parsed).compile()).gen();
+    let outputs: Vec<bool> = table.rows().map(|row| row.output).collect();
+
+    println!("{}", verify::format(&outputs));
+    println!("{}", source);
+}
+
+/// `--seed`, or one from the clock.
+fn random_seed(args: &Args) -> u64 {
+    args.seed.unwrap_or_else(|| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_nanos() as u64
+    })
+}
+
+/// A minimised equation for a random function of `--vars` inputs.
+fn random_source(args: &Args, config: &Config, seed: u64) -> String {
+    let limit = config
+        .max_inputs
+        .min(RANDOM_NAMES.len())
+        .min(minimize::MAX_INPUTS);
+    if args.vars > limit {
+        fail(
+            &format!("random functions have at most {} inputs", limit),
+            2,
+        );
+    }
+
+    let outputs = verify::random(1 << args.vars, seed, args.balanced);
+    let minterms: Vec<usize> = (0..outputs.len()).filter(|m| outputs[*m]).collect();
+    let equation = parser::Equation {
+        inputs: RANDOM_NAMES[..args.vars]
+            .iter()
+            .map(|&name| name.into())
+            .collect(),
+        lhs: minimize::to_expr(&minimize::cover(&minterms), args.vars),
+        output: "Q".into(),
+        assumptions: vec![],
+    };
+
+    equation.to_source(config.dialect)
+}
+
+fn open_table(args: &Args, config: &Config, path: &Path) {
+    let mut file = TableFile::open(path)
+        .unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err), 1));
+    let io_error = |err: io::Error| -> ! { fail(&format!("{}: {}", path.display(), err), 1) };
+
+    let Some(set) = &args.set else {
+        let table = file.to_table().unwrap_or_else(|err| io_error(err));
+        let format = args.format.unwrap_or(config.format);
+        print!("{}", export::render(&table, format, &config.style));
+        return;
+    };
+    let assignment =
+        cli::parse_assignment(set, &file.input_names).unwrap_or_else(|err| fail(&err, 2));
+    let row = assignments::to_row(&assignment);
+    match file.get(row).unwrap_or_else(|err| io_error(err)) {
+        Some(output) => println!("{}", output as u8),
+        None => fail("that row was left out of the table by an assumption", 1),
+    }
+}
+
+fn diff_tables(left: &Path, right: &Path) {
+    let open = |path: &Path| {
+        TableFile::open(path).unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err), 2))
+    };
+    let (mut left_file, mut right_file) = (open(left), open(right));
+    let differing = tablefile::diff(&mut left_file, &mut right_file)
+        .unwrap_or_else(|err| fail(&err.to_string(), 2));
+
+    let show = |output: Option<bool>| match output {
+        Some(output) => (output as u8).to_string(),
+        None => "-".to_string(),
+    };
+    for row in &differing {
+        let inputs: Vec<String> = left_file
+            .input_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let bit = row >> (left_file.input_names.len() - 1 - i) & 1;
+                format!("{}={}", name, bit)
+            })
+            .collect();
+        let outputs = [left_file.get(*row), right_file.get(*row)]
+            .map(|output| show(output.unwrap_or_else(|err| fail(&err.to_string(), 2))));
+        println!(
+            "row {} ({}): {} vs {}",
+            row,
+            inputs.join(","),
+            outputs[0],
+            outputs[1]
+        );
+    }
+
+    process::exit(if differing.is_empty() { 0 } else { 1 });
+}
+
+fn write_report(args: &Args, config: &Config, path: &Path) {
+    let source = fs::read_to_string(path)
+        .unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err), 1));
+    let document = document::generate(&source, config)
+        .unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err), 2));
+
+    match &args.output {
+        Some(output) => {
+            if let Err(err) = write_output(output, document.as_bytes(), args.force) {
+                fail(&format!("{}: {}", output.display(), err), 1);
+            }
+        }
+        None => print!("{}", document),
+    }
+}
+
+fn bench(args: &Args, config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+    let compiled_equation = Compiler::new(parsed_equation.clone()).compile();
+    let (inputs, ops) = (compiled_equation.inputs.len(), compiled_equation.lhs.len());
+
+    let parse_times = time(args.repeat, || Parser::new(input).parse_equation().unwrap());
+    let compile_times = time(args.repeat, || {
+        Compiler::new(parsed_equation.clone()).compile()
+    });
+    let incremental = Incremental::new(&compiled_equation);
+    let incremental_times = time(args.repeat, || incremental.gen(&compiled_equation));
+    let mut vm = VM::new(compiled_equation);
+    let gen_times = time(args.repeat, || vm.gen());
+    let mut vm = VM::new(Compiler::new(parsed_equation.clone()).compile_short_circuit());
+    let short_circuit_times = time(args.repeat, || vm.gen());
+
+    println!(
+        "{} inputs, {} rows, {} ops, {} runs\n",
+        inputs,
+        1usize << inputs,
+        ops,
+        args.repeat
+    );
+    println!("{:<15}{:>12}{:>12}{:>12}", "phase", "min", "mean", "max");
+    for (phase, times) in [
+        ("parse", parse_times),
+        ("compile", compile_times),
+        ("generate", gen_times),
+        ("short-circuit", short_circuit_times),
+        ("incremental", incremental_times),
+    ] {
+        let min = times.iter().min().unwrap();
+        let max = times.iter().max().unwrap();
+        let mean = times.iter().sum::<Duration>() / times.len() as u32;
+        println!(
+            "{:<15}{:>12}{:>12}{:>12}",
+            phase,
+            format!("{:.2?}", min),
+            format!("{:.2?}", mean),
+            format!("{:.2?}", max)
+        );
+    }
+}
+
+/// Run `f` `runs` times, returning how long each run took.
+fn time<T>(runs: usize, mut f: impl FnMut() -> T) -> Vec<Duration> {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            hint::black_box(f());
+            start.elapsed()
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn explore(_: &Config, _: &str) {
+    fail(
+        "the explorer needs a Unix terminal, so 'tui' isn't available here",
+        2,
+    );
+}
+
+#[cfg(unix)]
+fn explore(config: &Config, input: &str) {
+    let parsed_equation = parse(config, input);
+
+    let compiler = Compiler::new(parsed_equation.clone());
+    let mut vm = VM::new(compiler.compile());
+    let truth_table = vm.gen();
+    if truth_table.is_empty() {
+        fail("no assignment satisfies the assumptions", 1);
+    }
+
+    if let Err(err) = tui::run(&parsed_equation, &truth_table, config.dialect) {
+        fail(&err.to_string(), 1);
+    }
+}
 
-        println!("{}", truth_table);
+fn report(config: &Config, input: &str, err: &SyntaxError) {
+    let span = err.span();
+    // ariadne draws its diagrams with box-drawing characters
+    if config.plain {
+        eprintln!("Error: {} at {}", err, span);
+        return;
     }
+    Report::<Range<usize>>::build(ReportKind::Error, (), span.start)
+        .with_message(err)
+        .with_label(Label::new(span.into()).with_message(err))
+        .with_config(ariadne::Config::default().with_color(Paint::is_enabled()))
+        .finish()
+        .eprint(Source::from(input))
+        .unwrap();
 }
@@ -0,0 +1,659 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+pub use crate::cube::Cube;
+use crate::{
+    assignments,
+    lexer::Span,
+    parser::{Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr},
+    simplify,
+    vm::TruthTable,
+};
+
+/// Tables with more inputs are too large to minimise: an irregular function
+/// of 18 inputs has hundreds of thousands of primes and takes a minute to
+/// cover, and each input more about quadruples that. Callers check this
+/// before minimising.
+pub const MAX_INPUTS: usize = 16;
+
+/// The error for a table of `num_inputs` that's too large to minimise, if
+/// it is.
+pub fn check_size(num_inputs: usize) -> Result<(), String> {
+    match num_inputs > MAX_INPUTS {
+        true => Err(format!(
+            "{} inputs is too large to minimise, the limit is {}",
+            num_inputs, MAX_INPUTS
+        )),
+        false => Ok(()),
+    }
+}
+
+/// The minimiser's name for a [`Cube`], one of the products of a cover.
+pub type Implicant = Cube;
+
+/// Why a product term of a sum of products can be dropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Redundancy {
+    /// It has a variable both negated and not, so it's never true.
+    Empty,
+    /// Every assignment making it true makes this other term true.
+    Absorbed(Span),
+    /// It's covered by the consensus of these two other terms.
+    Consensus(Span, Span),
+    /// It's covered by several of the other terms together.
+    Covered,
+}
+
+/// The product terms of `expr` that can be dropped without changing it,
+/// with their spans and why, or `None` if it isn't a sum of products. A
+/// term is dropped if the terms that are left cover it, taking them in
+/// order, so what's left covers the function with no redundant terms,
+/// though not necessarily as few as `minimize` would find.
+pub fn redundant_terms(expr: &SpanExpr, num_inputs: usize) -> Option<Vec<(Span, Redundancy)>> {
+    let mut terms = vec![];
+    for term in flatten(BinOp::Or, expr.expr()) {
+        terms.push((term.span(), product(term, num_inputs)?));
+    }
+
+    let mut kept: Vec<bool> = vec![true; terms.len()];
+    let mut redundant = vec![];
+    for (i, (span, term)) in terms.iter().enumerate() {
+        let Some(term) = term else {
+            kept[i] = false;
+            redundant.push((*span, Redundancy::Empty));
+            continue;
+        };
+        let others: Vec<(Span, Cube)> = terms
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i && kept[*j])
+            .filter_map(|(_, (span, other))| Some((*span, (*other)?)))
+            .collect();
+        if !term
+            .minterms()
+            .all(|m| others.iter().any(|(_, other)| other.covers(m)))
+        {
+            continue;
+        }
+
+        kept[i] = false;
+        let absorbing = others.iter().find(|(_, other)| other.contains(term));
+        let consensus = others.iter().enumerate().find_map(|(a, (p_span, p))| {
+            others[a + 1..].iter().find_map(|(q_span, q)| {
+                p.consensus(q)
+                    .filter(|c| c.contains(term))
+                    .map(|_| Redundancy::Consensus(*p_span, *q_span))
+            })
+        });
+        let reason = match (absorbing, consensus) {
+            (Some((span, _)), _) => Redundancy::Absorbed(*span),
+            (None, Some(consensus)) => consensus,
+            (None, None) => Redundancy::Covered,
+        };
+        redundant.push((*span, reason));
+    }
+
+    Some(redundant)
+}
+
+/// The products of `expr` as implicants, leaving out any that are never
+/// true, or `None` if it isn't a sum of products.
+pub fn products(expr: &SpanExpr, num_inputs: usize) -> Option<Vec<Cube>> {
+    let mut products = vec![];
+    for term in flatten(BinOp::Or, expr.expr()) {
+        products.extend(product(term, num_inputs)?);
+    }
+
+    Some(products)
+}
+
+/// The operands of a chain of `op`, however it's bracketed.
+pub fn flatten(op: BinOp, expr: ExprRef) -> Vec<ExprRef> {
+    match expr.node() {
+        Expr::BinOp {
+            op: inner,
+            lhs,
+            rhs,
+        } if inner == op => {
+            let mut operands = flatten(op, lhs);
+            operands.extend(flatten(op, rhs));
+            operands
+        }
+        _ => vec![expr],
+    }
+}
+
+/// A product of literals as an implicant, `Some(None)` if it has a variable
+/// in both polarities, or `None` if it isn't a product of literals.
+fn product(expr: ExprRef, num_inputs: usize) -> Option<Option<Cube>> {
+    let mut value = 0;
+    let mut mask = (1 << num_inputs) - 1;
+    for factor in flatten(BinOp::And, expr) {
+        let (v, polarity) = match factor.node() {
+            Expr::Var(v) => (v, true),
+            Expr::Not(e) => match e.node() {
+                Expr::Var(v) => (v, false),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let bit = 1 << (num_inputs - 1 - v);
+        if mask & bit == 0 && (value & bit != 0) != polarity {
+            return Some(None);
+        }
+        mask &= !bit;
+        if polarity {
+            value |= bit;
+        }
+    }
+
+    Some(Some(Cube { value, mask }))
+}
+
+/// Minimise the table's output into a sum of products using Quine–McCluskey.
+/// Rows left out by assumptions are treated as false.
+pub fn minimize(table: &TruthTable) -> SpanExpr {
+    to_expr(&cover(&minterms(table)), table.input_names.len())
+}
+
+/// Minimise the table's output into a product of sums, by minimising its
+/// complement and applying De Morgan's laws. Rows left out by assumptions
+/// are treated as false.
+pub fn minimize_pos(table: &TruthTable) -> SpanExpr {
+    to_pos_expr(&cover(&maxterms(table)), table.input_names.len())
+}
+
+/// Minimise the table's output into a sum of products, then rewrite pairs of
+/// products that differ only in two literals, both flipped, as one product
+/// with an XOR or XNOR, which Quine–McCluskey never finds by itself.
+pub fn minimize_xor(table: &TruthTable) -> SpanExpr {
+    with_xor(&cover(&minterms(table)), table.input_names.len())
+}
+
+/// The sum of `products`, pairing each with the first later one that has
+/// the same variables and differs from it in exactly two literals, as in
+/// `A.B.!C + A.!B.C` to `A.(B ^ C)`. The paired products are simplified
+/// afterwards, so a pair of XORs can become an XOR of three.
+pub fn with_xor(products: &[Cube], num_inputs: usize) -> SpanExpr {
+    let all = (1 << num_inputs) - 1;
+    let mut arena = Arena::new();
+    let mut paired = vec![false; products.len()];
+    let mut terms = vec![];
+    for (i, p) in products.iter().enumerate() {
+        if paired[i] {
+            continue;
+        }
+        let partner = (i + 1..products.len()).find(|&j| {
+            let q = &products[j];
+            !paired[j] && q.mask == p.mask && (p.value ^ q.value).count_ones() == 2
+        });
+        let Some(j) = partner else {
+            terms.push(to_expr_in(&mut arena, &[*p], num_inputs));
+            continue;
+        };
+        paired[j] = true;
+
+        // The two literals that differ, as a sum of two products, which
+        // simplifies to their XOR or XNOR
+        let diff = p.value ^ products[j].value;
+        let pair = [*p, products[j]].map(|c| Cube {
+            value: c.value & diff,
+            mask: all & !diff,
+        });
+        let xor = arena.copy(simplify::simplify(&to_expr(&pair, num_inputs)).expr());
+        let common = Cube {
+            value: p.value & !diff,
+            mask: p.mask | diff,
+        };
+        terms.push(if common.mask == all {
+            xor
+        } else {
+            let common = to_expr_in(&mut arena, &[common], num_inputs);
+            chain(&mut arena, BinOp::And, vec![common, xor], true)
+        });
+    }
+    let sum = chain(&mut arena, BinOp::Or, terms, false);
+
+    simplify::simplify(&SpanExpr::new(arena, sum))
+}
+
+/// The sum of a product for each row on which the table is true.
+pub fn canonical_sop(table: &TruthTable) -> SpanExpr {
+    let full = |value| Cube { value, mask: 0 };
+    let products: Vec<Cube> = minterms(table).into_iter().map(full).collect();
+    to_expr(&products, table.input_names.len())
+}
+
+/// The product of a sum for each row on which the table is false.
+pub fn canonical_pos(table: &TruthTable) -> SpanExpr {
+    let full = |value| Cube { value, mask: 0 };
+    let sums: Vec<Cube> = maxterms(table).into_iter().map(full).collect();
+    to_pos_expr(&sums, table.input_names.len())
+}
+
+/// The rows on which the table is true, as numbers.
+pub(crate) fn minterms(table: &TruthTable) -> Vec<usize> {
+    table
+        .inputs
+        .iter()
+        .zip(&table.outputs)
+        .filter(|(_, output)| **output)
+        .map(|(inputs, _)| assignments::to_row(inputs))
+        .collect()
+}
+
+/// Every other row, including those left out by assumptions.
+fn maxterms(table: &TruthTable) -> Vec<usize> {
+    let minterms: HashSet<usize> = minterms(table).into_iter().collect();
+    (0..1 << table.input_names.len())
+        .filter(|m| !minterms.contains(m))
+        .collect()
+}
+
+/// Find a small set of prime implicants covering every minterm, taking the
+/// essential primes first and then greedily the one covering the most.
+/// Each minterm keeps the primes covering it, so choosing a prime only
+/// updates the counts of those sharing its minterms.
+pub fn cover(minterms: &[usize]) -> Vec<Cube> {
+    let primes = prime_implicants(minterms);
+    let index: HashMap<usize, usize> = minterms.iter().enumerate().map(|(i, &m)| (m, i)).collect();
+    let mut covering: Vec<Vec<usize>> = vec![vec![]; minterms.len()];
+    for (p, prime) in primes.iter().enumerate() {
+        // Every subset of the don't-cares, from all of them down to none
+        let mut free = prime.mask;
+        loop {
+            covering[index[&(prime.value | free)]].push(p);
+            if free == 0 {
+                break;
+            }
+            free = (free - 1) & prime.mask;
+        }
+    }
+
+    let mut counts: Vec<usize> = vec![0; primes.len()];
+    for primes in &covering {
+        for &p in primes {
+            counts[p] += 1;
+        }
+    }
+    let mut covered = vec![false; minterms.len()];
+    let mut chosen: Vec<Cube> = vec![];
+    let mut choose = |p: usize, counts: &mut Vec<usize>, chosen: &mut Vec<Cube>| {
+        chosen.push(primes[p]);
+        let prime = primes[p];
+        let mut free = prime.mask;
+        loop {
+            let m = index[&(prime.value | free)];
+            if !covered[m] {
+                covered[m] = true;
+                for &q in &covering[m] {
+                    counts[q] -= 1;
+                }
+            }
+            if free == 0 {
+                break;
+            }
+            free = (free - 1) & prime.mask;
+        }
+    };
+
+    for covered_by in &covering {
+        if let [essential] = covered_by[..] {
+            if !chosen.contains(&primes[essential]) {
+                choose(essential, &mut counts, &mut chosen);
+            }
+        }
+    }
+
+    loop {
+        let (best, count) = (0..primes.len())
+            .map(|p| (p, (counts[p], primes[p].mask.count_ones())))
+            .max_by_key(|(_, key)| *key)
+            .map_or((0, 0), |(p, (count, _))| (p, count));
+        if count == 0 {
+            break;
+        }
+        choose(best, &mut counts, &mut chosen);
+    }
+
+    chosen.sort_unstable_by_key(|p| Reverse((p.value, p.mask)));
+    chosen
+}
+
+/// Every prime implicant of the minterms, found from the cofactors on the
+/// first input rather than by merging every implicant in turn, since there
+/// can be far more implicants than primes. A prime either leaves the input
+/// out, so it's a prime of both cofactors ANDed together, or it's a prime
+/// of one cofactor with the input's literal added that isn't contained in
+/// one of the first kind.
+pub fn prime_implicants(minterms: &[usize]) -> Vec<Cube> {
+    let Some(&max) = minterms.iter().max() else {
+        return vec![];
+    };
+    // Inputs above the highest minterm's bit are always 0, which each prime
+    // keeps as a negated literal
+    let bits = usize::BITS - max.leading_zeros();
+    let mut column = Column::new(bits);
+    for &m in minterms {
+        column.set(m);
+    }
+
+    primes(&column, &mut HashMap::new())
+}
+
+/// A function of the `bits` lowest bits of a row, as a bit per row.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Column {
+    bits: u32,
+    words: Vec<u64>,
+}
+
+impl Column {
+    fn new(bits: u32) -> Self {
+        Self {
+            bits,
+            words: vec![0; (1usize << bits).div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, row: usize) {
+        self.words[row / 64] |= 1 << (row % 64);
+    }
+
+    fn is_false(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    fn is_true(&self) -> bool {
+        match self.bits {
+            0..=5 => self.words[0] == (1 << (1 << self.bits)) - 1,
+            _ => self.words.iter().all(|w| *w == u64::MAX),
+        }
+    }
+
+    /// The functions with the top bit 0 and 1.
+    fn cofactors(&self) -> (Column, Column) {
+        let bits = self.bits - 1;
+        match bits {
+            0..=5 => {
+                let half = 1 << bits;
+                let low = self.words[0] & ((1 << half) - 1);
+                let words = |w| Column {
+                    bits,
+                    words: vec![w],
+                };
+                (words(low), words(self.words[0] >> half))
+            }
+            _ => {
+                let (low, high) = self.words.split_at(self.words.len() / 2);
+                let words = |w: &[u64]| Column {
+                    bits,
+                    words: w.to_vec(),
+                };
+                (words(low), words(high))
+            }
+        }
+    }
+
+    fn and(&self, other: &Column) -> Column {
+        Column {
+            bits: self.bits,
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & b)
+                .collect(),
+        }
+    }
+}
+
+fn primes(column: &Column, memo: &mut HashMap<Column, Vec<Cube>>) -> Vec<Cube> {
+    if column.is_false() {
+        return vec![];
+    }
+    if column.is_true() {
+        return vec![Cube {
+            value: 0,
+            mask: (1 << column.bits) - 1,
+        }];
+    }
+    if let Some(primes) = memo.get(column) {
+        return primes.clone();
+    }
+
+    let bit = 1 << (column.bits - 1);
+    let (low, high) = column.cofactors();
+    let both = self::primes(&low.and(&high), memo);
+    let mut primes: Vec<Cube> = both
+        .iter()
+        .map(|p| Cube {
+            value: p.value,
+            mask: p.mask | bit,
+        })
+        .collect();
+    for (cofactor, value) in [(low, 0), (high, bit)] {
+        let extended = self::primes(&cofactor, memo)
+            .into_iter()
+            .filter(|p| !both.iter().any(|q| q.contains(p)))
+            .map(|p| Cube {
+                value: p.value | value,
+                mask: p.mask,
+            });
+        primes.extend(extended);
+    }
+
+    memo.insert(column.clone(), primes.clone());
+    primes
+}
+
+/// Build the product of sums that is false on exactly the implicants of
+/// `maxterms`, each sum being the complement of one of them.
+pub fn to_pos_expr(maxterms: &[Cube], num_inputs: usize) -> SpanExpr {
+    SpanExpr::build(|arena| {
+        let sums = maxterms
+            .iter()
+            .map(|p| {
+                let literals = literals(arena, p, num_inputs, true);
+                chain(arena, BinOp::Or, literals, false)
+            })
+            .collect();
+        chain(arena, BinOp::And, sums, true)
+    })
+}
+
+/// Build the sum of products `implicants` as an expression.
+pub fn to_expr(implicants: &[Cube], num_inputs: usize) -> SpanExpr {
+    SpanExpr::build(|arena| to_expr_in(arena, implicants, num_inputs))
+}
+
+/// Push the sum of products `implicants` into `arena`.
+pub(crate) fn to_expr_in(arena: &mut Arena, implicants: &[Cube], num_inputs: usize) -> ExprId {
+    let products = implicants
+        .iter()
+        .map(|p| {
+            let literals = literals(arena, p, num_inputs, false);
+            chain(arena, BinOp::And, literals, true)
+        })
+        .collect();
+
+    chain(arena, BinOp::Or, products, false)
+}
+
+/// The literals of `cube`, complemented if `complement` is set.
+fn literals(arena: &mut Arena, cube: &Cube, num_inputs: usize, complement: bool) -> Vec<ExprId> {
+    (0..num_inputs)
+        .filter_map(|i| {
+            let bit = 1 << (num_inputs - 1 - i);
+            if cube.mask & bit != 0 {
+                return None;
+            }
+            let var = node(arena, Node::Var(i));
+            Some(match (cube.value & bit != 0) != complement {
+                true => var,
+                false => node(arena, Node::Not(var)),
+            })
+        })
+        .collect()
+}
+
+/// `operands` joined with `op`, nesting to the right, or `empty` if there
+/// are none.
+pub(crate) fn chain(
+    arena: &mut Arena,
+    op: BinOp,
+    mut operands: Vec<ExprId>,
+    empty: bool,
+) -> ExprId {
+    let mut expr = match operands.pop() {
+        Some(last) => last,
+        None => return node(arena, Node::Bool(empty)),
+    };
+    while let Some(lhs) = operands.pop() {
+        expr = node(arena, Node::BinOp { op, lhs, rhs: expr });
+    }
+
+    expr
+}
+
+/// Push a node that has no source, so has an empty span.
+pub(crate) fn node(arena: &mut Arena, node: Node) -> ExprId {
+    arena.push((0..0).into(), node)
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let mut parser = Parser::new("(A AND B) OR (A AND C) OR (B AND C) OR (A AND B AND C) = Q");
+    let equation = parser.parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    let names = table.input_names.clone();
+    assert_eq!(
+        minimize(&table).display(&names).to_string(),
+        "(A AND B) OR (A AND C) OR (B AND C)"
+    );
+
+    let names = ["A".into(), "B".into()];
+    let table = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen()
+    };
+    let xor = table("A XOR B = Q");
+    let show = |expr: SpanExpr| expr.display(&names).to_string();
+    assert_eq!(
+        show(canonical_sop(&xor)),
+        "((NOT A) AND B) OR (A AND NOT B)"
+    );
+    assert_eq!(show(canonical_pos(&xor)), "(A OR B) AND ((NOT A) OR NOT B)");
+    assert_eq!(show(minimize_pos(&xor)), "((NOT A) OR NOT B) AND (A OR B)");
+    assert_eq!(show(minimize_xor(&xor)), "A XOR B");
+    assert_eq!(
+        show(minimize_xor(&table("(A AND B) OR ((NOT A) AND NOT B) = Q"))),
+        "NOT (A XOR B)"
+    );
+    let names = ["A".into(), "B".into(), "C".into()];
+    let show = |expr: SpanExpr| expr.display(&names).to_string();
+    assert_eq!(
+        show(minimize_xor(&table("A XOR B XOR C = Q"))),
+        "A XOR B XOR C"
+    );
+    assert_eq!(
+        show(minimize_xor(&table(
+            "(A AND (B XOR C)) OR ((NOT A) AND B AND C) = Q"
+        ))),
+        "(A AND (B XOR C)) OR ((NOT A) AND B AND C)"
+    );
+    let names = ["A".into(), "B".into()];
+    let show = |expr: SpanExpr| expr.display(&names).to_string();
+    let or = table("A OR (A AND B) = Q");
+    assert_eq!(show(minimize_pos(&or)), "A");
+    assert_eq!(show(canonical_pos(&table("A OR NOT A = Q"))), "1");
+
+    let redundant = |source| {
+        let mut parser = Parser::new(source);
+        let equation = parser.parse_equation().unwrap();
+        redundant_terms(&equation.lhs, equation.inputs.len()).map(|terms| {
+            terms
+                .iter()
+                .map(|(span, reason)| (&source[span.start..span.end], *reason))
+                .collect::<Vec<_>>()
+        })
+    };
+    let span = |source: &str, term: &str| {
+        let start = source.find(term).unwrap();
+        Span::from(start..start + term.len())
+    };
+
+    let source = "(A AND B) OR ((NOT B) AND C) OR (A AND C) = Q";
+    let consensus =
+        Redundancy::Consensus(span(source, "(A AND B)"), span(source, "((NOT B) AND C)"));
+    assert_eq!(redundant(source), Some(vec![("(A AND C)", consensus)]));
+    let source = "(A AND B AND C) OR (A AND B) OR (B AND NOT B) = Q";
+    assert_eq!(
+        redundant(source),
+        Some(vec![
+            (
+                "(A AND B AND C)",
+                Redundancy::Absorbed(span(source, "(A AND B)"))
+            ),
+            ("(B AND NOT B)", Redundancy::Empty),
+        ])
+    );
+    // C is covered by the other three together, but not by any one or the
+    // consensus of any two
+    let source = "C OR (A AND C) OR ((NOT A) AND B AND C) OR ((NOT A) AND (NOT B) AND C) = Q";
+    assert_eq!(redundant(source), Some(vec![("C", Redundancy::Covered)]));
+    assert_eq!(redundant("A OR B = Q"), Some(vec![]));
+    assert_eq!(redundant("(A OR B) AND C = Q"), None);
+    let equation = Parser::new("(A AND NOT B) OR (B AND NOT B) OR C = Q")
+        .parse_equation()
+        .unwrap();
+    assert_eq!(
+        products(&equation.lhs, 3),
+        Some(vec![
+            Cube {
+                value: 0b100,
+                mask: 0b001
+            },
+            Cube {
+                value: 0b001,
+                mask: 0b110
+            },
+        ])
+    );
+
+    // The primes are the implicants no other implicant contains, checked
+    // against every cube of four inputs for a spread of functions
+    let cubes: Vec<Cube> = (0..16)
+        .flat_map(|mask| (0..16).map(move |value| Cube { value, mask }))
+        .filter(|cube| cube.value & cube.mask == 0)
+        .collect();
+    for function in (0..1 << 16).step_by(997).chain([0xffff, 0x6996]) {
+        let minterms: Vec<usize> = (0..16).filter(|m| function >> m & 1 == 1).collect();
+        let implicants: Vec<&Cube> = cubes
+            .iter()
+            .filter(|cube| (0..16).all(|m| !cube.covers(m) || minterms.contains(&m)))
+            .collect();
+        let mut expected: Vec<Cube> = implicants
+            .iter()
+            .filter(|cube| {
+                !implicants
+                    .iter()
+                    .any(|other| other != *cube && other.contains(cube))
+            })
+            .map(|cube| **cube)
+            .collect();
+        let mut primes = prime_implicants(&minterms);
+        primes.sort_unstable_by_key(|p| (p.value, p.mask));
+        expected.sort_unstable_by_key(|p| (p.value, p.mask));
+        assert_eq!(primes, expected, "{:016b}", function);
+        assert!(minterms
+            .iter()
+            .all(|m| cover(&minterms).iter().any(|p| p.covers(*m))));
+    }
+    assert_eq!(check_size(MAX_INPUTS), Ok(()));
+    assert!(check_size(MAX_INPUTS + 1).is_err());
+}
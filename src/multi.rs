@@ -0,0 +1,410 @@
+//! Tables with several outputs, computed together. The equations are lowered
+//! into one register program, so a term they share is computed once per row
+//! rather than once per output.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use crate::{
+    assignments,
+    compiler::Compiler,
+    equiv,
+    export::{self, Format, RowOrder, RowSort, TableStyle},
+    intern::Symbol,
+    parser::Equation,
+    register::Program,
+    rom,
+    vm::{Op, Summary},
+};
+
+/// Several equations compiled over the union of their inputs.
+#[derive(Debug, Clone)]
+pub struct MultiEquation {
+    /// The inputs of all of the equations, in order of first appearance.
+    pub inputs: Vec<Symbol>,
+    pub outputs: Vec<Symbol>,
+    /// Computes each equation's output, followed by each of their
+    /// assumptions.
+    pub program: Program,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiTable {
+    pub input_names: Vec<Symbol>,
+    pub inputs: Vec<Vec<bool>>,
+    pub output_names: Vec<Symbol>,
+    /// The value of every output for each row.
+    pub outputs: Vec<Vec<bool>>,
+}
+
+impl MultiEquation {
+    pub fn compile(equations: &[Equation]) -> Self {
+        let mut inputs: Vec<Symbol> = vec![];
+        for equation in equations {
+            for name in &equation.inputs {
+                if !inputs.contains(name) {
+                    inputs.push(*name);
+                }
+            }
+        }
+
+        // Renumber each equation's variables into the shared inputs, so that
+        // equal terms from different equations compile to equal ops
+        let compiled: Vec<_> = equations
+            .iter()
+            .map(|equation| {
+                let remap = |expr| equiv::remap(expr, &equation.inputs, &inputs);
+                let shared = Equation {
+                    inputs: inputs.clone(),
+                    lhs: remap(&equation.lhs),
+                    output: equation.output,
+                    assumptions: equation.assumptions.iter().map(remap).collect(),
+                };
+                Compiler::new(shared).compile()
+            })
+            .collect();
+        let programs: Vec<&[Op]> = compiled
+            .iter()
+            .map(|equation| equation.lhs.as_slice())
+            .chain(
+                compiled
+                    .iter()
+                    .flat_map(|equation| equation.assumptions.iter().map(Vec::as_slice)),
+            )
+            .collect();
+
+        Self {
+            inputs,
+            outputs: equations.iter().map(|equation| equation.output).collect(),
+            program: Program::lower(&programs),
+        }
+    }
+
+    /// Evaluate every assignment, skipping those that break an assumption
+    /// of any of the equations.
+    pub fn gen(&self) -> MultiTable {
+        let length = self.inputs.len();
+        let mut scratch = vec![];
+        let mut table = MultiTable {
+            input_names: self.inputs.clone(),
+            inputs: vec![],
+            output_names: self.outputs.clone(),
+            outputs: vec![],
+        };
+
+        for row in 0..1usize << length {
+            let inputs = assignments::to_bools(row, length);
+            self.program.run(&inputs, &mut scratch);
+            let (outputs, assumptions) = self.program.outputs.split_at(self.outputs.len());
+            if assumptions.iter().all(|slot| scratch[*slot]) {
+                table.inputs.push(inputs);
+                table
+                    .outputs
+                    .push(outputs.iter().map(|slot| scratch[*slot]).collect());
+            }
+        }
+
+        table
+    }
+}
+
+impl MultiTable {
+    /// How many rows have each output true and false.
+    pub fn summaries(&self) -> Vec<Summary> {
+        (0..self.output_names.len())
+            .map(|i| {
+                let true_rows = self.outputs.iter().filter(|o| o[i]).count();
+                Summary {
+                    true_rows,
+                    false_rows: self.outputs.len() - true_rows,
+                }
+            })
+            .collect()
+    }
+
+    /// The table in `format`, which can't be `bdd-dot` since a decision
+    /// diagram has a single output, and the ROM formats need the outputs to
+    /// fit in a word.
+    pub fn render(&self, format: Format, style: &TableStyle) -> Result<String, String> {
+        let rom_outputs = match format {
+            Format::IntelHex => rom::MAX_HEX_OUTPUTS,
+            _ => rom::MAX_OUTPUTS,
+        };
+        if matches!(
+            format,
+            Format::Readmemh | Format::IntelHex | Format::VerilogCase
+        ) && self.output_names.len() > rom_outputs
+        {
+            return Err(format!(
+                "'{}' holds at most {} outputs at each address",
+                format, rom_outputs
+            ));
+        }
+
+        Ok(match format {
+            Format::Markdown => self.to_markdown(style),
+            Format::Csv => self.csv(style),
+            Format::Latex => self.latex(style),
+            Format::Html => self.html(style),
+            Format::Json => self.json(),
+            Format::Wavedrom => self.wavedrom(style),
+            Format::BddDot => {
+                return Err(
+                    "a decision diagram has one output, so 'bdd-dot' takes a single equation"
+                        .to_string(),
+                )
+            }
+            Format::Readmemh => rom::multi_readmemh(self),
+            Format::IntelHex => rom::multi_intel_hex(self),
+            Format::VerilogCase => rom::multi_verilog_case(self),
+        })
+    }
+
+    /// The indices of the rows in the order the style puts them, where
+    /// sorting by output puts rows with the first output true first, then
+    /// the second, and so on.
+    fn order(&self, style: &TableStyle) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.outputs.len()).collect();
+        match style.sort {
+            RowSort::Index => {}
+            RowSort::Output => order.sort_by(|a, b| self.outputs[*b].cmp(&self.outputs[*a])),
+            RowSort::Ones => {
+                order.sort_by_key(|row| self.inputs[*row].iter().filter(|b| **b).count())
+            }
+        }
+        if style.row_order == RowOrder::Descending {
+            order.reverse();
+        }
+
+        order
+    }
+
+    /// Each row's cells, inputs followed by outputs, in the style's order.
+    fn rows<'a>(&self, style: &'a TableStyle) -> Vec<Vec<&'a str>> {
+        self.order(style)
+            .into_iter()
+            .map(|row| {
+                self.inputs[row]
+                    .iter()
+                    .chain(&self.outputs[row])
+                    .map(|b| style.symbol(*b))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn headers(&self) -> Vec<&str> {
+        self.input_names
+            .iter()
+            .chain(&self.output_names)
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    /// The table in markdown, with `0` and `1` spelled as in `style`.
+    pub fn to_markdown(&self, style: &TableStyle) -> String {
+        let rows: Vec<Vec<String>> = self
+            .rows(style)
+            .into_iter()
+            .map(|row| row.into_iter().map(str::to_string).collect())
+            .collect();
+
+        export::grid(&self.headers(), self.input_names.len(), &rows, style)
+    }
+
+    fn csv(&self, style: &TableStyle) -> String {
+        let mut out = self.headers().join(",");
+        out.push('\n');
+        for row in self.rows(style) {
+            writeln!(out, "{}", row.join(",")).unwrap();
+        }
+
+        out
+    }
+
+    fn latex(&self, style: &TableStyle) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "\\begin{{tabular}}{{{}|{}}}",
+            "c".repeat(self.input_names.len()),
+            "c".repeat(self.output_names.len())
+        )
+        .unwrap();
+        let names: Vec<String> = self
+            .headers()
+            .iter()
+            .map(|name| name.replace('_', "\\_"))
+            .collect();
+        writeln!(out, "{} \\\\\n\\hline", names.join(" & ")).unwrap();
+        for row in self.rows(style) {
+            writeln!(out, "{} \\\\", row.join(" & ")).unwrap();
+        }
+        out.push_str("\\end{tabular}\n");
+
+        out
+    }
+
+    fn html(&self, style: &TableStyle) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+
+        let mut out = String::from("<table>\n  <thead>\n    <tr>");
+        for name in self.headers() {
+            write!(out, "<th>{}</th>", escape(name)).unwrap();
+        }
+        out.push_str("</tr>\n  </thead>\n  <tbody>\n");
+        for row in self.rows(style) {
+            out.push_str("    <tr>");
+            for cell in row {
+                write!(out, "<td>{}</td>", escape(cell)).unwrap();
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("  </tbody>\n</table>\n");
+
+        out
+    }
+
+    /// Like [`export::json`], with `outputs` in place of `output`.
+    fn json(&self) -> String {
+        let names = |names: &[Symbol]| {
+            let names: Vec<String> = names.iter().map(|n| export::json_string(n)).collect();
+            names.join(",")
+        };
+        let values = |values: &[bool]| {
+            let values: Vec<String> = values.iter().map(bool::to_string).collect();
+            values.join(",")
+        };
+        let rows: Vec<String> = self
+            .inputs
+            .iter()
+            .zip(&self.outputs)
+            .map(|(inputs, outputs)| {
+                format!(
+                    "{{\"inputs\":[{}],\"outputs\":[{}]}}",
+                    values(inputs),
+                    values(outputs)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"inputs\":[{}],\"outputs\":[{}],\"rows\":[{}]}}\n",
+            names(&self.input_names),
+            names(&self.output_names),
+            rows.join(",")
+        )
+    }
+
+    /// A WaveDrom timing diagram with every output below the inputs.
+    fn wavedrom(&self, style: &TableStyle) -> String {
+        let order = self.order(style);
+        let signal = |name: &str, value: &dyn Fn(usize) -> bool| {
+            let mut wave = String::new();
+            let mut previous = None;
+            for &row in &order {
+                let value = value(row);
+                wave.push(match previous {
+                    Some(p) if p == value => '.',
+                    _ => char::from(b'0' + value as u8),
+                });
+                previous = Some(value);
+            }
+            format!(
+                "  {{\"name\": {}, \"wave\": {}}}",
+                export::json_string(name),
+                export::json_string(&wave)
+            )
+        };
+
+        let mut signals: Vec<String> = self
+            .input_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| signal(name, &|row| self.inputs[row][i]))
+            .collect();
+        signals.push("  {}".to_string());
+        for (i, name) in self.output_names.iter().enumerate() {
+            signals.push(signal(name, &|row| self.outputs[row][i]));
+        }
+
+        format!("{{\"signal\": [\n{}\n]}}\n", signals.join(",\n"))
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{parser::Parser, vm::VM};
+
+    // A full adder, whose sum and carry share `A XOR B`
+    let sources = ["(A XOR B) XOR C = S", "(A AND B) OR (C AND (A XOR B)) = CO"];
+    let equations: Vec<Equation> = sources
+        .iter()
+        .map(|source| Parser::new(source).parse_equation().unwrap())
+        .collect();
+    let multi = MultiEquation::compile(&equations);
+    let xors = multi
+        .program
+        .instrs
+        .iter()
+        .filter(|instr| instr.to_string().contains("XOR"))
+        .count();
+    assert_eq!(xors, 2);
+
+    let table = multi.gen();
+    for equation in &equations {
+        let single = VM::new(Compiler::new(equation.clone()).compile()).gen();
+        let column = table
+            .output_names
+            .iter()
+            .position(|n| *n == equation.output);
+        let outputs: Vec<bool> = table.outputs.iter().map(|o| o[column.unwrap()]).collect();
+        assert_eq!(outputs, single.outputs);
+    }
+    assert!(table
+        .to_markdown(&TableStyle::default())
+        .starts_with("| A | B | C | S | CO |\n|---|---|---|---|----|\n"));
+
+    let filtered = MultiEquation::compile(&[
+        Parser::new("assume NOT A; A OR B = P")
+            .parse_equation()
+            .unwrap(),
+        Parser::new("B = R").parse_equation().unwrap(),
+    ])
+    .gen();
+    assert_eq!(filtered.outputs, [[false, false], [true, true]]);
+
+    let style = TableStyle::default();
+    assert_eq!(
+        filtered.render(Format::Csv, &style).unwrap(),
+        "A,B,P,R\n0,0,0,0\n0,1,1,1\n"
+    );
+    assert!(filtered
+        .render(Format::Json, &style)
+        .unwrap()
+        .starts_with("{\"inputs\":[\"A\",\"B\"],\"outputs\":[\"P\",\"R\"],"));
+    assert!(filtered.render(Format::BddDot, &style).is_err());
+    let sorted = TableStyle {
+        sort: RowSort::Output,
+        ..TableStyle::default()
+    };
+    assert!(table
+        .to_markdown(&sorted)
+        .contains("|---|---|---|---|----|\n| 1 | 1 | 1 | 1 | 1  |\n| 0 | 0 | 1 | 1 | 0  |\n"));
+    assert_eq!(
+        table.summaries()[1],
+        Summary {
+            true_rows: 4,
+            false_rows: 4
+        }
+    );
+}
@@ -1,8 +1,19 @@
-use crate::lexer::{Lexer, Span, Token, TK};
+use crate::{
+    intern::Symbol,
+    lexer::{Lexer, Span, Token, TK},
+    stdlib,
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt, iter::Peekable, mem, ops::Index, str::FromStr};
 use derive_more::Display;
-use std::{collections::HashMap, fmt, iter::Peekable};
 
-#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BinOp {
     #[display(fmt = "AND")]
     And,
@@ -23,66 +34,732 @@ impl From<TK> for BinOp {
     }
 }
 
-#[derive(Debug, Display, Clone, PartialEq)]
-#[display(fmt = "{}", node)]
-pub struct Spanned<T>
-where
-    T: fmt::Debug + fmt::Display + Clone + PartialEq,
-{
-    pub span: Span,
-    pub node: T,
+/// The index of a node in an [`Arena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExprId(u32);
+
+/// A node as it's stored in an [`Arena`], referring to its operands by id.
+/// Nodes never change once they're pushed, so passes can share any subtree
+/// they leave alone between their input and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Node {
+    Bool(bool),
+    Var(usize),
+    Not(ExprId),
+    BinOp { op: BinOp, lhs: ExprId, rhs: ExprId },
+}
+
+/// The nodes of one or more expressions and their spans, so that building
+/// or rewriting a tree costs a push per node rather than an allocation.
+#[derive(Debug, Clone, Default)]
+pub struct Arena {
+    nodes: Vec<Node>,
+    spans: Vec<Span>,
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, span: Span, node: Node) -> ExprId {
+        self.nodes.push(node);
+        self.spans.push(span);
+        ExprId(self.nodes.len() as u32 - 1)
+    }
+
+    pub fn get(&self, id: ExprId) -> ExprRef<'_> {
+        ExprRef { arena: self, id }
+    }
+
+    pub fn span(&self, id: ExprId) -> Span {
+        self.spans[id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Copy `expr` from another arena into this one.
+    pub fn copy(&mut self, expr: ExprRef) -> ExprId {
+        self.substitute(expr, |arena, v, span| arena.push(span, Node::Var(v)))
+    }
+
+    /// Copy `expr` from another arena into this one, with each variable
+    /// replaced by the node `var` pushes for it, given its number and span.
+    /// Subtrees that `expr` shares are copied once and stay shared.
+    pub fn substitute(
+        &mut self,
+        expr: ExprRef,
+        mut var: impl FnMut(&mut Arena, usize, Span) -> ExprId,
+    ) -> ExprId {
+        self.substitute_in(expr, &mut var, &mut BTreeMap::new())
+    }
+
+    fn substitute_in(
+        &mut self,
+        expr: ExprRef,
+        var: &mut impl FnMut(&mut Arena, usize, Span) -> ExprId,
+        copied: &mut BTreeMap<ExprId, ExprId>,
+    ) -> ExprId {
+        if let Some(&id) = copied.get(&expr.id) {
+            return id;
+        }
+        let node = match expr.node() {
+            Expr::Bool(b) => Node::Bool(b),
+            Expr::Var(v) => {
+                let id = var(self, v, expr.span());
+                copied.insert(expr.id, id);
+                return id;
+            }
+            Expr::Not(e) => Node::Not(self.substitute_in(e, var, copied)),
+            Expr::BinOp { op, lhs, rhs } => Node::BinOp {
+                op,
+                lhs: self.substitute_in(lhs, var, copied),
+                rhs: self.substitute_in(rhs, var, copied),
+            },
+        };
+        let id = self.push(expr.span(), node);
+        copied.insert(expr.id, id);
+
+        id
+    }
 }
 
-pub type SpanExpr = Spanned<Expr>;
-pub type Boxode = Box<SpanExpr>;
+impl Index<ExprId> for Arena {
+    type Output = Node;
+
+    fn index(&self, id: ExprId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+}
 
-#[derive(Debug, Display, Clone, PartialEq)]
-pub enum Expr {
-    #[display(fmt = "{}", _0)]
+/// A node of an expression in the arena holding it.
+#[derive(Clone, Copy)]
+pub struct ExprRef<'a> {
+    arena: &'a Arena,
+    id: ExprId,
+}
+
+/// A node with its operands as [`ExprRef`]s, for matching on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expr<'a> {
     Bool(bool),
-    #[display(fmt = "{}", _0)]
     Var(usize),
-    #[display(fmt = "(NOT {})", _0)]
-    Not(Boxode),
-    #[display(fmt = "({} {} {})", op, lhs, rhs)]
-    BinOp { op: BinOp, lhs: Boxode, rhs: Boxode },
+    Not(ExprRef<'a>),
+    BinOp {
+        op: BinOp,
+        lhs: ExprRef<'a>,
+        rhs: ExprRef<'a>,
+    },
+}
+
+impl<'a> ExprRef<'a> {
+    pub fn id(self) -> ExprId {
+        self.id
+    }
+
+    pub fn arena(self) -> &'a Arena {
+        self.arena
+    }
+
+    pub fn span(self) -> Span {
+        self.arena.span(self.id)
+    }
+
+    pub fn node(self) -> Expr<'a> {
+        let get = |id| self.arena.get(id);
+        match self.arena[self.id] {
+            Node::Bool(b) => Expr::Bool(b),
+            Node::Var(v) => Expr::Var(v),
+            Node::Not(e) => Expr::Not(get(e)),
+            Node::BinOp { op, lhs, rhs } => Expr::BinOp {
+                op,
+                lhs: get(lhs),
+                rhs: get(rhs),
+            },
+        }
+    }
+
+    /// Copy the expression out into an arena of its own.
+    pub fn to_span_expr(self) -> SpanExpr {
+        SpanExpr::build(|arena| arena.copy(self))
+    }
+
+    /// Render the expression in the input syntax, with variables resolved
+    /// to `names`. The output parses back to the same tree.
+    pub fn display(self, names: &'a [Symbol]) -> Infix<'a> {
+        self.display_in(names, Dialect::Words)
+    }
+
+    pub fn display_in(self, names: &'a [Symbol], dialect: Dialect) -> Infix<'a> {
+        Infix {
+            expr: self,
+            names,
+            dialect,
+        }
+    }
+
+    /// Render the tree one node per line, with each node's span.
+    pub fn tree(self, names: &[Symbol]) -> String {
+        let mut out = String::new();
+        self.write_tree(names, &mut out, "", "");
+        out
+    }
+
+    fn write_tree(self, names: &[Symbol], out: &mut String, first: &str, rest: &str) {
+        let (label, children) = match self.node() {
+            Expr::Bool(b) => (format!("BOOL {}", b), vec![]),
+            Expr::Var(v) => (format!("VAR {}", names[v]), vec![]),
+            Expr::Not(e) => ("NOT".to_string(), vec![e]),
+            Expr::BinOp { op, lhs, rhs } => (op.to_string(), vec![lhs, rhs]),
+        };
+        out.push_str(&format!("{}{} @ {}\n", first, label, self.span()));
+
+        for (i, child) in children.iter().enumerate() {
+            if i + 1 == children.len() {
+                child.write_tree(
+                    names,
+                    out,
+                    &format!("{}└── ", rest),
+                    &format!("{}    ", rest),
+                );
+            } else {
+                child.write_tree(
+                    names,
+                    out,
+                    &format!("{}├── ", rest),
+                    &format!("{}│   ", rest),
+                );
+            }
+        }
+    }
+}
+
+/// Two expressions are equal when their trees and spans are, wherever their
+/// nodes are stored.
+impl PartialEq for ExprRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.span() == other.span() && self.node() == other.node()
+    }
+}
+
+/// The tree as an s-expression, with variables by number.
+impl fmt::Display for ExprRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.node() {
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::Var(v) => write!(f, "{}", v),
+            Expr::Not(e) => write!(f, "(NOT {})", e),
+            Expr::BinOp { op, lhs, rhs } => write!(f, "({} {} {})", op, lhs, rhs),
+        }
+    }
+}
+
+impl fmt::Debug for ExprRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} @ {}", self, self.span())
+    }
+}
+
+/// An expression, as the arena holding its nodes and the id of its root.
+#[derive(Clone)]
+pub struct SpanExpr {
+    pub arena: Arena,
+    pub root: ExprId,
+}
+
+impl SpanExpr {
+    pub fn new(arena: Arena, root: ExprId) -> Self {
+        Self { arena, root }
+    }
+
+    /// The expression whose root `build` pushes into a new arena.
+    pub fn build(build: impl FnOnce(&mut Arena) -> ExprId) -> Self {
+        let mut arena = Arena::new();
+        let root = build(&mut arena);
+        Self { arena, root }
+    }
+
+    pub fn expr(&self) -> ExprRef<'_> {
+        self.arena.get(self.root)
+    }
+
+    pub fn node(&self) -> Expr<'_> {
+        self.expr().node()
+    }
+
+    pub fn span(&self) -> Span {
+        self.expr().span()
+    }
+
+    pub fn display<'a>(&'a self, names: &'a [Symbol]) -> Infix<'a> {
+        self.expr().display(names)
+    }
+
+    pub fn display_in<'a>(&'a self, names: &'a [Symbol], dialect: Dialect) -> Infix<'a> {
+        self.expr().display_in(names, dialect)
+    }
+
+    pub fn tree(&self, names: &[Symbol]) -> String {
+        self.expr().tree(names)
+    }
+}
+
+impl PartialEq for SpanExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr() == other.expr()
+    }
+}
+
+impl fmt::Display for SpanExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.expr(), f)
+    }
+}
+
+impl fmt::Debug for SpanExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.expr(), f)
+    }
+}
+
+/// A spelling of the operators for printing expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// `NOT`, `AND`, `OR`, `XOR`
+    #[default]
+    Words,
+    /// `!`, `.`, `+`, `^`
+    Symbols,
+    /// `¬`, `∧`, `∨`, `⊕`
+    Unicode,
+}
+
+impl Dialect {
+    fn not(self) -> &'static str {
+        match self {
+            Dialect::Words => "NOT ",
+            Dialect::Symbols => "!",
+            Dialect::Unicode => "¬",
+        }
+    }
+
+    fn binop(self, op: BinOp) -> &'static str {
+        match (self, op) {
+            (Dialect::Words, BinOp::And) => "AND",
+            (Dialect::Words, BinOp::Or) => "OR",
+            (Dialect::Words, BinOp::Xor) => "XOR",
+            (Dialect::Symbols, BinOp::And) => ".",
+            (Dialect::Symbols, BinOp::Or) => "+",
+            (Dialect::Symbols, BinOp::Xor) => "^",
+            (Dialect::Unicode, BinOp::And) => "∧",
+            (Dialect::Unicode, BinOp::Or) => "∨",
+            (Dialect::Unicode, BinOp::Xor) => "⊕",
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "words" => Ok(Dialect::Words),
+            "symbols" => Ok(Dialect::Symbols),
+            "unicode" => Ok(Dialect::Unicode),
+            _ => Err(format!(
+                "unknown dialect '{}', expected words, symbols or unicode",
+                s
+            )),
+        }
+    }
+}
+
+pub struct Infix<'a> {
+    expr: ExprRef<'a>,
+    names: &'a [Symbol],
+    dialect: Dialect,
+}
+
+impl fmt::Display for Infix<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `NOT` and binary operators extend as far right as possible, so any
+        // compound expression that isn't last in its chain is parenthesised
+        let operand = |f: &mut fmt::Formatter<'_>, expr: ExprRef, group: bool| {
+            let expr = expr.display_in(self.names, self.dialect);
+            if group {
+                write!(f, "({})", expr)
+            } else {
+                write!(f, "{}", expr)
+            }
+        };
+
+        match self.expr.node() {
+            Expr::Bool(b) => write!(f, "{}", if b { 1 } else { 0 }),
+            Expr::Var(v) => write!(f, "{}", self.names[v]),
+            Expr::Not(e) => {
+                write!(f, "{}", self.dialect.not())?;
+                operand(f, e, matches!(e.node(), Expr::BinOp { .. }))
+            }
+            Expr::BinOp { op, lhs, rhs } => {
+                // Chains of one operator are written in a loop, since sums
+                // of thousands of products would overflow the stack
+                let (mut lhs, mut rhs) = (lhs, rhs);
+                loop {
+                    let lhs_group = matches!(lhs.node(), Expr::Not(_) | Expr::BinOp { .. });
+                    operand(f, lhs, lhs_group)?;
+                    write!(f, " {} ", self.dialect.binop(op))?;
+                    match rhs.node() {
+                        Expr::BinOp {
+                            op: next,
+                            lhs: next_lhs,
+                            rhs: next_rhs,
+                        } if next == op => (lhs, rhs) = (next_lhs, next_rhs),
+                        node => return operand(f, rhs, matches!(node, Expr::BinOp { .. })),
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub struct Parser<'input> {
     input: &'input str,
     lexer: Peekable<Lexer<'input>>,
-    variables: HashMap<&'input str, usize>,
+    variables: BTreeMap<Symbol, usize>,
     counter: usize,
+    /// The nodes of the expression being parsed.
+    arena: Arena,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Display)]
 pub enum SyntaxError {
+    #[display(fmt = "Expected {}, got {}", expected, got)]
     UnexpectedToken { expected: String, got: Token },
+    #[display(fmt = "Unexpected end of input")]
     UnexpectedEof(Token),
+    /// A call of a built-in function that doesn't exist or can't take its
+    /// arguments.
+    #[display(fmt = "{}", message)]
+    BadCall { message: String, span: Span },
+}
+
+impl SyntaxError {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::UnexpectedToken { got, .. } => got.span,
+            SyntaxError::UnexpectedEof(token) => token.span,
+            SyntaxError::BadCall { span, .. } => *span,
+        }
+    }
 }
 
 pub type ParseResult<T> = Result<T, SyntaxError>;
 
-#[derive(Debug, Display)]
+/// Why an equation's inputs can't be renamed.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// Two inputs would end up with the same name.
+    #[display(fmt = "'{}' and '{}' would both be called '{}'", _0, _1, _2)]
+    Collision(Symbol, Symbol, Symbol),
+}
+
+#[derive(Debug, Display, Clone)]
 #[display(
     fmt = "Equation:\ninputs = {:#?}\nlhs = {}\noutput = {}",
     inputs,
     lhs,
     output
 )]
-pub struct Equation<'input> {
-    pub inputs: Vec<&'input str>,
+pub struct Equation {
+    pub inputs: Vec<Symbol>,
     pub lhs: SpanExpr,
-    pub output: &'input str,
+    pub output: Symbol,
+    /// Conditions from `assume` statements, restricting the assignments a
+    /// table enumerates to those that make all of them true.
+    pub assumptions: Vec<SpanExpr>,
 }
 
-macro_rules! spanned {
-    ($span:expr, $node:expr) => {
-        Ok(Spanned {
-            span: $span.into(),
-            node: $node,
+impl Equation {
+    /// The equation over the remaining inputs that is true when some value
+    /// of `name` makes this one true.
+    pub fn exists(&self, name: &str) -> Self {
+        self.quantify(name, BinOp::Or)
+    }
+
+    /// The equation over the remaining inputs that is true when every value
+    /// of `name` makes this one true.
+    pub fn forall(&self, name: &str) -> Self {
+        self.quantify(name, BinOp::And)
+    }
+
+    fn quantify(&self, name: &str, op: BinOp) -> Self {
+        let mut equation = self.clone();
+        let var = match self.inputs.iter().position(|n| *n == name) {
+            Some(var) => var,
+            None => return equation,
+        };
+
+        // Only values of `name` allowed by the assumptions count, so they
+        // guard the expression, and the remaining assignments are those
+        // allowed for at least one value of it
+        let mut lhs = self.lhs.clone();
+        if let Some(mut assumption) = self.assumption() {
+            let span = assumption.span();
+            let mut guard = lhs.arena.copy(assumption.expr());
+            let join = match op {
+                BinOp::Or => BinOp::And,
+                _ => {
+                    guard = lhs.arena.push(span, Node::Not(guard));
+                    BinOp::Or
+                }
+            };
+            lhs.root = lhs.arena.push(
+                lhs.span(),
+                Node::BinOp {
+                    op: join,
+                    lhs: guard,
+                    rhs: lhs.root,
+                },
+            );
+            assumption.root = quantify(&mut assumption.arena, assumption.root, var, BinOp::Or);
+            equation.assumptions = vec![assumption];
+        }
+        lhs.root = quantify(&mut lhs.arena, lhs.root, var, op);
+        equation.lhs = lhs;
+        equation.inputs.remove(var);
+
+        equation
+    }
+
+    /// This equation with each input named in `bindings` replaced by the
+    /// expression of the equation bound to it, so that a circuit can be
+    /// built from its stages. The inputs of the result are numbered by first
+    /// appearance, and an input of a part is the same as any other input with
+    /// its name. A part's nodes take the span of the variable they replace,
+    /// and its assumptions are added to this equation's. Bindings of names
+    /// that aren't inputs do nothing.
+    pub fn compose(&self, bindings: &[(&str, &Equation)]) -> Self {
+        let mut inputs = vec![];
+        let compose = |expr: &SpanExpr, inputs: &mut Vec<Symbol>| {
+            SpanExpr::build(|arena| {
+                arena.substitute(expr.expr(), |arena, v, span| {
+                    let name = self.inputs[v];
+                    match bindings.iter().find(|(bound, _)| *bound == name) {
+                        Some((_, part)) => {
+                            rename(arena, part.lhs.expr(), &part.inputs, inputs, span)
+                        }
+                        None => arena.push(span, Node::Var(position(inputs, name))),
+                    }
+                })
+            })
+        };
+        let mut assumptions: Vec<SpanExpr> = self
+            .assumptions
+            .iter()
+            .map(|assumption| compose(assumption, &mut inputs))
+            .collect();
+        let lhs = compose(&self.lhs, &mut inputs);
+        for (_, part) in bindings
+            .iter()
+            .filter(|(name, _)| self.inputs.iter().any(|input| input == name))
+        {
+            for assumption in &part.assumptions {
+                let span = assumption.span();
+                assumptions.push(SpanExpr::build(|arena| {
+                    rename(arena, assumption.expr(), &part.inputs, &mut inputs, span)
+                }));
+            }
+        }
+
+        Equation {
+            inputs,
+            lhs,
+            output: self.output,
+            assumptions,
+        }
+    }
+
+    /// This equation with each input and the output renamed by `mapping`, and
+    /// any name not in it left as it is, so that equations from different
+    /// sources can share names before they're compared or composed. Inputs
+    /// keep their numbering, so the expression is unchanged.
+    #[cfg(feature = "std")]
+    pub fn rename(
+        &self,
+        mapping: &std::collections::HashMap<Symbol, Symbol>,
+    ) -> Result<Self, RenameError> {
+        let renamed = |name: Symbol| mapping.get(&name).copied().unwrap_or(name);
+        let inputs: Vec<Symbol> = self.inputs.iter().map(|&name| renamed(name)).collect();
+        for (i, &name) in inputs.iter().enumerate() {
+            if let Some(j) = inputs[..i].iter().position(|&other| other == name) {
+                return Err(RenameError::Collision(self.inputs[j], self.inputs[i], name));
+            }
+        }
+
+        Ok(Equation {
+            inputs,
+            output: renamed(self.output),
+            ..self.clone()
         })
+    }
+
+    /// All of the assumptions joined with `AND`, if there are any.
+    pub fn assumption(&self) -> Option<SpanExpr> {
+        let (first, rest) = self.assumptions.split_first()?;
+        let mut joined = first.clone();
+        for assumption in rest {
+            let rhs = joined.arena.copy(assumption.expr());
+            joined.root = joined.arena.push(
+                (joined.span().start..assumption.span().end).into(),
+                Node::BinOp {
+                    op: BinOp::And,
+                    lhs: joined.root,
+                    rhs,
+                },
+            );
+        }
+
+        Some(joined)
+    }
+
+    /// Render the equation back in the input syntax.
+    pub fn to_source(&self, dialect: Dialect) -> String {
+        let mut out = String::new();
+        for assumption in &self.assumptions {
+            out.push_str(&format!(
+                "assume {}; ",
+                assumption.display_in(&self.inputs, dialect)
+            ));
+        }
+        out.push_str(&format!(
+            "{} = {}",
+            self.lhs.display_in(&self.inputs, dialect),
+            self.output
+        ));
+
+        out
+    }
+}
+
+/// `id` with `var` set to `value`, pushing the nodes above it that change.
+/// If `var` is leaving the numbering, later variables move down to fill its
+/// place.
+fn restrict(arena: &mut Arena, id: ExprId, var: usize, value: bool, remove: bool) -> ExprId {
+    let node = match arena[id] {
+        Node::Var(v) if v == var => Node::Bool(value),
+        Node::Var(v) if remove && v > var => Node::Var(v - 1),
+        Node::Bool(_) | Node::Var(_) => return id,
+        Node::Not(e) => Node::Not(restrict(arena, e, var, value, remove)),
+        Node::BinOp { op, lhs, rhs } => Node::BinOp {
+            op,
+            lhs: restrict(arena, lhs, var, value, remove),
+            rhs: restrict(arena, rhs, var, value, remove),
+        },
+    };
+
+    arena.push(arena.span(id), node)
+}
+
+/// `expr` over `names` copied into `arena` with its variables renumbered by
+/// their position in `inputs`, where new ones are added, and every node
+/// given `span`.
+fn rename(
+    arena: &mut Arena,
+    expr: ExprRef,
+    names: &[Symbol],
+    inputs: &mut Vec<Symbol>,
+    span: Span,
+) -> ExprId {
+    let node = match expr.node() {
+        Expr::Var(v) => Node::Var(position(inputs, names[v])),
+        Expr::Bool(b) => Node::Bool(b),
+        Expr::Not(e) => Node::Not(rename(arena, e, names, inputs, span)),
+        Expr::BinOp { op, lhs, rhs } => Node::BinOp {
+            op,
+            lhs: rename(arena, lhs, names, inputs, span),
+            rhs: rename(arena, rhs, names, inputs, span),
+        },
     };
+
+    arena.push(span, node)
+}
+
+/// The position of `name` in `inputs`, adding it to the end if it's new.
+fn position(inputs: &mut Vec<Symbol>, name: Symbol) -> usize {
+    inputs.iter().position(|n| *n == name).unwrap_or_else(|| {
+        inputs.push(name);
+        inputs.len() - 1
+    })
+}
+
+/// Quantify `var` out of `id` by joining both of its values with `op`,
+/// `OR` for exists and `AND` for forall, and take it out of the numbering.
+fn quantify(arena: &mut Arena, id: ExprId, var: usize, op: BinOp) -> ExprId {
+    let span = arena.span(id);
+    quantify_in(arena, id, var, op, true, span)
+}
+
+fn quantify_in(
+    arena: &mut Arena,
+    id: ExprId,
+    var: usize,
+    op: BinOp,
+    remove: bool,
+    span: Span,
+) -> ExprId {
+    let lhs = restrict(arena, id, var, false, remove);
+    let rhs = restrict(arena, id, var, true, remove);
+
+    arena.push(span, Node::BinOp { op, lhs, rhs })
+}
+
+/// Keeps the numbering of variables across several parses, so that a name
+/// has the same index in every equation of a REPL session or file. Each
+/// equation's `inputs` are all of the variables seen so far, in the order
+/// they were first seen, so equations can be tabulated over the same inputs.
+#[derive(Debug, Clone, Default)]
+pub struct ParserSession {
+    variables: BTreeMap<Symbol, usize>,
+    counter: usize,
+}
+
+impl ParserSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse an equation, numbering its new variables after those already
+    /// seen. An equation that fails to parse leaves the numbering as it was.
+    pub fn parse_equation(&mut self, input: &str) -> ParseResult<Equation> {
+        let mut parser = Parser {
+            variables: self.variables.clone(),
+            counter: self.counter,
+            ..Parser::new(input)
+        };
+        let equation = parser.parse_equation()?;
+        self.variables = parser.variables;
+        self.counter = parser.counter;
+
+        Ok(equation)
+    }
+
+    /// The variables seen so far, by index.
+    pub fn inputs(&self) -> Vec<Symbol> {
+        let mut variables: Vec<(Symbol, usize)> = self
+            .variables
+            .iter()
+            .map(|(&name, &index)| (name, index))
+            .collect();
+        variables.sort_unstable_by_key(|var| var.1);
+
+        variables.into_iter().map(|var| var.0).collect()
+    }
 }
 
 impl<'input> Parser<'input> {
@@ -90,39 +767,91 @@ impl<'input> Parser<'input> {
         Self {
             input,
             lexer: Lexer::new(input).peekable(),
-            variables: HashMap::new(),
+            variables: BTreeMap::new(),
             counter: 0,
+            arena: Arena::new(),
         }
     }
 
     pub fn parse_equation(&mut self) -> ParseResult<Equation> {
+        let mut assumptions = vec![];
+        while self.peek() == TK::Assume {
+            let assumption = self.parse_assumption()?;
+            assumptions.push(self.finish(assumption));
+        }
         let lhs = self.parse_expr()?;
+        let lhs = self.finish(lhs);
         self.consume(TK::Equals)?;
-        let output = self.expect(TK::Var)?.text(self.input);
+        let output = Symbol::intern(self.expect(TK::Var)?.text(self.input));
 
         let mut variables = self
             .variables
             .clone()
             .into_iter()
-            .collect::<Vec<(&str, usize)>>();
+            .collect::<Vec<(Symbol, usize)>>();
         variables.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
         let inputs = variables
             .into_iter()
             .map(|var| var.0)
-            .collect::<Vec<&str>>();
+            .collect::<Vec<Symbol>>();
 
         Ok(Equation {
             inputs,
             lhs,
             output,
+            assumptions,
         })
     }
 
-    fn parse_expr(&mut self) -> ParseResult<SpanExpr> {
+    /// Move the nodes parsed so far out into the expression rooted at `root`.
+    fn finish(&mut self, root: ExprId) -> SpanExpr {
+        SpanExpr::new(mem::take(&mut self.arena), root)
+    }
+
+    fn node(&mut self, span: impl Into<Span>, node: Node) -> ParseResult<ExprId> {
+        Ok(self.arena.push(span.into(), node))
+    }
+
+    /// `assume expr;`, where `expr` can also compare two expressions with
+    /// `==` or `!=`.
+    fn parse_assumption(&mut self) -> ParseResult<ExprId> {
+        self.advance();
+        let lhs = self.parse_expr()?;
+        let negate = match self.peek() {
+            TK::EqualsEquals => true,
+            TK::NotEquals => false,
+            _ => {
+                self.consume(TK::Semicolon)?;
+                return Ok(lhs);
+            }
+        };
+        self.advance();
+        let rhs = self.parse_expr()?;
+        self.consume(TK::Semicolon)?;
+
+        // `A != B` is `A XOR B`, and `A == B` its negation
+        let span = self.arena.span(lhs).start..self.arena.span(rhs).end;
+        let xor = self.node(
+            span.clone(),
+            Node::BinOp {
+                op: BinOp::Xor,
+                lhs,
+                rhs,
+            },
+        )?;
+        if negate {
+            self.node(span, Node::Not(xor))
+        } else {
+            Ok(xor)
+        }
+    }
+
+    fn parse_expr(&mut self) -> ParseResult<ExprId> {
         let mut lhs = match self.peek() {
             t @ TK::True | t @ TK::False => self.parse_bool(t),
             TK::Var => self.parse_var(),
             TK::Not => self.parse_not(),
+            TK::Exists | TK::Forall => self.parse_quantifier(),
             TK::LParen => self.parse_group(),
 
             _ => {
@@ -137,7 +866,13 @@ impl<'input> Parser<'input> {
         loop {
             let op = match self.peek() {
                 op @ TK::And | op @ TK::Or | op @ TK::Xor => BinOp::from(op),
-                TK::RParen | TK::Equals | TK::Eof => break,
+                TK::RParen
+                | TK::Equals
+                | TK::EqualsEquals
+                | TK::NotEquals
+                | TK::Semicolon
+                | TK::Comma
+                | TK::Eof => break,
                 _ => {
                     let token = self.next()?;
                     return Err(SyntaxError::UnexpectedToken {
@@ -149,45 +884,105 @@ impl<'input> Parser<'input> {
             self.advance();
 
             let rhs = self.parse_expr()?;
-            lhs = Spanned {
-                span: (lhs.span.start..rhs.span.end).into(),
-                node: Expr::BinOp {
-                    op,
-                    lhs: Box::new(lhs),
-                    rhs: Box::new(rhs),
-                },
-            };
+            let span = self.arena.span(lhs).start..self.arena.span(rhs).end;
+            lhs = self.node(span, Node::BinOp { op, lhs, rhs })?;
         }
 
         Ok(lhs)
     }
 
-    fn parse_bool(&mut self, t: TK) -> ParseResult<SpanExpr> {
+    fn parse_bool(&mut self, t: TK) -> ParseResult<ExprId> {
         let token = self.next().unwrap();
-        spanned!(token.span, Expr::Bool(t == TK::True))
+        self.node(token.span, Node::Bool(t == TK::True))
     }
 
-    fn parse_var(&mut self) -> ParseResult<SpanExpr> {
+    fn parse_var(&mut self) -> ParseResult<ExprId> {
         let token = self.next().unwrap();
         let text = token.text(self.input);
+        if self.peek() == TK::LParen {
+            return self.parse_call(token);
+        }
         let index = self.insert_var(text);
 
-        spanned!(token.span, Expr::Var(index))
+        self.node(token.span, Node::Var(index))
     }
 
-    fn parse_not(&mut self) -> ParseResult<SpanExpr> {
+    /// `name(expr, ...)`, a call of one of the `stdlib` functions, which is
+    /// expanded in place.
+    fn parse_call(&mut self, name: Token) -> ParseResult<ExprId> {
+        self.advance();
+        let mut args = vec![self.parse_expr()?];
+        while self.peek() == TK::Comma {
+            self.advance();
+            args.push(self.parse_expr()?);
+        }
+        let rp_token = self.expect(TK::RParen)?;
+
+        let span = (name.span.start..rp_token.span.end).into();
+        stdlib::call(&mut self.arena, name.text(self.input), args, span)
+            .map_err(|message| SyntaxError::BadCall { message, span })
+    }
+
+    fn parse_not(&mut self) -> ParseResult<ExprId> {
         let not_token = self.next().unwrap();
-        let expr = Box::new(self.parse_expr()?);
+        let expr = self.parse_expr()?;
+        let span = not_token.span.start..self.arena.span(expr).end;
 
-        spanned!(not_token.span.start..expr.span.end, Expr::Not(expr))
+        self.node(span, Node::Not(expr))
     }
 
-    fn parse_group(&mut self) -> ParseResult<SpanExpr> {
+    /// `exists C. expr` and `forall C. expr`, which like `NOT` extend as far
+    /// right as possible. `C` is bound within `expr`, so unless it is also
+    /// used outside it, it isn't an input of the equation.
+    fn parse_quantifier(&mut self) -> ParseResult<ExprId> {
+        let token = self.next().unwrap();
+        let op = match token.kind {
+            TK::Exists => BinOp::Or,
+            _ => BinOp::And,
+        };
+        let name = Symbol::intern(self.expect(TK::Var)?.text(self.input));
+        let dot = self.next()?;
+        if dot.text(self.input) != "." {
+            return Err(SyntaxError::UnexpectedToken {
+                expected: ".".to_string(),
+                got: dot,
+            });
+        }
+
+        let free = self.variables.contains_key(&name);
+        let body = self.parse_expr()?;
+        let span = (token.span.start..self.arena.span(body).end).into();
+        let var = match self.variables.get(&name) {
+            Some(var) => *var,
+            // The body doesn't mention it, so there is nothing to quantify
+            None => {
+                let node = self.arena[body];
+                return self.node(span, node);
+            }
+        };
+
+        // The name was first seen in the body, so its number is given back
+        // for the next new variable
+        if !free {
+            self.variables.remove(&name);
+            for index in self.variables.values_mut() {
+                if *index > var {
+                    *index -= 1;
+                }
+            }
+            self.counter -= 1;
+        }
+
+        Ok(quantify_in(&mut self.arena, body, var, op, !free, span))
+    }
+
+    fn parse_group(&mut self) -> ParseResult<ExprId> {
         let lp_token = self.next().unwrap();
         let expr = self.parse_expr()?;
         let rp_token = self.expect(TK::RParen)?;
+        let node = self.arena[expr];
 
-        spanned!(lp_token.span.start..rp_token.span.end, expr.node)
+        self.node(lp_token.span.start..rp_token.span.end, node)
     }
 
     fn next(&mut self) -> ParseResult<Token> {
@@ -232,12 +1027,105 @@ impl<'input> Parser<'input> {
         }
     }
 
-    fn insert_var(&mut self, key: &'input str) -> usize {
-        let a = self.variables.entry(key).or_insert_with(|| {
-            self.counter += 1;
-            self.counter - 1
-        });
+    fn insert_var(&mut self, key: &str) -> usize {
+        let a = self
+            .variables
+            .entry(Symbol::intern(key))
+            .or_insert_with(|| {
+                self.counter += 1;
+                self.counter - 1
+            });
 
         *a
     }
 }
+
+#[test]
+fn test() {
+    let table = |source: &str| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let names: Vec<&str> = equation.inputs.iter().map(|n| n.as_str()).collect();
+        let names = names.join(",");
+        let mut vm = crate::vm::VM::new(crate::compiler::Compiler::new(equation).compile());
+        (names, vm.gen().outputs)
+    };
+
+    // A multiplexer, quantified over its select line
+    let mux = "(A AND NOT C) OR (B AND C)";
+    assert_eq!(
+        table(&format!("exists C. {} = Q", mux)),
+        ("A,B".to_string(), vec![false, true, true, true])
+    );
+    assert_eq!(
+        table(&format!("forall C. {} = Q", mux)),
+        ("A,B".to_string(), vec![false, false, false, true])
+    );
+    // `C` is free outside the quantifier, and bound inside it
+    assert_eq!(
+        table("(exists C. C AND D) OR C = Q"),
+        ("D,C".to_string(), vec![false, true, true, true])
+    );
+
+    // Assumptions drop the rows they don't allow
+    assert_eq!(
+        table("assume A != B; assume C; A OR B = Q"),
+        ("A,B,C".to_string(), vec![true, true])
+    );
+
+    // Indices carry over from one equation to the next
+    let mut session = ParserSession::new();
+    session.parse_equation("A AND B = P").unwrap();
+    assert!(session.parse_equation("C OR = Q").is_err());
+    let equation = session.parse_equation("C OR A = Q").unwrap();
+    assert_eq!(equation.inputs, ["A", "B", "C"]);
+    assert_eq!(equation.lhs.to_string(), "(OR 2 0)");
+    assert_eq!(session.inputs(), ["A", "B", "C"]);
+
+    let source = format!("{} = Q", mux);
+    let equation = Parser::new(&source).parse_equation().unwrap();
+    let projected = equation.exists("C");
+    assert_eq!(projected.inputs, ["A", "B"]);
+    assert_eq!(
+        projected.to_source(Dialect::Words),
+        "((A AND NOT 0) OR (B AND 0)) OR (A AND NOT 1) OR (B AND 1) = Q"
+    );
+
+    // Two stages composed into a third, sharing the input A
+    let parse = |source| Parser::new(source).parse_equation().unwrap();
+    let (sum, half, carry) = (
+        parse("X XOR C = S"),
+        parse("A XOR B = X"),
+        parse("assume A OR D; D = C"),
+    );
+    let composed = sum.compose(&[("X", &half), ("C", &carry), ("Z", &half)]);
+    assert_eq!(composed.inputs, ["A", "B", "D"]);
+    assert_eq!(
+        composed.to_source(Dialect::Words),
+        "assume A OR D; (A XOR B) XOR D = S"
+    );
+    assert_eq!(composed.lhs.span(), sum.lhs.span());
+    assert_eq!(sum.compose(&[]).to_source(Dialect::Words), "X XOR C = S");
+
+    #[cfg(feature = "std")]
+    {
+        let mapping = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|&(from, to)| (from.into(), to.into()))
+                .collect()
+        };
+        let renamed = half
+            .rename(&mapping(&[("A", "P"), ("X", "Y"), ("Q", "Out")]))
+            .unwrap();
+        assert_eq!(renamed.to_source(Dialect::Words), "P XOR B = Y");
+        assert_eq!(
+            half.rename(&mapping(&[("A", "B")])).unwrap_err(),
+            RenameError::Collision("A".into(), "B".into(), "B".into())
+        );
+        let mapping = mapping(&[("A", "B"), ("B", "A")]);
+        assert_eq!(
+            half.rename(&mapping).unwrap().to_source(Dialect::Words),
+            "B XOR A = X"
+        );
+    }
+}
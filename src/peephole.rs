@@ -0,0 +1,152 @@
+//! Peephole optimisation of compiled ops, rewriting short runs of ops that
+//! are obviously wasteful without looking at the expression they came from.
+
+use alloc::{vec, vec::Vec};
+
+use crate::vm::Op;
+
+/// Apply the rewrites below until none apply. Ops are added to the output
+/// one at a time and its tail rewritten after each, so a rewrite that
+/// exposes another is caught straight away:
+///
+/// - `NOT NOT` is removed
+/// - `PUSH c NOT` becomes `PUSH !c`
+/// - an op with a constant operand becomes its other operand when the
+///   constant is the identity, that operand and `NOT` for `XOR 1`, and the
+///   constant itself for `AND 0` and `OR 1`
+/// - `LOAD i LOAD i op` becomes `LOAD i`, or `PUSH false` for `XOR`
+///
+/// Ops with jumps are returned as they are, since moving ops would break
+/// their targets.
+pub fn optimise(ops: &[Op]) -> Vec<Op> {
+    if ops
+        .iter()
+        .any(|op| matches!(op, Op::JumpIfFalse(_) | Op::JumpIfTrue(_)))
+    {
+        return ops.to_vec();
+    }
+
+    let mut out: Vec<Op> = vec![];
+    // Where the value each op in `out` leaves on the stack starts, so that
+    // a constant's other operand can be dropped along with it
+    let mut starts: Vec<usize> = vec![];
+
+    for &op in ops {
+        let start = match op {
+            Op::Push(_) | Op::Load(_) => out.len(),
+            Op::Not => starts[out.len() - 1],
+            Op::And | Op::Or | Op::Xor => {
+                let lhs = starts[out.len() - 1];
+                starts[lhs - 1]
+            }
+            Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => unreachable!(),
+        };
+        out.push(op);
+        starts.push(start);
+
+        while rewrite(&mut out, &mut starts) {}
+    }
+
+    out
+}
+
+/// Rewrite the tail of `out` once, returning whether anything changed.
+fn rewrite(out: &mut Vec<Op>, starts: &mut Vec<usize>) -> bool {
+    let len = out.len();
+    match out.as_slice() {
+        [.., Op::Not, Op::Not] => {
+            out.truncate(len - 2);
+            starts.truncate(len - 2);
+        }
+        [.., Op::Push(c), Op::Not] => {
+            out[len - 2] = Op::Push(!*c);
+            out.pop();
+            starts.pop();
+        }
+        [.., Op::Load(a), Op::Load(b), op @ (Op::And | Op::Or | Op::Xor)] if a == b => {
+            out[len - 3] = match op {
+                Op::Xor => Op::Push(false),
+                _ => Op::Load(*a),
+            };
+            out.truncate(len - 2);
+            starts.truncate(len - 2);
+        }
+        [.., op @ (Op::And | Op::Or | Op::Xor)] => {
+            let op = *op;
+            let lhs = starts[len - 2];
+            let rhs = starts[lhs - 1];
+            // Every op's operands commute, so it doesn't matter which side
+            // the constant is on
+            let (c, other) = match (out[lhs], out[rhs]) {
+                (Op::Push(c), _) if lhs == len - 2 => (c, rhs..lhs),
+                (_, Op::Push(c)) if rhs == lhs - 1 => (c, lhs..len - 1),
+                _ => return false,
+            };
+
+            let mut ops: Vec<Op> = out[other.clone()].to_vec();
+            let mut op_starts: Vec<usize> = starts[other.clone()]
+                .iter()
+                .map(|s| s - other.start + rhs)
+                .collect();
+            match (op, c) {
+                (Op::And, true) | (Op::Or | Op::Xor, false) => {}
+                (Op::Xor, true) => {
+                    ops.push(Op::Not);
+                    op_starts.push(rhs);
+                }
+                // `AND 0` and `OR 1` don't depend on the other operand
+                _ => {
+                    ops = vec![Op::Push(c)];
+                    op_starts = vec![rhs];
+                }
+            }
+            out.truncate(rhs);
+            starts.truncate(rhs);
+            out.extend(ops);
+            starts.extend(op_starts);
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let compile = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        Compiler::new(equation).compile_unoptimised()
+    };
+
+    for (source, expected) in [
+        ("NOT NOT A = Q", "0000 LOAD A\n"),
+        ("NOT (0 XOR 1) = Q", "0000 PUSH false\n"),
+        ("(A OR B) AND 1 = Q", "0000 LOAD B\n0001 LOAD A\n0002 OR\n"),
+        ("(NOT A) OR 1 = Q", "0000 PUSH true\n"),
+        (
+            "B AND (A XOR 1) = Q",
+            "0000 LOAD A\n0001 NOT\n0002 LOAD B\n0003 AND\n",
+        ),
+        ("(A AND A) XOR (B XOR B) = Q", "0000 LOAD A\n"),
+    ] {
+        let mut equation = compile(source);
+        let unoptimised = equation.lhs.clone();
+        equation.lhs = optimise(&unoptimised);
+        assert_eq!(equation.disassemble(), expected, "{}", source);
+
+        let names = equation.inputs.len();
+        let mut optimised = VM::new(equation);
+        for row in 0..1usize << names {
+            let inputs: Vec<bool> = (0..names).map(|i| row >> i & 1 == 1).collect();
+            let mut stack = vec![];
+            assert_eq!(
+                optimised.eval(&inputs),
+                crate::vm::execute(&mut stack, &unoptimised, &inputs, |_, _| {}),
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Proofs that two expressions are equal, found by searching for a chain of
+//! laws leading from one to the other.
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    parser::{Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr},
+    simplify::{self, Applied, Law, Step},
+};
+
+/// Every rewrite that applies at the root of `id`. Unlike simplification
+/// these include laws that don't make the expression smaller, applied in
+/// both directions. Results take the root's span, apart from
+/// simplifications that keep an operand's.
+fn rewrites(arena: &mut Arena, id: ExprId) -> Vec<(Law, ExprId)> {
+    let span = arena.span(id);
+    let not = |arena: &mut Arena, e| arena.push(span, Node::Not(e));
+    let binop = |arena: &mut Arena, op, lhs, rhs| arena.push(span, Node::BinOp { op, lhs, rhs });
+    let dual = |op| match op {
+        BinOp::And => Some(BinOp::Or),
+        BinOp::Or => Some(BinOp::And),
+        BinOp::Xor => None,
+    };
+
+    let mut out: Vec<(Law, Node)> = vec![];
+    // Double negation backwards, which De Morgan needs to apply to `A OR B`
+    if let Node::Var(_) | Node::BinOp { .. } = arena[id] {
+        out.push((Law::DoubleNegation, Node::Not(not(arena, id))));
+    }
+    match arena[id] {
+        Node::Bool(_) | Node::Var(_) => {}
+        Node::Not(e) => {
+            if let Node::BinOp { op, lhs, rhs } = arena[e] {
+                if let Some(dual) = dual(op) {
+                    let (lhs, rhs) = (not(arena, lhs), not(arena, rhs));
+                    out.push((Law::DeMorgan, Node::BinOp { op: dual, lhs, rhs }));
+                }
+            }
+        }
+        Node::BinOp { op, lhs, rhs } => {
+            out.push((
+                Law::Commutativity,
+                Node::BinOp {
+                    op,
+                    lhs: rhs,
+                    rhs: lhs,
+                },
+            ));
+
+            // Regroup `(A op B) op C` as `A op (B op C)`, and back
+            if let Node::BinOp {
+                op: inner,
+                lhs: a,
+                rhs: b,
+            } = arena[lhs]
+            {
+                if inner == op {
+                    let right = binop(arena, op, b, rhs);
+                    out.push((
+                        Law::Associativity,
+                        Node::BinOp {
+                            op,
+                            lhs: a,
+                            rhs: right,
+                        },
+                    ));
+                }
+            }
+            if let Node::BinOp {
+                op: inner,
+                lhs: b,
+                rhs: c,
+            } = arena[rhs]
+            {
+                if inner == op {
+                    let left = binop(arena, op, lhs, b);
+                    out.push((
+                        Law::Associativity,
+                        Node::BinOp {
+                            op,
+                            lhs: left,
+                            rhs: c,
+                        },
+                    ));
+                }
+                // Distribute `A op (B dual C)`
+                if Some(inner) == dual(op) {
+                    let left = binop(arena, op, lhs, b);
+                    let right = binop(arena, op, lhs, c);
+                    out.push((
+                        Law::Distributivity,
+                        Node::BinOp {
+                            op: inner,
+                            lhs: left,
+                            rhs: right,
+                        },
+                    ));
+                }
+            }
+
+            // De Morgan backwards, from `(NOT A) op (NOT B)`
+            if let (Node::Not(a), Node::Not(b), Some(dual)) = (arena[lhs], arena[rhs], dual(op)) {
+                out.push((Law::DeMorgan, Node::Not(binop(arena, dual, a, b))));
+            }
+
+            // Factor `(A op B) dual (A op C)` into `A op (B dual C)`
+            if let (
+                Node::BinOp {
+                    op: l_op,
+                    lhs: a,
+                    rhs: b,
+                },
+                Node::BinOp {
+                    op: r_op,
+                    lhs: a2,
+                    rhs: c,
+                },
+            ) = (arena[lhs], arena[rhs])
+            {
+                if l_op == r_op
+                    && Some(l_op) == dual(op)
+                    && simplify::same(arena.get(a), arena.get(a2))
+                {
+                    let inner = binop(arena, op, b, c);
+                    out.push((
+                        Law::Distributivity,
+                        Node::BinOp {
+                            op: l_op,
+                            lhs: a,
+                            rhs: inner,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut rewrites: Vec<(Law, ExprId)> = simplify::rewrite(arena, id).into_iter().collect();
+    for (law, node) in out {
+        rewrites.push((law, arena.push(span, node)));
+    }
+
+    rewrites
+}
+
+/// Every expression one law away from `id`, anywhere in the tree. Only the
+/// nodes above each rewritten one are pushed again, so the neighbours share
+/// the rest of the tree with `id` and each other.
+fn neighbours(arena: &mut Arena, id: ExprId) -> Vec<Applied> {
+    let span = arena.span(id);
+    let mut out: Vec<Applied> = rewrites(arena, id)
+        .into_iter()
+        .map(|(law, after)| Applied {
+            law,
+            before: id,
+            after,
+            result: after,
+        })
+        .collect();
+    match arena[id] {
+        Node::Bool(_) | Node::Var(_) => {}
+        Node::Not(e) => {
+            for step in neighbours(arena, e) {
+                let result = arena.push(span, Node::Not(step.result));
+                out.push(Applied { result, ..step });
+            }
+        }
+        Node::BinOp { op, lhs, rhs } => {
+            for step in neighbours(arena, lhs) {
+                let node = Node::BinOp {
+                    op,
+                    lhs: step.result,
+                    rhs,
+                };
+                let result = arena.push(span, node);
+                out.push(Applied { result, ..step });
+            }
+            for step in neighbours(arena, rhs) {
+                let node = Node::BinOp {
+                    op,
+                    lhs,
+                    rhs: step.result,
+                };
+                let result = arena.push(span, node);
+                out.push(Applied { result, ..step });
+            }
+        }
+    }
+
+    out
+}
+
+fn size(expr: ExprRef) -> usize {
+    match expr.node() {
+        Expr::Bool(_) | Expr::Var(_) => 1,
+        Expr::Not(e) => 1 + size(e),
+        Expr::BinOp { lhs, rhs, .. } => 1 + size(lhs) + size(rhs),
+    }
+}
+
+/// The expressions reached from one side, each with the step that reached
+/// it from its parent. Both sides keep their expressions in one arena.
+struct Search {
+    seen: BTreeMap<String, Option<(String, ExprId, Applied)>>,
+    queue: VecDeque<ExprId>,
+}
+
+impl Search {
+    fn new(arena: &Arena, start: ExprId) -> Self {
+        let mut seen = BTreeMap::new();
+        seen.insert(arena.get(start).to_string(), None);
+        Self {
+            seen,
+            queue: VecDeque::from([start]),
+        }
+    }
+
+    /// The steps leading from the start to `key`, each with the expression
+    /// it was applied to.
+    fn path(&self, key: &str) -> Vec<(ExprId, Applied)> {
+        let mut steps = vec![];
+        let mut key = key.to_string();
+        while let Some(Some((parent, expr, step))) = self.seen.get(&key) {
+            steps.push((*expr, *step));
+            key = parent.clone();
+        }
+        steps.reverse();
+
+        steps
+    }
+}
+
+/// Search for a chain of laws turning `left` into `right`, both over the
+/// same variables, exploring at most `limit` expressions from each side.
+/// Expressions are never allowed to grow past twice the larger of the two,
+/// which keeps distributivity from running away.
+pub fn prove(left: &SpanExpr, right: &SpanExpr, limit: usize) -> Option<Vec<Step>> {
+    let max_size = 2 * size(left.expr()).max(size(right.expr()));
+    let mut arena = Arena::new();
+    let starts = [left, right].map(|side| arena.copy(side.expr()));
+    let mut sides = starts.map(|start| Search::new(&arena, start));
+    let mut explored = 0;
+
+    if simplify::same(left.expr(), right.expr()) {
+        return Some(vec![]);
+    }
+
+    let meet = 'search: loop {
+        if explored >= limit {
+            return None;
+        }
+
+        // Expand whichever side has less left to explore. Once either side
+        // runs out, everything it can reach has been seen without meeting
+        let side = if sides[0].queue.len() <= sides[1].queue.len() {
+            0
+        } else {
+            1
+        };
+        let expr = sides[side].queue.pop_front()?;
+        explored += 1;
+
+        let parent = arena.get(expr).to_string();
+        for step in neighbours(&mut arena, expr) {
+            let result = arena.get(step.result);
+            if size(result) > max_size {
+                continue;
+            }
+            let key = result.to_string();
+            if sides[side].seen.contains_key(&key) {
+                continue;
+            }
+            let found = sides[1 - side].seen.contains_key(&key);
+            sides[side].queue.push_back(step.result);
+            sides[side]
+                .seen
+                .insert(key.clone(), Some((parent.clone(), expr, step)));
+            if found {
+                break 'search key;
+            }
+        }
+    };
+
+    // The right side's steps lead away from `right`, so walk them backwards
+    let mut steps: Vec<Step> = sides[0]
+        .path(&meet)
+        .into_iter()
+        .map(|(_, step)| step.to_step(&arena))
+        .collect();
+    for (parent, step) in sides[1].path(&meet).into_iter().rev() {
+        let backwards = Applied {
+            law: step.law,
+            before: step.after,
+            after: step.before,
+            result: parent,
+        };
+        steps.push(backwards.to_step(&arena));
+    }
+
+    Some(steps)
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let left = Parser::new("NOT (A AND B) = Q").parse_equation().unwrap();
+    let right = Parser::new("(NOT B) OR (NOT A) = Q")
+        .parse_equation()
+        .unwrap();
+    // Parsed separately, `B` and `A` are numbered differently in `right`
+    let right_lhs = crate::equiv::remap(&right.lhs, &right.inputs, &left.inputs);
+
+    let steps = prove(&left.lhs, &right_lhs, 1000).unwrap();
+    let laws: Vec<Law> = steps.iter().map(|s| s.law).collect();
+    assert_eq!(laws, [Law::DeMorgan, Law::Commutativity]);
+    assert!(simplify::same(
+        steps.last().unwrap().result.expr(),
+        right_lhs.expr()
+    ));
+
+    // Every step has to keep the truth table the same
+    let left = Parser::new("A AND (B OR NOT C) = Q")
+        .parse_equation()
+        .unwrap();
+    let right = Parser::new("NOT ((NOT A) OR (C AND NOT B)) = Q")
+        .parse_equation()
+        .unwrap();
+    let right_lhs = crate::equiv::remap(&right.lhs, &right.inputs, &left.inputs);
+    let steps = prove(&left.lhs, &right_lhs, 5000).unwrap();
+    for row in 0..8 {
+        let inputs = [row & 4 != 0, row & 2 != 0, row & 1 != 0];
+        let expected = crate::interp::eval(left.lhs.expr(), &inputs);
+        for step in &steps {
+            assert_eq!(crate::interp::eval(step.result.expr(), &inputs), expected);
+        }
+    }
+
+    let other = Parser::new("A OR B = Q").parse_equation().unwrap();
+    assert!(prove(&left.lhs, &other.lhs, 200).is_none());
+}
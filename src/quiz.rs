@@ -0,0 +1,200 @@
+//! `booltable quiz`, which alternates between two kinds of question about
+//! random functions: writing an expression for a truth table, checked for
+//! equivalence rather than against one answer, and reading a few rows of a
+//! table off an expression.
+
+use std::io::{self, Write};
+
+use booltable::{
+    compiler::Compiler,
+    equiv,
+    export::{self, Format},
+    parser::{Equation, Parser},
+    vm::{TruthTable, VM},
+};
+
+use crate::{cli::Args, config::Config};
+
+/// How many rows of an expression's table are asked about.
+const ROWS_ASKED: usize = 4;
+
+struct Score {
+    right: usize,
+    asked: usize,
+}
+
+impl Score {
+    fn mark(&mut self, right: bool) {
+        self.asked += 1;
+        if right {
+            self.right += 1;
+            println!("Correct!");
+        }
+    }
+}
+
+pub fn run(args: &Args, config: &Config, seed: u64) {
+    println!("Answer each question, or enter q to stop.");
+    let mut score = Score { right: 0, asked: 0 };
+
+    for round in 0u64.. {
+        let source = crate::random_source(args, config, seed.wrapping_add(round));
+        let equation = crate::parse(config, &source);
+        let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+
+        println!();
+        let answered = if round % 2 == 0 {
+            write_expression(config, &equation, &table, &mut score)
+        } else {
+            read_rows(
+                config,
+                &source,
+                &table,
+                seed.wrapping_add(round),
+                &mut score,
+            )
+        };
+        if answered.is_none() {
+            break;
+        }
+    }
+
+    println!("\nScore: {}/{}", score.right, score.asked);
+}
+
+/// Read an answer, or `None` at the end of input or if the user quits.
+fn prompt(text: &str) -> Option<String> {
+    print!("{}", text);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        return None;
+    }
+    match input.trim() {
+        "q" | ":q" | ":quit" => None,
+        answer => Some(answer.to_string()),
+    }
+}
+
+/// The answer as an equation, giving it the output `Q` if it has none.
+fn as_equation(answer: &str) -> String {
+    if answer.contains('=') {
+        answer.to_string()
+    } else {
+        format!("{} = Q", answer)
+    }
+}
+
+fn write_expression(
+    config: &Config,
+    equation: &Equation,
+    table: &TruthTable,
+    score: &mut Score,
+) -> Option<()> {
+    print!("{}", export::render(table, Format::Markdown, &config.style));
+    loop {
+        let answer = as_equation(&prompt("Write an expression for this table: ")?);
+        let mut parser = Parser::new(&answer);
+        let attempt = match parser.parse_equation() {
+            Ok(attempt) => attempt,
+            Err(err) => {
+                crate::report(config, &answer, &err);
+                continue;
+            }
+        };
+
+        let result = equiv::check(equation, &attempt);
+        score.mark(result.is_ok());
+        if let Err(counterexample) = result {
+            let assignment: Vec<String> = counterexample
+                .assignment
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, *value as u8))
+                .collect();
+            println!(
+                "Not quite: for {} the table has {} but yours gives {}",
+                assignment.join(", "),
+                counterexample.left as u8,
+                counterexample.right as u8
+            );
+            println!("One answer is {}", equation.to_source(config.dialect));
+        }
+        return Some(());
+    }
+}
+
+fn read_rows(
+    config: &Config,
+    source: &str,
+    table: &TruthTable,
+    seed: u64,
+    score: &mut Score,
+) -> Option<()> {
+    println!("{}", source);
+    // A random half of the rows, of which the first few are asked
+    let chosen = chosen_rows(table.len(), seed);
+    for index in chosen.into_iter().take(ROWS_ASKED) {
+        let row = table.row(index).unwrap();
+        let assignment: Vec<String> = table
+            .input_names
+            .iter()
+            .zip(row.assignment)
+            .map(|(name, value)| format!("{}={}", name, *value as u8))
+            .collect();
+
+        let expected = row.output;
+        loop {
+            let answer = prompt(&format!("{} gives Q = ", assignment.join(", ")))?;
+            match bit(config, &answer) {
+                Some(value) => {
+                    score.mark(value == expected);
+                    if value != expected {
+                        println!("No, it's {}", expected as u8);
+                    }
+                    break;
+                }
+                None => eprintln!("Error: answer 0 or 1"),
+            }
+        }
+    }
+
+    Some(())
+}
+
+/// The rows to ask about, a random half of the table's `rows` in order.
+fn chosen_rows(rows: usize, seed: u64) -> Vec<usize> {
+    let chosen = booltable::verify::random(rows, seed, true);
+    let mut indices: Vec<usize> = (0..rows).filter(|i| chosen[*i]).collect();
+    if indices.is_empty() {
+        indices.extend(0..rows);
+    }
+
+    indices
+}
+
+/// A row's output as answered, in `0` and `1` or the configured symbols.
+fn bit(config: &Config, answer: &str) -> Option<bool> {
+    match answer {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ if answer == config.style.true_symbol => Some(true),
+        _ if answer == config.style.false_symbol => Some(false),
+        _ => None,
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(as_equation("A AND B"), "A AND B = Q");
+    assert_eq!(as_equation("A AND B = Out"), "A AND B = Out");
+
+    let config = Config::default();
+    assert_eq!(bit(&config, "1"), Some(true));
+    assert_eq!(bit(&config, "0"), Some(false));
+    assert_eq!(bit(&config, "yes"), None);
+
+    let rows = chosen_rows(8, 3);
+    assert_eq!(rows.len(), 4);
+    assert!(rows.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(chosen_rows(1, 3), [0]);
+}
@@ -0,0 +1,243 @@
+//! A register-based form of compiled code. Every intermediate value gets a
+//! numbered slot instead of a place on a stack, so a value computed once can
+//! be read by every op that needs it: common subexpressions are shared, and
+//! so is anything several outputs have in common.
+
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use derive_more::Display;
+
+use crate::{compiler::Equation, vm::Op};
+
+pub type Slot = usize;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    #[display(fmt = "r{} = {}", dst, "*value as u8")]
+    Const { dst: Slot, value: bool },
+    #[display(fmt = "r{} = LOAD {}", dst, input)]
+    Load { dst: Slot, input: usize },
+    #[display(fmt = "r{} = NOT r{}", dst, src)]
+    Not { dst: Slot, src: Slot },
+    #[display(fmt = "r{} = AND r{} r{}", dst, lhs, rhs)]
+    And { dst: Slot, lhs: Slot, rhs: Slot },
+    #[display(fmt = "r{} = OR r{} r{}", dst, lhs, rhs)]
+    Or { dst: Slot, lhs: Slot, rhs: Slot },
+    #[display(fmt = "r{} = XOR r{} r{}", dst, lhs, rhs)]
+    Xor { dst: Slot, lhs: Slot, rhs: Slot },
+}
+
+/// A value before slots are allocated: the kind of op and the numbers of
+/// the values it reads, in the order `(kind, a, b)` so that identical values
+/// compare equal.
+type Key = (u8, usize, usize);
+
+const CONST: u8 = 0;
+const LOAD: u8 = 1;
+const NOT: u8 = 2;
+const AND: u8 = 3;
+const OR: u8 = 4;
+const XOR: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub instrs: Vec<Instr>,
+    /// The slot holding each output once every instruction has run.
+    pub outputs: Vec<Slot>,
+    /// How many slots running the program needs.
+    pub slots: usize,
+}
+
+impl Program {
+    /// Lower several stack programs into one, with an output for each.
+    /// Values that are computed more than once, within one program or
+    /// across them, are computed once and shared.
+    pub fn lower(programs: &[&[Op]]) -> Self {
+        let mut values: Vec<Key> = vec![];
+        let mut numbers: BTreeMap<Key, usize> = BTreeMap::new();
+        let mut value = |key: Key| {
+            *numbers.entry(key).or_insert_with(|| {
+                values.push(key);
+                values.len() - 1
+            })
+        };
+
+        let mut roots = vec![];
+        for ops in programs {
+            let mut stack = vec![];
+            for op in *ops {
+                let key = match op {
+                    Op::Push(b) => (CONST, *b as usize, 0),
+                    Op::Load(i) => (LOAD, *i, 0),
+                    Op::Not => (NOT, stack.pop().unwrap(), 0),
+                    Op::And | Op::Or | Op::Xor => {
+                        let lhs = stack.pop().unwrap();
+                        let rhs = stack.pop().unwrap();
+                        let kind = match op {
+                            Op::And => AND,
+                            Op::Or => OR,
+                            _ => XOR,
+                        };
+                        // All three commute, so order operands canonically
+                        (kind, lhs.min(rhs), lhs.max(rhs))
+                    }
+                    // Every op is run, so jumps have nothing to skip
+                    Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => continue,
+                };
+                stack.push(value(key));
+            }
+            roots.push(stack.pop().unwrap());
+        }
+
+        allocate(&values, &roots)
+    }
+
+    /// The program computing an equation's output followed by each of its
+    /// assumptions.
+    pub fn from_equation(equation: &Equation) -> Self {
+        let programs: Vec<&[Op]> = [&equation.lhs]
+            .into_iter()
+            .chain(&equation.assumptions)
+            .map(Vec::as_slice)
+            .collect();
+
+        Self::lower(&programs)
+    }
+
+    /// Run every instruction over `inputs`, leaving the outputs in the
+    /// slots named by `outputs`. `scratch` is resized to fit, so it can be
+    /// reused from one row to the next.
+    pub fn run(&self, inputs: &[bool], scratch: &mut Vec<bool>) {
+        scratch.resize(self.slots, false);
+        for instr in &self.instrs {
+            match *instr {
+                Instr::Const { dst, value } => scratch[dst] = value,
+                Instr::Load { dst, input } => scratch[dst] = inputs[input],
+                Instr::Not { dst, src } => scratch[dst] = !scratch[src],
+                Instr::And { dst, lhs, rhs } => scratch[dst] = scratch[lhs] && scratch[rhs],
+                Instr::Or { dst, lhs, rhs } => scratch[dst] = scratch[lhs] || scratch[rhs],
+                Instr::Xor { dst, lhs, rhs } => scratch[dst] = scratch[lhs] ^ scratch[rhs],
+            }
+        }
+    }
+
+    /// The value of each output for `inputs`.
+    pub fn eval(&self, inputs: &[bool]) -> Vec<bool> {
+        let mut scratch = vec![];
+        self.run(inputs, &mut scratch);
+        self.outputs.iter().map(|slot| scratch[*slot]).collect()
+    }
+
+    /// List the instructions one per line, followed by the output slots.
+    pub fn disassemble(&self) -> String {
+        let mut out = String::new();
+        for (i, instr) in self.instrs.iter().enumerate() {
+            writeln!(out, "{:04} {}", i, instr).unwrap();
+        }
+        let outputs: Vec<_> = self
+            .outputs
+            .iter()
+            .map(|slot| format!("r{}", slot))
+            .collect();
+        writeln!(out, "outputs: {}", outputs.join(", ")).unwrap();
+
+        out
+    }
+}
+
+/// Give each value a slot, reusing the slots of values that won't be read
+/// again, so that the scratch space is as small as a linear scan allows.
+/// Outputs are read after the last instruction, so theirs are never reused.
+fn allocate(values: &[Key], roots: &[usize]) -> Program {
+    let operands = |(kind, a, b): Key| -> Vec<usize> {
+        match kind {
+            CONST | LOAD => vec![],
+            NOT => vec![a],
+            _ => vec![a, b],
+        }
+    };
+    let mut last_use = vec![0; values.len()];
+    for (i, key) in values.iter().enumerate() {
+        for operand in operands(*key) {
+            last_use[operand] = i;
+        }
+    }
+    for root in roots {
+        last_use[*root] = usize::MAX;
+    }
+
+    let mut slot_of = vec![0; values.len()];
+    let mut free: Vec<Slot> = vec![];
+    let mut slots = 0;
+    let mut instrs = vec![];
+    for (i, key) in values.iter().enumerate() {
+        // An instruction reads its operands before writing, so it can write
+        // over one that this is the last read of
+        let mut operands = operands(*key);
+        operands.dedup();
+        for operand in operands {
+            if last_use[operand] == i {
+                free.push(slot_of[operand]);
+            }
+        }
+        let dst = free.pop().unwrap_or_else(|| {
+            slots += 1;
+            slots - 1
+        });
+        slot_of[i] = dst;
+
+        let (kind, a, b) = *key;
+        instrs.push(match kind {
+            CONST => Instr::Const { dst, value: a == 1 },
+            LOAD => Instr::Load { dst, input: a },
+            NOT => Instr::Not {
+                dst,
+                src: slot_of[a],
+            },
+            AND => Instr::And {
+                dst,
+                lhs: slot_of[a],
+                rhs: slot_of[b],
+            },
+            OR => Instr::Or {
+                dst,
+                lhs: slot_of[a],
+                rhs: slot_of[b],
+            },
+            _ => Instr::Xor {
+                dst,
+                lhs: slot_of[a],
+                rhs: slot_of[b],
+            },
+        });
+    }
+
+    Program {
+        instrs,
+        outputs: roots.iter().map(|root| slot_of[*root]).collect(),
+        slots,
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let source = "assume (A AND B) OR C; ((A AND B) XOR (B AND A)) OR ((A AND B) OR C) = Q";
+    let equation = Compiler::new(Parser::new(source).parse_equation().unwrap()).compile();
+    let program = Program::from_equation(&equation);
+    // `A AND B` and the whole assumption are computed once
+    assert_eq!(
+        program.disassemble(),
+        "0000 r0 = LOAD 2\n0001 r1 = LOAD 1\n0002 r2 = LOAD 0\n0003 r2 = AND r1 r2\n\
+         0004 r0 = OR r0 r2\n0005 r2 = XOR r2 r2\n0006 r2 = OR r0 r2\noutputs: r2, r0\n"
+    );
+    assert_eq!(program.slots, 3);
+
+    let mut vm = VM::new(equation);
+    for row in 0..8usize {
+        let inputs: Vec<bool> = (0..3).map(|i| row >> (2 - i) & 1 == 1).collect();
+        assert_eq!(program.eval(&inputs)[0], vm.eval(&inputs), "{:?}", inputs);
+    }
+}
@@ -0,0 +1,388 @@
+//! The output column as the contents of a ROM whose address bus is the
+//! inputs, the first being the most significant address bit, which is how
+//! FPGAs implement functions that don't simplify well. Each address holds
+//! the output for that row, with rows left out by assumptions as `0`.
+
+use crate::intern::Symbol;
+use alloc::{format, string::String, vec, vec::Vec};
+use core::{fmt::Write, slice};
+
+use crate::{assignments, classes, multi::MultiTable, vm::TruthTable};
+
+/// Verilog-2005's keywords, which can't name a port without being escaped.
+const KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// `name` as a Verilog identifier, escaped as `\\name ` if it's a keyword.
+fn identifier(name: &str) -> String {
+    match KEYWORDS.contains(&name) {
+        true => format!("\\{} ", name),
+        false => String::from(name),
+    }
+}
+
+/// The contents of a ROM, a word for each address holding the outputs, the
+/// first as its most significant bit.
+struct Rom {
+    address_names: Vec<&'static str>,
+    output_names: Vec<&'static str>,
+    words: Vec<u64>,
+}
+
+/// The names to print, since a ROM is only ever printed.
+fn names(names: &[Symbol]) -> Vec<&'static str> {
+    names.iter().map(|name| name.as_str()).collect()
+}
+
+impl Rom {
+    fn new(table: &TruthTable) -> Self {
+        Self {
+            address_names: names(&table.input_names),
+            output_names: names(slice::from_ref(&table.output_name)),
+            words: classes::column(table).into_iter().map(u64::from).collect(),
+        }
+    }
+
+    fn multi(table: &MultiTable) -> Self {
+        let mut words = vec![0; 1 << table.input_names.len()];
+        for (inputs, outputs) in table.inputs.iter().zip(&table.outputs) {
+            words[assignments::to_row(inputs)] = outputs
+                .iter()
+                .fold(0, |word, output| word << 1 | *output as u64);
+        }
+
+        Self {
+            address_names: names(&table.input_names),
+            output_names: names(&table.output_names),
+            words,
+        }
+    }
+
+    /// The address bits as the inputs' names, most significant first.
+    fn address(&self) -> String {
+        match self.address_names.is_empty() {
+            true => String::from("none"),
+            false => self.address_names.join(", "),
+        }
+    }
+
+    fn readmemh(&self) -> String {
+        let digits = self.output_names.len().div_ceil(4);
+        let mut out = format!(
+            "// {}, by address {}\n",
+            self.output_names.join(", "),
+            self.address()
+        );
+        for word in &self.words {
+            writeln!(out, "{:0digits$x}", word).unwrap();
+        }
+
+        out
+    }
+
+    fn intel_hex(&self) -> String {
+        fn record(out: &mut String, kind: u8, address: u16, data: &[u8]) {
+            let mut sum = data.len() as u8;
+            sum = sum
+                .wrapping_add((address >> 8) as u8)
+                .wrapping_add(address as u8);
+            sum = sum.wrapping_add(kind);
+            write!(out, ":{:02X}{:04X}{:02X}", data.len(), address, kind).unwrap();
+            for byte in data {
+                write!(out, "{:02X}", byte).unwrap();
+                sum = sum.wrapping_add(*byte);
+            }
+            writeln!(out, "{:02X}", sum.wrapping_neg()).unwrap();
+        }
+
+        let bytes: Vec<u8> = self.words.iter().map(|word| *word as u8).collect();
+        let mut out = String::new();
+        for (i, chunk) in bytes.chunks(RECORD_LEN).enumerate() {
+            let address = i * RECORD_LEN;
+            if address > 0 && address.is_multiple_of(0x10000) {
+                record(&mut out, 4, 0, &((address >> 16) as u16).to_be_bytes());
+            }
+            record(&mut out, 0, address as u16, chunk);
+        }
+        record(&mut out, 1, 0, &[]);
+
+        out
+    }
+
+    fn verilog_case(&self) -> String {
+        let n = self.address_names.len();
+        let width = n.max(1);
+        let bits = self.output_names.len();
+        let outputs: Vec<String> = self.output_names.iter().map(|o| identifier(o)).collect();
+        // Several outputs are set together, as one concatenation
+        let target = match outputs.as_slice() {
+            [output] => output.clone(),
+            outputs => format!("{{{}}}", outputs.join(", ")),
+        };
+
+        // The address port is `addr`, with underscores added until no
+        // output has the same name
+        let mut addr = String::from("addr");
+        while self.output_names.contains(&addr.as_str()) {
+            addr.push('_');
+        }
+
+        let mut out = String::new();
+        writeln!(out, "module {}_rom (", self.output_names.join("_")).unwrap();
+        writeln!(
+            out,
+            "    input wire [{}:0] {}, // {}",
+            width - 1,
+            addr,
+            self.address()
+        )
+        .unwrap();
+        let ports: Vec<String> = outputs
+            .iter()
+            .map(|output| format!("    output reg {}", output))
+            .collect();
+        writeln!(out, "{}", ports.join(",\n")).unwrap();
+        writeln!(out, ");").unwrap();
+        writeln!(out, "    always @(*) begin").unwrap();
+        writeln!(out, "        case ({})", addr).unwrap();
+        for (address, word) in self.words.iter().enumerate().filter(|(_, w)| **w != 0) {
+            writeln!(
+                out,
+                "            {}'d{}: {} = {}'b{:0bits$b};",
+                width, address, target, bits, word
+            )
+            .unwrap();
+        }
+        writeln!(out, "            default: {} = {}'b0;", target, bits).unwrap();
+        writeln!(out, "        endcase").unwrap();
+        writeln!(out, "    end").unwrap();
+        writeln!(out, "endmodule").unwrap();
+
+        out
+    }
+}
+
+/// One hex word per line for Verilog's `$readmemh`, starting from address
+/// 0.
+pub fn readmemh(table: &TruthTable) -> String {
+    Rom::new(table).readmemh()
+}
+
+/// Bytes per data record, the usual choice.
+const RECORD_LEN: usize = 16;
+
+/// An Intel HEX file of one byte per address, with extended linear address
+/// records for tables of more than 16 inputs.
+pub fn intel_hex(table: &TruthTable) -> String {
+    Rom::new(table).intel_hex()
+}
+
+/// A Verilog module looking the output up with a `case` statement on the
+/// address, listing the addresses where it's 1.
+pub fn verilog_case(table: &TruthTable) -> String {
+    Rom::new(table).verilog_case()
+}
+
+/// The most outputs a word can hold, and the most an Intel HEX byte can.
+pub const MAX_OUTPUTS: usize = 64;
+pub const MAX_HEX_OUTPUTS: usize = 8;
+
+/// Like [`readmemh`], with a word of all of the outputs at each address.
+pub fn multi_readmemh(table: &MultiTable) -> String {
+    Rom::multi(table).readmemh()
+}
+
+/// Like [`intel_hex`], with a byte of all of the outputs at each address,
+/// so there can be at most [`MAX_HEX_OUTPUTS`].
+pub fn multi_intel_hex(table: &MultiTable) -> String {
+    Rom::multi(table).intel_hex()
+}
+
+/// Like [`verilog_case`], with a port for each output, set together.
+pub fn multi_verilog_case(table: &MultiTable) -> String {
+    Rom::multi(table).verilog_case()
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let equation = Parser::new("(A AND B) OR C = Q").parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+
+    assert_eq!(
+        readmemh(&table),
+        "// Q, by address A, B, C\n0\n1\n0\n1\n0\n1\n1\n1\n"
+    );
+    assert_eq!(
+        intel_hex(&table),
+        ":080000000001000100010101F3\n:00000001FF\n"
+    );
+    let verilog = verilog_case(&table);
+    assert!(verilog.starts_with("module Q_rom (\n    input wire [2:0] addr, // A, B, C\n"));
+    assert!(verilog.contains("            3'd6: Q = 1'b1;\n            3'd7: Q = 1'b1;\n            default: Q = 1'b0;\n"));
+
+    // Addresses past 64K start a new segment
+    let names = [
+        "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P",
+    ];
+    let source = format!("{} OR X = Q", names.join(" AND "));
+    let equation = Parser::new(&source).parse_equation().unwrap();
+    let table = VM::new(Compiler::new(equation).compile()).gen();
+    let hex = intel_hex(&table);
+    assert!(hex.contains("\n:020000040001F9\n:10000000"));
+
+    // Several outputs share each word
+    let adder = crate::multi::MultiEquation::compile(&[
+        Parser::new("(A XOR B) XOR C = S").parse_equation().unwrap(),
+        Parser::new("(A AND B) OR (C AND (A XOR B)) = CO")
+            .parse_equation()
+            .unwrap(),
+    ])
+    .gen();
+    assert_eq!(
+        multi_readmemh(&adder),
+        "// S, CO, by address A, B, C\n0\n2\n2\n1\n2\n1\n1\n3\n"
+    );
+    let verilog = multi_verilog_case(&adder);
+    assert!(verilog.contains("    output reg S,\n    output reg CO\n);\n"));
+    assert!(verilog
+        .contains("            3'd7: {S, CO} = 2'b11;\n            default: {S, CO} = 2'b0;\n"));
+
+    // Keywords are escaped, with the space that ends an escaped identifier
+    let equation = Parser::new("A = module").parse_equation().unwrap();
+    let verilog = verilog_case(&VM::new(Compiler::new(equation).compile()).gen());
+    assert!(verilog.starts_with("module module_rom (\n"));
+    assert!(verilog.contains("    output reg \\module \n);\n"));
+    assert!(verilog.contains("            1'd1: \\module  = 1'b1;\n"));
+
+    // The address port is renamed rather than clash with an output
+    let equation = Parser::new("A = addr").parse_equation().unwrap();
+    let verilog = verilog_case(&VM::new(Compiler::new(equation).compile()).gen());
+    assert!(verilog.contains("    input wire [0:0] addr_, // A\n    output reg addr\n"));
+    assert!(verilog.contains("        case (addr_)\n"));
+}
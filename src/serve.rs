@@ -0,0 +1,276 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use booltable::api;
+use serde::Deserialize;
+
+/// Requests larger than this are refused rather than read into memory.
+const MAX_BODY: usize = 64 * 1024;
+
+/// The request line and each header must fit in this many bytes, and there
+/// can be at most `MAX_HEADERS` headers.
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+/// A client that sends or reads nothing for this long is dropped.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections handled at once, beyond which clients are told to retry.
+const MAX_CONNECTIONS: usize = 32;
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    /// The API's result, where an invalid equation is the caller's mistake
+    /// rather than the server's.
+    fn json(result: Result<String, String>) -> Self {
+        match result {
+            Ok(body) => Self {
+                status: "200 OK",
+                body,
+            },
+            Err(body) => Self {
+                status: "400 Bad Request",
+                body,
+            },
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            body: api::error(message, None),
+        }
+    }
+}
+
+/// Serve the JSON API on `port` until the process is killed, handling each
+/// connection on its own thread, so a slow client doesn't hold up the rest.
+pub fn run(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                continue;
+            }
+        };
+        let busy = active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS;
+        let active = Arc::clone(&active);
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, busy) {
+                eprintln!("Error: {}", err);
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, busy: bool) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let response = match busy {
+        true => Response::error(
+            "503 Service Unavailable",
+            "the server is handling too many requests, try again later",
+        ),
+        false => match read_request(&mut BufReader::new(&mut stream))? {
+            Ok((method, path, body)) => route(&method, &path, &body),
+            Err(response) => response,
+        },
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+type Request = (String, String, String);
+
+/// Read a line of at most `MAX_LINE` bytes, or `None` if it's longer.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = vec![];
+    reader
+        .by_ref()
+        .take(MAX_LINE as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.len() > MAX_LINE {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Response>> {
+    let too_long = || {
+        Ok(Err(Response::error(
+            "431 Request Header Fields Too Large",
+            "the request line or headers are too long",
+        )))
+    };
+    let Some(line) = read_line(reader)? else {
+        return too_long();
+    };
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Ok(Err(Response::error("400 Bad Request", "malformed request"))),
+    };
+
+    let mut length = 0;
+    for count in 0.. {
+        let Some(header) = read_line(reader)? else {
+            return too_long();
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return too_long();
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = match value.trim().parse() {
+                    Ok(length) => length,
+                    Err(_) => {
+                        return Ok(Err(Response::error(
+                            "400 Bad Request",
+                            "invalid Content-Length",
+                        )))
+                    }
+                };
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Ok(Err(Response::error(
+            "413 Payload Too Large",
+            "the request body is too large",
+        )));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    match String::from_utf8(body) {
+        Ok(body) => Ok(Ok((method, path, body))),
+        Err(_) => Ok(Err(Response::error(
+            "400 Bad Request",
+            "the body isn't valid UTF-8",
+        ))),
+    }
+}
+
+#[derive(Deserialize)]
+struct EquationRequest {
+    equation: String,
+}
+
+#[derive(Deserialize)]
+struct EquivRequest {
+    left: String,
+    right: String,
+}
+
+fn request<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, Response> {
+    serde_json::from_str(body).map_err(|err| {
+        Response::error(
+            "400 Bad Request",
+            &format!("the body isn't a valid request: {}", err),
+        )
+    })
+}
+
+fn route(method: &str, path: &str, body: &str) -> Response {
+    if !matches!(path, "/parse" | "/table" | "/minimize" | "/equiv") {
+        return Response::error("404 Not Found", &format!("no endpoint at {}", path));
+    }
+    if method != "POST" {
+        return Response::error("405 Method Not Allowed", "endpoints only accept POST");
+    }
+
+    let result = match path {
+        "/equiv" => request(body).map(|r: EquivRequest| api::equiv(&r.left, &r.right)),
+        _ => request(body).map(|r: EquationRequest| match path {
+            "/parse" => api::parse(&r.equation),
+            "/table" => api::truth_table_json(&r.equation),
+            _ => api::minimize(&r.equation),
+        }),
+    };
+    match result {
+        Ok(result) => Response::json(result),
+        Err(response) => response,
+    }
+}
+
+#[test]
+fn test() {
+    use std::io::Cursor;
+
+    use booltable::export::JsonTable;
+
+    let request = |raw: &str| match read_request(&mut Cursor::new(raw.as_bytes())).unwrap() {
+        Ok((method, path, body)) => Ok(format!("{} {} {}", method, path, body)),
+        Err(response) => Err(response.status),
+    };
+    assert_eq!(
+        request("POST /parse HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+        Ok("POST /parse {}".to_string())
+    );
+    let long = format!(
+        "POST /parse HTTP/1.1\r\nX: {}\r\n\r\n",
+        "a".repeat(MAX_LINE)
+    );
+    assert_eq!(request(&long), Err("431 Request Header Fields Too Large"));
+    let many = format!(
+        "POST /parse HTTP/1.1\r\n{}\r\n",
+        "X: a\r\n".repeat(MAX_HEADERS + 1)
+    );
+    assert_eq!(request(&many), Err("431 Request Header Fields Too Large"));
+
+    let response = |path, body| route("POST", path, body);
+    let parsed = response("/parse", r#"{"equation": "A = \ud83d\ude00"}"#);
+    assert_eq!(parsed.status, "400 Bad Request");
+    assert!(parsed.body.contains("\"span\":{\"start\":4,\"end\":8}"));
+    assert!(response("/parse", r#"{"equation": "\ud83d"}"#)
+        .body
+        .contains("isn't a valid request"));
+    assert!(response("/minimize", r#"{"left": "A = Q"}"#)
+        .body
+        .contains("missing field `equation`"));
+    assert_eq!(route("GET", "/parse", "").status, "405 Method Not Allowed");
+    assert_eq!(response("/", "").status, "404 Not Found");
+
+    let table = response("/table", r#"{"equation": "A . B = Q"}"#);
+    assert_eq!(table.status, "200 OK");
+    let table: JsonTable = serde_json::from_str(&table.body).unwrap();
+    assert_eq!(
+        (table.inputs, table.rows[3].output),
+        (vec!["A".to_string(), "B".to_string()], true)
+    );
+    let equiv = response("/equiv", r#"{"left": "A = Q", "right": "!!A = Q"}"#);
+    assert_eq!(equiv.status, "200 OK");
+    assert_eq!(equiv.body, r#"{"equivalent":true,"counterexample":null}"#);
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::{
+    assignments,
+    column::BitColumn,
+    compiler::Compiler,
+    intern::Symbol,
+    lexer::{Lexer, Span, TK},
+    parser::{Arena, Equation, Expr, ExprId, ExprRef, Node, SpanExpr},
+    vm::{TruthTable, VM},
+};
+
+/// Definitions with at most this many inputs keep their table, which takes
+/// `2^n` bits.
+const CACHE_INPUTS: usize = 20;
+
+/// The outputs of a table generated for an equation, packed into bits for
+/// `Session::define` to keep.
+pub struct Column {
+    inputs: Vec<Symbol>,
+    outputs: BitColumn,
+}
+
+impl Column {
+    /// The outputs of `table`, if it has a row for every assignment of at
+    /// most `CACHE_INPUTS` inputs, so none were left out by assumptions.
+    pub fn new(table: &TruthTable) -> Option<Self> {
+        let num_inputs = table.input_names.len();
+        if num_inputs > CACHE_INPUTS || table.len() != 1 << num_inputs {
+            return None;
+        }
+
+        Some(Self {
+            inputs: table.input_names.clone(),
+            outputs: BitColumn::from_bools(table.outputs.iter().copied()),
+        })
+    }
+}
+
+struct Definition {
+    inputs: Vec<Symbol>,
+    lhs: SpanExpr,
+    /// The output for every assignment of the inputs given first, by row
+    /// number. They're those of the table, which leaves out inputs that
+    /// don't affect it, so they can differ from `inputs`.
+    column: Option<(Vec<Symbol>, BitColumn)>,
+}
+
+/// Remembers the outputs of previous equations so that later equations can
+/// use them as inputs, with their definitions inlined in place.
+#[derive(Default)]
+pub struct Session {
+    definitions: HashMap<Symbol, Definition>,
+    /// The output of each equation defined, in order.
+    history: Vec<Symbol>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `equation.output` as a signal defined by `equation.lhs`, so
+    /// later equations can refer to it by name. Assumptions only restrict
+    /// the equation's own table, so they aren't part of the definition.
+    /// `column` is kept from the table generated for the equation, so that
+    /// later tables can read it instead of evaluating the definition again.
+    pub fn define(&mut self, equation: &Equation, column: Option<Column>) {
+        let expanded = self.expand(equation.clone());
+        let definition = Definition {
+            inputs: expanded.inputs,
+            lhs: expanded.lhs,
+            column: column.map(|column| (column.inputs, column.outputs)),
+        };
+        self.definitions.insert(equation.output, definition);
+        self.history.push(equation.output);
+    }
+
+    /// Replace each `$n` token in `input` with the output of the `n`th
+    /// equation defined, counting from 1, so that earlier results can be
+    /// used without remembering their names.
+    pub fn substitute(&self, input: &str) -> Result<String, String> {
+        // The name stays a token of its own, rather than running into a
+        // neighbouring name as in `$1B`
+        let word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let mut out = String::with_capacity(input.len());
+        let mut end = 0;
+        for token in Lexer::new(input).filter(|token| token.kind == TK::Ref) {
+            let number = &token.text(input)[1..];
+            let output = number
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| self.history.get(n.checked_sub(1)?))
+                .ok_or_else(|| format!("there is no equation ${}", number))?;
+
+            out.push_str(&input[end..token.span.start]);
+            if word(out.chars().next_back()) {
+                out.push(' ');
+            }
+            out.push_str(output);
+            if word(input[token.span.end..].chars().next()) {
+                out.push(' ');
+            }
+            end = token.span.end;
+        }
+        out.push_str(&input[end..]);
+
+        Ok(out)
+    }
+
+    /// Replace every reference to a previously defined output with its
+    /// definition, renumbering the variables of the resulting equation.
+    pub fn expand(&self, equation: Equation) -> Equation {
+        let mut inputs = vec![];
+        let assumptions = equation
+            .assumptions
+            .iter()
+            .map(|assumption| self.expand_expr(assumption, &equation.inputs, &mut inputs))
+            .collect();
+        let lhs = self.expand_expr(&equation.lhs, &equation.inputs, &mut inputs);
+
+        Equation {
+            inputs,
+            lhs,
+            output: equation.output,
+            assumptions,
+        }
+    }
+
+    /// The table of `equation` over `inputs`, the inputs of its expanded
+    /// form, reading previously defined outputs from their stored tables
+    /// instead of computing them again. Inputs left out of `inputs` must
+    /// not affect the result, and are taken to be false. Returns `None` if
+    /// the equation doesn't use a stored table.
+    pub fn table(&self, equation: &Equation, inputs: &[Symbol]) -> Option<TruthTable> {
+        // Where each of the equation's variables comes from: an input, or a
+        // stored table and the inputs it's indexed by
+        enum Source<'s> {
+            Input(Option<usize>),
+            Table(&'s BitColumn, Vec<Option<usize>>),
+        }
+        let position = |name: Symbol| inputs.iter().position(|&n| n == name);
+        let mut cached = false;
+        let mut sources = vec![];
+        for &name in &equation.inputs {
+            sources.push(match self.definitions.get(&name) {
+                Some(definition) => {
+                    cached = true;
+                    let (column_inputs, column) = definition.column.as_ref()?;
+                    let positions = column_inputs.iter().map(|&name| position(name)).collect();
+                    Source::Table(column, positions)
+                }
+                None => Source::Input(position(name)),
+            });
+        }
+        if !cached {
+            return None;
+        }
+
+        // Defined outputs are plain inputs of the unexpanded equation
+        let mut vm = VM::new(Compiler::new(equation.clone()).compile());
+        let bit = |row: usize, position: Option<usize>| {
+            position.is_some_and(|i| row >> (inputs.len() - 1 - i) & 1 == 1)
+        };
+        let mut table = TruthTable {
+            input_names: inputs.to_vec(),
+            inputs: vec![],
+            output_name: equation.output,
+            outputs: vec![],
+        };
+        let mut values = vec![false; sources.len()];
+        for row in 0..1usize << inputs.len() {
+            for (value, source) in values.iter_mut().zip(&sources) {
+                *value = match source {
+                    Source::Input(position) => bit(row, *position),
+                    Source::Table(column, positions) => {
+                        let index = positions
+                            .iter()
+                            .fold(0, |n, p| n << 1 | bit(row, *p) as usize);
+                        column.get(index).unwrap()
+                    }
+                };
+            }
+            if vm.assumed(&values) {
+                table.inputs.push(assignments::to_bools(row, inputs.len()));
+                table.outputs.push(vm.eval(&values));
+            }
+        }
+
+        Some(table)
+    }
+
+    fn expand_expr(&self, expr: &SpanExpr, names: &[Symbol], inputs: &mut Vec<Symbol>) -> SpanExpr {
+        SpanExpr::build(|arena| {
+            arena.substitute(expr.expr(), |arena, v, span| {
+                match self.definitions.get(&names[v]) {
+                    Some(def) => relabel(arena, def.lhs.expr(), &def.inputs, inputs, span),
+                    None => arena.push(span, Node::Var(index_of(inputs, names[v]))),
+                }
+            })
+        })
+    }
+}
+
+/// Copy a stored definition into a new equation, giving every node the span
+/// of the reference it replaces.
+fn relabel(
+    arena: &mut Arena,
+    expr: ExprRef,
+    names: &[Symbol],
+    inputs: &mut Vec<Symbol>,
+    span: Span,
+) -> ExprId {
+    let node = match expr.node() {
+        Expr::Bool(b) => Node::Bool(b),
+        Expr::Var(v) => Node::Var(index_of(inputs, names[v])),
+        Expr::Not(e) => Node::Not(relabel(arena, e, names, inputs, span)),
+        Expr::BinOp { op, lhs, rhs } => Node::BinOp {
+            op,
+            lhs: relabel(arena, lhs, names, inputs, span),
+            rhs: relabel(arena, rhs, names, inputs, span),
+        },
+    };
+
+    arena.push(span, node)
+}
+
+fn index_of(inputs: &mut Vec<Symbol>, name: Symbol) -> usize {
+    inputs.iter().position(|n| *n == name).unwrap_or_else(|| {
+        inputs.push(name);
+        inputs.len() - 1
+    })
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let define = |session: &mut Session, source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let expanded = session.expand(equation.clone());
+        let table = VM::new(Compiler::new(expanded).compile()).gen();
+        let column = Column::new(&table);
+        session.define(&equation, column);
+    };
+    let mut session = Session::new();
+    define(&mut session, "A XOR B = S");
+    let equation = session.expand(Parser::new("S AND C = Q").parse_equation().unwrap());
+    assert_eq!(equation.inputs, ["A", "B", "C"]);
+    assert_eq!(equation.lhs.to_string(), "(AND (XOR 0 1) 2)");
+
+    let parsed = Parser::new("assume NOT S; C OR S = Q")
+        .parse_equation()
+        .unwrap();
+    let expanded = session.expand(parsed.clone());
+    let table = VM::new(Compiler::new(expanded.clone()).compile()).gen();
+    let cached = session.table(&parsed, &expanded.inputs).unwrap();
+    assert_eq!(cached.input_names, ["A", "B", "C"]);
+    assert_eq!(cached.inputs, table.inputs);
+    assert_eq!(cached.outputs, table.outputs);
+    assert!(session
+        .table(
+            &Parser::new("A = R").parse_equation().unwrap(),
+            &["A".into()]
+        )
+        .is_none());
+
+    assert_eq!(session.substitute("$1 AND C = R").unwrap(), "S AND C = R");
+    assert_eq!(session.substitute("$1B = R").unwrap(), "S B = R");
+    assert_eq!(session.substitute("A.$1.B = R").unwrap(), "A.S.B = R");
+    assert!(session.substitute("$2 = R").is_err());
+    assert!(session.substitute("$ = R").is_err());
+
+    // A dead input is left out of the stored table, and a table with rows
+    // left out by assumptions isn't stored
+    define(&mut session, "A OR (B AND 0) = T");
+    let parsed = Parser::new("T XOR C = R").parse_equation().unwrap();
+    let cached = session.table(&parsed, &["A".into(), "C".into()]).unwrap();
+    assert_eq!(cached.outputs, [false, true, true, false]);
+    define(&mut session, "assume A; B = U");
+    let parsed = Parser::new("U = R").parse_equation().unwrap();
+    assert!(session.table(&parsed, &["B".into()]).is_none());
+}
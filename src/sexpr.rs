@@ -0,0 +1,206 @@
+//! S-expressions as an interchange format for the syntax tree, e.g.
+//! `(and A (not (xor B true)))`. Operators are `not`, `and`, `or` and `xor`,
+//! constants `true` and `false`, and any other atom is a variable.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use derive_more::Display;
+
+use crate::{
+    intern::Symbol,
+    lexer::Span,
+    parser::{Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr},
+};
+
+#[derive(Debug, Display, Clone, PartialEq)]
+#[display(fmt = "{}", message)]
+pub struct SexprError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ExprRef<'_> {
+    /// Render the expression with variables resolved to `names`. Binary
+    /// operators always have two operands, so the output is stable.
+    pub fn to_sexpr(self, names: &[Symbol]) -> String {
+        match self.node() {
+            Expr::Bool(b) => b.to_string(),
+            Expr::Var(v) => names[v].to_string(),
+            Expr::Not(e) => format!("(not {})", e.to_sexpr(names)),
+            Expr::BinOp { op, lhs, rhs } => format!(
+                "({} {} {})",
+                op.to_string().to_lowercase(),
+                lhs.to_sexpr(names),
+                rhs.to_sexpr(names)
+            ),
+        }
+    }
+}
+
+impl SpanExpr {
+    /// Parse an s-expression, appending unseen variables to `names` so that
+    /// existing indices are kept. Binary operators also accept more than two
+    /// operands, which associate to the right like the infix syntax.
+    pub fn from_sexpr(source: &str, names: &mut Vec<Symbol>) -> Result<SpanExpr, SexprError> {
+        let mut reader = Reader {
+            source,
+            tokens: tokenize(source),
+            pos: 0,
+            arena: Arena::new(),
+        };
+        let expr = reader.expr(names)?;
+        match reader.tokens.get(reader.pos) {
+            Some(&(_, span)) => Err(reader.error("expected end of input", span)),
+            None => Ok(SpanExpr::new(reader.arena, expr)),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Vec<(&str, Span)> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        if c != '(' && c != ')' {
+            while let Some(&(i, c)) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push((&source[start..end], (start..end).into()));
+    }
+
+    tokens
+}
+
+struct Reader<'a> {
+    source: &'a str,
+    tokens: Vec<(&'a str, Span)>,
+    pos: usize,
+    arena: Arena,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: &str, span: Span) -> SexprError {
+        SexprError {
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    fn next(&mut self) -> Result<(&'a str, Span), SexprError> {
+        let len = self.source.len();
+        let token = self
+            .tokens
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end of input", (len..len).into()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expr(&mut self, names: &mut Vec<Symbol>) -> Result<ExprId, SexprError> {
+        let (text, span) = self.next()?;
+        let node = match text {
+            "(" => return self.list(span, names),
+            ")" => return Err(self.error("unexpected ')'", span)),
+            "true" | "1" => Node::Bool(true),
+            "false" | "0" => Node::Bool(false),
+            name if is_identifier(name) => match names.iter().position(|n| *n == name) {
+                Some(index) => Node::Var(index),
+                None => {
+                    names.push(Symbol::intern(name));
+                    Node::Var(names.len() - 1)
+                }
+            },
+            _ => return Err(self.error(&format!("'{}' isn't a valid variable", text), span)),
+        };
+
+        Ok(self.arena.push(span, node))
+    }
+
+    fn list(&mut self, open: Span, names: &mut Vec<Symbol>) -> Result<ExprId, SexprError> {
+        let (head, head_span) = self.next()?;
+        let mut operands = vec![];
+        let close = loop {
+            match self.tokens.get(self.pos) {
+                Some(&(")", span)) => {
+                    self.pos += 1;
+                    break span;
+                }
+                _ => operands.push(self.expr(names)?),
+            }
+        };
+        let span: Span = (open.start..close.end).into();
+
+        let op = match head {
+            "not" if operands.len() == 1 => {
+                return Ok(self.arena.push(span, Node::Not(operands[0])));
+            }
+            "not" => return Err(self.error("'not' takes one operand", span)),
+            "and" => BinOp::And,
+            "or" => BinOp::Or,
+            "xor" => BinOp::Xor,
+            _ => {
+                return Err(self.error(
+                    &format!("unknown operator '{}', expected not, and, or or xor", head),
+                    head_span,
+                ))
+            }
+        };
+        if operands.len() < 2 {
+            return Err(self.error(&format!("'{}' takes at least two operands", head), span));
+        }
+
+        // The outermost node covers the parentheses too
+        let mut rhs = operands.pop().unwrap();
+        while let Some(lhs) = operands.pop() {
+            let inner = (self.arena.span(lhs).start..self.arena.span(rhs).end).into();
+            let span = if operands.is_empty() { span } else { inner };
+            rhs = self.arena.push(span, Node::BinOp { op, lhs, rhs });
+        }
+
+        Ok(rhs)
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new("A AND NOT (B XOR 1) OR C = Q");
+    let equation = parser.parse_equation().unwrap();
+    let sexpr = equation.lhs.expr().to_sexpr(&equation.inputs);
+    assert_eq!(sexpr, "(and A (not (or (xor B true) C)))");
+
+    let mut names = equation.inputs.clone();
+    let parsed = SpanExpr::from_sexpr(&sexpr, &mut names).unwrap();
+    assert_eq!(parsed.to_string(), equation.lhs.to_string());
+    assert_eq!(names, equation.inputs);
+
+    let mut names = vec![];
+    let chained = SpanExpr::from_sexpr("(or A B C)", &mut names).unwrap();
+    assert_eq!(chained.expr().to_sexpr(&names), "(or A (or B C))");
+    assert_eq!(
+        SpanExpr::from_sexpr("(nand A B)", &mut names)
+            .unwrap_err()
+            .span,
+        (1..5).into()
+    );
+}
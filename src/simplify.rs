@@ -0,0 +1,379 @@
+//! Simplification by the laws of boolean algebra. Unlike minimisation this
+//! works on the expression as written, so whatever isn't simplified keeps
+//! the structure the user gave it.
+
+use crate::intern::Symbol;
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Write;
+
+use derive_more::Display;
+
+use crate::parser::{Arena, BinOp, Dialect, Expr, ExprId, ExprRef, Node, SpanExpr};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Law {
+    /// `A AND 1`, `A OR 0` and `A XOR 0` are `A`
+    #[display(fmt = "identity")]
+    Identity,
+    /// `A AND 0` is `0` and `A OR 1` is `1`
+    #[display(fmt = "annihilation")]
+    Annihilation,
+    /// `A AND A` and `A OR A` are `A`
+    #[display(fmt = "idempotence")]
+    Idempotence,
+    /// `A AND (A OR B)` and `A OR (A AND B)` are `A`
+    #[display(fmt = "absorption")]
+    Absorption,
+    /// `A AND NOT A` is `0`, `A OR NOT A` is `1`, and `A XOR A` is `0`
+    #[display(fmt = "complementation")]
+    Complementation,
+    /// `NOT NOT A` is `A`
+    #[display(fmt = "double negation")]
+    DoubleNegation,
+    /// `NOT 0` is `1` and `NOT 1` is `0`
+    #[display(fmt = "negated constant")]
+    NegatedConstant,
+    /// `A XOR 1` is `NOT A`
+    #[display(fmt = "XOR with 1")]
+    XorTrue,
+    /// `(A AND NOT B) OR ((NOT A) AND B)` is `A XOR B`, and `(A AND B) OR
+    /// ((NOT A) AND NOT B)` is `NOT (A XOR B)`
+    #[display(fmt = "XOR extraction")]
+    XorExtraction,
+    /// `A op B` is `B op A`
+    #[display(fmt = "commutativity")]
+    Commutativity,
+    /// `(A op B) op C` is `A op (B op C)`
+    #[display(fmt = "associativity")]
+    Associativity,
+    /// `NOT (A AND B)` is `(NOT A) OR (NOT B)`, and likewise for `OR`
+    #[display(fmt = "De Morgan")]
+    DeMorgan,
+    /// `A AND (B OR C)` is `(A AND B) OR (A AND C)`, and likewise with `AND`
+    /// and `OR` swapped
+    #[display(fmt = "distributivity")]
+    Distributivity,
+}
+
+/// Whether two expressions have the same structure, ignoring spans.
+pub fn same(a: ExprRef, b: ExprRef) -> bool {
+    if a.id() == b.id() && core::ptr::eq(a.arena(), b.arena()) {
+        return true;
+    }
+    match (a.node(), b.node()) {
+        (Expr::Bool(a), Expr::Bool(b)) => a == b,
+        (Expr::Var(a), Expr::Var(b)) => a == b,
+        (Expr::Not(a), Expr::Not(b)) => same(a, b),
+        (
+            Expr::BinOp { op, lhs, rhs },
+            Expr::BinOp {
+                op: op_b,
+                lhs: lhs_b,
+                rhs: rhs_b,
+            },
+        ) => op == op_b && same(lhs, lhs_b) && same(rhs, rhs_b),
+        _ => false,
+    }
+}
+
+/// Whether `a` is `NOT b` or `b` is `NOT a`.
+fn complementary(a: ExprRef, b: ExprRef) -> bool {
+    matches!(a.node(), Expr::Not(a) if same(a, b)) || matches!(b.node(), Expr::Not(b) if same(b, a))
+}
+
+/// `a OR b` as an XOR of two operands, and whether it's an XNOR, if they're
+/// products of two operands that are each the complement of one in the
+/// other product, in either order. The operands are taken without their
+/// NOTs, so the result only has a NOT at the top, for XNOR.
+fn xor_of(a: ExprRef, b: ExprRef) -> Option<(ExprId, ExprId, bool)> {
+    let (
+        Expr::BinOp {
+            op: BinOp::And,
+            lhs: a1,
+            rhs: b1,
+        },
+        Expr::BinOp {
+            op: BinOp::And,
+            lhs: l2,
+            rhs: r2,
+        },
+    ) = (a.node(), b.node())
+    else {
+        return None;
+    };
+    let (a2, b2) = if complementary(a1, l2) {
+        (l2, r2)
+    } else {
+        (r2, l2)
+    };
+    if !complementary(a1, a2) || !complementary(b1, b2) {
+        return None;
+    }
+
+    // Whichever of the pair isn't the NOT of the other, and whether the
+    // first product has the NOT
+    let positive = |first: ExprRef, second: ExprRef| match first.node() {
+        Expr::Not(inner) if same(inner, second) => (inner.id(), true),
+        _ => (first.id(), false),
+    };
+    let ((x, x_negated), (y, y_negated)) = (positive(a1, a2), positive(b1, b2));
+
+    // The first product is true when both are or neither is, for XNOR
+    Some((x, y, x_negated == y_negated))
+}
+
+/// Apply a single simplifying law at the root of `id`, if one applies,
+/// pushing any new nodes. When the result is one of the operands it keeps
+/// that operand's span, so the rest of the root's span is what the law
+/// removed, and new nodes take the root's span.
+pub(crate) fn rewrite(arena: &mut Arena, id: ExprId) -> Option<(Law, ExprId)> {
+    let span = arena.span(id);
+    let result = match arena[id] {
+        Node::Bool(_) | Node::Var(_) => return None,
+        Node::Not(e) => match arena[e] {
+            Node::Not(inner) => (Law::DoubleNegation, inner),
+            Node::Bool(b) => (Law::NegatedConstant, arena.push(span, Node::Bool(!b))),
+            _ => return None,
+        },
+        Node::BinOp { op, lhs: l, rhs: r } => {
+            let (a, b) = (arena.get(l), arena.get(r));
+            match (op, arena[l], arena[r]) {
+                (BinOp::And, Node::Bool(true), _)
+                | (BinOp::Or | BinOp::Xor, Node::Bool(false), _) => (Law::Identity, r),
+                (BinOp::And, _, Node::Bool(true))
+                | (BinOp::Or | BinOp::Xor, _, Node::Bool(false)) => (Law::Identity, l),
+                (BinOp::And, Node::Bool(false), _) | (BinOp::And, _, Node::Bool(false)) => {
+                    (Law::Annihilation, arena.push(span, Node::Bool(false)))
+                }
+                (BinOp::Or, Node::Bool(true), _) | (BinOp::Or, _, Node::Bool(true)) => {
+                    (Law::Annihilation, arena.push(span, Node::Bool(true)))
+                }
+                (BinOp::Xor, Node::Bool(true), _) | (BinOp::Xor, _, Node::Bool(true)) => {
+                    let e = if let Node::Bool(true) = arena[l] {
+                        r
+                    } else {
+                        l
+                    };
+                    (Law::XorTrue, arena.push(span, Node::Not(e)))
+                }
+                (BinOp::And | BinOp::Or, ..) if same(a, b) => (Law::Idempotence, l),
+                (BinOp::Xor, ..) if same(a, b) => {
+                    (Law::Complementation, arena.push(span, Node::Bool(false)))
+                }
+                (op, ..) if complementary(a, b) => (
+                    Law::Complementation,
+                    arena.push(span, Node::Bool(op != BinOp::And)),
+                ),
+                (BinOp::Or, ..) if xor_of(a, b).is_some() => {
+                    let (x, y, xnor) = xor_of(a, b).unwrap();
+                    let xor = arena.push(
+                        span,
+                        Node::BinOp {
+                            op: BinOp::Xor,
+                            lhs: x,
+                            rhs: y,
+                        },
+                    );
+                    let result = match xnor {
+                        true => arena.push(span, Node::Not(xor)),
+                        false => xor,
+                    };
+                    (Law::XorExtraction, result)
+                }
+                (op, ..) => {
+                    // `A op (A dual B)`, in any order of operands
+                    let dual = match op {
+                        BinOp::And => BinOp::Or,
+                        BinOp::Or => BinOp::And,
+                        BinOp::Xor => return None,
+                    };
+                    let absorbs = |term: ExprRef, other: ExprRef| match other.node() {
+                        Expr::BinOp { op, lhs, rhs } if op == dual => {
+                            same(term, lhs) || same(term, rhs)
+                        }
+                        _ => false,
+                    };
+                    if absorbs(a, b) {
+                        (Law::Absorption, l)
+                    } else if absorbs(b, a) {
+                        (Law::Absorption, r)
+                    } else {
+                        return None;
+                    }
+                }
+            }
+        }
+    };
+
+    Some(result)
+}
+
+/// One application of a law in a proof.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub law: Law,
+    /// The subexpression the law was applied to, and what it became. Spans
+    /// point back at the source, as described for `rewrite`.
+    pub before: SpanExpr,
+    pub after: SpanExpr,
+    /// The whole expression after the step.
+    pub result: SpanExpr,
+}
+
+/// A `Step` as the ids of its expressions in the arena being rewritten.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Applied {
+    pub law: Law,
+    pub before: ExprId,
+    pub after: ExprId,
+    pub result: ExprId,
+}
+
+impl Applied {
+    /// The step with its expressions copied out of `arena`.
+    pub fn to_step(self, arena: &Arena) -> Step {
+        let copy = |id| arena.get(id).to_span_expr();
+        Step {
+            law: self.law,
+            before: copy(self.before),
+            after: copy(self.after),
+            result: copy(self.result),
+        }
+    }
+}
+
+/// Apply one law to the innermost, leftmost subexpression of `id` it applies
+/// to. Only the nodes above the rewritten one are pushed again.
+fn step(arena: &mut Arena, id: ExprId) -> Option<Applied> {
+    let span = arena.span(id);
+    let child = match arena[id] {
+        Node::Bool(_) | Node::Var(_) => None,
+        Node::Not(e) => step(arena, e).map(|s| Applied {
+            result: arena.push(span, Node::Not(s.result)),
+            ..s
+        }),
+        Node::BinOp { op, lhs, rhs } => match step(arena, lhs) {
+            Some(s) => Some(Applied {
+                result: arena.push(
+                    span,
+                    Node::BinOp {
+                        op,
+                        lhs: s.result,
+                        rhs,
+                    },
+                ),
+                ..s
+            }),
+            None => step(arena, rhs).map(|s| Applied {
+                result: arena.push(
+                    span,
+                    Node::BinOp {
+                        op,
+                        lhs,
+                        rhs: s.result,
+                    },
+                ),
+                ..s
+            }),
+        },
+    };
+
+    child.or_else(|| {
+        rewrite(arena, id).map(|(law, after)| Applied {
+            law,
+            before: id,
+            after,
+            result: after,
+        })
+    })
+}
+
+/// Every step taken to simplify `expr`, in order. The last step's result is
+/// what `simplify` returns.
+pub fn proof(expr: &SpanExpr) -> Vec<Step> {
+    let (mut arena, mut root) = (expr.arena.clone(), expr.root);
+    let mut steps = vec![];
+    while let Some(applied) = step(&mut arena, root) {
+        root = applied.result;
+        steps.push(applied.to_step(&arena));
+    }
+
+    steps
+}
+
+/// Apply the laws until none of them do anything more. Each law makes the
+/// expression smaller, so this always terminates.
+pub fn simplify(expr: &SpanExpr) -> SpanExpr {
+    let (mut arena, mut root) = (expr.arena.clone(), expr.root);
+    while let Some(applied) = step(&mut arena, root) {
+        root = applied.result;
+    }
+
+    arena.get(root).to_span_expr()
+}
+
+/// Render a proof starting from `expr` as lines a reader can follow, each
+/// naming the law, the subexpression it rewrote, and the expression after it:
+///
+/// ```text
+///   A OR (A AND B)
+/// absorption: A OR (A AND B) → A
+/// = A
+/// ```
+pub fn render_proof(expr: &SpanExpr, steps: &[Step], names: &[Symbol], dialect: Dialect) -> String {
+    let mut out = format!("  {}\n", expr.display_in(names, dialect));
+    for step in steps {
+        writeln!(
+            out,
+            "{}: {} → {}\n= {}",
+            step.law,
+            step.before.display_in(names, dialect),
+            step.after.display_in(names, dialect),
+            step.result.display_in(names, dialect)
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    for (source, simplified) in [
+        ("A OR (A AND B) = Q", "A"),
+        ("(A AND 1) OR (B AND 0) = Q", "A"),
+        ("NOT NOT (A XOR 1) = Q", "NOT A"),
+        ("(A OR NOT A) AND (B OR B) = Q", "B"),
+        ("(A AND B) OR (C AND D) = Q", "(A AND B) OR (C AND D)"),
+        ("(A AND NOT B) OR ((NOT A) AND B) = Q", "A XOR B"),
+        ("((NOT A) AND NOT B) OR (B AND A) = Q", "NOT (A XOR B)"),
+        (
+            "((NOT A) AND (B OR C)) OR (A AND NOT (B OR C)) = Q",
+            "A XOR (B OR C)",
+        ),
+    ] {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let result = simplify(&equation.lhs);
+        assert_eq!(
+            result.display(&equation.inputs).to_string(),
+            simplified,
+            "{}",
+            source
+        );
+    }
+
+    let equation = Parser::new("A OR (A AND B) OR 0 = Q")
+        .parse_equation()
+        .unwrap();
+    let steps = proof(&equation.lhs);
+    // `(A AND B)` survives the first step with its own span, so the `OR 0`
+    // after it is what was removed
+    assert_eq!(steps[0].before.span(), (5..19).into());
+    assert_eq!(steps[0].after.span(), (5..14).into());
+    assert_eq!(
+        render_proof(&equation.lhs, &steps, &equation.inputs, Dialect::Symbols),
+        "  A + (A . B) + 0\nidentity: (A . B) + 0 → A . B\n= A + (A . B)\nabsorption: A + (A . B) → A\n= A\n"
+    );
+}
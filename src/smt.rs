@@ -0,0 +1,92 @@
+//! SMT-LIB2 scripts for handing equations off to solvers such as Z3 or cvc5.
+
+use crate::intern::Symbol;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt::Write;
+
+use crate::parser::{BinOp, Dialect, Equation, Expr, ExprRef};
+
+/// Symbols that already mean something in the core theory, which inputs named
+/// the same way would shadow.
+const RESERVED: &[&str] = &[
+    "and", "or", "not", "xor", "true", "false", "ite", "distinct", "let", "as", "par", "match",
+    "forall", "exists",
+];
+
+fn symbol(name: &str) -> String {
+    if RESERVED.contains(&name) || name == "_" {
+        format!("|{}|", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn term(expr: ExprRef, names: &[Symbol], out: &mut String) {
+    match expr.node() {
+        Expr::Bool(b) => write!(out, "{}", b).unwrap(),
+        Expr::Var(v) => out.push_str(&symbol(&names[v])),
+        Expr::Not(e) => {
+            out.push_str("(not ");
+            term(e, names, out);
+            out.push(')');
+        }
+        Expr::BinOp { op, lhs, rhs } => {
+            let op = match op {
+                BinOp::And => "and",
+                BinOp::Or => "or",
+                BinOp::Xor => "xor",
+            };
+            write!(out, "({} ", op).unwrap();
+            term(lhs, names, out);
+            out.push(' ');
+            term(rhs, names, out);
+            out.push(')');
+        }
+    }
+}
+
+/// A script declaring each input and asserting the equation's expression, or
+/// its negation if `negate` is set, followed by `(check-sat)`. Asserting the
+/// negation turns a tautology check into an unsatisfiability check.
+/// Assumptions are asserted as they are, restricting either check to the
+/// assignments they allow.
+pub fn script(equation: &Equation, negate: bool) -> String {
+    let mut out = format!(
+        "; {}\n(set-logic QF_UF)\n",
+        equation.to_source(Dialect::Words)
+    );
+    for name in &equation.inputs {
+        writeln!(out, "(declare-const {} Bool)", symbol(name)).unwrap();
+    }
+
+    for assumption in &equation.assumptions {
+        let mut formula = String::new();
+        term(assumption.expr(), &equation.inputs, &mut formula);
+        writeln!(out, "(assert {})", formula).unwrap();
+    }
+
+    let mut formula = String::new();
+    term(equation.lhs.expr(), &equation.inputs, &mut formula);
+    if negate {
+        formula = format!("(not {})", formula);
+    }
+    writeln!(out, "(assert {})\n(check-sat)", formula).unwrap();
+
+    out
+}
+
+#[test]
+fn test() {
+    use crate::parser::Parser;
+
+    let mut parser = Parser::new("and XOR NOT B = Q");
+    let equation = parser.parse_equation().unwrap();
+    assert_eq!(
+        script(&equation, true),
+        "; and XOR NOT B = Q\n(set-logic QF_UF)\n(declare-const |and| Bool)\n\
+         (declare-const B Bool)\n(assert (not (xor |and| (not B))))\n(check-sat)\n"
+    );
+}
@@ -0,0 +1,244 @@
+//! Built-in functions, so that common functions don't have to be typed out
+//! by hand. `parity`, `majority` and `mux` can be called in equations, as in
+//! `majority(A, B, C) = Q`, taking any number of arguments, and every
+//! generator, including the adder and decoder with their several outputs,
+//! can be expanded into equations by size with [`generate`].
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    lexer::Span,
+    parser::{Arena, BinOp, ExprId, Node},
+};
+
+/// The functions that can be called in equations.
+pub const FUNCTIONS: &[&str] = &["parity", "majority", "mux"];
+
+/// The generators `generate` knows.
+pub const GENERATORS: &[&str] = &["parity", "majority", "mux", "adder", "decoder"];
+
+/// The expression for a call of `name` on `args`, pushed into `arena`, or
+/// why it can't be made. Every new node has the span of the call, and each
+/// argument is shared by the nodes that use it rather than copied.
+pub fn call(
+    arena: &mut Arena,
+    name: &str,
+    args: Vec<ExprId>,
+    span: Span,
+) -> Result<ExprId, String> {
+    let mut build = Build { arena, span };
+    if args.is_empty() {
+        return Err(format!("'{}' needs at least one argument", name));
+    }
+
+    match name {
+        // True when an odd number of the arguments are
+        "parity" => Ok(args
+            .into_iter()
+            .reduce(|lhs, rhs| build.op(BinOp::Xor, lhs, rhs))
+            .unwrap()),
+        // True when more than half of the arguments are
+        "majority" => Ok(build.threshold(args.len() / 2 + 1, &args)),
+        // `k` select lines, the first most significant, then the `2^k` data
+        // lines they choose between
+        "mux" => {
+            let select = (0..usize::BITS as usize)
+                .find(|k| k + (1 << k) >= args.len())
+                .unwrap();
+            if select + (1 << select) != args.len() {
+                return Err(format!(
+                    "'mux' takes k select lines and 2^k data lines, so 1, 3, 6, 11, ... arguments, not {}",
+                    args.len()
+                ));
+            }
+            let (select, data) = args.split_at(select);
+            Ok(build.mux(select, data))
+        }
+        _ => Err(format!(
+            "unknown function '{}', expected one of: {}",
+            name,
+            FUNCTIONS.join(", ")
+        )),
+    }
+}
+
+struct Build<'a> {
+    arena: &'a mut Arena,
+    span: Span,
+}
+
+impl Build<'_> {
+    fn node(&mut self, node: Node) -> ExprId {
+        self.arena.push(self.span, node)
+    }
+
+    fn op(&mut self, op: BinOp, lhs: ExprId, rhs: ExprId) -> ExprId {
+        self.node(Node::BinOp { op, lhs, rhs })
+    }
+
+    /// True when at least `t` of `args` are, by expanding on each argument
+    /// in turn.
+    fn threshold(&mut self, t: usize, args: &[ExprId]) -> ExprId {
+        if t == 0 {
+            return self.node(Node::Bool(true));
+        }
+        if t > args.len() {
+            return self.node(Node::Bool(false));
+        }
+        if t == args.len() {
+            return args
+                .iter()
+                .copied()
+                .reduce(|lhs, rhs| self.op(BinOp::And, lhs, rhs))
+                .unwrap();
+        }
+
+        let (&first, rest) = args.split_first().unwrap();
+        let with = match t {
+            1 => first,
+            _ => {
+                let rest = self.threshold(t - 1, rest);
+                self.op(BinOp::And, first, rest)
+            }
+        };
+        let without = self.threshold(t, rest);
+        self.op(BinOp::Or, with, without)
+    }
+
+    fn mux(&mut self, select: &[ExprId], data: &[ExprId]) -> ExprId {
+        let Some((&first, rest)) = select.split_first() else {
+            return data[0];
+        };
+        let (low, high) = data.split_at(data.len() / 2);
+        let not_first = self.node(Node::Not(first));
+        let low = self.mux(rest, low);
+        let low = self.op(BinOp::And, not_first, low);
+        let high = self.mux(rest, high);
+        let high = self.op(BinOp::And, first, high);
+
+        self.op(BinOp::Or, low, high)
+    }
+}
+
+/// `prefix` followed by each number below `n`.
+fn names(prefix: &str, n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+/// The equations for generator `name` of size `n`, one per output, in the
+/// input syntax. Bit 0 of an adder's operands and sum is the least
+/// significant, and select line 0 of a mux or decoder the most.
+pub fn generate(name: &str, n: usize) -> Result<Vec<String>, String> {
+    if n == 0 {
+        return Err(format!("'{}' needs a size of at least 1", name));
+    }
+
+    Ok(match name {
+        "parity" => alloc::vec![format!("parity({}) = P", names("X", n).join(", "))],
+        "majority" => alloc::vec![format!("majority({}) = M", names("X", n).join(", "))],
+        "mux" => {
+            let mut args = names("S", n);
+            args.extend(names("D", 1 << n));
+            alloc::vec![format!("mux({}) = Y", args.join(", "))]
+        }
+        // A ripple-carry adder, with each carry written out in full where
+        // it's used
+        "adder" => {
+            let mut equations = alloc::vec!["A0 XOR B0 = S0".to_string()];
+            let mut carry = "A0 AND B0".to_string();
+            for i in 1..n {
+                equations.push(format!("A{i} XOR B{i} XOR ({}) = S{i}", carry));
+                carry = format!("(A{i} AND B{i}) OR ((A{i} XOR B{i}) AND ({}))", carry);
+            }
+            equations.push(format!("{} = COUT", carry));
+            equations
+        }
+        "decoder" => (0..1usize << n)
+            .map(|i| {
+                let literals: Vec<String> = (0..n)
+                    .map(|bit| match i >> (n - 1 - bit) & 1 {
+                        1 => format!("S{}", bit),
+                        _ => format!("NOT S{}", bit),
+                    })
+                    .map(|literal| bracket(&literal))
+                    .collect();
+                format!("{} = Y{}", literals.join(" AND "), i)
+            })
+            .collect(),
+        _ => {
+            return Err(format!(
+                "unknown generator '{}', expected one of: {}",
+                name,
+                GENERATORS.join(", ")
+            ))
+        }
+    })
+}
+
+/// `expr` in brackets unless it's a single name or constant.
+fn bracket(expr: &str) -> String {
+    if expr.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        expr.to_string()
+    } else {
+        format!("({})", expr)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let outputs = |source: &str| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen().outputs
+    };
+    let count = |row: usize, bits: usize| (0..bits).filter(|b| row >> b & 1 == 1).count();
+
+    let parity = outputs("parity(A, B, C, D) = Q");
+    assert!((0..16).all(|row| parity[row] == (count(row, 4) % 2 == 1)));
+    let majority = outputs("majority(A, B, C, D, E) = Q");
+    assert!((0..32).all(|row| majority[row] == (count(row, 5) >= 3)));
+    // The select lines are the top two bits of the row, and D0 to D3 the
+    // rest, highest first
+    let mux = outputs("mux(S0, S1, D0, D1, D2, D3) = Q");
+    assert!((0..64).all(|row| mux[row] == (row >> (3 - (row >> 4)) & 1 == 1)));
+    assert!(Parser::new("mux(A, B) = Q").parse_equation().is_err());
+    assert!(Parser::new("median(A, B) = Q").parse_equation().is_err());
+
+    // Every generated equation parses, and the adder adds
+    for name in GENERATORS {
+        for equation in generate(name, 3).unwrap() {
+            Parser::new(&equation).parse_equation().unwrap();
+        }
+    }
+    let sources = generate("adder", 2).unwrap();
+    let mut adder: Vec<_> = sources
+        .iter()
+        .map(|source| {
+            let equation = Parser::new(source).parse_equation().unwrap();
+            let names = equation.inputs.clone();
+            (names, VM::new(Compiler::new(equation).compile()))
+        })
+        .collect();
+    assert_eq!(adder.len(), 3);
+    for a in 0..4usize {
+        for b in 0..4usize {
+            let bit = |name: &str| {
+                let (number, i) = name.split_at(1);
+                (if number == "A" { a } else { b }) >> i.parse::<usize>().unwrap() & 1 == 1
+            };
+            let total = adder
+                .iter_mut()
+                .enumerate()
+                .fold(0, |total, (i, (names, vm))| {
+                    let inputs: Vec<bool> = names.iter().map(|name| bit(name)).collect();
+                    total | (vm.eval(&inputs) as usize) << i
+                });
+            assert_eq!(total, a + b);
+        }
+    }
+}
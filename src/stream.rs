@@ -0,0 +1,174 @@
+//! Writing tables as they're generated, for tables too big to hold in
+//! memory. Rows are rendered into a buffer that is written out and flushed
+//! a chunk at a time, so at most a chunk is held at once and a reader
+//! following the file sees it grow steadily. With the `gzip` feature the
+//! chunks can be compressed on their way to the file.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+#[cfg(feature = "gzip")]
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    assignments,
+    export::{RowOrder, TableStyle},
+    vm::VM,
+};
+
+/// The default size of a chunk in bytes.
+pub const CHUNK_SIZE: usize = 1 << 20;
+
+/// A writer that collects writes until it has a chunk's worth, then writes
+/// the chunk to `inner` and flushes it.
+pub struct ChunkedWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    chunk_size: usize,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_chunk_size(inner, CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(inner: W, chunk_size: usize) -> Self {
+        Self {
+            inner,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+
+    /// Write out whatever is left, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= self.chunk_size {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.inner.flush()
+    }
+}
+
+/// Whether `path` is for a gzipped file, going by its `.gz` extension.
+pub fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Call `f` with a writer that compresses into `inner` with gzip, and end
+/// the stream once it returns. Each chunk flushed through it is flushed out
+/// of the compressor too, so a reader can decompress what's there so far.
+#[cfg(feature = "gzip")]
+pub fn gzip<W: Write, T>(
+    inner: W,
+    f: impl FnOnce(&mut GzEncoder<W>) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut encoder = GzEncoder::new(inner, Compression::default());
+    let result = f(&mut encoder)?;
+    encoder.finish()?;
+    Ok(result)
+}
+
+/// Generate the table of `vm`'s equation row by row, writing it to `out` as
+/// CSV, the same as `export::render` would. Returns how many rows were
+/// written.
+pub fn write_csv(vm: &mut VM, out: impl Write, style: &TableStyle) -> io::Result<usize> {
+    let mut out = ChunkedWriter::new(out);
+    let equation = &vm.equation;
+    let length = equation.inputs.len();
+    let headers: Vec<&str> = equation
+        .inputs
+        .iter()
+        .chain([&equation.output])
+        .map(|name| name.as_str())
+        .collect();
+    writeln!(out, "{}", headers.join(","))?;
+
+    let num_rows = 1usize << length;
+    let mut written = 0;
+    for i in 0..num_rows {
+        let row = match style.row_order {
+            RowOrder::Ascending => i,
+            RowOrder::Descending => num_rows - 1 - i,
+        };
+        let inputs = assignments::to_bools(row, length);
+        if !vm.assumed(&inputs) {
+            continue;
+        }
+        let output = vm.eval(&inputs);
+        for input in &inputs {
+            write!(out, "{},", style.symbol(*input))?;
+        }
+        writeln!(out, "{}", style.symbol(output))?;
+        written += 1;
+    }
+
+    out.finish()?;
+    Ok(written)
+}
+
+#[test]
+fn test() {
+    use crate::{
+        compiler::Compiler,
+        export::{self, Format},
+        parser::Parser,
+    };
+
+    let equation = Parser::new("assume A != C; (A AND B) OR C = Q")
+        .parse_equation()
+        .unwrap();
+    let mut vm = VM::new(Compiler::new(equation).compile());
+    let style = TableStyle {
+        row_order: RowOrder::Descending,
+        ..TableStyle::default()
+    };
+    let mut out = vec![];
+    assert_eq!(write_csv(&mut vm, &mut out, &style).unwrap(), 4);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        export::render(&vm.gen(), Format::Csv, &style)
+    );
+
+    let mut chunked = ChunkedWriter::with_chunk_size(vec![], 4);
+    chunked.write_all(b"abc").unwrap();
+    assert!(chunked.inner.is_empty());
+    chunked.write_all(b"de").unwrap();
+    assert_eq!(chunked.inner, b"abcde");
+    chunked.write_all(b"f").unwrap();
+    assert_eq!(chunked.finish().unwrap(), b"abcdef");
+
+    assert!(is_gzip(Path::new("q.csv.gz")));
+    assert!(!is_gzip(Path::new("q.csv")));
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Read;
+
+        use flate2::read::GzDecoder;
+
+        let mut compressed = vec![];
+        let rows = gzip(&mut compressed, |out| write_csv(&mut vm, out, &style)).unwrap();
+        assert_eq!(rows, 4);
+        let mut csv = String::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut csv)
+            .unwrap();
+        assert_eq!(csv, export::render(&vm.gen(), Format::Csv, &style));
+    }
+}
@@ -0,0 +1,426 @@
+//! A binary file format for generated tables, so that big ones can be kept
+//! and queried later without generating them again. On Unix the file is
+//! memory-mapped, and elsewhere, or if mapping fails, rows are read with
+//! seeks when they're asked for, so either way opening a file doesn't
+//! depend on the size of the table.
+//!
+//! A file is `MAGIC`, the version byte, the length of the header as a
+//! little-endian `u32`, and the header: the number of inputs as a varint,
+//! then the input names and the output name, each as in the bytecode format.
+//! The rows follow in groups of eight, each group a byte of outputs and a
+//! byte with the rows that are in the table set, so that rows left out by
+//! assumptions can be told apart. Bit `i` of group `g` is row `8 * g + i`,
+//! with input 0 as the highest bit of the row number, and a table of fewer
+//! than eight rows still takes a whole group.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use derive_more::Display;
+
+use crate::{
+    assignments,
+    bytecode::{write_str, write_varint, Reader},
+    intern::Symbol,
+    stream::{ChunkedWriter, CHUNK_SIZE},
+    vm::{TruthTable, VM},
+};
+
+pub const MAGIC: &[u8; 4] = b"BTTB";
+pub const VERSION: u8 = 1;
+/// The extension the CLI writes the format for.
+pub const EXTENSION: &str = "btt";
+
+/// Headers longer than this are refused before anything is read into
+/// memory. 63 names, the most a table can have, fit many times over.
+const MAX_HEADER: usize = 1 << 20;
+
+#[derive(Debug, Display)]
+pub enum TableFileError {
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+    #[display(fmt = "not a booltable table file")]
+    BadMagic,
+    #[display(fmt = "unsupported table file version {}", _0)]
+    UnsupportedVersion(u8),
+    #[display(fmt = "the table file's header is malformed")]
+    BadHeader,
+    #[display(
+        fmt = "the table file should be {} bytes long, but is {}",
+        expected,
+        actual
+    )]
+    WrongLength { expected: u64, actual: u64 },
+    #[display(fmt = "the tables have different inputs")]
+    DifferentInputs,
+}
+
+impl From<io::Error> for TableFileError {
+    fn from(err: io::Error) -> Self {
+        TableFileError::Io(err)
+    }
+}
+
+/// The number of bytes taken by the rows of a table with `num_inputs` inputs.
+pub(crate) fn data_len(num_inputs: usize) -> u64 {
+    2 * 1u64.max((1u64 << num_inputs) / 8)
+}
+
+/// Generate the table of `vm`'s equation row by row, writing it to `out` in
+/// the table file format.
+pub fn write(vm: &mut VM, out: impl Write) -> io::Result<()> {
+    let mut out = ChunkedWriter::new(out);
+    let equation = &vm.equation;
+    let length = equation.inputs.len();
+    write_header(&mut out, &equation.inputs, &equation.output)?;
+
+    let num_rows = 1usize << length;
+    for group in 0..num_rows.div_ceil(8) {
+        let (mut outputs, mut included) = (0u8, 0u8);
+        for bit in 0..8.min(num_rows) {
+            let inputs = assignments::to_bools(group * 8 + bit, length);
+            if vm.assumed(&inputs) {
+                included |= 1 << bit;
+                outputs |= (vm.eval(&inputs) as u8) << bit;
+            }
+        }
+        out.write_all(&[outputs, included])?;
+    }
+
+    out.finish()?;
+    Ok(())
+}
+
+/// Everything before the rows, for a table with inputs `inputs` and
+/// output `output`.
+pub(crate) fn write_header(
+    mut out: impl Write,
+    inputs: &[Symbol],
+    output: &Symbol,
+) -> io::Result<()> {
+    let mut header = vec![];
+    write_varint(&mut header, inputs.len());
+    for name in inputs {
+        write_str(&mut header, name);
+    }
+    write_str(&mut header, output);
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    out.write_all(&(header.len() as u32).to_le_bytes())?;
+    out.write_all(&header)
+}
+
+/// A read-only mapping of a whole file, unmapped when dropped. The file
+/// mustn't be truncated while it's mapped, as reading past its new end
+/// raises `SIGBUS`.
+#[cfg(unix)]
+#[derive(Debug)]
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and owned by one `Mapping`
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    /// Map the first `len` bytes of `file`, or `None` if it can't be.
+    fn new(file: &File, len: u64) -> Option<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        // Empty mappings aren't allowed
+        let len = usize::try_from(len).ok().filter(|len| *len > 0)?;
+        // SAFETY: a private, read-only mapping that only this struct uses
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        (ptr != libc::MAP_FAILED).then_some(Self { ptr, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: `ptr` is a live mapping of `len` readable bytes
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` are exactly what `mmap` returned
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/// Where an open file's bytes come from.
+#[derive(Debug)]
+enum Contents {
+    #[cfg(unix)]
+    Mapped(Mapping),
+    File(File),
+}
+
+impl Contents {
+    /// `len` bytes from `offset`, borrowed from the mapping or read from
+    /// the file.
+    fn read(&mut self, offset: u64, len: usize) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            #[cfg(unix)]
+            Contents::Mapped(mapping) => usize::try_from(offset)
+                .ok()
+                .and_then(|start| mapping.bytes().get(start..start.checked_add(len)?))
+                .map(Cow::Borrowed)
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into()),
+            Contents::File(file) => {
+                let mut bytes = vec![0; len];
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut bytes)?;
+                Ok(Cow::Owned(bytes))
+            }
+        }
+    }
+}
+
+/// An open table file.
+#[derive(Debug)]
+pub struct TableFile {
+    contents: Contents,
+    pub input_names: Vec<Symbol>,
+    pub output_name: Symbol,
+    /// Where the rows start.
+    data: u64,
+}
+
+impl TableFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TableFileError> {
+        Self::from_file(File::open(path)?, cfg!(unix))
+    }
+
+    /// Read the header of `file`, mapping it if `map` is set and that works.
+    fn from_file(file: File, map: bool) -> Result<Self, TableFileError> {
+        let actual = file.metadata()?.len();
+        let mut contents = match map {
+            #[cfg(unix)]
+            true => match Mapping::new(&file, actual) {
+                Some(mapping) => Contents::Mapped(mapping),
+                None => Contents::File(file),
+            },
+            _ => Contents::File(file),
+        };
+
+        let start: [u8; 9] = match contents.read(0, 9) {
+            Ok(start) => start.as_ref().try_into().unwrap(),
+            Err(_) => return Err(TableFileError::BadMagic),
+        };
+        if &start[..4] != MAGIC {
+            return Err(TableFileError::BadMagic);
+        }
+        if start[4] != VERSION {
+            return Err(TableFileError::UnsupportedVersion(start[4]));
+        }
+
+        // The length comes from the file, so it's checked before anything
+        // that size is read
+        let header_len = u32::from_le_bytes(start[5..].try_into().unwrap()) as usize;
+        if header_len > MAX_HEADER || start.len() as u64 + header_len as u64 > actual {
+            return Err(TableFileError::BadHeader);
+        }
+        let header = contents
+            .read(start.len() as u64, header_len)
+            .map_err(|_| TableFileError::BadHeader)?;
+        let mut reader = Reader::new(&header);
+        let mut names = || -> Option<(Vec<Symbol>, Symbol)> {
+            let num_inputs = reader.varint().ok()?;
+            let inputs = (0..num_inputs)
+                .map(|_| reader.str().ok().map(Symbol::intern))
+                .collect::<Option<Vec<_>>>()?;
+            Some((inputs, Symbol::intern(reader.str().ok()?)))
+        };
+        let (input_names, output_name) = names().ok_or(TableFileError::BadHeader)?;
+        if reader.position() != header.len() || input_names.len() >= 64 {
+            return Err(TableFileError::BadHeader);
+        }
+        drop(header);
+
+        let data = start.len() as u64 + header_len as u64;
+        let expected = data + data_len(input_names.len());
+        if actual != expected {
+            return Err(TableFileError::WrongLength { expected, actual });
+        }
+
+        Ok(Self {
+            contents,
+            input_names,
+            output_name,
+            data,
+        })
+    }
+
+    pub fn num_rows(&self) -> usize {
+        1 << self.input_names.len()
+    }
+
+    /// The output for row `row`, or `None` if it was left out by an
+    /// assumption.
+    ///
+    /// # Panics
+    ///
+    /// If `row` is past the end of the table.
+    pub fn get(&mut self, row: usize) -> io::Result<Option<bool>> {
+        assert!(row < self.num_rows(), "row {} is past the end", row);
+        let group = self.contents.read(self.data + 2 * (row / 8) as u64, 2)?;
+        let [outputs, included] = [group[0], group[1]].map(|byte| byte >> (row % 8) & 1 == 1);
+
+        Ok(included.then_some(outputs))
+    }
+
+    /// Call `f` with each row number and its output, or `None` for rows
+    /// left out by assumptions, reading the file a chunk at a time.
+    pub fn scan(&mut self, mut f: impl FnMut(usize, Option<bool>)) -> io::Result<()> {
+        for start in (0..self.num_rows()).step_by(CHUNK_SIZE) {
+            self.scan_range(start, start + CHUNK_SIZE, &mut f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `scan`, for the rows from `start` up to `end`, where `start` is
+    /// a multiple of eight.
+    fn scan_range(
+        &mut self,
+        start: usize,
+        end: usize,
+        mut f: impl FnMut(usize, Option<bool>),
+    ) -> io::Result<()> {
+        let end = end.min(self.num_rows());
+        let bytes = self.contents.read(
+            self.data + 2 * (start / 8) as u64,
+            2 * (end - start).div_ceil(8),
+        )?;
+        for row in start..end {
+            let group = 2 * ((row - start) / 8);
+            let [output, included] =
+                [bytes[group], bytes[group + 1]].map(|byte| byte >> (row % 8) & 1 == 1);
+            f(row, included.then_some(output));
+        }
+
+        Ok(())
+    }
+
+    /// Read the whole table into memory.
+    pub fn to_table(&mut self) -> io::Result<TruthTable> {
+        let length = self.input_names.len();
+        let (mut inputs, mut outputs) = (vec![], vec![]);
+        self.scan(|row, output| {
+            if let Some(output) = output {
+                inputs.push(assignments::to_bools(row, length));
+                outputs.push(output);
+            }
+        })?;
+
+        Ok(TruthTable {
+            input_names: self.input_names.clone(),
+            inputs,
+            output_name: self.output_name,
+            outputs,
+        })
+    }
+}
+
+/// The rows on which two tables over the same inputs differ, either in
+/// output or in whether they're in the table at all.
+pub fn diff(left: &mut TableFile, right: &mut TableFile) -> Result<Vec<usize>, TableFileError> {
+    if left.input_names != right.input_names {
+        return Err(TableFileError::DifferentInputs);
+    }
+
+    // Compare the tables a chunk of rows at a time, so that neither has to
+    // be in memory all at once
+    let mut outputs = vec![];
+    let mut differing = vec![];
+    for start in (0..left.num_rows()).step_by(CHUNK_SIZE) {
+        let end = start + CHUNK_SIZE;
+        outputs.clear();
+        left.scan_range(start, end, |_, output| outputs.push(output))?;
+        right.scan_range(start, end, |row, output| {
+            if outputs[row - start] != output {
+                differing.push(row);
+            }
+        })?;
+    }
+
+    Ok(differing)
+}
+
+#[test]
+fn test() {
+    use std::{env, fs, process};
+
+    use crate::{compiler::Compiler, parser::Parser};
+
+    let dir = env::temp_dir().join(format!("booltable-tablefile-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let save = |name: &str, source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        let mut vm = VM::new(Compiler::new(equation).compile());
+        let path = dir.join(name);
+        write(&mut vm, File::create(&path).unwrap()).unwrap();
+        (TableFile::open(&path).unwrap(), vm.gen())
+    };
+
+    let (mut file, table) = save("q.btt", "assume A != D; (A AND B) OR (C XOR D) = Q");
+    #[cfg(unix)]
+    assert!(matches!(file.contents, Contents::Mapped(_)));
+    assert_eq!(file.get(0b1001).unwrap(), Some(true));
+    assert_eq!(file.get(0b1111).unwrap(), None);
+    let loaded = file.to_table().unwrap();
+    assert_eq!(loaded.input_names, table.input_names);
+    assert_eq!(loaded.inputs, table.inputs);
+    assert_eq!(loaded.outputs, table.outputs);
+
+    let (mut small, _) = save("small.btt", "A = Q");
+    assert_eq!(small.get(1).unwrap(), Some(true));
+    let (mut other, _) = save("r.btt", "assume A != D; (A AND B) OR C = R");
+    // The inputs are A, D, B and C, and the tables differ where D is set
+    // and so A isn't
+    assert_eq!(diff(&mut file, &mut other).unwrap(), [4, 5, 6, 7]);
+    assert!(matches!(
+        diff(&mut file, &mut small),
+        Err(TableFileError::DifferentInputs)
+    ));
+
+    // Reading with seeks gives the same rows as the mapping
+    let mut unmapped = TableFile::from_file(File::open(dir.join("q.btt")).unwrap(), false).unwrap();
+    assert!(matches!(unmapped.contents, Contents::File(_)));
+    assert_eq!(unmapped.get(0b1001).unwrap(), Some(true));
+    assert_eq!(unmapped.to_table().unwrap().outputs, table.outputs);
+    assert_eq!(diff(&mut unmapped, &mut other).unwrap(), [4, 5, 6, 7]);
+
+    fs::write(dir.join("bad.btt"), b"BTBC\x01").unwrap();
+    assert!(matches!(
+        TableFile::open(dir.join("bad.btt")),
+        Err(TableFileError::BadMagic)
+    ));
+    // A header length past the end of the file is refused before it's read
+    fs::write(dir.join("long.btt"), b"BTTB\x01\xff\xff\xff\xff").unwrap();
+    assert!(matches!(
+        TableFile::open(dir.join("long.btt")),
+        Err(TableFileError::BadHeader)
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
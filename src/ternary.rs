@@ -0,0 +1,207 @@
+//! Three-valued evaluation, where an input can also be `X` for unknown and
+//! the operators follow Kleene's logic: an unknown only reaches the output
+//! when its value could change it, so `0 AND X` is still `0`.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::ops::Not;
+
+use derive_more::Display;
+
+use crate::{
+    compiler::Equation,
+    export::{self, TableStyle},
+    intern::Symbol,
+    vm::Op,
+};
+
+/// Tables grow as `3^n`, so they stop being useful sooner than binary ones.
+pub const MAX_INPUTS: usize = 10;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Logic {
+    #[display(fmt = "0")]
+    False,
+    #[display(fmt = "1")]
+    True,
+    #[display(fmt = "X")]
+    Unknown,
+}
+
+impl From<bool> for Logic {
+    fn from(value: bool) -> Self {
+        if value {
+            Logic::True
+        } else {
+            Logic::False
+        }
+    }
+}
+
+impl Not for Logic {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            Logic::False => Logic::True,
+            Logic::True => Logic::False,
+            Logic::Unknown => Logic::Unknown,
+        }
+    }
+}
+
+impl Logic {
+    /// Each value in the order rows enumerate them.
+    pub const ALL: [Logic; 3] = [Logic::False, Logic::True, Logic::Unknown];
+
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Logic::False, _) | (_, Logic::False) => Logic::False,
+            (Logic::True, Logic::True) => Logic::True,
+            _ => Logic::Unknown,
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (Logic::True, _) | (_, Logic::True) => Logic::True,
+            (Logic::False, Logic::False) => Logic::False,
+            _ => Logic::Unknown,
+        }
+    }
+
+    pub fn xor(self, other: Self) -> Self {
+        match (self, other) {
+            (Logic::Unknown, _) | (_, Logic::Unknown) => Logic::Unknown,
+            (a, b) => Logic::from(a != b),
+        }
+    }
+}
+
+/// Run compiled ops over three-valued inputs. Jumps are never taken, since
+/// the ops they skip give the same result when run.
+pub fn eval(ops: &[Op], inputs: &[Logic]) -> Logic {
+    let mut stack: Vec<Logic> = vec![];
+    for op in ops {
+        let value = match op {
+            Op::Push(v) => Logic::from(*v),
+            Op::Load(i) => inputs[*i],
+            Op::Not => !stack.pop().unwrap(),
+            Op::And | Op::Or | Op::Xor => {
+                let lhs = stack.pop().unwrap();
+                let rhs = stack.pop().unwrap();
+                match op {
+                    Op::And => lhs.and(rhs),
+                    Op::Or => lhs.or(rhs),
+                    _ => lhs.xor(rhs),
+                }
+            }
+            Op::JumpIfFalse(_) | Op::JumpIfTrue(_) => continue,
+        };
+        stack.push(value);
+    }
+
+    stack.pop().unwrap()
+}
+
+/// Like a `TruthTable`, but with a row for every assignment of `0`, `1` and
+/// `X`, counting in base 3 with the first input most significant. As in a
+/// `TruthTable`, rows that break an assumption are left out, which for a
+/// row with unknowns is when the assumption is `0` whatever they are.
+#[derive(Debug)]
+pub struct TernaryTable {
+    pub input_names: Vec<Symbol>,
+    pub inputs: Vec<Vec<Logic>>,
+    pub output_name: Symbol,
+    pub outputs: Vec<Logic>,
+}
+
+impl TernaryTable {
+    pub fn new(equation: &Equation) -> Self {
+        let length = equation.inputs.len();
+        let inputs: Vec<Vec<Logic>> = (0..3usize.pow(length as u32))
+            .map(|row| {
+                (1..=length)
+                    .map(|i| Logic::ALL[row / 3usize.pow((length - i) as u32) % 3])
+                    .collect()
+            })
+            .filter(|inputs: &Vec<Logic>| {
+                equation
+                    .assumptions
+                    .iter()
+                    .all(|ops| eval(ops, inputs) != Logic::False)
+            })
+            .collect();
+        let outputs = inputs
+            .iter()
+            .map(|inputs| eval(&equation.lhs, inputs))
+            .collect();
+
+        Self {
+            input_names: equation.inputs.clone(),
+            inputs,
+            output_name: equation.output,
+            outputs,
+        }
+    }
+
+    /// The table in markdown, with `0` and `1` spelled as in `style`.
+    pub fn to_markdown(&self, style: &TableStyle) -> String {
+        let symbol = |value: &Logic| match value {
+            Logic::False => style.false_symbol.clone(),
+            Logic::True => style.true_symbol.clone(),
+            Logic::Unknown => "X".into(),
+        };
+        let headers: Vec<&str> = self
+            .input_names
+            .iter()
+            .chain([&self.output_name])
+            .map(|name| name.as_str())
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .inputs
+            .iter()
+            .zip(&self.outputs)
+            .map(|(inputs, output)| inputs.iter().chain([output]).map(symbol).collect())
+            .collect();
+
+        export::grid(&headers, self.input_names.len(), &rows, style)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser};
+
+    let equation = Parser::new("A AND (B OR NOT B) = Q")
+        .parse_equation()
+        .unwrap();
+    let table = TernaryTable::new(&Compiler::new(equation).compile());
+    assert_eq!(table.outputs.len(), 9);
+    // `B OR NOT B` is unknown when B is, even though it's a tautology, which
+    // is the pessimism X-propagation is known for
+    assert_eq!(
+        table.outputs,
+        [
+            Logic::False,
+            Logic::False,
+            Logic::False,
+            Logic::True,
+            Logic::True,
+            Logic::Unknown,
+            Logic::Unknown,
+            Logic::Unknown,
+            Logic::Unknown,
+        ]
+    );
+    assert!(table
+        .to_markdown(&TableStyle::default())
+        .ends_with("| X | X | X |\n"));
+
+    // Only A = 0 breaks the assumption for certain, so A = X stays
+    let equation = Parser::new("assume A; A AND B = Q")
+        .parse_equation()
+        .unwrap();
+    let table = TernaryTable::new(&Compiler::new(equation).compile());
+    assert_eq!(table.inputs.len(), 6);
+    assert!(table.inputs.iter().all(|inputs| inputs[0] != Logic::False));
+}
@@ -0,0 +1,199 @@
+//! Seeded random equations for property tests, both the crate's own and
+//! those of downstream users, e.g. checking that a rewrite preserves the
+//! truth table. The same seed always gives the same sequence.
+//!
+//! With the `proptest` feature, `equations` is a strategy drawing on
+//! [`Generator`] and `any::<Equation>()` works, and with the `arbitrary`
+//! feature, fuzzers can build an `Equation` from raw bytes.
+
+use alloc::vec::Vec;
+#[cfg(feature = "proptest")]
+use core::ops::RangeInclusive;
+
+#[cfg(feature = "proptest")]
+use proptest::prelude::{any, BoxedStrategy, Strategy};
+
+use crate::{
+    parser::{Arena, BinOp, Equation, ExprId, Node, SpanExpr},
+    rng::Rng,
+};
+
+/// Names for generated inputs, which is also the most a generator can use.
+pub const NAMES: &[&str] = &[
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
+
+/// The most inputs and the deepest nesting of an arbitrary equation, small
+/// enough that its table is quick to build.
+pub const MAX_INPUTS: usize = 8;
+pub const MAX_DEPTH: usize = 6;
+
+/// Seeded with a `u64`, using the same SplitMix64 PRNG as sampling.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    /// A value in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.rng.below(n)
+    }
+
+    /// A random expression over variables `0..num_inputs`, nested at most
+    /// `depth` operators deep. Nodes have empty spans.
+    pub fn expr(&mut self, num_inputs: usize, depth: usize) -> SpanExpr {
+        SpanExpr::build(|arena| self.expr_in(arena, num_inputs, depth))
+    }
+
+    fn expr_in(&mut self, arena: &mut Arena, num_inputs: usize, depth: usize) -> ExprId {
+        let node = if depth == 0 || self.below(4) == 0 {
+            if num_inputs == 0 || self.below(10) == 0 {
+                Node::Bool(self.below(2) == 1)
+            } else {
+                Node::Var(self.below(num_inputs))
+            }
+        } else if self.below(4) == 0 {
+            Node::Not(self.expr_in(arena, num_inputs, depth - 1))
+        } else {
+            Node::BinOp {
+                op: [BinOp::And, BinOp::Or, BinOp::Xor][self.below(3)],
+                lhs: self.expr_in(arena, num_inputs, depth - 1),
+                rhs: self.expr_in(arena, num_inputs, depth - 1),
+            }
+        };
+
+        arena.push((0..0).into(), node)
+    }
+
+    /// A random equation drawing on the first `num_inputs` of `NAMES`, with
+    /// its inputs numbered in order of first appearance like the parser's.
+    pub fn equation(&mut self, num_inputs: usize, depth: usize) -> Equation {
+        assert!(num_inputs <= NAMES.len(), "at most {} inputs", NAMES.len());
+        let expr = self.expr(num_inputs, depth);
+        let mut order = Vec::new();
+        let lhs = SpanExpr::build(|arena| {
+            arena.substitute(expr.expr(), |arena, v, span| {
+                let v = order.iter().position(|&o| o == v).unwrap_or_else(|| {
+                    order.push(v);
+                    order.len() - 1
+                });
+                arena.push(span, Node::Var(v))
+            })
+        });
+
+        Equation {
+            inputs: order.into_iter().map(|v| NAMES[v].into()).collect(),
+            lhs,
+            output: "OUT".into(),
+            assumptions: Vec::new(),
+        }
+    }
+}
+
+/// Equations with a number of inputs in `inputs` and nested to a depth in
+/// `depth`, each from a [`Generator`] with an arbitrary seed. They shrink
+/// towards fewer inputs and shallower expressions.
+#[cfg(feature = "proptest")]
+pub fn equations(
+    inputs: RangeInclusive<usize>,
+    depth: RangeInclusive<usize>,
+) -> impl Strategy<Value = Equation> {
+    assert!(
+        *inputs.end() <= NAMES.len(),
+        "at most {} inputs",
+        NAMES.len()
+    );
+    (inputs, depth, any::<u64>())
+        .prop_map(|(inputs, depth, seed)| Generator::new(seed).equation(inputs, depth))
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Equation {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Equation>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        equations(0..=MAX_INPUTS, 0..=MAX_DEPTH).boxed()
+    }
+}
+
+/// Takes the number of inputs, the depth and the seed from the bytes, so a
+/// fuzzer's mutations still give well-formed equations.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Equation {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let inputs = u.int_in_range(0..=MAX_INPUTS)?;
+        let depth = u.int_in_range(0..=MAX_DEPTH)?;
+
+        Ok(Generator::new(u.arbitrary()?).equation(inputs, depth))
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, equiv, interp, minimize, parser::Parser, simplify, vm::VM};
+
+    let mut generator = Generator::new(42);
+    for _ in 0..100 {
+        let equation = generator.equation(5, 6);
+        let source = equation.to_source(Default::default());
+        let reparsed = Parser::new(&source).parse_equation().unwrap();
+        assert_eq!(reparsed.inputs, equation.inputs, "{}", source);
+        assert_eq!(interp::cross_check(&equation), Ok(()), "{}", source);
+
+        let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+        let minimised = Equation {
+            inputs: table.input_names.clone(),
+            lhs: minimize::minimize(&table),
+            output: "OUT".into(),
+            assumptions: Vec::new(),
+        };
+        assert_eq!(equiv::check(&equation, &minimised), Ok(()), "{}", source);
+
+        let simplified = Equation {
+            lhs: simplify::simplify(&equation.lhs),
+            ..equation.clone()
+        };
+        assert_eq!(equiv::check(&equation, &simplified), Ok(()), "{}", source);
+
+        let canonical = Equation {
+            lhs: equation.lhs.expr().canonicalise(),
+            ..equation.clone()
+        };
+        assert_eq!(equiv::check(&equation, &canonical), Ok(()), "{}", source);
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::test_runner::TestRunner::deterministic()
+        .run(&any::<Equation>(), |equation| {
+            proptest::prop_assert!(equation.inputs.len() <= MAX_INPUTS);
+            proptest::prop_assert_eq!(interp::cross_check(&equation), Ok(()));
+            Ok(())
+        })
+        .unwrap();
+
+    #[cfg(feature = "arbitrary")]
+    {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes: Vec<u8> = (0..1024).map(|_| generator.next_u64() as u8).collect();
+        let mut u = Unstructured::new(&bytes);
+        while !u.is_empty() {
+            let equation = Equation::arbitrary(&mut u).unwrap();
+            assert!(equation.inputs.len() <= MAX_INPUTS);
+            assert_eq!(interp::cross_check(&equation), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Rewrites of an expression: into a related function, its dual, with AND
+//! and OR and the constants swapped, and its complement, negated with De
+//! Morgan's laws rather than by wrapping it in NOT; and into the same
+//! function, in negation normal form or with its repeated subexpressions
+//! shared. Nodes keep the spans of those they came from.
+
+use alloc::collections::BTreeMap;
+
+use crate::parser::{Arena, BinOp, Expr, ExprId, ExprRef, Node, SpanExpr};
+
+/// The dual of `expr`: AND and OR swapped, as are `0` and `1`. XOR becomes
+/// its complement, XNOR, written as `NOT (A XOR B)`, since that's the dual
+/// of XOR as a function.
+pub fn dual(expr: &SpanExpr) -> SpanExpr {
+    SpanExpr::build(|arena| dual_in(expr.expr(), arena))
+}
+
+fn dual_in(expr: ExprRef, arena: &mut Arena) -> ExprId {
+    let span = expr.span();
+    let node = match expr.node() {
+        Expr::Bool(b) => Node::Bool(!b),
+        Expr::Var(v) => Node::Var(v),
+        Expr::Not(e) => Node::Not(dual_in(e, arena)),
+        Expr::BinOp { op, lhs, rhs } => {
+            let op = match op {
+                BinOp::And => BinOp::Or,
+                BinOp::Or => BinOp::And,
+                BinOp::Xor => BinOp::Xor,
+            };
+            let (lhs, rhs) = (dual_in(lhs, arena), dual_in(rhs, arena));
+            let swapped = Node::BinOp { op, lhs, rhs };
+            if op == BinOp::Xor {
+                Node::Not(arena.push(span, swapped))
+            } else {
+                swapped
+            }
+        }
+    };
+
+    arena.push(span, node)
+}
+
+/// The complement of `expr`, with the negation pushed down to the variables
+/// by De Morgan's laws. For XOR only the left operand is complemented.
+pub fn complement(expr: &SpanExpr) -> SpanExpr {
+    SpanExpr::build(|arena| complement_in(expr.expr(), arena))
+}
+
+fn complement_in(expr: ExprRef, arena: &mut Arena) -> ExprId {
+    let node = match expr.node() {
+        Expr::Bool(b) => Node::Bool(!b),
+        Expr::Var(_) => Node::Not(arena.copy(expr)),
+        Expr::Not(e) => return arena.copy(e),
+        Expr::BinOp { op, lhs, rhs } => match op {
+            BinOp::And | BinOp::Or => Node::BinOp {
+                op: if op == BinOp::And {
+                    BinOp::Or
+                } else {
+                    BinOp::And
+                },
+                lhs: complement_in(lhs, arena),
+                rhs: complement_in(rhs, arena),
+            },
+            BinOp::Xor => Node::BinOp {
+                op: BinOp::Xor,
+                lhs: complement_in(lhs, arena),
+                rhs: arena.copy(rhs),
+            },
+        },
+    };
+
+    arena.push(expr.span(), node)
+}
+
+/// `expr` in negation normal form, with every `NOT` pushed down onto a
+/// variable. Negated constants are folded, and `XOR` stays as it is, with a
+/// negation moved onto its left operand.
+pub fn nnf(expr: &SpanExpr) -> SpanExpr {
+    SpanExpr::build(|arena| nnf_in(expr.expr(), false, arena))
+}
+
+fn nnf_in(expr: ExprRef, negate: bool, arena: &mut Arena) -> ExprId {
+    let node = match expr.node() {
+        Expr::Bool(b) => Node::Bool(b != negate),
+        Expr::Var(v) if !negate => Node::Var(v),
+        Expr::Var(_) => Node::Not(nnf_in(expr, false, arena)),
+        Expr::Not(e) => return nnf_in(e, !negate, arena),
+        Expr::BinOp { op, lhs, rhs } => {
+            let op = match (op, negate) {
+                (BinOp::And, true) => BinOp::Or,
+                (BinOp::Or, true) => BinOp::And,
+                (op, _) => op,
+            };
+            Node::BinOp {
+                op,
+                lhs: nnf_in(lhs, negate, arena),
+                rhs: nnf_in(rhs, negate && op != BinOp::Xor, arena),
+            }
+        }
+    };
+
+    arena.push(expr.span(), node)
+}
+
+/// `expr` with its repeated subexpressions stored once, so that both `A AND
+/// B` in `(A AND B) OR NOT (A AND B)` are the same node. Each keeps the span
+/// of where it first appears.
+pub fn share(expr: &SpanExpr) -> SpanExpr {
+    let (mut nodes, mut copied) = (BTreeMap::new(), BTreeMap::new());
+    SpanExpr::build(|arena| share_in(expr.expr(), arena, &mut nodes, &mut copied))
+}
+
+/// `nodes` holds the id of each node already in `arena`, and `copied` the
+/// id each node of the source already visited became.
+fn share_in(
+    expr: ExprRef,
+    arena: &mut Arena,
+    nodes: &mut BTreeMap<Node, ExprId>,
+    copied: &mut BTreeMap<ExprId, ExprId>,
+) -> ExprId {
+    if let Some(&id) = copied.get(&expr.id()) {
+        return id;
+    }
+    let node = match expr.node() {
+        Expr::Bool(b) => Node::Bool(b),
+        Expr::Var(v) => Node::Var(v),
+        Expr::Not(e) => Node::Not(share_in(e, arena, nodes, copied)),
+        Expr::BinOp { op, lhs, rhs } => Node::BinOp {
+            op,
+            lhs: share_in(lhs, arena, nodes, copied),
+            rhs: share_in(rhs, arena, nodes, copied),
+        },
+    };
+    let id = *nodes
+        .entry(node)
+        .or_insert_with(|| arena.push(expr.span(), node));
+    copied.insert(expr.id(), id);
+
+    id
+}
+
+#[test]
+fn test() {
+    use crate::{interp, parser::Parser};
+
+    let parse = |source| Parser::new(source).parse_equation().unwrap();
+    let show = |expr: SpanExpr, source| expr.display(&parse(source).inputs).to_string();
+
+    let source = "(A AND 1) OR NOT (B AND C) = Q";
+    assert_eq!(
+        show(dual(&parse(source).lhs), source),
+        "(A OR 0) AND NOT (B OR C)"
+    );
+    assert_eq!(
+        show(complement(&parse(source).lhs), source),
+        "((NOT A) OR 0) AND B AND C"
+    );
+    let source = "NOT ((A OR NOT B) AND (C XOR 1)) = Q";
+    assert_eq!(
+        show(nnf(&parse(source).lhs), source),
+        "((NOT A) AND B) OR ((NOT C) XOR 1)"
+    );
+
+    for source in [source, "A XOR (B OR NOT C) = Q", "NOT (A XOR B) AND C = Q"] {
+        let lhs = parse(source).lhs;
+        let (dual, complement, nnf) = (dual(&lhs), complement(&lhs), nnf(&lhs));
+        for row in 0..8 {
+            let inputs: [bool; 3] = core::array::from_fn(|i| row >> i & 1 == 1);
+            let negated = inputs.map(|b| !b);
+            let value = interp::eval(lhs.expr(), &inputs);
+            assert_eq!(
+                interp::eval(complement.expr(), &inputs),
+                !value,
+                "{}",
+                source
+            );
+            assert_eq!(
+                interp::eval(dual.expr(), &inputs),
+                !interp::eval(lhs.expr(), &negated),
+                "{}",
+                source
+            );
+            assert_eq!(interp::eval(nnf.expr(), &inputs), value, "{}", source);
+        }
+    }
+
+    // The two products and their variables are each stored once
+    let source = "(A AND B) OR NOT (A AND B) = Q";
+    let lhs = parse(source).lhs;
+    let shared = share(&lhs);
+    assert_eq!(
+        SpanExpr::build(|arena| arena.copy(lhs.expr())).arena.len(),
+        8
+    );
+    assert_eq!(shared.arena.len(), 5);
+    assert_eq!(show(shared.clone(), source), "(A AND B) OR NOT (A AND B)");
+    let Expr::BinOp { lhs, rhs, .. } = shared.node() else {
+        unreachable!()
+    };
+    assert_eq!(lhs.span(), (0..9).into());
+    assert_eq!(rhs.span(), (13..26).into());
+}
@@ -0,0 +1,321 @@
+//! The interactive explorer, drawn with escape codes on a Unix terminal
+//! that is put into non-canonical mode with termios.
+
+use std::{
+    io::{self, Read, Write},
+    mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use booltable::{
+    highlight::highlight,
+    minimize,
+    parser::{self, Dialect},
+    vm::{Row, TruthTable},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const REVERSE: &str = "\x1b[7m";
+const GREEN: &str = "\x1b[32m";
+const DIM: &str = "\x1b[2m";
+
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Toggle(Option<usize>),
+    Copy,
+    Quit,
+    /// The terminal changed size, so the screen needs drawing again.
+    Resize,
+    Other,
+}
+
+/// Set by the `SIGWINCH` handler, and cleared when the size is read again.
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_: libc::c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+/// Puts the terminal into unbuffered, no-echo mode on an alternate screen
+/// for as long as it is alive, noting when it's resized.
+struct Terminal {
+    saved: libc::termios,
+    resize: libc::sigaction,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        // SAFETY: the termios and sigaction structs are plain data that the
+        // calls fill in, and the handler only stores to an atomic
+        unsafe {
+            let mut saved = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut saved) != 0 {
+                return Err(io::Error::other("stdin isn't a terminal"));
+            }
+            let mut raw = saved;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // Without SA_RESTART, so that a resize interrupts the read
+            // waiting for a key
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut resize = mem::zeroed();
+            libc::sigaction(libc::SIGWINCH, &action, &mut resize);
+
+            print!("\x1b[?1049h\x1b[?25l");
+            io::stdout().flush()?;
+            Ok(Self { saved, resize })
+        }
+    }
+}
+
+/// The terminal's height and width, if stdout is one.
+pub fn size() -> Option<(usize, usize)> {
+    // SAFETY: TIOCGWINSZ only writes a winsize
+    let size = unsafe {
+        let mut size: libc::winsize = mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0 {
+            return None;
+        }
+        size
+    };
+
+    (size.ws_row > 0 && size.ws_col > 0).then_some((size.ws_row as usize, size.ws_col as usize))
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+        // SAFETY: both were filled in by `enter`
+        unsafe {
+            libc::sigaction(libc::SIGWINCH, &self.resize, std::ptr::null_mut());
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+fn read_key(stdin: &mut impl Read) -> io::Result<Key> {
+    let mut byte = [0];
+    loop {
+        match stdin.read(&mut byte) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => break,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                if RESIZED.load(Ordering::SeqCst) {
+                    return Ok(Key::Resize);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(match byte[0] {
+        b'q' | b'Q' => Key::Quit,
+        b'c' => Key::Copy,
+        b' ' | b'\n' => Key::Toggle(None),
+        b @ b'1'..=b'9' => Key::Toggle(Some((b - b'1') as usize)),
+        b'k' => Key::Up,
+        b'j' => Key::Down,
+        b'h' => Key::Left,
+        b'l' => Key::Right,
+        0x1b => {
+            let mut seq = [0; 2];
+            if stdin.read_exact(&mut seq).is_err() || seq[0] != b'[' {
+                return Ok(Key::Other);
+            }
+            match seq[1] {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'C' => Key::Right,
+                b'D' => Key::Left,
+                _ => Key::Other,
+            }
+        }
+        _ => Key::Other,
+    })
+}
+
+struct Explorer<'a> {
+    table: &'a TruthTable,
+    source: String,
+    minimised: String,
+    lines: Vec<String>,
+    row: usize,
+    cursor: usize,
+    /// Shown under the table until the next key.
+    status: Option<String>,
+    /// The terminal's height, read when the explorer starts and whenever
+    /// it's resized.
+    height: usize,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(equation: &parser::Equation, table: &'a TruthTable, dialect: Dialect) -> Self {
+        let minimised = match minimize::check_size(table.input_names.len()) {
+            Ok(()) => highlight(&format!(
+                "{} = {}",
+                minimize::minimize(table).display_in(&table.input_names, dialect),
+                table.output_name
+            )),
+            Err(err) => err,
+        };
+        Self {
+            table,
+            source: highlight(&equation.to_source(dialect)),
+            minimised,
+            lines: table.to_string().lines().map(String::from).collect(),
+            row: 0,
+            cursor: 0,
+            status: None,
+            height: Self::height(),
+        }
+    }
+
+    fn height() -> usize {
+        RESIZED.store(false, Ordering::SeqCst);
+        size().map_or(24, |(height, _)| height)
+    }
+
+    fn width(&self) -> usize {
+        self.table.input_names.len()
+    }
+
+    /// Flip one input, moving to the row for the new assignment unless an
+    /// assumption left it out of the table.
+    fn toggle(&mut self, input: usize) {
+        if input >= self.width() {
+            return;
+        }
+        let mut assignment = self.current().assignment.to_vec();
+        assignment[input] = !assignment[input];
+        if let Some(row) = self
+            .table
+            .rows()
+            .position(|row| row.assignment == assignment)
+        {
+            self.row = row;
+        }
+    }
+
+    fn current(&self) -> Row<'_> {
+        self.table.row(self.row).unwrap()
+    }
+
+    /// Put the table on the clipboard, saying how that went.
+    fn copy(&self) -> String {
+        #[cfg(feature = "clipboard")]
+        {
+            let mut table = self.lines.join("\n");
+            table.push('\n');
+            match crate::clipboard::copy(&table) {
+                Ok(()) => "Copied the table".to_string(),
+                Err(err) => format!("Couldn't copy the table: {}", err),
+            }
+        }
+        #[cfg(not(feature = "clipboard"))]
+        {
+            "Copying needs booltable built with the 'clipboard' feature".to_string()
+        }
+    }
+
+    fn handle(&mut self, key: Key) -> bool {
+        let rows = self.table.len();
+        self.status = None;
+        match key {
+            Key::Up => self.row = (self.row + rows - 1) % rows,
+            Key::Down => self.row = (self.row + 1) % rows,
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.width().saturating_sub(1)),
+            Key::Toggle(input) => self.toggle(input.unwrap_or(self.cursor)),
+            Key::Copy => self.status = Some(self.copy()),
+            Key::Quit => return false,
+            Key::Resize => self.height = Self::height(),
+            Key::Other => {}
+        }
+
+        true
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "\x1b[H\x1b[2J")?;
+
+        writeln!(out, "{BOLD}Inputs{RESET}")?;
+        let row = self.current();
+        for (i, name) in self.table.input_names.iter().enumerate() {
+            let on = row.assignment[i];
+            let style = if i == self.cursor { REVERSE } else { "" };
+            let lamp = if on {
+                format!("{GREEN}[1]{RESET}")
+            } else {
+                "[0]".to_string()
+            };
+            write!(out, "  {style}{name}{RESET} {lamp}")?;
+        }
+        writeln!(out)?;
+
+        let lamp = if row.output {
+            format!("{GREEN}● 1{RESET}")
+        } else {
+            "○ 0".to_string()
+        };
+        writeln!(
+            out,
+            "{BOLD}Output{RESET}\n  {} {lamp}\n",
+            self.table.output_name
+        )?;
+
+        writeln!(out, "{BOLD}Expression{RESET}\n  {}", self.source)?;
+        writeln!(out, "{BOLD}Minimised{RESET}\n  {}\n", self.minimised)?;
+
+        // Keep the current row in view, leaving room for the panes above
+        let visible = self.height.saturating_sub(14).max(1);
+        let first = self
+            .row
+            .saturating_sub(visible / 2)
+            .min(self.table.len().saturating_sub(visible));
+        let (header, body) = self.lines.split_at(2);
+        writeln!(out, "{}\n{}", header[0], header[1])?;
+        for (i, line) in body.iter().enumerate().skip(first).take(visible) {
+            if i == self.row {
+                writeln!(out, "{REVERSE}{line}{RESET}")?;
+            } else {
+                writeln!(out, "{line}")?;
+            }
+        }
+
+        if let Some(status) = &self.status {
+            write!(out, "\n{status}")?;
+        }
+        write!(
+            out,
+            "\n{DIM}↑/↓ row  ←/→ select  space toggle  1-9 toggle input  c copy  q quit{RESET}"
+        )?;
+        out.flush()
+    }
+}
+
+/// Run the interactive explorer for an equation until the user quits.
+pub fn run(equation: &parser::Equation, table: &TruthTable, dialect: Dialect) -> io::Result<()> {
+    let _terminal = Terminal::enter()?;
+    let mut explorer = Explorer::new(equation, table, dialect);
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        explorer.draw(&mut stdout)?;
+        if !explorer.handle(read_key(&mut stdin)?) {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Checking a table against an expected truth vector, the output column
+//! written as a number whose bit `i` is the output for minterm `i`. For
+//! example, majority of three is `0xE8`, `0b11101000`.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use derive_more::Display;
+
+use crate::{assignments, column::BitColumn, rng::Rng, vm::TruthTable};
+
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum VectorError {
+    #[display(fmt = "a truth vector starts with 0x for hex or 0b for binary")]
+    MissingPrefix,
+    #[display(fmt = "'{}' is not a digit of the truth vector", _0)]
+    InvalidDigit(char),
+    #[display(fmt = "the truth vector has bits set past the table's {} rows", _0)]
+    TooLong(usize),
+}
+
+/// Parse a hex or binary truth vector for a table of `rows` rows, with
+/// minterm 0 in the lowest bit. Underscores are ignored, and missing high
+/// digits are zero.
+pub fn parse(spec: &str, rows: usize) -> Result<BitColumn, VectorError> {
+    let spec = spec.trim();
+    let (digits, radix) = match spec.get(..2) {
+        Some("0x" | "0X") => (&spec[2..], 16),
+        Some("0b" | "0B") => (&spec[2..], 2),
+        _ => return Err(VectorError::MissingPrefix),
+    };
+    let width = if radix == 16 { 4 } else { 1 };
+
+    let mut bits = Vec::with_capacity(rows);
+    for c in digits.chars().rev().filter(|c| *c != '_') {
+        let digit = c.to_digit(radix).ok_or(VectorError::InvalidDigit(c))?;
+        for bit in 0..width {
+            bits.push(digit >> bit & 1 == 1);
+        }
+    }
+    if bits.iter().skip(rows).any(|b| *b) {
+        return Err(VectorError::TooLong(rows));
+    }
+    bits.resize(rows, false);
+
+    Ok(BitColumn::from_bools(bits))
+}
+
+/// `outputs` as a hex truth vector, the inverse of `parse`, with a digit
+/// for every four rows, or part of one.
+pub fn format(outputs: &[bool]) -> String {
+    let digits: String = outputs
+        .chunks(4)
+        .rev()
+        .map(|chunk| {
+            let digit = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |n, (bit, b)| n | (*b as u32) << bit);
+            char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+
+    format!("0x{}", digits)
+}
+
+/// A table's output column as a number whose bit `i` is the output for
+/// minterm `i`, in the smallest type that holds every row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TruthVector {
+    /// Up to 6 inputs.
+    U64(u64),
+    /// 7 inputs.
+    U128(u128),
+    /// More than 7 inputs, in 64-bit words with the lowest rows first.
+    Big(Vec<u64>),
+}
+
+impl fmt::Display for TruthVector {
+    /// In hex, without leading zeros, as in `0xE8`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TruthVector::U64(n) => write!(f, "{:#X}", n),
+            TruthVector::U128(n) => write!(f, "{:#X}", n),
+            TruthVector::Big(words) => {
+                let mut words = words.iter().rev().skip_while(|w| **w == 0);
+                match words.next() {
+                    None => f.write_str("0x0"),
+                    Some(first) => {
+                        write!(f, "{:#X}", first)?;
+                        words.try_for_each(|w| write!(f, "{:016X}", w))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TruthTable {
+    /// The output column as a truth vector. Rows left out by assumptions are
+    /// treated as false, as they are when minimising.
+    pub fn as_truth_vector(&self) -> TruthVector {
+        let num_inputs = self.input_names.len();
+        let mut words = vec![0u64; (1usize << num_inputs).div_ceil(64)];
+        for row in self.rows().filter(|row| row.output) {
+            let minterm = assignments::to_row(row.assignment);
+            words[minterm / 64] |= 1 << (minterm % 64);
+        }
+
+        match num_inputs {
+            0..=6 => TruthVector::U64(words[0]),
+            7 => TruthVector::U128(words[0] as u128 | (words[1] as u128) << 64),
+            _ => TruthVector::Big(words),
+        }
+    }
+}
+
+/// A random output column for a table of `rows` rows. When `balanced`, it
+/// is true on exactly half of them, rounding down. The same seed gives the
+/// same column.
+pub fn random(rows: usize, seed: u64, balanced: bool) -> Vec<bool> {
+    let mut rng = Rng::new(seed);
+    if !balanced {
+        return (0..rows).map(|_| rng.next_u64() & 1 == 1).collect();
+    }
+
+    // Shuffle a column with the right number of true rows
+    let mut outputs = vec![false; rows];
+    outputs[..rows / 2].fill(true);
+    for i in (1..rows).rev() {
+        outputs.swap(i, rng.below(i + 1));
+    }
+
+    outputs
+}
+
+/// The minterms whose output differs from `expected`, in order. Rows left
+/// out by assumptions aren't checked.
+pub fn mismatches(table: &TruthTable, expected: &BitColumn) -> Vec<usize> {
+    table
+        .rows()
+        .filter_map(|row| {
+            let minterm = assignments::to_row(row.assignment);
+            (expected.get(minterm) != Some(row.output)).then_some(minterm)
+        })
+        .collect()
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let table = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen()
+    };
+    let majority = table("(A AND B) OR (C AND (A OR B)) = Q");
+    assert!(mismatches(&majority, &parse("0xE8", 8).unwrap()).is_empty());
+    assert_eq!(
+        mismatches(&majority, &parse("0b1110_1001", 8).unwrap()),
+        [0]
+    );
+    assert_eq!(
+        mismatches(&table("A AND B AND C = Q"), &parse("0xe8", 8).unwrap()),
+        [3, 5, 6]
+    );
+
+    assert_eq!(parse("E8", 8), Err(VectorError::MissingPrefix));
+    assert_eq!(parse("0xG8", 8), Err(VectorError::InvalidDigit('G')));
+    assert_eq!(parse("0x1E8", 8), Err(VectorError::TooLong(8)));
+    assert_eq!(parse("0x0_1", 2), Ok(BitColumn::from_bools([true, false])));
+
+    assert_eq!(majority.as_truth_vector(), TruthVector::U64(0xE8));
+    assert_eq!(majority.as_truth_vector().to_string(), "0xE8");
+    // An AND of `n` inputs is only true on the last row
+    let and = |n: usize| {
+        let names: Vec<String> = (0..n).map(|i| format!("X{}", i)).collect();
+        format!("{} = Q", names.join(" AND "))
+    };
+    let (seven, eight) = (and(7), and(8));
+    assert_eq!(table(&seven).as_truth_vector(), TruthVector::U128(1 << 127));
+    let vector = table(&eight).as_truth_vector();
+    assert_eq!(vector, TruthVector::Big(vec![0, 0, 0, 1 << 63]));
+    assert_eq!(vector.to_string(), format!("0x8{}", "0".repeat(63)));
+    assert_eq!(
+        parse(&vector.to_string(), 256),
+        Ok(BitColumn::from_bools((0..256).map(|i| i == 255)))
+    );
+    // Rows left out count as false
+    let filtered = table("assume A; A OR B = Q");
+    assert_eq!(filtered.as_truth_vector().to_string(), "0xC");
+
+    let majority: Vec<bool> = majority.rows().map(|row| row.output).collect();
+    assert_eq!(format(&majority), "0xE8");
+    assert_eq!(format(&[false, true]), "0x2");
+    for balanced in [false, true] {
+        let outputs = random(64, 42, balanced);
+        assert_eq!(outputs, random(64, 42, balanced));
+        let column = BitColumn::from_bools(outputs.iter().copied());
+        assert_eq!(parse(&format(&outputs), 64), Ok(column));
+    }
+    assert_eq!(random(64, 42, true).iter().filter(|b| **b).count(), 32);
+}
@@ -1,11 +1,22 @@
-use std::fmt;
+use alloc::{vec, vec::Vec};
+use core::{fmt, ops::Index};
 
 use derive_more::Display;
 
-use crate::compiler::Equation;
+use crate::{
+    assignments,
+    compiler::{verify, Equation},
+    export::{self, Format, TableStyle},
+    intern::Symbol,
+};
 
 pub type Value = bool;
 
+/// Ops for a stack machine. A binary op pops its left operand and then its
+/// right, so the right operand is pushed first: `A AND B` is `LOAD B`,
+/// `LOAD A`, `AND`. Every op that exists commutes, but an op that doesn't
+/// (implication, say) has to keep to this order for the compiler, the VM and
+/// the other passes over ops to agree, which `compiler::decompile` checks.
 #[derive(Debug, Display, Clone, Copy)]
 pub enum Op {
     #[display(fmt = "PUSH {}", _0)]
@@ -20,72 +31,224 @@ pub enum Op {
     Or,
     #[display(fmt = "XOR")]
     Xor,
+    /// Jump to the op at the index if the top of the stack is false,
+    /// leaving it there. Skipping the second operand of an `AND` and the
+    /// `AND` itself leaves the result it would have given.
+    #[display(fmt = "JUMP IF FALSE {}", _0)]
+    JumpIfFalse(usize),
+    /// Like `JumpIfFalse`, for skipping the rest of an `OR`.
+    #[display(fmt = "JUMP IF TRUE {}", _0)]
+    JumpIfTrue(usize),
 }
 
-pub struct VM<'input> {
-    equation: Equation<'input>,
+pub struct VM {
+    pub(crate) equation: Equation,
     stack: Vec<Value>,
 }
 
 #[derive(Debug)]
-pub struct TruthTable<'input> {
-    pub input_names: Vec<&'input str>,
-    pub inputs: Vec<Vec<bool>>,
-    pub output_name: &'input str,
-    pub outputs: Vec<bool>,
+pub struct TruthTable {
+    pub input_names: Vec<Symbol>,
+    pub(crate) inputs: Vec<Vec<bool>>,
+    pub output_name: Symbol,
+    pub(crate) outputs: Vec<bool>,
 }
 
-impl<'input> fmt::Display for TruthTable<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let output_length = self.output_name.len();
-        let lengths: Vec<usize> = self.input_names.iter().map(|n| n.len()).collect();
-        writeln!(
-            f,
-            "| {} | {} |",
-            join(&self.input_names, " | "),
-            self.output_name
-        )?;
-        writeln!(
-            f,
-            "|{}|{}|",
-            lengths
-                .iter()
-                .map(|x| "-".repeat(*x + 2))
-                .collect::<Vec<_>>()
-                .join("|"),
-            "-".repeat(output_length + 2)
-        )?;
-
-        let rows: Vec<String> = self
-            .inputs
+/// How many of a table's rows have each output.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[display(
+    fmt = "{} true, {} false ({:.1}% true)",
+    true_rows,
+    false_rows,
+    "self.fraction() * 100.0"
+)]
+pub struct Summary {
+    pub true_rows: usize,
+    pub false_rows: usize,
+}
+
+impl Summary {
+    /// The fraction of rows that are true, or 0 for an empty table.
+    pub fn fraction(&self) -> f64 {
+        match self.true_rows + self.false_rows {
+            0 => 0.0,
+            total => self.true_rows as f64 / total as f64,
+        }
+    }
+}
+
+/// One row of a table, whose inputs can be read by position from
+/// `assignment` or by name with `row["A"]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row<'a> {
+    pub names: &'a [Symbol],
+    pub assignment: &'a [bool],
+    pub output: bool,
+}
+
+impl Row<'_> {
+    /// The value of the input called `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<bool> {
+        let index = self.names.iter().position(|n| *n == name)?;
+        Some(self.assignment[index])
+    }
+}
+
+impl Index<&str> for Row<'_> {
+    type Output = bool;
+
+    /// # Panics
+    ///
+    /// If `name` isn't an input of the table.
+    fn index(&self, name: &str) -> &bool {
+        match self.names.iter().position(|n| *n == name) {
+            Some(index) => &self.assignment[index],
+            None => panic!("'{}' is not an input of the table", name),
+        }
+    }
+}
+
+/// The rows of a table in order, from `TruthTable::rows`.
+pub struct Rows<'a> {
+    table: &'a TruthTable,
+    next: usize,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Row<'a>;
+
+    fn next(&mut self) -> Option<Row<'a>> {
+        let row = self.table.row(self.next)?;
+        self.next += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.len() - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+impl<'a> IntoIterator for &'a TruthTable {
+    type Item = Row<'a>;
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Rows<'a> {
+        self.rows()
+    }
+}
+
+impl TruthTable {
+    /// The number of rows, which is less than `2^n` when assumptions left
+    /// some out.
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Row `index`, counting from the first assignment.
+    pub fn row(&self, index: usize) -> Option<Row<'_>> {
+        Some(Row {
+            names: &self.input_names,
+            assignment: self.inputs.get(index)?,
+            output: self.outputs[index],
+        })
+    }
+
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            table: self,
+            next: 0,
+        }
+    }
+
+    /// Whether some assignment makes the output true.
+    pub fn is_satisfiable(&self) -> bool {
+        self.outputs.iter().any(|o| *o)
+    }
+
+    /// Whether every assignment makes the output true.
+    pub fn is_tautology(&self) -> bool {
+        self.outputs.iter().all(|o| *o)
+    }
+
+    /// Whether no assignment makes the output true.
+    pub fn is_contradiction(&self) -> bool {
+        !self.is_satisfiable()
+    }
+
+    /// How many of the assignments make the output true.
+    pub fn count(&self) -> usize {
+        self.outputs.iter().filter(|o| **o).count()
+    }
+
+    pub fn summary(&self) -> Summary {
+        let true_rows = self.count();
+        Summary {
+            true_rows,
+            false_rows: self.len() - true_rows,
+        }
+    }
+
+    /// The output for `assignment`, given in the order of `input_names`, or
+    /// `None` if it is the wrong length or its row was left out by an
+    /// assumption.
+    pub fn get(&self, assignment: &[bool]) -> Option<bool> {
+        if assignment.len() != self.input_names.len() {
+            return None;
+        }
+        if self.outputs.len() == 1 << assignment.len() {
+            let row = assignments::to_row(assignment);
+            return Some(self.outputs[row]);
+        }
+
+        self.inputs
             .iter()
-            .map(|r| {
-                format!(
-                    "| {} |",
-                    r.iter()
-                        .zip(&lengths)
-                        .map(|(a, b)| format!("{:<b$}", if *a { 1 } else { 0 }))
-                        .collect::<Vec<_>>()
-                        .join(" | ")
-                )
-            })
-            .zip(&self.outputs)
-            .map(|(i, o)| format!("{} {:<output_length$} |", i, if *o { 1 } else { 0 }))
+            .position(|inputs| inputs == assignment)
+            .map(|row| self.outputs[row])
+    }
+
+    /// Like `get`, but with the assignment given by name. Returns `None` if
+    /// an input is missing, as well as for names that aren't inputs.
+    #[cfg(feature = "std")]
+    pub fn get_named(&self, assignment: &std::collections::HashMap<&str, bool>) -> Option<bool> {
+        if assignment.len() != self.input_names.len() {
+            return None;
+        }
+        let assignment: Option<Vec<bool>> = self
+            .input_names
+            .iter()
+            .map(|name| assignment.get(name.as_str()).copied())
             .collect();
-        writeln!(f, "{}", join(&rows, "\n"),)
+
+        self.get(&assignment?)
     }
 }
 
-fn join<T: ToString>(things: &[T], sep: &str) -> String {
-    things
-        .iter()
-        .map(ToString::to_string)
-        .collect::<Vec<_>>()
-        .join(sep)
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = TableStyle::default();
+        f.write_str(&export::render(self, Format::Markdown, &style))
+    }
 }
 
-impl<'input> VM<'input> {
-    pub fn new(equation: Equation<'input>) -> Self {
+/// The most inputs a table can have, as rows are numbered with a `usize`.
+pub const MAX_INPUTS: usize = usize::BITS as usize - 1;
+
+/// How many rows `VM::gen_with_progress` generates between reports.
+pub const PROGRESS_INTERVAL: usize = 1 << 12;
+
+impl VM {
+    pub fn new(equation: Equation) -> Self {
+        for ops in equation.assumptions.iter().chain([&equation.lhs]) {
+            debug_assert_eq!(verify(ops, equation.inputs.len()), Ok(()));
+        }
+
         Self {
             equation,
             stack: vec![],
@@ -93,58 +256,162 @@ impl<'input> VM<'input> {
     }
 
     fn exec(&mut self, inputs: &[Value]) -> Value {
-        macro_rules! binop {
-            ($op:tt) => {{
-                let lhs = self.pop();
-                let rhs = self.pop();
-                self.stack.push(lhs $op rhs);
-            }};
-        }
+        self.run(inputs, |_, _| {})
+    }
 
-        for ip in 0..self.equation.lhs.len() {
-            match self.equation.lhs[ip] {
-                Op::Push(v) => self.stack.push(v),
-                Op::Load(i) => self.stack.push(inputs[i]),
-                Op::Not => {
-                    let operand = self.pop();
-                    self.stack.push(!operand);
-                }
-                Op::And => binop!(&&),
-                Op::Or => binop!(||),
-                Op::Xor => binop!(^),
-            }
-        }
+    fn run(&mut self, inputs: &[Value], step: impl FnMut(Op, &[Value])) -> Value {
+        execute(&mut self.stack, &self.equation.lhs, inputs, step)
+    }
+
+    /// Whether `inputs` satisfy every assumption, so its row belongs in the
+    /// table.
+    pub(crate) fn assumed(&mut self, inputs: &[Value]) -> bool {
+        self.equation
+            .assumptions
+            .iter()
+            .all(|ops| execute(&mut self.stack, ops, inputs, |_, _| {}))
+    }
 
-        self.pop()
+    /// Evaluate the equation for a single assignment of its inputs.
+    pub fn eval(&mut self, inputs: &[Value]) -> Value {
+        assert_eq!(inputs.len(), self.equation.inputs.len());
+        self.exec(inputs)
     }
 
-    pub fn gen(&mut self) -> TruthTable<'input> {
+    /// Like `eval`, but also returns each executed op along with the stack
+    /// as it was left by that op.
+    pub fn trace(&mut self, inputs: &[Value]) -> (Value, Vec<(Op, Vec<Value>)>) {
+        assert_eq!(inputs.len(), self.equation.inputs.len());
+        let mut steps = vec![];
+        let output = self.run(inputs, |op, stack| steps.push((op, stack.to_vec())));
+
+        (output, steps)
+    }
+
+    /// Evaluate every assignment, skipping those that break an assumption.
+    pub fn gen(&mut self) -> TruthTable {
+        self.gen_with_progress(|_, _| true).unwrap()
+    }
+
+    /// Like `gen`, but calls `progress` with the number of rows done so far
+    /// and the total every `PROGRESS_INTERVAL` rows, and once at the end.
+    /// Generation stops, returning `None`, as soon as `progress` returns
+    /// false, so it can be cancelled, e.g. by checking an `AtomicBool`.
+    pub fn gen_with_progress(
+        &mut self,
+        mut progress: impl FnMut(usize, usize) -> bool,
+    ) -> Option<TruthTable> {
         let length = self.equation.inputs.len();
         let num_rows = 1 << length;
-        let inputs: Vec<Vec<bool>> = (0..num_rows).map(|i| usize_to_bools(i, length)).collect();
-        let outputs: Vec<bool> = inputs.iter().map(|inputs| self.exec(inputs)).collect();
-
-        TruthTable {
+        let mut table = TruthTable {
             input_names: self.equation.inputs.clone(),
-            inputs,
+            inputs: vec![],
             output_name: self.equation.output,
-            outputs,
+            outputs: vec![],
+        };
+
+        for row in 0..num_rows {
+            if row % PROGRESS_INTERVAL == 0 && row > 0 && !progress(row, num_rows) {
+                return None;
+            }
+            let inputs = assignments::to_bools(row, length);
+            if self.assumed(&inputs) {
+                table.outputs.push(self.exec(&inputs));
+                table.inputs.push(inputs);
+            }
         }
-    }
 
-    fn pop(&mut self) -> Value {
-        self.stack.pop().unwrap()
+        progress(num_rows, num_rows).then_some(table)
     }
 }
 
-fn usize_to_bools(num: usize, digits: usize) -> Vec<bool> {
-    (1..=digits)
-        .map(|i| (num >> (digits - i)) & 1 == 1)
-        .collect()
+pub(crate) fn execute(
+    stack: &mut Vec<Value>,
+    ops: &[Op],
+    inputs: &[Value],
+    mut step: impl FnMut(Op, &[Value]),
+) -> Value {
+    macro_rules! binop {
+        ($op:tt) => {{
+            let lhs = stack.pop().unwrap();
+            let rhs = stack.pop().unwrap();
+            stack.push(lhs $op rhs);
+        }};
+    }
+
+    let mut pc = 0;
+    while let Some(&op) = ops.get(pc) {
+        pc += 1;
+        match op {
+            Op::Push(v) => stack.push(v),
+            Op::Load(i) => stack.push(inputs[i]),
+            Op::Not => {
+                let operand = stack.pop().unwrap();
+                stack.push(!operand);
+            }
+            Op::And => binop!(&&),
+            Op::Or => binop!(||),
+            Op::Xor => binop!(^),
+            Op::JumpIfFalse(target) => {
+                if !stack.last().unwrap() {
+                    pc = target;
+                }
+            }
+            Op::JumpIfTrue(target) => {
+                if *stack.last().unwrap() {
+                    pc = target;
+                }
+            }
+        }
+        step(op, stack);
+    }
+
+    stack.pop().unwrap()
 }
 
 #[test]
 fn test() {
+    use crate::{compiler::Compiler, parser::Parser};
+
     let test = 0b101010;
-    println!("{:#b} {:?}", test, usize_to_bools(test, 6));
+    println!("{:#b} {:?}", test, assignments::to_bools(test, 6));
+
+    let table = |source| {
+        let equation = Parser::new(source).parse_equation().unwrap();
+        VM::new(Compiler::new(equation).compile()).gen()
+    };
+    let full = table("A AND NOT B = Q");
+    assert_eq!(full.get(&[true, false]), Some(true));
+    assert_eq!(full.get(&[true]), None);
+    #[cfg(feature = "std")]
+    assert_eq!(
+        full.get_named(&[("B", false), ("A", true)].into_iter().collect()),
+        Some(true)
+    );
+
+    let rows: Vec<(bool, bool)> = full.rows().map(|row| (row["B"], row.output)).collect();
+    assert_eq!(
+        rows,
+        [(false, false), (true, false), (false, true), (true, false)]
+    );
+
+    let filtered = table("assume A != B; A AND NOT B = Q");
+    assert_eq!((&filtered).into_iter().len(), 2);
+    assert_eq!(filtered.get(&[true, false]), Some(true));
+    assert_eq!(filtered.get(&[true, true]), None);
+    assert_eq!(full.summary().to_string(), "1 true, 3 false (25.0% true)");
+
+    let equation = Parser::new("A OR B OR C OR D OR E OR F OR G OR H OR I OR J OR K OR L OR M = Q")
+        .parse_equation()
+        .unwrap();
+    let mut vm = VM::new(Compiler::new(equation).compile());
+    let mut reports = vec![];
+    assert!(vm
+        .gen_with_progress(|done, total| {
+            reports.push((done, total));
+            true
+        })
+        .is_some());
+    assert_eq!(reports, [(4096, 8192), (8192, 8192)]);
+    assert!(vm.gen_with_progress(|done, _| done < 4096).is_none());
 }
@@ -0,0 +1,49 @@
+//! The JSON API from `api` for JavaScript hosts, through wasm-bindgen. Each
+//! function takes the source of an equation and returns the result as a
+//! JavaScript object, or throws an error object
+//! `{message: "...", span: {start: 0, end: 1}}`, whose span is `null` when
+//! the error isn't about one place in the source.
+
+use js_sys::{Reflect, JSON};
+use wasm_bindgen::prelude::*;
+
+use crate::api;
+
+pub use crate::api::MAX_INPUTS;
+
+/// Turn one of `api`'s documents into a value, or its error into an
+/// exception.
+fn result(document: Result<String, String>) -> Result<JsValue, JsValue> {
+    match document {
+        Ok(document) => JSON::parse(&document),
+        Err(document) => Err(Reflect::get(
+            &JSON::parse(&document)?,
+            &JsValue::from_str("error"),
+        )?),
+    }
+}
+
+/// The parsed equation: `{inputs, output, source, ast}`.
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsValue, JsValue> {
+    result(api::parse(source))
+}
+
+/// The equation's truth table, in the same shape as `--format json`.
+#[wasm_bindgen(js_name = truthTable)]
+pub fn truth_table(source: &str) -> Result<JsValue, JsValue> {
+    result(api::truth_table_json(source))
+}
+
+/// The minimised sum of products: `{expression, output}`.
+#[wasm_bindgen]
+pub fn minimize(source: &str) -> Result<JsValue, JsValue> {
+    result(api::minimize(source))
+}
+
+/// `{equivalent, counterexample}` for two equations. Errors say which
+/// equation was invalid as `side`, either `"left"` or `"right"`.
+#[wasm_bindgen]
+pub fn equiv(left: &str, right: &str) -> Result<JsValue, JsValue> {
+    result(api::equiv(left, right))
+}
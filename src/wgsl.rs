@@ -0,0 +1,217 @@
+//! Exporting an equation as a WGSL compute shader, so that a GPU host can
+//! generate tables too big for the CPU, which [`crate::gpu`] does with the
+//! `wgpu` feature. Each invocation works out one word of 32 rows,
+//! evaluating the equation's register form with bitwise ops on `u32`s, so a
+//! single op covers all 32 rows at once.
+//!
+//! The shader reads a uniform `base`, the word the dispatch starts at, so a
+//! host can run it in chunks and stream each chunk's words back. Bit `j` of
+//! word `w` is row `32 * w + j`, as in [`eval_word`], which computes the same
+//! words on the CPU.
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{
+    compiler::Equation,
+    register::{Instr, Program},
+};
+
+/// Words are numbered with a `u32`, which covers 2^37 rows.
+pub const MAX_INPUTS: usize = 37;
+
+pub const WORKGROUP_SIZE: usize = 64;
+
+/// How many words of 32 rows a table of `num_inputs` inputs takes.
+pub fn words(num_inputs: usize) -> u64 {
+    1u64.max((1 << num_inputs) / 32)
+}
+
+/// The rows of a word that are part of the table, which is every row unless
+/// there are fewer than 32.
+fn valid(num_inputs: usize) -> u32 {
+    if num_inputs >= 5 {
+        u32::MAX
+    } else {
+        (1 << (1 << num_inputs)) - 1
+    }
+}
+
+/// The column of an input within a word: the row bit it's read from cycles
+/// within the word for the lowest five bits, and is the same for every row
+/// of the word above them.
+fn input_word(num_inputs: usize, input: usize, word: u32) -> u32 {
+    const PATTERNS: [u32; 5] = [
+        0xAAAA_AAAA,
+        0xCCCC_CCCC,
+        0xF0F0_F0F0,
+        0xFF00_FF00,
+        0xFFFF_0000,
+    ];
+
+    // Input 0 is the highest bit of the row
+    let bit = num_inputs - 1 - input;
+    match PATTERNS.get(bit) {
+        Some(pattern) => *pattern,
+        None if word >> (bit - 5) & 1 == 1 => u32::MAX,
+        None => 0,
+    }
+}
+
+/// The shader for `equation`, with bindings:
+///
+/// - `0`: a uniform `Params { base: u32 }`
+/// - `1`: `outputs`, a `u32` per word of the chunk, with the output of each
+///   row. Its length is the size of the chunk, and invocations past it do
+///   nothing
+/// - `2`: `assumed`, a `u32` per word, with the rows satisfying the assumptions
+/// - `3`: `count`, an atomic `u32` counting the rows that satisfy the
+///   assumptions and have an output of 1, for model counting without
+///   reading the table back
+///
+/// # Panics
+///
+/// If the equation has more than [`MAX_INPUTS`] inputs.
+pub fn shader(equation: &Equation) -> String {
+    let num_inputs = equation.inputs.len();
+    assert!(
+        num_inputs <= MAX_INPUTS,
+        "a shader can have at most {} inputs",
+        MAX_INPUTS
+    );
+    let program = Program::from_equation(equation);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// {}: {} inputs, 2^{} rows, 32 rows to a word\n",
+        equation.output, num_inputs, num_inputs
+    )
+    .unwrap();
+    out.push_str(
+        "struct Params {\n    base: u32,\n}\n\n\
+         @group(0) @binding(0) var<uniform> params: Params;\n\
+         @group(0) @binding(1) var<storage, read_write> outputs: array<u32>;\n\
+         @group(0) @binding(2) var<storage, read_write> assumed: array<u32>;\n\
+         @group(0) @binding(3) var<storage, read_write> count: atomic<u32>;\n\n",
+    );
+    writeln!(out, "@compute @workgroup_size({})", WORKGROUP_SIZE).unwrap();
+    out.push_str("fn main(@builtin(global_invocation_id) id: vec3<u32>) {\n");
+    // Invocations past the end of the chunk, in its last workgroup
+    out.push_str("    if (id.x >= arrayLength(&outputs)) {\n        return;\n    }\n");
+    writeln!(out, "    let word = params.base + id.x;").unwrap();
+    // With the most inputs, every word a `u32` can number is in the table
+    if words(num_inputs) <= u64::from(u32::MAX) {
+        writeln!(
+            out,
+            "    if (word >= {}u) {{\n        return;\n    }}",
+            words(num_inputs)
+        )
+        .unwrap();
+    }
+    writeln!(out, "    var r: array<u32, {}>;", program.slots.max(1)).unwrap();
+
+    for instr in &program.instrs {
+        let line = match *instr {
+            Instr::Const { dst, value } => {
+                format!("r[{}] = {}u;", dst, if value { "0xffffffff" } else { "0" })
+            }
+            Instr::Load { dst, input } => {
+                let bit = num_inputs - 1 - input;
+                if bit < 5 {
+                    format!("r[{}] = 0x{:08x}u;", dst, input_word(num_inputs, input, 0))
+                } else {
+                    format!(
+                        "r[{}] = select(0u, 0xffffffffu, ((word >> {}u) & 1u) == 1u);",
+                        dst,
+                        bit - 5
+                    )
+                }
+            }
+            Instr::Not { dst, src } => format!("r[{}] = ~r[{}];", dst, src),
+            Instr::And { dst, lhs, rhs } => format!("r[{}] = r[{}] & r[{}];", dst, lhs, rhs),
+            Instr::Or { dst, lhs, rhs } => format!("r[{}] = r[{}] | r[{}];", dst, lhs, rhs),
+            Instr::Xor { dst, lhs, rhs } => format!("r[{}] = r[{}] ^ r[{}];", dst, lhs, rhs),
+        };
+        writeln!(out, "    {}", line).unwrap();
+    }
+
+    let (output, assumptions) = program.outputs.split_first().unwrap();
+    let mut ok = format!("0x{:08x}u", valid(num_inputs));
+    for slot in assumptions {
+        write!(ok, " & r[{}]", slot).unwrap();
+    }
+    writeln!(out, "    let ok = {};", ok).unwrap();
+    writeln!(out, "    outputs[id.x] = r[{}];", output).unwrap();
+    out.push_str("    assumed[id.x] = ok;\n");
+    writeln!(
+        out,
+        "    atomicAdd(&count, countOneBits(r[{}] & ok));",
+        output
+    )
+    .unwrap();
+    out.push_str("}\n");
+
+    out
+}
+
+/// What the shader computes for `word`: the outputs of its 32 rows, and
+/// which of them satisfy the assumptions.
+pub fn eval_word(program: &Program, num_inputs: usize, word: u32) -> (u32, u32) {
+    let mut r: Vec<u32> = alloc::vec![0; program.slots];
+    for instr in &program.instrs {
+        match *instr {
+            Instr::Const { dst, value } => r[dst] = if value { u32::MAX } else { 0 },
+            Instr::Load { dst, input } => r[dst] = input_word(num_inputs, input, word),
+            Instr::Not { dst, src } => r[dst] = !r[src],
+            Instr::And { dst, lhs, rhs } => r[dst] = r[lhs] & r[rhs],
+            Instr::Or { dst, lhs, rhs } => r[dst] = r[lhs] | r[rhs],
+            Instr::Xor { dst, lhs, rhs } => r[dst] = r[lhs] ^ r[rhs],
+        }
+    }
+
+    let (output, assumptions) = program.outputs.split_first().unwrap();
+    let ok = assumptions
+        .iter()
+        .fold(valid(num_inputs), |ok, slot| ok & r[*slot]);
+    (r[*output], ok)
+}
+
+#[test]
+fn test() {
+    use crate::{assignments, compiler::Compiler, parser::Parser, vm::VM};
+
+    for source in [
+        "(A AND B) OR (C AND (A XOR B)) = Q",
+        "assume A != F; (A OR B) AND NOT (C XOR D) AND (E OR F OR G) = Q",
+    ] {
+        let equation = Compiler::new(Parser::new(source).parse_equation().unwrap()).compile();
+        let num_inputs = equation.inputs.len();
+        let program = Program::from_equation(&equation);
+        let mut rows = alloc::vec![];
+        for word in 0..words(num_inputs) {
+            let (outputs, ok) = eval_word(&program, num_inputs, word as u32);
+            for bit in 0..32.min(1 << num_inputs) {
+                if ok >> bit & 1 == 1 {
+                    rows.push((word as usize * 32 + bit, outputs >> bit & 1 == 1));
+                }
+            }
+        }
+
+        let table = VM::new(equation).gen();
+        let expected: Vec<(usize, bool)> = table
+            .inputs
+            .iter()
+            .map(|inputs| assignments::to_row(inputs))
+            .zip(table.outputs)
+            .collect();
+        assert_eq!(rows, expected, "{}", source);
+    }
+
+    let equation =
+        Compiler::new(Parser::new("A AND NOT B = Q").parse_equation().unwrap()).compile();
+    let shader = shader(&equation);
+    assert!(shader.contains("if (word >= 1u)"));
+    assert!(shader.contains("= 0xccccccccu;"));
+    assert!(shader.contains("let ok = 0x0000000fu;"));
+}
@@ -0,0 +1,304 @@
+//! Zero-suppressed decision diagrams, which store a family of sets of
+//! elements `0..num_elements`. Where a BDD skips a test that makes no
+//! difference, a ZDD skips an element no set contains, so a family of a few
+//! small sets out of many elements needs few nodes, like a sparse on-set or
+//! the candidate sets of a set cover problem.
+
+use std::collections::BTreeMap;
+
+use crate::cover::Cover;
+
+/// A node of the diagram, where `0` is the empty family and `1` the family
+/// holding only the empty set, and other nodes are numbered from `2`.
+pub type NodeId = usize;
+
+pub const EMPTY: NodeId = 0;
+pub const BASE: NodeId = 1;
+
+/// The sets below `low` and those below `high` with `element` added. Every
+/// element below is greater, and `high` is never `EMPTY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Node {
+    pub element: usize,
+    pub low: NodeId,
+    pub high: NodeId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zdd {
+    pub num_elements: usize,
+    /// The nodes, the `i`th being node `i + 2`. Children always come before
+    /// their parents.
+    pub nodes: Vec<Node>,
+    pub root: NodeId,
+}
+
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<Node>,
+    unique: BTreeMap<Node, NodeId>,
+}
+
+impl Builder {
+    fn node(&mut self, element: usize, low: NodeId, high: NodeId) -> NodeId {
+        if high == EMPTY {
+            return low;
+        }
+
+        let node = Node { element, low, high };
+        *self.unique.entry(node).or_insert_with(|| {
+            self.nodes.push(node);
+            self.nodes.len() + 1
+        })
+    }
+
+    fn finish(self, num_elements: usize, root: NodeId) -> Zdd {
+        Zdd {
+            num_elements,
+            nodes: self.nodes,
+            root,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Zdd {
+    /// The family with no sets.
+    pub fn empty(num_elements: usize) -> Self {
+        Builder::default().finish(num_elements, EMPTY)
+    }
+
+    /// The family of just the empty set.
+    pub fn base(num_elements: usize) -> Self {
+        Builder::default().finish(num_elements, BASE)
+    }
+
+    /// The family of one set.
+    pub fn singleton(num_elements: usize, set: &[usize]) -> Self {
+        let mut elements = set.to_vec();
+        elements.sort_unstable();
+        elements.dedup();
+
+        let mut builder = Builder::default();
+        let root = elements
+            .iter()
+            .rev()
+            .fold(BASE, |below, element| builder.node(*element, EMPTY, below));
+        builder.finish(num_elements, root)
+    }
+
+    /// The family of `sets`.
+    pub fn from_sets(num_elements: usize, sets: &[Vec<usize>]) -> Self {
+        sets.iter().fold(Self::empty(num_elements), |family, set| {
+            family.union(&Self::singleton(num_elements, set))
+        })
+    }
+
+    /// The cover's minterms, each as the set of inputs that are true in it.
+    /// Each cube is built directly, without listing its minterms, so a cover
+    /// of a few large cubes is cheap.
+    pub fn on_set(cover: &Cover) -> Self {
+        let n = cover.num_inputs;
+        cover.cubes.iter().fold(Self::empty(n), |family, cube| {
+            let mut builder = Builder::default();
+            let root = (0..n).rev().fold(BASE, |below, i| match cube.get(i, n) {
+                Some(true) => builder.node(i, EMPTY, below),
+                Some(false) => below,
+                None => builder.node(i, below, below),
+            });
+            family.union(&builder.finish(n, root))
+        })
+    }
+
+    /// The cover's cubes, each as its set of literals, where element `2i` is
+    /// input `i` and `2i + 1` its negation.
+    pub fn cubes(cover: &Cover) -> Self {
+        let n = cover.num_inputs;
+        let sets: Vec<Vec<usize>> = cover
+            .cubes
+            .iter()
+            .map(|cube| {
+                (0..n)
+                    .filter_map(|i| cube.get(i, n).map(|value| 2 * i + !value as usize))
+                    .collect()
+            })
+            .collect();
+        Self::from_sets(2 * n, &sets)
+    }
+
+    /// The number of nodes, leaving out the terminals.
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node(&self, id: NodeId) -> Node {
+        self.nodes[id - 2]
+    }
+
+    /// The number of sets in the family.
+    pub fn count(&self) -> usize {
+        let mut counts = vec![0, 1];
+        for node in &self.nodes {
+            counts.push(counts[node.low] + counts[node.high]);
+        }
+
+        counts[self.root]
+    }
+
+    pub fn contains(&self, set: &[usize]) -> bool {
+        let mut set = set.to_vec();
+        set.sort_unstable();
+        set.dedup();
+
+        // Every element of the set has to be taken on the way down
+        let (mut id, mut taken) = (self.root, 0);
+        while id > BASE {
+            let node = self.node(id);
+            id = match set.contains(&node.element) {
+                true => {
+                    taken += 1;
+                    node.high
+                }
+                false => node.low,
+            };
+        }
+
+        id == BASE && taken == set.len()
+    }
+
+    /// Every set of the family, each in increasing order, in the order the
+    /// diagram gives them: those without the smallest element first.
+    pub fn sets(&self) -> Vec<Vec<usize>> {
+        fn visit(zdd: &Zdd, id: NodeId, prefix: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+            match id {
+                EMPTY => {}
+                BASE => out.push(prefix.clone()),
+                _ => {
+                    let node = zdd.node(id);
+                    visit(zdd, node.low, prefix, out);
+                    prefix.push(node.element);
+                    visit(zdd, node.high, prefix, out);
+                    prefix.pop();
+                }
+            }
+        }
+
+        let mut out = vec![];
+        visit(self, self.root, &mut vec![], &mut out);
+        out
+    }
+
+    /// The sets in either family.
+    pub fn union(&self, other: &Zdd) -> Zdd {
+        self.apply(other, Op::Union)
+    }
+
+    /// The sets in both families.
+    pub fn intersection(&self, other: &Zdd) -> Zdd {
+        self.apply(other, Op::Intersection)
+    }
+
+    /// The sets in this family that aren't in `other`.
+    pub fn difference(&self, other: &Zdd) -> Zdd {
+        self.apply(other, Op::Difference)
+    }
+
+    fn apply(&self, other: &Zdd, op: Op) -> Zdd {
+        let mut builder = Builder::default();
+        let mut memo = BTreeMap::new();
+        let root = apply(
+            self,
+            self.root,
+            other,
+            other.root,
+            op,
+            &mut builder,
+            &mut memo,
+        );
+        builder.finish(self.num_elements.max(other.num_elements), root)
+    }
+}
+
+/// Combine the families below `p` in `a` and `q` in `b`. A family whose
+/// smallest element is larger than the other's has no sets with the
+/// smaller, so its high side there is empty.
+fn apply(
+    a: &Zdd,
+    p: NodeId,
+    b: &Zdd,
+    q: NodeId,
+    op: Op,
+    builder: &mut Builder,
+    memo: &mut BTreeMap<(NodeId, NodeId), NodeId>,
+) -> NodeId {
+    if p <= BASE && q <= BASE {
+        let (p, q) = (p == BASE, q == BASE);
+        return match op {
+            Op::Union => p || q,
+            Op::Intersection => p && q,
+            Op::Difference => p && !q,
+        } as NodeId;
+    }
+    if let Some(&id) = memo.get(&(p, q)) {
+        return id;
+    }
+
+    let top = |zdd: &Zdd, id| match id {
+        EMPTY | BASE => usize::MAX,
+        _ => zdd.node(id).element,
+    };
+    let element = top(a, p).min(top(b, q));
+    let split = |zdd: &Zdd, id| match top(zdd, id) == element {
+        true => (zdd.node(id).low, zdd.node(id).high),
+        false => (id, EMPTY),
+    };
+    let ((p0, p1), (q0, q1)) = (split(a, p), split(b, q));
+    let low = apply(a, p0, b, q0, op, builder, memo);
+    let high = apply(a, p1, b, q1, op, builder, memo);
+
+    let id = builder.node(element, low, high);
+    memo.insert((p, q), id);
+    id
+}
+
+#[test]
+fn test() {
+    use crate::{compiler::Compiler, parser::Parser, vm::VM};
+
+    let family = Zdd::from_sets(4, &[vec![0, 2], vec![1], vec![], vec![2, 0]]);
+    assert_eq!(family.count(), 3);
+    assert_eq!(family.sets(), [vec![], vec![1], vec![0, 2]]);
+    assert!(family.contains(&[2, 0]));
+    assert!(!family.contains(&[0]));
+    assert!(!family.contains(&[1, 3]));
+
+    let other = Zdd::from_sets(4, &[vec![1], vec![3]]);
+    assert_eq!(family.union(&other).count(), 4);
+    assert_eq!(family.intersection(&other).sets(), [vec![1]]);
+    assert_eq!(family.difference(&other).sets(), [vec![], vec![0, 2]]);
+    assert_eq!(family.difference(&family).count(), 0);
+
+    // The on-set is built from cubes, without listing their minterms
+    let equation = Parser::new("(A AND B) OR (C AND NOT D) = Q")
+        .parse_equation()
+        .unwrap();
+    let table = VM::new(Compiler::new(equation.clone()).compile()).gen();
+    let minterms: Vec<Vec<usize>> = Cover::on_set(&table)
+        .minterms()
+        .into_iter()
+        .map(|m| (0..4).filter(|i| m >> (3 - i) & 1 == 1).collect())
+        .collect();
+    let cover = Cover::from_expr(equation.lhs.expr(), 4);
+    assert_eq!(
+        Zdd::on_set(&cover).sets(),
+        Zdd::from_sets(4, &minterms).sets()
+    );
+    assert_eq!(Zdd::on_set(&cover).count(), 7);
+    assert_eq!(Zdd::cubes(&cover).sets(), [vec![4, 7], vec![0, 2]]);
+}